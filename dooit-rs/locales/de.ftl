@@ -0,0 +1,226 @@
+# Deutsche Meldungen von dooit-rs, siehe `en.ftl`
+
+no-tasks = Nichts zu tun!
+no-tasks-yet = Noch keine Aufgaben, füge mit `dooit-rs add` welche hinzu
+creating-task-dir = Das Aufgabenverzeichnis existiert nicht, es wird angelegt...
+no-tasks-modified = Keine Aufgaben passen zum Filter oder müssen geändert werden
+
+task-added = { $name } hinzugefügt
+task-captured = { $name } erfasst
+task-completed = { $name } erledigt
+task-recurs = { $name } erledigt, als Nächstes fällig am { $due }
+task-created = { $name } angelegt
+task-deleted = { $name } gelöscht
+task-modified = { $name } geändert
+
+would-create = Würde { $path } anlegen
+would-modify = Würde { $path } ändern
+would-delete = Würde { $path } löschen
+
+tasks-to-create = { $count } Aufgabe(n) werden angelegt
+tasks-to-import = { $count } Aufgabe(n) werden importiert
+tasks-to-delete = { $count } Aufgabe(n) werden gelöscht
+tasks-to-modify = { $count } Aufgabe(n) werden geändert
+confirm = { $summary }, fortfahren? [y/N]
+confirm-needs-yes = { $summary }, mit --yes bestätigen
+
+digest-overdue = Überfällig
+digest-due-this-week = Diese Woche fällig
+digest-completed-last-week = Letzte Woche erledigt
+
+context-active = Kontext: { $name }
+context-set = Zu Kontext { $name } gewechselt
+context-cleared = Kontext aufgehoben, alle Aufgaben werden angezeigt
+no-contexts = Keine Kontexte, füge welche unter `contexts` in der Konfiguration hinzu
+
+report-no-group = (keine)
+report-completed = Erledigt
+column-urgency = Dringlichkeit
+column-due = Fällig
+column-name = Name
+column-tags = Tags
+column-status = Status
+column-estimate = Schätzung
+column-spent = Aufgewendet
+column-score = Punkte
+column-description = Beschreibung
+column-project = Projekt
+column-day = Tag
+column-hours = Stunden
+column-tasks = Aufgaben
+column-ratio = Aufgewendet/Schätzung
+report-total = Summe
+no-time-logged = Keine Zeit erfasst
+
+review-stale = Liegengeblieben
+review-undated = Ohne Fälligkeitsdatum
+review-waiting = Wartend
+review-summary = { $reviewed } Aufgaben durchgesehen, { $changed } geändert
+
+dedupe-none = Keine doppelten Aufgaben gefunden
+dedupe-found = { $count } Paar(e) wahrscheinlich doppelter Aufgaben gefunden
+dedupe-merged = { $from } in { $into } zusammengeführt
+dedupe-summary = { $count } doppelte Aufgabe(n) zusammengeführt
+habit-stats = Serie { $streak }, { $rate } erledigt in den letzten { $days } Tagen
+no-habits = Noch keine Gewohnheiten, füge eine mit `add --recur 1d --habit` hinzu
+
+critical-path = Kritischer Pfad von { $project }: { $duration } Arbeit
+critical-path-none = Keine offenen Aufgaben unter { $project }
+critical-path-unestimated = { $count } Aufgabe(n) auf dem Pfad haben keine Schätzung und zählen als sofort erledigt
+critical-path-late = { $project } ist am { $due } fällig, bevor der Pfad abgeschlossen werden kann
+
+accuracy-none = Keine erledigten Aufgaben mit Schätzung und erfasster Zeit
+accuracy-under = Du unterschätzt { $group }-Aufgaben um das { $ratio }-fache
+accuracy-over = Du überschätzt { $group }-Aufgaben um das { $ratio }-fache
+
+forecast-velocity = Tempo: { $velocity } Aufgaben pro Woche in den letzten { $weeks } Wochen
+forecast-remaining = { $count } Aufgabe(n) übrig
+forecast-done = Nichts mehr zu tun
+forecast-optimistic = Optimistisch: { $date }
+forecast-expected = Erwartet: { $date }
+forecast-pessimistic = Pessimistisch: { $date }
+forecast-never = nie bei diesem Tempo
+
+plan-title = Plan für { $day }: { $load } von { $capacity }
+plan-empty = Heute ist nichts fällig oder geplant
+plan-unestimated = { $count } Aufgabe(n) haben keine Schätzung und zählen als sofort erledigt
+plan-over = { $over } über der Kapazität, diese könnten verschoben werden:
+plan-over-due = Allein mit den heute fälligen Aufgaben noch { $over } über der Kapazität
+plan-sheet-schedule = Zeitplan
+plan-sheet-todo = Zu erledigen
+plan-sheet-due = fällig { $due }
+plan-sheet-notes = Notizen
+
+schedule-none = Nichts zu planen
+schedule-late = { $name } ist am { $due } fällig, vor dem geplanten Tag
+schedule-unplaced = { $count } Aufgabe(n) passen nicht in den Zeitraum: { $names }
+tasks-to-schedule = { $count } Aufgabe(n) werden eingeplant
+tasks-scheduled = { $count } Aufgabe(n) eingeplant
+
+stats-tasks = { $pending } offen, { $overdue } überfällig, { $completed } erledigt
+stats-streak = Serie: { $current } Tag(e) in Folge, längste { $longest }
+stats-points = Punkte: { $today } heute, { $week } diese Woche, { $total } insgesamt
+stats-streak-at-risk = Erledige heute eine Aufgabe, um die Serie fortzusetzen
+
+history-none = Keine erfassten Änderungen an { $name }
+history-entry = { $at } { $action } von { $who }
+history-added = erstellt
+history-modified = geändert
+history-deleted = gelöscht
+
+verify-modified = geändert: { $path }
+verify-missing = fehlt: { $path }
+verify-unindexed = nicht im Index: { $path }
+verify-ok = Alle Aufgabendateien stimmen mit dem Index überein
+verify-accepted = { $count } Aufgabendatei(en) indiziert
+
+watch-event = { $at } { $event } { $name }
+watch-added = erstellt
+watch-modified = geändert
+watch-completed = erledigt
+watch-deleted = gelöscht
+watch-overdue = überfällig
+limit-exceeded = { $group } hat { $count } Aufgaben, mehr als die Grenze von { $limit }
+limit-started = begonnen
+limit-urgency = Dringlichkeit { $urgency }
+attached = { $file } an { $name } angehängt
+show-links = Links:
+show-attachments = Anhänge:
+read-only-label = [schreibgeschützt]
+task-unchanged = { $name } ist unverändert
+edit-again = Die Aufgabe muss zum Speichern korrigiert werden, Editor erneut öffnen
+search-no-match = Keine Aufgaben passen zu { $query }
+doctor-ok = Keine widersprüchlichen Aufgabendateien
+doctor-duplicate = { $name } steht in mehreren Dateien:
+doctor-misplaced = { $name } steht in { $path } statt in { $expected }
+doctor-invalid-name = keiner Datei (der Name ist ungültig)
+signing-unsigned = Die Aufgaben sind noch nicht signiert, prüfe sie und führe `dooit-rs verify --accept` aus, um sie zu signieren
+signing-invalid = { $path } ist keine gültige Signatur von { $key }, die Aufgabendateien könnten manipuliert worden sein
+signing-modified = { $path } wurde seit der Signatur geändert
+signing-missing = { $path } wurde seit der Signatur gelöscht
+signing-unindexed = { $path } ist nicht signiert
+signing-refused = Die geänderten Aufgaben wurden nicht signiert, da ihre Signatur nicht stimmte: führe `dooit-rs verify --accept` aus, sobald die Aufgabendateien stimmen
+task-moved = { $from } nach { $to } verschoben ({ $count } Aufgabe(n))
+task-archived = { $name } archiviert
+would-archive = Würde { $name } archivieren
+no-tasks-archived = Keine erledigten Aufgaben zum Archivieren
+task-snoozed = { $name } auf { $due } verschoben
+review-up = Zur Wiedervorlage
+undone = { $command } vom { $at } rückgängig gemacht
+undo-nothing = Nichts rückgängig zu machen
+no-tasks-purged = Keine erledigten Aufgaben zum Löschen
+
+task-reopened = { $name } wieder geöffnet
+tui-help = q beenden  c erledigen  e bearbeiten  s verschieben  a hinzufügen  / filtern  o sortieren  h erledigte  b Board
+tui-reloaded = Aufgaben neu geladen
+tui-new-task = Neue Aufgabe: { $name }
+tui-tasks = Aufgaben ({ $sort })
+tui-details = Details
+tui-urgency = Dringlichkeit: { $urgency }
+tui-status = Status: { $status }
+tui-due = Fällig: { $due }
+tui-completed = Erledigt: { $completed }
+tui-yes = ja
+tui-no = nein
+board-pending = Offen
+board-started = Begonnen
+board-waiting = Wartend
+board-done = Erledigt
+board-moved = { $name } nach { $column } verschoben
+focus-title = Fokus
+focus-completed = Erledigt
+focus-due-in = Fällig in { $time }
+focus-overdue-by = Überfällig seit { $time }
+focus-no-due = Kein Fälligkeitsdatum
+focus-focused-for = Fokussiert seit { $time }
+focus-logged-before = { $time } zuvor erfasst
+focus-checklist = Checkliste { $done }/{ $total }
+focus-help = q beenden  j/k auswählen  Leertaste abhaken  c erledigen
+daemon-running = Daemon läuft mit PID { $pid }, { $tasks } Aufgaben geladen
+daemon-next-reminder = Nächste Erinnerung um { $at }
+history-archived = archiviert
+sync-local = lokal
+sync-remote = entfernt
+sync-keep = [o]urs (lokal) oder [t]heirs (entfernt) behalten (Standard { $default })?
+unknown-answer = Unbekannte Antwort { $answer }
+sync-conflict = { $name }: { $change } (Wert von { $kept } behalten)
+sync-done = Synchronisiert: { $pulled } Änderung(en) geholt, { $pushed } übertragen, { $conflicts } Konflikt(e)
+config-untrusted = { $options } aus { $path } ignoriert, führe `dooit-rs config trust` aus, wenn du ihr vertraust
+no-project-config = Keine Projektkonfiguration (.dooit/config.toml) hier oder in einem übergeordneten Verzeichnis
+config-trusted = { $path } wird vertraut
+config-already-trusted = { $path } wurde bereits vertraut
+config-untrusted-now = { $path } wird nicht mehr vertraut
+config-not-trusted = { $path } wurde nicht vertraut
+dry-run-refused = `{ $command }` unterstützt --dry-run nicht, es kann nicht ohne seine Nebenwirkungen laufen
+task-exists = Es gibt schon eine Aufgabe namens { $name }
+add-name = Name:
+add-description = Beschreibung (optional, :e um sie im Editor zu schreiben):
+add-due = Fällig (z.B. tomorrow 17:00, friday, in 2d; optional):
+add-due-at = Fällig { $due }
+add-urgency = Dringlichkeit ({ $levels }) [{ $default }]:
+add-tags = Tags (optional):
+dedupe-keep = [1] oder [2] behalten, über[s]pringen oder [q] beenden?
+review-choice = [k] behalten, [r] verschieben, [d] delegieren, [l] später, [D] löschen, [c] erledigen oder [q] beenden?
+review-due = Fällig (z.B. friday, in 2w):
+review-again = Wieder durchsehen (z.B. in 4w, next monday):
+review-waiting-on = Wartet auf:
+review-waiting-note = Wartet auf { $who }
+review-show-due = fällig { $due }
+review-show-review = durchsehen { $review }
+review-show-urgency = Dringlichkeit { $urgency }, { $status }
+review-show-tags = Tags: { $tags }
+serve-http = Aufgaben unter http://{ $listen } bereitgestellt
+serve-dbus = Aufgaben auf dem Session-Bus als { $name } bereitgestellt
+daemon-watching = Beobachte { $path } auf Änderungen
+import-skipped = vorhandene Aufgabe { $name } übersprungen
+config-written = { $path } geschrieben
+config-no-files = Keine Konfigurationsdateien, verwende die Standardwerte
+config-unknown = unbekannte Option { $key }
+config-unknown-suggest = unbekannte Option { $key }, war { $suggestion } gemeint?
+config-valid = { $path } ist gültig
+signing-check-failed = konnte die Aufgabendateien nicht mit ihrer Signatur vergleichen: { $error }
+signing-verify-failed = konnte die Signatur der Aufgaben nicht prüfen: { $error }
+signing-failed = konnte die Aufgaben nicht signieren: { $error }
+lookup-which = Welche Aufgabe?
+doctor-keep-which = Welche Datei behalten? (ihre Nummer, leer zum Überspringen)
+doctor-move-or-rename = Aufgabe dorthin verschieben, wohin ihr Name führt ([m]ove), nach ihrer Datei umbenennen ([r]ename) oder überspringen?
@@ -0,0 +1,229 @@
+# Messages shown by dooit-rs
+#
+# Every translation is a file like this one named after its language (e.g. `de.ftl`), messages
+# missing from it are shown in English. `{ $name }` is replaced by the value of `name`.
+
+no-tasks = No tasks to do!
+no-tasks-yet = No tasks yet, add some by running `dooit-rs add`
+creating-task-dir = The task directory doesn't exist, creating it...
+no-tasks-modified = No tasks match the filter or need changes
+
+task-added = Added { $name }
+task-captured = Captured { $name }
+task-completed = Completed { $name }
+task-recurs = Completed { $name }, next due { $due }
+task-created = Created { $name }
+task-deleted = Deleted { $name }
+task-modified = Modified { $name }
+
+would-create = Would create { $path }
+would-modify = Would modify { $path }
+would-delete = Would delete { $path }
+
+tasks-to-create = { $count } task(s) will be created
+tasks-to-import = { $count } task(s) will be imported
+tasks-to-delete = { $count } task(s) will be deleted
+tasks-to-modify = { $count } task(s) will be modified
+confirm = { $summary }, continue? [y/N]
+confirm-needs-yes = { $summary }, pass --yes to confirm
+
+digest-overdue = Overdue
+digest-due-this-week = Due this week
+digest-completed-last-week = Completed last week
+
+context-active = Context: { $name }
+context-set = Switched to context { $name }
+context-cleared = Cleared the context, listing every task
+no-contexts = No contexts, add some under `contexts` in the config
+
+report-no-group = (none)
+report-completed = Completed
+column-urgency = Urgency
+column-due = Due
+column-name = Name
+column-tags = Tags
+column-status = Status
+column-estimate = Estimate
+column-spent = Spent
+column-score = Score
+column-description = Description
+column-project = Project
+column-day = Day
+column-hours = Hours
+column-tasks = Tasks
+column-ratio = Spent/estimate
+report-total = Total
+no-time-logged = No time logged
+
+review-stale = Stale
+review-undated = Without a due date
+review-waiting = Waiting
+review-summary = Reviewed { $reviewed } tasks, changed { $changed }
+
+dedupe-none = No duplicate tasks found
+dedupe-found = Found { $count } pair(s) of likely duplicates
+dedupe-merged = Merged { $from } into { $into }
+dedupe-summary = Merged { $count } duplicate task(s)
+habit-stats = Streak { $streak }, { $rate } done in the last { $days } days
+no-habits = No habits yet, add one with `add --recur 1d --habit`
+
+critical-path = Critical path of { $project }: { $duration } of work
+critical-path-none = No pending tasks under { $project }
+critical-path-unestimated = { $count } task(s) on the path have no estimate and count as taking no time
+critical-path-late = { $project } is due { $due }, before the path can be finished
+
+accuracy-none = No completed tasks with an estimate and logged time
+accuracy-under = You underestimate { $group } tasks by { $ratio }×
+accuracy-over = You overestimate { $group } tasks by { $ratio }×
+
+forecast-velocity = Velocity: { $velocity } tasks per week over the last { $weeks } weeks
+forecast-remaining = { $count } task(s) left
+forecast-done = Nothing left to do
+forecast-optimistic = Optimistic: { $date }
+forecast-expected = Expected: { $date }
+forecast-pessimistic = Pessimistic: { $date }
+forecast-never = never at this pace
+
+plan-title = Plan for { $day }: { $load } of { $capacity }
+plan-empty = Nothing due or scheduled today
+plan-unestimated = { $count } task(s) have no estimate and count as taking no time
+plan-over = Over capacity by { $over }, consider deferring:
+plan-over-due = Still over capacity by { $over } with the tasks due today alone
+plan-sheet-schedule = Schedule
+plan-sheet-todo = To do
+plan-sheet-due = due { $due }
+plan-sheet-notes = Notes
+
+schedule-none = Nothing to schedule
+schedule-late = { $name } is due { $due }, before the day it was scheduled for
+schedule-unplaced = { $count } task(s) don't fit in the horizon: { $names }
+tasks-to-schedule = { $count } task(s) will be scheduled
+tasks-scheduled = Scheduled { $count } task(s)
+
+stats-tasks = { $pending } pending, { $overdue } overdue, { $completed } completed
+stats-streak = Streak: { $current } day(s) in a row, longest { $longest }
+stats-points = Points: { $today } today, { $week } this week, { $total } in total
+stats-streak-at-risk = Complete a task today to keep the streak going
+
+history-none = No recorded changes to { $name }
+history-entry = { $at } { $action } by { $who }
+history-added = added
+history-modified = modified
+history-deleted = deleted
+
+verify-modified = modified: { $path }
+verify-missing = missing: { $path }
+verify-unindexed = not in the index: { $path }
+verify-ok = Every task file matches the index
+verify-accepted = Indexed { $count } task file(s)
+
+watch-event = { $at } { $event } { $name }
+watch-added = added
+watch-modified = modified
+watch-completed = completed
+watch-deleted = deleted
+watch-overdue = overdue
+limit-exceeded = { $group } has { $count } tasks, over its limit of { $limit }
+limit-started = started
+limit-urgency = urgency { $urgency }
+attached = Attached { $file } to { $name }
+show-links = Links:
+show-attachments = Attachments:
+read-only-label = [read-only]
+task-unchanged = { $name } is unchanged
+edit-again = The task has to be fixed to be saved, reopen the editor
+search-no-match = No tasks match { $query }
+doctor-ok = No conflicting task files
+doctor-duplicate = { $name } is in several files:
+doctor-misplaced = { $name } is in { $path } instead of { $expected }
+doctor-invalid-name = nowhere (its name isn't valid)
+signing-unsigned = The tasks aren't signed yet, check them and run `dooit-rs verify --accept` to sign them
+signing-invalid = { $path } isn't a valid signature by { $key }, the task files may have been tampered with
+signing-modified = { $path } changed since it was signed
+signing-missing = { $path } was deleted since it was signed
+signing-unindexed = { $path } isn't signed
+signing-refused = Didn't sign the changed tasks, their signature didn't check out: run `dooit-rs verify --accept` once the task files look right
+task-moved = Moved { $from } to { $to } ({ $count } task(s))
+task-archived = Archived { $name }
+would-archive = Would archive { $name }
+no-tasks-archived = No completed tasks to archive
+task-snoozed = Snoozed { $name } until { $due }
+review-up = Up for review
+undone = Undid { $command } from { $at }
+undo-nothing = Nothing to undo
+no-tasks-purged = No completed tasks to purge
+
+task-reopened = Reopened { $name }
+tui-help = q quit  c complete  e edit  s snooze  a add  / filter  o sort  h completed  b board
+tui-reloaded = Reloaded tasks
+tui-new-task = New task: { $name }
+tui-tasks = Tasks ({ $sort })
+tui-details = Details
+tui-urgency = Urgency: { $urgency }
+tui-status = Status: { $status }
+tui-due = Due: { $due }
+tui-completed = Completed: { $completed }
+tui-yes = yes
+tui-no = no
+board-pending = Pending
+board-started = Started
+board-waiting = Waiting
+board-done = Done
+board-moved = Moved { $name } to { $column }
+focus-title = Focus
+focus-completed = Completed
+focus-due-in = Due in { $time }
+focus-overdue-by = Overdue by { $time }
+focus-no-due = No due date
+focus-focused-for = Focused for { $time }
+focus-logged-before = { $time } logged before
+focus-checklist = Checklist { $done }/{ $total }
+focus-help = q quit  j/k select  space check  c complete
+daemon-running = Daemon running with pid { $pid }, { $tasks } tasks loaded
+daemon-next-reminder = Next reminder at { $at }
+history-archived = archived
+sync-local = local
+sync-remote = remote
+sync-keep = Keep [o]urs or [t]heirs (default { $default })?
+unknown-answer = Unknown answer { $answer }
+sync-conflict = { $name }: { $change } (kept the { $kept } value)
+sync-done = Synced: { $pulled } change(s) pulled, { $pushed } pushed, { $conflicts } conflict(s)
+config-untrusted = Ignored { $options } from { $path }, run `dooit-rs config trust` if you trust it
+no-project-config = No project config (.dooit/config.toml) here or in a parent directory
+config-trusted = Trusted { $path }
+config-already-trusted = { $path } was already trusted
+config-untrusted-now = Stopped trusting { $path }
+config-not-trusted = { $path } wasn't trusted
+dry-run-refused = `{ $command }` doesn't support --dry-run, it can't run without its side effects
+task-exists = A task named { $name } already exists
+add-name = Name:
+add-description = Description (optional, :e to write it in the editor):
+add-due = Due (e.g. tomorrow 17:00, friday, in 2d; optional):
+add-due-at = Due { $due }
+add-urgency = Urgency ({ $levels }) [{ $default }]:
+add-tags = Tags (optional):
+dedupe-keep = Keep [1] or [2], [s]kip or [q]uit?
+review-choice = [k]eep, [r]eschedule, [d]elegate, [l]ater, [D]elete, [c]omplete or [q]uit?
+review-due = Due (e.g. friday, in 2w):
+review-again = Review again (e.g. in 4w, next monday):
+review-waiting-on = Waiting on:
+review-waiting-note = Waiting on { $who }
+review-show-due = due { $due }
+review-show-review = review { $review }
+review-show-urgency = { $urgency } urgency, { $status }
+review-show-tags = tags: { $tags }
+serve-http = Serving tasks on http://{ $listen }
+serve-dbus = Serving tasks on the session bus as { $name }
+daemon-watching = Watching { $path } for changes
+import-skipped = skipping existing task { $name }
+config-written = Wrote { $path }
+config-no-files = No config files, using the defaults
+config-unknown = unknown option { $key }
+config-unknown-suggest = unknown option { $key }, did you mean { $suggestion }?
+config-valid = { $path } is valid
+signing-check-failed = couldn't check the task files against their signature: { $error }
+signing-verify-failed = couldn't check the signature of the tasks: { $error }
+signing-failed = couldn't sign the tasks: { $error }
+lookup-which = Which task?
+doctor-keep-which = Keep which file? (its number, empty to skip)
+doctor-move-or-rename = [m]ove the task to where its name leads, [r]ename it after its file, or skip?
@@ -0,0 +1,201 @@
+//! `report accuracy`: how the estimates of completed tasks compare to the time logged on them
+//!
+//! Only completed tasks with an estimate and some logged time count. The ratio is the time spent
+//! over the time estimated, summed over the tasks of each project or tag, so bigger tasks weigh
+//! more.
+
+use std::collections::BTreeMap;
+
+use chrono::{Duration, Utc};
+use clap::ValueEnum;
+use color_eyre::eyre::WrapErr;
+use dooit_tasks::{duration::format_duration, HumanDuration, Task, TaskStore};
+
+use crate::{color, config::Config, i18n::tr, report, timesheet};
+
+/// Ratios this close to 1 are accurate enough to not mention
+const TOLERANCE: f64 = 0.2;
+
+/// What the tasks are grouped by
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum AccuracyGroup {
+    /// The top-level task
+    #[default]
+    Project,
+    /// Each tag of the task
+    Tag,
+}
+
+/// Time estimated and spent on the tasks of a group
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Accuracy {
+    tasks: usize,
+    estimated: Duration,
+    spent: Duration,
+}
+
+impl Default for Accuracy {
+    fn default() -> Self {
+        Self {
+            tasks: 0,
+            estimated: Duration::zero(),
+            spent: Duration::zero(),
+        }
+    }
+}
+
+impl Accuracy {
+    fn add(&mut self, estimated: Duration, spent: Duration) {
+        self.tasks += 1;
+        self.estimated = self.estimated + estimated;
+        self.spent = self.spent + spent;
+    }
+
+    /// Time spent for every unit of time estimated
+    fn ratio(&self) -> f64 {
+        self.spent.num_seconds() as f64 / self.estimated.num_seconds() as f64
+    }
+}
+
+/// Accuracy of the completed `tasks` per group, and of all of them
+fn accuracy(tasks: &[Task], by: AccuracyGroup) -> (BTreeMap<String, Accuracy>, Accuracy) {
+    let mut groups: BTreeMap<String, Accuracy> = BTreeMap::new();
+    let mut total = Accuracy::default();
+    for task in tasks {
+        let Some(HumanDuration(estimated)) = task.estimate else {
+            continue;
+        };
+        if !task.completed || task.time_log.is_empty() {
+            continue;
+        }
+
+        let spent = task.time_spent();
+        total.add(estimated, spent);
+        let keys = match by {
+            AccuracyGroup::Project => timesheet::project(task).into_iter().collect(),
+            AccuracyGroup::Tag if task.tags.is_empty() => vec![tr!("report-no-group")],
+            AccuracyGroup::Tag => task.tags.clone(),
+        };
+        for key in keys {
+            groups.entry(key).or_default().add(estimated, spent);
+        }
+    }
+    (groups, total)
+}
+
+/// Print how accurate the estimates of the tasks completed in the last `since` were
+pub fn run(
+    config: &Config,
+    store: &impl TaskStore,
+    by: AccuracyGroup,
+    since: Option<Duration>,
+) -> color_eyre::Result<()> {
+    let now = Utc::now();
+    let mut tasks = store.load_all().wrap_err("load tasks")?;
+    if let Some(since) = since {
+        tasks.retain(|task| task.completed_at.is_some_and(|at| at >= now - since));
+    }
+
+    let (groups, total) = accuracy(&tasks, by);
+    if total.tasks == 0 {
+        println!("{}", tr!("accuracy-none"));
+        return Ok(());
+    }
+
+    let row = |name: String, accuracy: &Accuracy| {
+        vec![
+            name,
+            accuracy.tasks.to_string(),
+            format_duration(accuracy.estimated),
+            format_duration(accuracy.spent),
+            format!("{:.1}\u{d7}", accuracy.ratio()),
+        ]
+    };
+    let title = match by {
+        AccuracyGroup::Project => tr!("column-project"),
+        AccuracyGroup::Tag => tr!("column-tags"),
+    };
+    let header = vec![
+        title,
+        tr!("column-tasks"),
+        tr!("column-estimate"),
+        tr!("column-spent"),
+        tr!("column-ratio"),
+    ];
+    let rows: Vec<_> = std::iter::once(header)
+        .chain(
+            groups
+                .iter()
+                .map(|(name, accuracy)| row(name.clone(), accuracy)),
+        )
+        .chain(std::iter::once(row(tr!("report-total"), &total)))
+        .collect();
+    let lines = report::table(&rows);
+    let last = lines.len() - 1;
+    for (index, line) in lines.into_iter().enumerate() {
+        if index == 0 || index == last {
+            println!("{}", color::header(&line, config));
+        } else {
+            println!("{line}");
+        }
+    }
+
+    let verdicts: Vec<_> = groups
+        .iter()
+        .filter_map(|(name, accuracy)| match accuracy.ratio() {
+            ratio if ratio > 1.0 + TOLERANCE => {
+                let ratio = format!("{ratio:.1}");
+                Some(tr!("accuracy-under", group = name, ratio = ratio))
+            }
+            ratio if ratio < 1.0 - TOLERANCE => {
+                let ratio = format!("{:.1}", 1.0 / ratio);
+                Some(tr!("accuracy-over", group = name, ratio = ratio))
+            }
+            _ => None,
+        })
+        .collect();
+    if !verdicts.is_empty() {
+        println!("\n{}", verdicts.join("\n"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{Duration, TimeZone, Utc};
+    use dooit_tasks::{HumanDuration, Task, TimeEntry};
+    use pretty_assertions::assert_eq;
+
+    use super::{accuracy, AccuracyGroup};
+
+    #[test]
+    fn test_accuracy() {
+        let start = Utc.with_ymd_and_hms(2024, 5, 1, 9, 0, 0).unwrap();
+        let task = |name: &str, estimate: i64, spent: i64| Task {
+            estimate: Some(HumanDuration(Duration::hours(estimate))),
+            time_log: vec![TimeEntry {
+                start,
+                end: start + Duration::hours(spent),
+            }],
+            ..Task::new(name).with_tags(["infra"]).complete()
+        };
+        let tasks = [
+            task("infra/dns", 1, 3),
+            task("infra/ci", 2, 3),
+            task("blog", 4, 2),
+            Task {
+                completed: false,
+                ..task("infra/pending", 1, 10)
+            },
+        ];
+
+        let (groups, total) = accuracy(&tasks, AccuracyGroup::Project);
+        assert_eq!(groups["infra"].tasks, 2);
+        assert_eq!(groups["infra"].ratio(), 2.0);
+        assert_eq!(groups["blog"].ratio(), 0.5);
+        assert_eq!(total.tasks, 3);
+
+        let (groups, _) = accuracy(&tasks, AccuracyGroup::Tag);
+        assert_eq!(groups["infra"].spent, Duration::hours(8));
+    }
+}
@@ -0,0 +1,574 @@
+//! Operations shared by the CLI and the different server frontends
+//!
+//! Every mutation goes through here so hooks and webhooks see all of them.
+
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{bail, eyre, WrapErr};
+use dooit_tasks::{
+    tasks::sort_tasks_with, urgency, DirStore, EventKind, ListFilter, SortMode, Task, TaskEvent,
+    TaskStore, Urgency,
+};
+
+use crate::{audit, config::Config, failure::Failure, hooks::Hook, journal, limits, webhooks};
+
+/// Load the tasks matching the `list` filters, sorted by `sort`
+pub fn list(
+    config: &Config,
+    store: &impl TaskStore,
+    sort: SortMode,
+    completed: bool,
+    overdue: bool,
+) -> color_eyre::Result<Vec<Task>> {
+    let tasks = store.load_all().wrap_err("load tasks")?;
+    Ok(listed(config, tasks, sort, completed, overdue))
+}
+
+/// [`list`] only `parent` and its subtasks, without reading the rest of the store
+///
+/// Only meant for showing the tasks: their time logs are skipped, see
+/// [`TaskStore::load_summaries_under`].
+pub fn list_under(
+    config: &Config,
+    store: &impl TaskStore,
+    parent: &Path,
+    sort: SortMode,
+    completed: bool,
+    overdue: bool,
+) -> color_eyre::Result<Vec<Task>> {
+    let tasks = store.load_summaries_under(parent).wrap_err("load tasks")?;
+    Ok(listed(config, tasks, sort, completed, overdue))
+}
+
+/// The `tasks` that are listed, sorted
+fn listed(
+    config: &Config,
+    tasks: Vec<Task>,
+    sort: SortMode,
+    completed: bool,
+    overdue: bool,
+) -> Vec<Task> {
+    let filter = ListFilter {
+        completed,
+        overdue,
+        ..ListFilter::default()
+    };
+    let now = chrono::Utc::now();
+    let filtered = tasks
+        .into_iter()
+        .filter(|task| filter.matches(task, now))
+        .collect();
+
+    sort_tasks_with(filtered, sort, &config.score)
+}
+
+fn load(store: &impl TaskStore, name: &Path) -> color_eyre::Result<Task> {
+    store
+        .load(name)
+        .wrap_err_with(|| format!("load task {}", name.display()))?
+        .ok_or_else(|| eyre!("no task named {}", name.display()))
+}
+
+/// Fail if the task called `name` is [read-only](Config::read_only_source)
+pub fn writable(config: &Config, name: &Path) -> color_eyre::Result<()> {
+    if let Some(source) = config.read_only_source(name) {
+        return Err(Failure::ReadOnly.report().wrap_err(format!(
+            "{} can't be changed, {} is read-only in the config",
+            name.display(),
+            source.display()
+        )));
+    }
+    Ok(())
+}
+
+/// Save `task`, which was `old` before (under another name if it's being renamed), and record
+/// the change
+fn save(
+    config: &Config,
+    store: &impl TaskStore,
+    old: Option<&Task>,
+    task: &Task,
+) -> color_eyre::Result<()> {
+    task.validate()?;
+    limits::check(config, store, old, task)?;
+    match old {
+        Some(old) if old.name != task.name => store.rename(&old.name, task),
+        _ => store.save(task),
+    }
+    .wrap_err_with(|| format!("save task {}", task.name.display()))?;
+    audit::record(config, old, Some(task));
+    journal::record(config, old, Some(task));
+    Ok(())
+}
+
+/// Record when a task gets completed, and forget it again if it's reopened
+///
+/// Callers validating a changed task before saving it should stamp it first, so reopening a task
+/// doesn't fail for the completion date it still has.
+pub(crate) fn stamp_completion(old: Option<&Task>, mut task: Task) -> Task {
+    if !task.completed {
+        task.completed_at = None;
+    } else if task.recur.is_some() && old.is_some_and(|old| !old.completed) {
+        // Completing a recurring task moves it to its next occurrence instead
+        let now = task.completed_at.unwrap_or_else(chrono::Utc::now);
+        return task.next_occurrence(now);
+    } else if !old.is_some_and(|old| old.completed) {
+        task.completed_at = task.completed_at.or_else(|| Some(chrono::Utc::now()));
+    }
+    task
+}
+
+/// Fail if there already is a task called `name`, adding it would overwrite it
+fn absent(store: &impl TaskStore, name: &Path) -> color_eyre::Result<()> {
+    if store
+        .load(name)
+        .wrap_err_with(|| format!("load task {}", name.display()))?
+        .is_some()
+    {
+        bail!("a task named {} already exists", name.display());
+    }
+    Ok(())
+}
+
+/// Save the new `task`, failing if there already is one with its name
+pub fn add(config: &Config, store: &impl TaskStore, task: Task) -> color_eyre::Result<()> {
+    writable(config, &task.name)?;
+    absent(store, &task.name)?;
+    let task = stamp_completion(None, task);
+    let task = config.hooks.run(Hook::Add, None, task)?;
+    save(config, store, None, &task)?;
+
+    webhooks::fire(&config.webhooks, &TaskEvent::new(EventKind::Added, task));
+
+    Ok(())
+}
+
+/// [`add`] every task in `tasks`, saving them all at once (see [`TaskStore::save_all`])
+///
+/// Meant for importing many tasks: nothing is saved if one of them already exists (or is given
+/// twice), if a hook rejects any of them, or if they put a group over its limit together (see
+/// [`limits::check_all`]).
+pub fn add_all(
+    config: &Config,
+    store: &impl TaskStore,
+    tasks: Vec<Task>,
+) -> color_eyre::Result<()> {
+    let mut added: Vec<Task> = Vec::with_capacity(tasks.len());
+    for task in tasks {
+        writable(config, &task.name)?;
+        absent(store, &task.name)?;
+        if added.iter().any(|other| other.name == task.name) {
+            bail!("{} is given more than once", task.name.display());
+        }
+        let task = stamp_completion(None, task);
+        let task = config.hooks.run(Hook::Add, None, task)?;
+        task.validate()?;
+        added.push(task);
+    }
+    limits::check_all(config, store, &added)?;
+    store.save_all(&added).wrap_err("save tasks")?;
+
+    for task in added {
+        audit::record(config, None, Some(&task));
+        journal::record(config, None, Some(&task));
+        webhooks::fire(&config.webhooks, &TaskEvent::new(EventKind::Added, task));
+    }
+    Ok(())
+}
+
+/// Mark the task called `name` as completed, returns the updated task
+pub fn complete(config: &Config, store: &impl TaskStore, name: &Path) -> color_eyre::Result<Task> {
+    writable(config, name)?;
+    let old = load(store, name)?;
+
+    if old.completed {
+        return Ok(old);
+    }
+
+    let task = Task {
+        completed: true,
+        ..old.clone()
+    };
+    let task = stamp_completion(Some(&old), task);
+    let task = config.hooks.run(Hook::Complete, Some(&old), task)?;
+    save(config, store, Some(&old), &task)?;
+
+    webhooks::fire(
+        &config.webhooks,
+        &TaskEvent::new(EventKind::Completed, task.clone()),
+    );
+
+    Ok(task)
+}
+
+/// Push the due date of the task called `name` back by `by` (from now if it has no due date)
+pub fn snooze(
+    config: &Config,
+    store: &impl TaskStore,
+    name: &Path,
+    by: chrono::Duration,
+) -> color_eyre::Result<Task> {
+    let task = load(store, name)?;
+    let due = task.due.unwrap_or_else(chrono::Utc::now) + by;
+    let task = Task {
+        due: Some(due),
+        ..task
+    };
+
+    update(config, store, task.clone())?;
+    Ok(task)
+}
+
+/// Add the `added` tags to the task called `name` and drop the `removed` ones, returns the
+/// updated task
+pub fn retag(
+    config: &Config,
+    store: &impl TaskStore,
+    name: &Path,
+    added: &[String],
+    removed: &[String],
+) -> color_eyre::Result<Task> {
+    let mut task = load(store, name)?;
+    task.tags.retain(|tag| !removed.contains(tag));
+    for tag in added {
+        if !task.tags.contains(tag) {
+            task.tags.push(tag.clone());
+        }
+    }
+
+    update(config, store, task.clone())?;
+    Ok(task)
+}
+
+/// Move the task called `name` to the next urgency level (the previous one unless `up`), returns
+/// the updated task
+pub fn bump(
+    config: &Config,
+    store: &impl TaskStore,
+    name: &Path,
+    up: bool,
+) -> color_eyre::Result<Task> {
+    let task = load(store, name)?;
+    let Some(rank) = task.urgency.rank() else {
+        bail!(
+            "{} has the urgency {}, which isn't one of the configured levels",
+            name.display(),
+            task.urgency
+        );
+    };
+    let level = match up {
+        true => urgency::levels().get(rank + 1),
+        false => rank
+            .checked_sub(1)
+            .and_then(|rank| urgency::levels().get(rank)),
+    };
+    let Some(level) = level else {
+        let edge = if up { "highest" } else { "lowest" };
+        bail!("{} already has the {edge} urgency", name.display());
+    };
+    let task = task.with_urgency(Urgency::from(level.name.clone()));
+
+    update(config, store, task.clone())?;
+    Ok(task)
+}
+
+/// Replace a task with `task`, returns `Ok(false)` if it didn't exist before
+pub fn update(config: &Config, store: &impl TaskStore, task: Task) -> color_eyre::Result<bool> {
+    writable(config, &task.name)?;
+    let old = store
+        .load(&task.name)
+        .wrap_err_with(|| format!("load task {}", task.name.display()))?;
+
+    let (hook, event) = match &old {
+        None => (Hook::Add, EventKind::Added),
+        Some(old) if !old.completed && task.completed => (Hook::Complete, EventKind::Completed),
+        Some(_) => (Hook::Modify, EventKind::Modified),
+    };
+
+    let task = stamp_completion(old.as_ref(), task);
+    let task = config.hooks.run(hook, old.as_ref(), task)?;
+    save(config, store, old.as_ref(), &task)?;
+
+    if old.as_ref() != Some(&task) {
+        webhooks::fire(&config.webhooks, &TaskEvent::new(event, task));
+    }
+
+    Ok(old.is_some())
+}
+
+/// [`update`] every task in `tasks`, saving them together with [`TaskStore::save_all`]
+///
+/// Nothing is saved if one of them can't be, e.g. because it's read-only.
+pub fn update_all(
+    config: &Config,
+    store: &impl TaskStore,
+    tasks: Vec<Task>,
+) -> color_eyre::Result<()> {
+    let mut changes: Vec<(Option<Task>, Task)> = Vec::with_capacity(tasks.len());
+    let mut events = Vec::with_capacity(tasks.len());
+    for task in tasks {
+        writable(config, &task.name)?;
+        if changes.iter().any(|(_, other)| other.name == task.name) {
+            bail!("{} is given more than once", task.name.display());
+        }
+        let old = store
+            .load(&task.name)
+            .wrap_err_with(|| format!("load task {}", task.name.display()))?;
+        let (hook, event) = match &old {
+            None => (Hook::Add, EventKind::Added),
+            Some(old) if !old.completed && task.completed => (Hook::Complete, EventKind::Completed),
+            Some(_) => (Hook::Modify, EventKind::Modified),
+        };
+        let task = stamp_completion(old.as_ref(), task);
+        let task = config.hooks.run(hook, old.as_ref(), task)?;
+        task.validate()?;
+        changes.push((old, task));
+        events.push(event);
+    }
+    limits::check_changes(config, store, &changes)?;
+    let tasks: Vec<_> = changes.iter().map(|(_, task)| task.clone()).collect();
+    store.save_all(&tasks).wrap_err("save tasks")?;
+
+    for ((old, task), event) in changes.into_iter().zip(events) {
+        audit::record(config, old.as_ref(), Some(&task));
+        journal::record(config, old.as_ref(), Some(&task));
+        if old.as_ref() != Some(&task) {
+            webhooks::fire(&config.webhooks, &TaskEvent::new(event, task));
+        }
+    }
+    Ok(())
+}
+
+/// Rename the task called `from` to `to`, moving its subtasks along and pointing the tasks
+/// depending on any of them to their new names, returns how many tasks were renamed
+pub fn rename(
+    config: &Config,
+    store: &impl TaskStore,
+    from: &Path,
+    to: &Path,
+) -> color_eyre::Result<usize> {
+    writable(config, from)?;
+    writable(config, to)?;
+    if to.starts_with(from) {
+        bail!(
+            "{} can't be moved under itself to {}",
+            from.display(),
+            to.display()
+        );
+    }
+    let renamed = |name: &Path| -> Option<PathBuf> {
+        let rest = name.strip_prefix(from).ok()?;
+        Some(match rest.as_os_str().is_empty() {
+            true => to.to_path_buf(),
+            false => to.join(rest),
+        })
+    };
+
+    let tasks = store
+        .load_under(from)
+        .wrap_err_with(|| format!("load task {}", from.display()))?;
+    if !tasks.iter().any(|task| task.name == from) {
+        bail!("no task named {}", from.display());
+    }
+    for task in &tasks {
+        let name = renamed(&task.name).expect("loaded under the renamed task");
+        writable(config, &task.name)?;
+        writable(config, &name)?;
+        let exists = store
+            .load(&name)
+            .wrap_err_with(|| format!("load task {}", name.display()))?
+            .is_some();
+        if exists {
+            bail!("there already is a task named {}", name.display());
+        }
+    }
+    // Check the tasks depending on the renamed ones too, so nothing is saved if one can't change
+    for task in store.load_all().wrap_err("load tasks")? {
+        if task.depends_on.iter().any(|name| renamed(name).is_some()) {
+            writable(config, &task.name)?;
+        }
+    }
+
+    // Subtasks go before their parents, so the directories left empty behind them get dropped
+    let mut tasks = tasks;
+    tasks.sort_by_key(|task| std::cmp::Reverse(task.name.components().count()));
+    for old in &tasks {
+        let task = Task {
+            name: renamed(&old.name).expect("loaded under the renamed task"),
+            ..old.clone()
+        };
+        let task = config.hooks.run(Hook::Modify, Some(old), task)?;
+        save(config, store, Some(old), &task)?;
+        webhooks::fire(&config.webhooks, &TaskEvent::new(EventKind::Modified, task));
+    }
+
+    let dependents = store.load_all().wrap_err("load tasks")?;
+    for mut task in dependents {
+        let mut changed = false;
+        for dependency in &mut task.depends_on {
+            if let Some(name) = renamed(dependency) {
+                *dependency = name;
+                changed = true;
+            }
+        }
+        if changed {
+            update(config, store, task)?;
+        }
+    }
+
+    Ok(tasks.len())
+}
+
+/// Delete the task called `name`, returns the deleted task
+pub fn remove(config: &Config, store: &impl TaskStore, name: &Path) -> color_eyre::Result<Task> {
+    writable(config, name)?;
+    let task = load(store, name)?;
+    let task = config.hooks.run(Hook::Delete, None, task)?;
+
+    store
+        .remove(name)
+        .wrap_err_with(|| format!("remove task {}", name.display()))?;
+    audit::record(config, Some(&task), None);
+    journal::record(config, Some(&task), None);
+    webhooks::fire(
+        &config.webhooks,
+        &TaskEvent::new(EventKind::Deleted, task.clone()),
+    );
+
+    Ok(task)
+}
+
+/// Move `task` to the archive of `store` (see [`DirStore::archive_task`]), returns whether it was
+/// there to move
+///
+/// Runs the delete hooks, since the task leaves the task list, and journals the move so `undo`
+/// brings it back.
+pub fn archive(config: &Config, store: &DirStore, task: &Task) -> color_eyre::Result<bool> {
+    writable(config, &task.name)?;
+    let task = config.hooks.run(Hook::Delete, None, task.clone())?;
+
+    let archived = store
+        .archive_task(&task.name)
+        .wrap_err_with(|| format!("archive {}", task.name.display()))?;
+    if archived {
+        audit::record_archived(config, &task);
+        journal::record_archived(config, &task);
+    }
+    Ok(archived)
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use dooit_tasks::{DirStore, Task, TaskStore};
+    use pretty_assertions::assert_eq;
+
+    use super::{rename, update_all};
+    use crate::{
+        config::{AuditConfig, Config, JournalConfig},
+        failure::Failure,
+    };
+
+    /// A store that fails the test if tasks are saved one by one
+    struct Batched<'a>(&'a DirStore);
+
+    impl TaskStore for Batched<'_> {
+        fn load_all(&self) -> dooit_tasks::Result<Vec<Task>> {
+            self.0.load_all()
+        }
+
+        fn load(&self, name: &Path) -> dooit_tasks::Result<Option<Task>> {
+            self.0.load(name)
+        }
+
+        fn save(&self, task: &Task) -> dooit_tasks::Result<()> {
+            panic!("{} was saved on its own", task.name.display())
+        }
+
+        fn save_all(&self, tasks: &[Task]) -> dooit_tasks::Result<()> {
+            self.0.save_all(tasks)
+        }
+
+        fn remove(&self, name: &Path) -> dooit_tasks::Result<bool> {
+            self.0.remove(name)
+        }
+    }
+
+    #[test]
+    fn test_rename() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = DirStore::new(dir.path());
+        let config = Config {
+            audit: AuditConfig { enabled: false },
+            journal: JournalConfig { enabled: false },
+            ..Config::default()
+        };
+        let tasks = [
+            Task::new("plan").with_dependencies(["team/notes"]),
+            Task::new("team"),
+            Task::new("team/notes"),
+        ];
+        store.save_all(&tasks).unwrap();
+        let load_all = || {
+            let mut tasks = store.load_all().unwrap();
+            tasks.sort_by(|a, b| a.name.cmp(&b.name));
+            tasks
+        };
+
+        // Nothing is moved if a subtask or a task depending on one can't change
+        for read_only in ["team/notes", "plan"] {
+            let config = Config {
+                read_only: vec![read_only.into()],
+                ..config.clone()
+            };
+            let err = rename(&config, &store, Path::new("team"), Path::new("crew")).unwrap_err();
+            assert_eq!(Failure::of(&err), Some(Failure::ReadOnly));
+            assert_eq!(load_all(), tasks);
+        }
+
+        assert_eq!(
+            rename(&config, &store, Path::new("team"), Path::new("crew")).unwrap(),
+            2
+        );
+        assert_eq!(
+            load_all(),
+            [
+                Task::new("crew"),
+                Task::new("crew/notes"),
+                Task::new("plan").with_dependencies(["crew/notes"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_update_all() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir_store = DirStore::new(dir.path());
+        let store = Batched(&dir_store);
+        let config = Config {
+            audit: AuditConfig { enabled: false },
+            journal: JournalConfig { enabled: false },
+            ..Config::default()
+        };
+        let report = Task::new("report");
+        dir_store.save(&report).unwrap();
+
+        let changed = [report.complete(), Task::new("team/notes")];
+        let read_only = Config {
+            read_only: vec!["team".into()],
+            ..config.clone()
+        };
+        let err = update_all(&read_only, &store, changed.to_vec()).unwrap_err();
+        assert_eq!(Failure::of(&err), Some(Failure::ReadOnly));
+        assert_eq!(dir_store.load_all().unwrap(), [Task::new("report")]);
+
+        update_all(&config, &store, changed.to_vec()).unwrap();
+        let mut tasks = dir_store.load_all().unwrap();
+        tasks.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(tasks.len(), 2);
+        assert!(tasks[0].completed && tasks[0].completed_at.is_some());
+        assert_eq!(tasks[1], Task::new("team/notes"));
+        assert_eq!(dir_store.verify().unwrap(), []);
+    }
+}
@@ -0,0 +1,195 @@
+//! Moving completed tasks out of the way, into the store's [archive](DirStore::archive) or for good
+//!
+//! `archive` moves every completed task (or those completed more than `--older-than` ago), the
+//! daemon moves the ones completed more than `archive.after` ago. Archived tasks keep their names
+//! in `.archive` at the root of the data dir, which loading the tasks skips, and `list --archived`
+//! shows them. `purge` deletes the ones completed more than `--completed-before` ago instead.
+//! Either way tasks with subtasks that stay are kept, so no subtask is left without its parent, and
+//! read-only tasks are left alone. Both are journaled, so `undo` brings the tasks back.
+
+use std::{
+    cmp::Reverse,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Duration, Utc};
+use color_eyre::eyre::WrapErr;
+use dooit_tasks::{DirStore, Task, TaskStore};
+
+use crate::{actions, config::Config, i18n::tr, prompt};
+
+/// Whether `task` gets archived or purged: it's completed (more than `older_than` before `now` if given,
+/// which leaves out tasks completed before completion times were recorded)
+fn expired(config: &Config, task: &Task, older_than: Option<Duration>, now: DateTime<Utc>) -> bool {
+    let old_enough = match older_than {
+        Some(older_than) => task.completed_at.is_some_and(|at| now - at >= older_than),
+        None => true,
+    };
+    task.completed && old_enough && config.read_only_source(&task.name).is_none()
+}
+
+/// Move the `tasks` that get [expired] (see [`expiring`]) to the archive, returns their names
+pub fn completed(
+    config: &Config,
+    store: &DirStore,
+    tasks: &[Task],
+    older_than: Option<Duration>,
+    now: DateTime<Utc>,
+    dry_run: bool,
+) -> color_eyre::Result<Vec<PathBuf>> {
+    let mut moved = Vec::new();
+    for task in expiring(config, tasks, older_than, now) {
+        if dry_run {
+            println!("{}", tr!("would-archive", name = task.name.display()));
+            moved.push(task.name.clone());
+            continue;
+        }
+        if actions::archive(config, store, &task)? {
+            moved.push(task.name.clone());
+        }
+    }
+    Ok(moved)
+}
+
+/// Archive the completed tasks (completed more than `older_than` ago if given)
+pub fn run(
+    config: &Config,
+    store: &DirStore,
+    older_than: Option<Duration>,
+    dry_run: bool,
+) -> color_eyre::Result<()> {
+    let tasks = store.load_all().wrap_err("load tasks")?;
+    let moved = completed(config, store, &tasks, older_than, Utc::now(), dry_run)?;
+    if moved.is_empty() {
+        println!("{}", tr!("no-tasks-archived"));
+    }
+    if !dry_run {
+        for name in moved {
+            println!("{}", tr!("task-archived", name = name.display()));
+        }
+    }
+    Ok(())
+}
+
+/// The `tasks` that get [expired] without subtasks that stay, subtasks first
+fn expiring(
+    config: &Config,
+    tasks: &[Task],
+    older_than: Option<Duration>,
+    now: DateTime<Utc>,
+) -> Vec<Task> {
+    let (mut candidates, staying): (Vec<_>, Vec<_>) = tasks
+        .iter()
+        .partition(|task| expired(config, task, older_than, now));
+    let mut staying: Vec<&Path> = staying.iter().map(|task| task.name.as_path()).collect();
+    candidates.sort_by_key(|task| Reverse(task.name.components().count()));
+
+    let mut expiring = Vec::new();
+    for task in candidates {
+        if staying.iter().any(|name| name.starts_with(&task.name)) {
+            staying.push(&task.name);
+        } else {
+            expiring.push(task.clone());
+        }
+    }
+    expiring
+}
+
+/// Delete the tasks completed more than `older_than` ago for good, asking first unless `force`
+pub fn purge(
+    config: &Config,
+    store: &impl TaskStore,
+    older_than: Duration,
+    force: bool,
+    dry_run: bool,
+) -> color_eyre::Result<()> {
+    let tasks = store
+        .load_summaries_under(Path::new(""))
+        .wrap_err("load tasks")?;
+    let purged = expiring(config, &tasks, Some(older_than), Utc::now());
+    if purged.is_empty() {
+        println!("{}", tr!("no-tasks-purged"));
+        return Ok(());
+    }
+
+    let summary = tr!("tasks-to-delete", count = purged.len());
+    if !prompt::confirm(&summary, force || dry_run)? {
+        return Ok(());
+    }
+    for task in purged {
+        actions::remove(config, store, &task.name)?;
+        if !dry_run {
+            println!("{}", tr!("task-deleted", name = task.name.display()));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{Duration, Utc};
+    use dooit_tasks::Task;
+
+    use super::{expired, expiring};
+    use crate::config::Config;
+
+    #[test]
+    fn test_expired() {
+        let now = Utc::now();
+        let config = Config {
+            read_only: vec!["team".into()],
+            ..Config::default()
+        };
+        let completed = |name: &str, days_ago: Option<i64>| Task {
+            completed_at: days_ago.map(|days| now - Duration::days(days)),
+            ..Task::new(name).complete()
+        };
+        let week = Some(Duration::weeks(1));
+
+        assert!(expired(&config, &completed("old", Some(30)), None, now));
+        assert!(expired(&config, &completed("old", Some(30)), week, now));
+        assert!(!expired(&config, &completed("recent", Some(2)), week, now));
+        assert!(expired(&config, &completed("untimed", None), None, now));
+        assert!(!expired(&config, &completed("untimed", None), week, now));
+        assert!(!expired(&config, &Task::new("pending"), None, now));
+        assert!(!expired(
+            &config,
+            &completed("team/done", Some(30)),
+            None,
+            now
+        ));
+    }
+
+    #[test]
+    fn test_expiring() {
+        let now = Utc::now();
+        let config = Config::default();
+        let old = |name: &str| Task {
+            completed_at: Some(now - Duration::days(60)),
+            ..Task::new(name).complete()
+        };
+        let tasks = [
+            old("project"),
+            Task::new("project/step"),
+            old("release"),
+            old("release/notes"),
+            old("taxes"),
+            Task {
+                completed_at: Some(now - Duration::days(2)),
+                ..Task::new("recent").complete()
+            },
+        ];
+
+        let names = |older_than| -> Vec<_> {
+            expiring(&config, &tasks, older_than, now)
+                .into_iter()
+                .map(|task| task.name.display().to_string())
+                .collect()
+        };
+        assert_eq!(
+            names(Some(Duration::days(30))),
+            ["release/notes", "release", "taxes"]
+        );
+        assert_eq!(names(None), ["release/notes", "release", "taxes", "recent"]);
+    }
+}
@@ -0,0 +1,194 @@
+//! Files attached to tasks
+//!
+//! `attach` copies the files into the task's [attachments
+//! directory](DirStore::attachments_dir) and adds their names to the task, `show` lists them and
+//! `open --attachment <FILE>` opens one with the desktop's default application.
+
+use std::{
+    ffi::{OsStr, OsString},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use color_eyre::eyre::{bail, eyre, WrapErr};
+use dooit_tasks::{DirStore, Task, TaskStore};
+
+use crate::{actions, color, config::Config, dry_run::DryRun, habits, i18n::tr, lookup};
+
+fn load(store: &impl TaskStore, name: &Path) -> color_eyre::Result<Task> {
+    let name = lookup::resolve(store, name)?;
+    store
+        .load(&name)
+        .wrap_err_with(|| format!("load task {}", name.display()))?
+        .ok_or_else(|| eyre!("no task named {}", name.display()))
+}
+
+/// Copy `files` into the attachments of the task called `name`, replacing ones with the same name
+pub fn attach(
+    config: &Config,
+    dir_store: &DirStore,
+    name: &Path,
+    files: &[PathBuf],
+    dry_run: bool,
+) -> color_eyre::Result<()> {
+    let store = DryRun::new(dir_store, dry_run);
+    let mut task = load(&store, name)?;
+    actions::writable(config, &task.name)?;
+    let dir = dir_store.attachments_dir(&task.name)?;
+
+    for file in files {
+        let Some(file_name) = file.file_name().and_then(OsStr::to_str) else {
+            bail!(
+                "{} has no file name or it isn't valid UTF-8",
+                file.display()
+            );
+        };
+        let target = dir.join(file_name);
+        if dry_run {
+            let message = match target.exists() {
+                true => tr!("would-modify", path = target.display()),
+                false => tr!("would-create", path = target.display()),
+            };
+            println!("{message}");
+        } else {
+            std::fs::create_dir_all(&dir).wrap_err_with(|| format!("create {}", dir.display()))?;
+            std::fs::copy(file, &target)
+                .wrap_err_with(|| format!("copy {} to {}", file.display(), target.display()))?;
+        }
+        if !task
+            .attachments
+            .iter()
+            .any(|attached| attached == file_name)
+        {
+            task.attachments.push(file_name.to_string());
+        }
+    }
+
+    let name = task.name.clone();
+    actions::update(config, &store, task)?;
+    if !dry_run {
+        for file in files {
+            let file = file.file_name().unwrap_or_default().to_string_lossy();
+            println!("{}", tr!("attached", file = file, name = name.display()));
+        }
+    }
+    Ok(())
+}
+
+/// Print the task called `name` with its links and attachments, and its last days if it's a
+/// habit
+pub fn show(config: &Config, store: &DirStore, name: &Path) -> color_eyre::Result<()> {
+    let task = load(store, name)?;
+    let now = chrono::Utc::now();
+    println!("{}", color::task(&task, config, now));
+    if task.habit {
+        habits::print(config, &task, habits::DAYS, now);
+    }
+
+    if !task.links.is_empty() {
+        println!("{}", tr!("show-links"));
+        for link in &task.links {
+            println!("    {link}");
+        }
+    }
+    if !task.attachments.is_empty() {
+        println!("{}", tr!("show-attachments"));
+        let dir = store.attachments_dir(&task.name)?;
+        for attachment in &task.attachments {
+            println!("    {}", dir.join(attachment).display());
+        }
+    }
+    Ok(())
+}
+
+/// Open `target` with the desktop's default application for it
+fn open_with_default(target: &OsStr) -> color_eyre::Result<()> {
+    #[cfg(target_os = "macos")]
+    let mut command = Command::new("open");
+    #[cfg(windows)]
+    let mut command = {
+        let mut command = Command::new("cmd");
+        // The empty argument is the title of the window `start` opens
+        command.args(["/C", "start", ""]);
+        command
+    };
+    #[cfg(not(any(target_os = "macos", windows)))]
+    let mut command = Command::new("xdg-open");
+
+    let status = command
+        .arg(target)
+        .status()
+        .wrap_err_with(|| format!("open {}", target.to_string_lossy()))?;
+    if !status.success() {
+        bail!("opening {} failed: {status}", target.to_string_lossy());
+    }
+    Ok(())
+}
+
+/// Open the links of the task called `name`, or its `attachment` if given
+pub fn open(store: &DirStore, name: &Path, attachment: Option<&str>) -> color_eyre::Result<()> {
+    let task = load(store, name)?;
+    let targets: Vec<OsString> = match attachment {
+        Some(attachment) if task.attachments.iter().any(|file| file == attachment) => {
+            let path = store.attachments_dir(&task.name)?.join(attachment);
+            vec![path.into()]
+        }
+        Some(_) if task.attachments.is_empty() => {
+            bail!("{} has no attachments", task.name.display())
+        }
+        Some(attachment) => bail!(
+            "{} has no attachment {attachment}, it has {}",
+            task.name.display(),
+            task.attachments.join(", ")
+        ),
+        None if task.links.is_empty() => bail!(
+            "{} has no links, pass --attachment to open one of its attachments",
+            task.name.display()
+        ),
+        None => task.links.iter().map(OsString::from).collect(),
+    };
+
+    for target in targets {
+        open_with_default(&target)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use dooit_tasks::{DirStore, Task, TaskStore};
+    use pretty_assertions::assert_eq;
+
+    use super::attach;
+    use crate::config::Config;
+
+    #[test]
+    fn test_attach() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let store = DirStore::new(root.join("tasks"));
+        store.save(&Task::new("report")).unwrap();
+        let file = root.join("spec.pdf");
+        std::fs::write(&file, "spec").unwrap();
+        let mut config = Config::default();
+        config.audit.enabled = false;
+
+        attach(
+            &config,
+            &store,
+            "rep".as_ref(),
+            &[file.clone(), file],
+            false,
+        )
+        .unwrap();
+        let task = store.load("report".as_ref()).unwrap().unwrap();
+        assert_eq!(task.attachments, ["spec.pdf"]);
+        let attached = store.attachments_dir(&task.name).unwrap().join("spec.pdf");
+        assert_eq!(std::fs::read_to_string(&attached).unwrap(), "spec");
+        // The attachments aren't taken for subtasks
+        assert_eq!(store.load_all().unwrap(), [task]);
+
+        store.remove("report".as_ref()).unwrap();
+        assert!(!attached.exists());
+    }
+}
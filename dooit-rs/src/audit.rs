@@ -0,0 +1,204 @@
+//! Log of every change made to tasks, shown by `dooit-rs history <task>`
+//!
+//! Each change is appended as a line of JSON to `<state dir>/audit.jsonl`, with who made it, when,
+//! and the fields that changed. Lines are never rewritten, so the log also keeps the history of
+//! deleted tasks. It only sees changes made through dooit-rs on this machine, not the ones synced
+//! from elsewhere.
+
+use std::{
+    fs::OpenOptions,
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Local, Utc};
+use color_eyre::eyre::{eyre, WrapErr};
+use dooit_tasks::{dirs, Task};
+use serde::{Deserialize, Serialize};
+
+use crate::{color, config::Config, i18n::tr};
+
+fn log_path() -> color_eyre::Result<PathBuf> {
+    dirs::get_state_dir()
+        .map(|dir| dir.join("audit.jsonl"))
+        .ok_or_else(|| eyre!("state dir not available"))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    Added,
+    Modified,
+    Deleted,
+    /// Moved to the archive, see [`crate::archive`]
+    Archived,
+}
+
+/// A change to a task, one line of the log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    pub at: DateTime<Utc>,
+    /// The user that made the change
+    pub who: String,
+    pub task: PathBuf,
+    pub action: Action,
+    /// The fields that changed, like `due: none -> 2024-05-01 09:00:00 UTC`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub changes: Vec<String>,
+}
+
+impl Entry {
+    /// The change from `old` to `new`, `None` for either means the task didn't exist (then)
+    ///
+    /// Returns `None` if nothing changed. Added tasks list the fields that aren't the default.
+    fn new(old: Option<&Task>, new: Option<&Task>) -> Option<Self> {
+        let (task, action, changes) = match (old, new) {
+            (None, Some(new)) => (new, Action::Added, Task::new(&new.name).diff(new)),
+            (Some(old), Some(new)) => (new, Action::Modified, old.diff(new)),
+            (Some(old), None) => (old, Action::Deleted, Vec::new()),
+            (None, None) => return None,
+        };
+        if action == Action::Modified && changes.is_empty() {
+            return None;
+        }
+        let who = std::env::var("USER")
+            .or_else(|_| std::env::var("USERNAME"))
+            .unwrap_or_else(|_| "unknown".to_string());
+        Some(Self {
+            at: Utc::now(),
+            who,
+            task: task.name.clone(),
+            action,
+            changes: changes.iter().map(ToString::to_string).collect(),
+        })
+    }
+}
+
+/// Append the change from `old` to `new` to the log, unless disabled in `config`
+///
+/// Failing to write the log doesn't undo the change, it's only logged.
+pub fn record(config: &Config, old: Option<&Task>, new: Option<&Task>) {
+    if !config.audit.enabled {
+        return;
+    }
+    if let Some(entry) = Entry::new(old, new) {
+        log(&entry);
+    }
+}
+
+/// Append the move of `task` to the archive to the log, unless disabled in `config`
+pub fn record_archived(config: &Config, task: &Task) {
+    if !config.audit.enabled {
+        return;
+    }
+    if let Some(entry) = Entry::new(Some(task), None) {
+        log(&Entry {
+            action: Action::Archived,
+            ..entry
+        });
+    }
+}
+
+fn log(entry: &Entry) {
+    if let Err(err) = append(entry) {
+        tracing::warn!(
+            "failed to record the change to {}: {err:?}",
+            entry.task.display()
+        );
+    }
+}
+
+fn append(entry: &Entry) -> color_eyre::Result<()> {
+    let path = log_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).wrap_err_with(|| format!("create {}", parent.display()))?;
+    }
+    let mut line = serde_json::to_string(entry).wrap_err("serialize the entry")?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .wrap_err_with(|| format!("write {}", path.display()))
+}
+
+/// The entries of the log about `name`, oldest first
+fn history(name: &Path) -> color_eyre::Result<Vec<Entry>> {
+    let path = log_path()?;
+    let file = match std::fs::File::open(&path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err).wrap_err_with(|| format!("read {}", path.display())),
+    };
+    let mut entries = Vec::new();
+    for (number, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.wrap_err_with(|| format!("read {}", path.display()))?;
+        // A line cut short by a crash shouldn't hide the rest of the history
+        match serde_json::from_str::<Entry>(&line) {
+            Ok(entry) if entry.task == name => entries.push(entry),
+            Ok(_) => {}
+            Err(err) => {
+                let line = number + 1;
+                tracing::warn!("skipping line {line} of {}: {err}", path.display());
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// Print every recorded change to the task called `name`
+pub fn run(config: &Config, name: &Path) -> color_eyre::Result<()> {
+    let entries = history(name)?;
+    if entries.is_empty() {
+        println!("{}", tr!("history-none", name = name.display()));
+        return Ok(());
+    }
+
+    for entry in entries {
+        let at = entry
+            .at
+            .with_timezone(&Local)
+            .format("%a %F %T")
+            .to_string();
+        let action = match entry.action {
+            Action::Added => tr!("history-added"),
+            Action::Modified => tr!("history-modified"),
+            Action::Deleted => tr!("history-deleted"),
+            Action::Archived => tr!("history-archived"),
+        };
+        let line = tr!("history-entry", at = at, who = entry.who, action = action);
+        println!("{}", color::header(&line, config));
+        for change in entry.changes {
+            println!("  {change}");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use dooit_tasks::{Task, Urgency};
+    use pretty_assertions::assert_eq;
+
+    use super::{Action, Entry};
+
+    #[test]
+    fn test_entry() {
+        let task = Task::new("report").with_tags(["work"]);
+        let added = Entry::new(None, Some(&task)).unwrap();
+        assert_eq!(added.action, Action::Added);
+        assert_eq!(added.changes, ["tags: +[work] -[]"]);
+
+        let urgent = task.clone().with_urgency(Urgency::High);
+        let modified = Entry::new(Some(&task), Some(&urgent)).unwrap();
+        assert_eq!(modified.changes, ["urgency: Low -> High"]);
+        assert_eq!(Entry::new(Some(&task), Some(&task)), None);
+
+        let deleted = Entry::new(Some(&urgent), None).unwrap();
+        assert_eq!(deleted.action, Action::Deleted);
+
+        let line = serde_json::to_string(&modified).unwrap();
+        assert_eq!(serde_json::from_str::<Entry>(&line).unwrap(), modified);
+    }
+}
@@ -0,0 +1,122 @@
+//! Create tasks from a snippet of text, e.g. the clipboard's contents
+//!
+//! The first line becomes the task's name and the rest its description, URLs anywhere in the text
+//! are added to the task's links.
+
+use std::{
+    io::Read,
+    process::{Command, Stdio},
+};
+
+use color_eyre::eyre::{bail, WrapErr};
+use dooit_tasks::Task;
+use unicode_width::UnicodeWidthChar;
+
+/// Longest name (in terminal columns) taken from the first line
+const MAX_NAME_LEN: usize = 80;
+
+/// Commands printing the clipboard's contents, tried in order
+const PASTE_COMMANDS: [(&str, &[&str]); 5] = [
+    ("wl-paste", &["--no-newline"]),
+    ("xclip", &["-selection", "clipboard", "-out"]),
+    ("xsel", &["--clipboard", "--output"]),
+    ("pbpaste", &[]),
+    ("powershell", &["-NoProfile", "-Command", "Get-Clipboard"]),
+];
+
+pub fn read_clipboard() -> color_eyre::Result<String> {
+    for (program, args) in PASTE_COMMANDS {
+        let output = match Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+        {
+            Ok(output) => output,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err).wrap_err_with(|| format!("run {program}")),
+        };
+
+        if output.status.success() {
+            return String::from_utf8(output.stdout).wrap_err("the clipboard doesn't contain text");
+        }
+    }
+
+    bail!("couldn't read the clipboard (install wl-clipboard, xclip or xsel)")
+}
+
+pub fn read_stdin() -> color_eyre::Result<String> {
+    let mut text = String::new();
+    std::io::stdin()
+        .read_to_string(&mut text)
+        .wrap_err("read stdin")?;
+    Ok(text)
+}
+
+fn is_url(word: &str) -> bool {
+    word.starts_with("https://") || word.starts_with("http://")
+}
+
+/// Build a task from `text`, returns `None` if there's nothing to make a task of
+pub fn parse(text: &str) -> Option<Task> {
+    let text = text.trim();
+    let (first, rest) = text.split_once('\n').unwrap_or((text, ""));
+
+    let links: Vec<_> = text
+        .split_whitespace()
+        .map(|word| word.trim_matches(|c: char| "<>()[]\"',.;".contains(c)))
+        .filter(|word| is_url(word))
+        .map(String::from)
+        .collect();
+
+    let name = first
+        .split_whitespace()
+        .filter(|word| !links.iter().any(|link| word.contains(link.as_str())))
+        .collect::<Vec<_>>()
+        .join(" ");
+    // A bare URL is named after its host
+    let name = if name.is_empty() {
+        links.first()?.split('/').nth(2)?.to_string()
+    } else {
+        name
+    };
+    let mut width = 0;
+    let name: String = name
+        .chars()
+        .take_while(|c| {
+            width += c.width().unwrap_or(0);
+            width <= MAX_NAME_LEN
+        })
+        .collect();
+
+    let task = Task::new(name.trim()).with_links(links);
+    Some(match rest.trim() {
+        "" => task,
+        description => task.with_description(description.to_string()),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use dooit_tasks::Task;
+    use pretty_assertions::assert_eq;
+
+    use super::parse;
+
+    #[test]
+    fn test_parse() {
+        let task = parse("Read this (https://example.com/post)\nlooks interesting\n").unwrap();
+        let expect = Task::new("Read this")
+            .with_links(["https://example.com/post"])
+            .with_description("looks interesting".to_string());
+        assert_eq!(task, expect);
+
+        let task = parse("https://example.com/a/b").unwrap();
+        assert_eq!(
+            task,
+            Task::new("example.com").with_links(["https://example.com/a/b"])
+        );
+
+        assert_eq!(parse("  \n"), None);
+    }
+}
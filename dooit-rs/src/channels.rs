@@ -0,0 +1,358 @@
+//! Where reminders are delivered: desktop notifications, a Telegram chat or a Matrix room
+//!
+//! The Telegram bot token and the Matrix access token are read from the keyring (see
+//! [`secrets`](crate::secrets)), store them with `dooit-rs auth set telegram` or `dooit-rs auth
+//! set matrix`.
+//!
+//! Desktop notifications can offer [`Action`]s where the platform supports them (Linux and the
+//! BSDs), they're only useful from long running processes like the daemon.
+
+use std::time::{Duration, SystemTime};
+
+use color_eyre::eyre::{eyre, WrapErr};
+use notify_rust::Notification;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{config::NotifyConfig, secrets};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Channel {
+    /// Notification on this machine's desktop
+    Desktop,
+    /// Message from a bot, see [`TelegramConfig`]
+    Telegram,
+    /// Message in a room, see [`MatrixConfig`]
+    Matrix,
+}
+
+impl Channel {
+    fn name(self) -> &'static str {
+        match self {
+            Channel::Desktop => "desktop",
+            Channel::Telegram => "telegram",
+            Channel::Matrix => "matrix",
+        }
+    }
+}
+
+/// How intrusive a notification should be
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    /// Delivered silently where the channel supports it
+    Low,
+    #[default]
+    Normal,
+    /// Stays on screen until dismissed where the channel supports it
+    Critical,
+}
+
+/// Button offered on desktop notifications
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Done,
+    Snooze,
+}
+
+impl Action {
+    const ALL: [Action; 2] = [Action::Done, Action::Snooze];
+
+    fn id(self) -> &'static str {
+        match self {
+            Action::Done => "done",
+            Action::Snooze => "snooze",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Action::Done => "Done",
+            Action::Snooze => "Snooze 1h",
+        }
+    }
+}
+
+/// Called from a background thread with the action chosen on a notification
+pub type ActionHandler = Box<dyn FnOnce(Action) + Send>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelegramConfig {
+    /// Chat the bot sends its messages to
+    pub chat_id: String,
+    /// Token of the bot, if it isn't in the keyring (see `dooit-rs auth set telegram`)
+    pub token: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatrixConfig {
+    /// URL of the homeserver, e.g. `https://matrix.org`
+    pub homeserver: String,
+    /// Room the messages are posted in, e.g. `!abcdef:matrix.org`
+    pub room_id: String,
+    /// Access token of the account, if it isn't in the keyring (see `dooit-rs auth set matrix`)
+    pub token: Option<String>,
+}
+
+/// The token of `channel` from the keyring, or `fallback` from the config
+fn token(channel: Channel, fallback: Option<&str>) -> color_eyre::Result<String> {
+    secrets::get_or(channel.name(), fallback)?.ok_or_else(|| {
+        eyre!(
+            "no {0} token stored, add it with `dooit-rs auth set {0}`",
+            channel.name()
+        )
+    })
+}
+
+fn desktop(
+    priority: Priority,
+    summary: &str,
+    body: &str,
+    on_action: Option<ActionHandler>,
+) -> color_eyre::Result<()> {
+    let mut notification = Notification::new();
+    notification
+        .appname(env!("CARGO_PKG_NAME"))
+        .summary(summary)
+        .body(body);
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        notification.urgency(match priority {
+            Priority::Low => notify_rust::Urgency::Low,
+            Priority::Normal => notify_rust::Urgency::Normal,
+            Priority::Critical => notify_rust::Urgency::Critical,
+        });
+
+        if let Some(on_action) = on_action {
+            for action in Action::ALL {
+                notification.action(action.id(), action.label());
+            }
+            let handle = notification.show().wrap_err("show desktop notification")?;
+            std::thread::spawn(move || {
+                handle.wait_for_action(|id| {
+                    if let Some(action) = Action::ALL.into_iter().find(|action| action.id() == id) {
+                        on_action(action);
+                    }
+                });
+            });
+            return Ok(());
+        }
+    }
+    #[cfg(not(all(unix, not(target_os = "macos"))))]
+    let _ = (priority, on_action);
+
+    notification
+        .show()
+        .map(|_| ())
+        .wrap_err("show desktop notification")
+}
+
+fn telegram(config: &TelegramConfig, priority: Priority, text: &str) -> color_eyre::Result<()> {
+    let url = format!(
+        "https://api.telegram.org/bot{}/sendMessage",
+        token(Channel::Telegram, config.token.as_deref())?
+    );
+
+    ureq::AgentBuilder::new()
+        .timeout(TIMEOUT)
+        .build()
+        .post(&url)
+        .send_json(json!({
+            "chat_id": config.chat_id,
+            "text": text,
+            "disable_notification": priority == Priority::Low,
+        }))
+        // Errors contain the URL, and with it the bot's token
+        .map_err(|err| match err {
+            ureq::Error::Status(status, _) => eyre!("telegram responded with status {status}"),
+            ureq::Error::Transport(err) => eyre!("couldn't reach telegram: {}", err.kind()),
+        })?;
+
+    Ok(())
+}
+
+fn matrix(config: &MatrixConfig, priority: Priority, text: &str) -> color_eyre::Result<()> {
+    // Matrix deduplicates messages by transaction ID
+    let transaction = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let url = format!(
+        "{}/_matrix/client/v3/rooms/{}/send/m.room.message/dooit-{transaction}",
+        config.homeserver.trim_end_matches('/'),
+        utf8_percent_encode(&config.room_id, NON_ALPHANUMERIC),
+    );
+
+    ureq::AgentBuilder::new()
+        .timeout(TIMEOUT)
+        .build()
+        .put(&url)
+        .set(
+            "Authorization",
+            &format!(
+                "Bearer {}",
+                token(Channel::Matrix, config.token.as_deref())?
+            ),
+        )
+        // Clients usually don't notify about notices
+        .send_json(json!({
+            "msgtype": if priority == Priority::Low { "m.notice" } else { "m.text" },
+            "body": text,
+        }))
+        .wrap_err_with(|| format!("post to matrix room {}", config.room_id))?;
+
+    Ok(())
+}
+
+/// Deliver a message through `channel`, offering actions if `on_action` is given
+pub fn send(
+    config: &NotifyConfig,
+    channel: Channel,
+    priority: Priority,
+    summary: &str,
+    body: &str,
+    on_action: Option<ActionHandler>,
+) -> color_eyre::Result<()> {
+    let text = match body {
+        "" => summary.to_string(),
+        body => format!("{summary}\n{body}"),
+    };
+    let missing = || {
+        eyre!(
+            "{0} is enabled but notify.{0} isn't configured",
+            channel.name()
+        )
+    };
+
+    match channel {
+        Channel::Desktop => desktop(priority, summary, body, on_action),
+        Channel::Telegram => telegram(
+            config.telegram.as_ref().ok_or_else(missing)?,
+            priority,
+            &text,
+        ),
+        Channel::Matrix => matrix(config.matrix.as_ref().ok_or_else(missing)?, priority, &text),
+    }
+}
+
+/// Deliver a message through every configured channel
+///
+/// Succeeds if at least one channel delivered it, failures of the others are only reported, so a
+/// flaky channel doesn't cause repeats on the others.
+pub fn broadcast(
+    config: &NotifyConfig,
+    priority: Priority,
+    summary: &str,
+    body: &str,
+    mut on_action: Option<ActionHandler>,
+) -> color_eyre::Result<()> {
+    let mut delivered = false;
+    let mut errors = Vec::new();
+    for &channel in &config.channels {
+        let on_action = match channel {
+            Channel::Desktop => on_action.take(),
+            _ => None,
+        };
+        match send(config, channel, priority, summary, body, on_action) {
+            Ok(()) => delivered = true,
+            Err(err) => errors.push(err),
+        }
+    }
+
+    let failed = (!delivered).then(|| errors.pop()).flatten();
+    for err in errors {
+        tracing::warn!("{err:#}");
+    }
+    failed.map_or(Ok(()), Err)
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use serde_json::{json, Value};
+    use tiny_http::{Response, Server};
+
+    use super::{broadcast, Channel, MatrixConfig, Priority};
+    use crate::config::NotifyConfig;
+
+    #[test]
+    fn test_broadcast() {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let homeserver = format!("http://{}/", server.server_addr().to_ip().unwrap());
+        let (sent, received) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            for mut request in server.incoming_requests() {
+                let authorization = request
+                    .headers()
+                    .iter()
+                    .find(|header| header.field.equiv("Authorization"))
+                    .map(|header| header.value.to_string());
+                let mut body = String::new();
+                request.as_reader().read_to_string(&mut body).unwrap();
+                let body: Value = serde_json::from_str(&body).unwrap();
+                let url = request.url().to_string();
+                // Rooms of other servers can't be posted to
+                let status = if url.contains("elsewhere") { 403 } else { 200 };
+                let response = Response::from_string("{}").with_status_code(status);
+                request.respond(response).unwrap();
+                sent.send((url, authorization, body)).unwrap();
+            }
+        });
+
+        // Only read by this test, keeps the keyring out of it
+        std::env::set_var("DOOIT_MATRIX_TOKEN", "secret");
+        let room = |room_id: &str| MatrixConfig {
+            homeserver: homeserver.clone(),
+            room_id: room_id.to_string(),
+            token: None,
+        };
+        let config = NotifyConfig {
+            channels: vec![Channel::Telegram, Channel::Matrix],
+            matrix: Some(room("!room:example.org")),
+            ..NotifyConfig::default()
+        };
+
+        // Telegram isn't configured, but the message still got through to the room, silently
+        broadcast(&config, Priority::Low, "Report", "due in an hour", None).unwrap();
+        let (url, authorization, body) = received.recv().unwrap();
+        let prefix = "/_matrix/client/v3/rooms/%21room%3Aexample%2Eorg/send/m.room.message/dooit-";
+        assert!(url.starts_with(prefix), "{url}");
+        assert_eq!(authorization.as_deref(), Some("Bearer secret"));
+        assert_eq!(
+            body,
+            json!({ "msgtype": "m.notice", "body": "Report\ndue in an hour" })
+        );
+
+        // Other priorities notify, and every message gets its own transaction
+        broadcast(&config, Priority::Critical, "Report", "", None).unwrap();
+        let (second, _, body) = received.recv().unwrap();
+        assert_eq!(body, json!({ "msgtype": "m.text", "body": "Report" }));
+        assert_ne!(second, url);
+
+        // It fails when no channel delivered the message
+        let telegram = NotifyConfig {
+            channels: vec![Channel::Telegram],
+            ..config.clone()
+        };
+        let err = broadcast(&telegram, Priority::Normal, "Report", "", None).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "telegram is enabled but notify.telegram isn't configured"
+        );
+        let refused = NotifyConfig {
+            matrix: Some(room("!elsewhere:example.org")),
+            ..config
+        };
+        let err = broadcast(&refused, Priority::Normal, "Report", "", None).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "post to matrix room !elsewhere:example.org"
+        );
+        received.recv().unwrap();
+    }
+}
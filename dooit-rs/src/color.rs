@@ -0,0 +1,121 @@
+//! Colored terminal output, see the `color` and `theme` options in the config
+
+use std::{fmt::Display, str::FromStr};
+
+use chrono::{DateTime, Duration, Utc};
+use color_eyre::eyre::eyre;
+use dooit_tasks::Task;
+use serde::{Deserialize, Serialize};
+
+use crate::{config::Config, theme::Style};
+
+/// A terminal color, e.g. `red` or `bright-blue`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    BrightBlack,
+    BrightRed,
+    BrightGreen,
+    BrightYellow,
+    BrightBlue,
+    BrightMagenta,
+    BrightCyan,
+    BrightWhite,
+}
+
+/// Every color with its name, SGR code for the foreground (the background's is 10 more) and the
+/// RGB value xterm uses for it
+const COLORS: [(Color, &str, u8, &str); 16] = [
+    (Color::Black, "black", 30, "#000000"),
+    (Color::Red, "red", 31, "#cd0000"),
+    (Color::Green, "green", 32, "#00cd00"),
+    (Color::Yellow, "yellow", 33, "#cdcd00"),
+    (Color::Blue, "blue", 34, "#0000ee"),
+    (Color::Magenta, "magenta", 35, "#cd00cd"),
+    (Color::Cyan, "cyan", 36, "#00cdcd"),
+    (Color::White, "white", 37, "#e5e5e5"),
+    (Color::BrightBlack, "bright-black", 90, "#7f7f7f"),
+    (Color::BrightRed, "bright-red", 91, "#ff0000"),
+    (Color::BrightGreen, "bright-green", 92, "#00ff00"),
+    (Color::BrightYellow, "bright-yellow", 93, "#ffff00"),
+    (Color::BrightBlue, "bright-blue", 94, "#5c5cff"),
+    (Color::BrightMagenta, "bright-magenta", 95, "#ff00ff"),
+    (Color::BrightCyan, "bright-cyan", 96, "#00ffff"),
+    (Color::BrightWhite, "bright-white", 97, "#ffffff"),
+];
+
+impl Color {
+    fn entry(self) -> (Color, &'static str, u8, &'static str) {
+        *COLORS
+            .iter()
+            .find(|(color, ..)| *color == self)
+            .expect("every color is in COLORS")
+    }
+
+    /// SGR code setting the text to this color
+    pub fn fg_code(self) -> u8 {
+        self.entry().2
+    }
+
+    /// The color as `#rrggbb`, for output that isn't shown in a terminal
+    pub fn hex(self) -> &'static str {
+        self.entry().3
+    }
+}
+
+impl Display for Color {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.entry().1)
+    }
+}
+
+impl FromStr for Color {
+    type Err = color_eyre::Report;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        COLORS
+            .iter()
+            .find(|(_, other, ..)| *other == name)
+            .map(|(color, ..)| *color)
+            .ok_or_else(|| eyre!("unknown color {name:?}"))
+    }
+}
+
+/// Style of `task` in the current theme: overdue, due soon, completed or the one for its urgency
+pub fn style(task: &Task, config: &Config, now: DateTime<Utc>) -> Style {
+    let theme = config.theme();
+    match task {
+        _ if task.completed => theme.completed,
+        _ if task.is_overdue(now) => theme.overdue,
+        _ if task.is_due_within(Duration::days(1), now) => theme.due_soon,
+        _ => theme
+            .urgency(&task.urgency)
+            .or_else(|| config.urgency(&task.urgency)?.color.map(Style::fg))
+            .unwrap_or_default(),
+    }
+}
+
+/// `task` as `list` shows it, see [`style`]
+pub fn task(task: &Task, config: &Config, now: DateTime<Utc>) -> String {
+    let line = task.display(config.icons).to_string();
+    if !config.color.enabled() {
+        return line;
+    }
+    style(task, config, now).paint(&line)
+}
+
+/// `text` in the theme's header style, if colors are enabled
+pub fn header(text: &str, config: &Config) -> String {
+    if !config.color.enabled() {
+        return text.to_string();
+    }
+    config.theme().header.paint(text)
+}
@@ -0,0 +1,313 @@
+//! Shell completion of subcommands, options, task names and tags
+//!
+//! `dooit-rs completions <SHELL>` prints a script that asks the hidden
+//! `dooit-rs __complete -- <WORDS>...` for candidates, `WORDS` being the words after `dooit-rs` up
+//! to the one under the cursor (empty if there's none yet). The candidates starting with that word
+//! are printed one per line.
+//!
+//! Task names come from the store's index, so completing them doesn't parse any task. Tags are read
+//! from the daemon if it's running, and from the tasks otherwise. Projects are the task names and
+//! the directories they're in.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ffi::OsString,
+    path::Path,
+};
+
+use clap::{Arg, Command, CommandFactory, ValueEnum};
+use color_eyre::eyre::WrapErr;
+use dooit_tasks::{Task, TaskStore};
+
+use crate::{config::Config, expand_aliases, failure, Cli};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+const BASH: &str = r#"_dooit_rs() {
+    local IFS=$'\n'
+    COMPREPLY=($(dooit-rs __complete -- "${COMP_WORDS[@]:1:COMP_CWORD}" 2>/dev/null))
+}
+complete -o default -F _dooit_rs dooit-rs
+"#;
+
+const ZSH: &str = r#"#compdef dooit-rs
+_dooit_rs() {
+    local -a candidates
+    candidates=("${(@f)$(dooit-rs __complete -- "${(@)words[2,CURRENT]}" 2>/dev/null)}")
+    compadd -a candidates
+}
+compdef _dooit_rs dooit-rs
+"#;
+
+const FISH: &str = r#"function __dooit_rs_complete
+    set -l words (commandline -opc)
+    set -e words[1]
+    dooit-rs __complete -- $words (commandline -ct) 2>/dev/null
+end
+complete -c dooit-rs -f -a '(__dooit_rs_complete)'
+"#;
+
+/// Print the completion script for `shell`
+pub fn script(shell: Shell) {
+    print!(
+        "{}",
+        match shell {
+            Shell::Bash => BASH,
+            Shell::Zsh => ZSH,
+            Shell::Fish => FISH,
+        }
+    );
+}
+
+/// What the word under the cursor can be
+#[derive(Debug, PartialEq, Eq)]
+enum Completion {
+    /// One of these
+    Values(Vec<String>),
+    Tasks,
+    Projects,
+    Tags,
+    Reports,
+    Contexts,
+}
+
+/// The argument of `command` the option `word` (e.g. `--sort` or `-s`) stands for
+fn option<'a>(command: &'a Command, word: &str) -> Option<&'a Arg> {
+    if let Some(long) = word.strip_prefix("--") {
+        return command
+            .get_arguments()
+            .find(|arg| arg.get_long() == Some(long));
+    }
+    let mut shorts = word.strip_prefix('-')?.chars();
+    let short = shorts.next()?;
+    if shorts.next().is_some() {
+        return None;
+    }
+    command
+        .get_arguments()
+        .find(|arg| arg.get_short() == Some(short))
+}
+
+/// The values `arg` of `command` takes
+fn values(command: &Command, arg: &Arg) -> Completion {
+    match (command.get_name(), arg.get_id().as_str()) {
+        ("report", "name") => Completion::Reports,
+        ("set", "name") => Completion::Contexts,
+        ("set" | "remove", "service") => {
+            Completion::Values(["telegram", "matrix", "smtp"].map(String::from).to_vec())
+        }
+        (_, "name" | "depends_on") => Completion::Tasks,
+        (_, "project" | "under") => Completion::Projects,
+        (_, "tags") => Completion::Tags,
+        _ => Completion::Values(
+            arg.get_possible_values()
+                .iter()
+                .filter(|value| !value.is_hide_set())
+                .map(|value| value.get_name().to_string())
+                .collect(),
+        ),
+    }
+}
+
+/// What the last of `words` can be, see the [module docs](self)
+fn complete(
+    mut root: Command,
+    aliases: &BTreeMap<String, String>,
+    words: &[String],
+) -> Vec<Completion> {
+    root.build();
+    let Some((current, before)) = words.split_last() else {
+        return Vec::new();
+    };
+
+    let mut command = &root;
+    let mut positional = 0;
+    let mut words = before.iter();
+    while let Some(word) = words.next() {
+        if word.starts_with('-') && word.len() > 1 {
+            let takes_value = option(command, word)
+                .filter(|arg| !word.contains('=') && arg.get_action().takes_values());
+            // Skip its value, unless that's the word under the cursor
+            if let Some(arg) = takes_value {
+                if words.next().is_none() {
+                    return vec![values(command, arg)];
+                }
+            }
+            continue;
+        }
+        match command.find_subcommand(word) {
+            Some(subcommand) => {
+                command = subcommand;
+                positional = 0;
+            }
+            None => positional += 1,
+        }
+    }
+
+    if current.starts_with('-') {
+        let options = command
+            .get_arguments()
+            .filter(|arg| !arg.is_hide_set())
+            .filter_map(|arg| arg.get_long())
+            .map(|long| format!("--{long}"))
+            .collect();
+        return vec![Completion::Values(options)];
+    }
+    let positionals: Vec<_> = command.get_positionals().collect();
+    if command.has_subcommands() {
+        let mut subcommands: Vec<_> = command
+            .get_subcommands()
+            .filter(|subcommand| !subcommand.is_hide_set())
+            .flat_map(|subcommand| {
+                std::iter::once(subcommand.get_name()).chain(subcommand.get_visible_aliases())
+            })
+            .map(String::from)
+            .collect();
+        if std::ptr::eq(command, &root) {
+            subcommands.extend(aliases.keys().cloned());
+        }
+        // Like `report`, which takes the name of a report or a subcommand
+        let first = positionals.first().map(|arg| values(command, arg));
+        return std::iter::once(Completion::Values(subcommands))
+            .chain(first)
+            .collect();
+    }
+    // Extra words go to the last positional argument, if it takes several
+    positionals
+        .get(positional)
+        .or(positionals.last())
+        .map(|arg| values(command, arg))
+        .into_iter()
+        .collect()
+}
+
+/// Every task, from the daemon if it's running
+fn tasks(config: &Config) -> color_eyre::Result<Vec<Task>> {
+    #[cfg(unix)]
+    if let Ok(crate::ipc::Response::Tasks(tasks)) = crate::ipc::query(&crate::ipc::Request::Tasks) {
+        return Ok(tasks);
+    }
+    failure::unchecked_store(config)?
+        .load_summaries_under(Path::new(""))
+        .wrap_err("load tasks")
+}
+
+/// The names of the tasks, from the index if the store has one
+fn names(config: &Config) -> color_eyre::Result<Vec<String>> {
+    let store = failure::unchecked_store(config)?;
+    let index = store.index().wrap_err("read the index")?;
+    let mut names: Vec<_> = index
+        .paths()
+        .filter_map(|path| store.task_name(&store.root().join(path)))
+        .map(|name| name.to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    if !names.is_empty() {
+        return Ok(names);
+    }
+    Ok(tasks(config)?
+        .into_iter()
+        .map(|task| task.name.to_string_lossy().into_owned())
+        .collect())
+}
+
+/// Print the candidates for the last of `words`
+pub fn run(config: &Config, words: &[String]) -> color_eyre::Result<()> {
+    let Some((current, before)) = words.split_last() else {
+        return Ok(());
+    };
+    let args = std::iter::once("dooit-rs")
+        .chain(before.iter().map(String::as_str))
+        .map(OsString::from)
+        .collect();
+    let mut words: Vec<_> = expand_aliases(args, &config.aliases)
+        .into_iter()
+        .skip(1)
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect();
+    words.push(current.clone());
+
+    let mut candidates = Vec::new();
+    for completion in complete(Cli::command(), &config.aliases, &words) {
+        candidates.extend(match completion {
+            Completion::Values(values) => values,
+            Completion::Tasks => names(config)?,
+            Completion::Projects => {
+                let names = names(config)?;
+                let parents = names
+                    .iter()
+                    .flat_map(|name| Path::new(name).ancestors().skip(1))
+                    .filter(|parent| !parent.as_os_str().is_empty())
+                    .map(|parent| parent.to_string_lossy().into_owned());
+                let projects: BTreeSet<_> = parents.chain(names.iter().cloned()).collect();
+                projects.into_iter().collect()
+            }
+            Completion::Tags => {
+                let tags: BTreeSet<_> = tasks(config)?
+                    .into_iter()
+                    .flat_map(|task| task.tags)
+                    .collect();
+                // `tag add` takes tags written as `+tag` too
+                let plus = if current.starts_with('+') { "+" } else { "" };
+                tags.into_iter().map(|tag| format!("{plus}{tag}")).collect()
+            }
+            Completion::Reports => config.reports.keys().cloned().collect(),
+            Completion::Contexts => config.contexts.keys().cloned().collect(),
+        });
+    }
+    for candidate in candidates {
+        if candidate.starts_with(current.as_str()) {
+            println!("{candidate}");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use clap::CommandFactory;
+    use pretty_assertions::assert_eq;
+
+    use super::{complete, Completion};
+    use crate::Cli;
+
+    #[test]
+    fn test_complete() {
+        let aliases = BTreeMap::from([("today".to_string(), "list --overdue".to_string())]);
+        let complete = |words: &[&str]| {
+            let words: Vec<_> = words.iter().map(|word| word.to_string()).collect();
+            complete(Cli::command(), &aliases, &words)
+        };
+
+        let [Completion::Values(subcommands)] = &complete(&["-C", "locale=de", "li"])[..] else {
+            panic!("expected subcommands");
+        };
+        assert!(subcommands.iter().any(|name| name == "list"));
+        assert!(subcommands.iter().any(|name| name == "today"));
+        assert!(!subcommands.iter().any(|name| name == "__complete"));
+
+        let [Completion::Values(options)] = &complete(&["list", "--"])[..] else {
+            panic!("expected options");
+        };
+        assert!(options.iter().any(|option| option == "--under"));
+        assert!(options.iter().any(|option| option == "--dry-run"));
+
+        assert_eq!(complete(&["list", "--under", ""]), [Completion::Projects]);
+        assert_eq!(complete(&["history", ""]), [Completion::Tasks]);
+        assert_eq!(complete(&["tag", "add", ""]), [Completion::Tasks]);
+        assert_eq!(
+            complete(&["tag", "add", "report", "+work", ""]),
+            [Completion::Tags]
+        );
+        assert!(complete(&["report", ""]).contains(&Completion::Reports));
+        assert_eq!(complete(&["context", "set", ""]), [Completion::Contexts]);
+        assert_eq!(complete(&["add", "--tag", "w"]), [Completion::Tags]);
+    }
+}
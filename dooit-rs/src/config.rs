@@ -0,0 +1,607 @@
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    io::IsTerminal,
+    path::{Path, PathBuf},
+    sync::Once,
+};
+
+use color_eyre::eyre::{bail, eyre, WrapErr};
+use dooit_tasks::{
+    dirs, EventKind, HumanDuration, IconSet, ScoreConfig, SortMode, Task, Urgency, UrgencyLevel,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    channels::{Channel, MatrixConfig, Priority, TelegramConfig},
+    color::Color,
+    hooks::Hooks,
+    i18n::tr,
+    report::Report,
+    theme::Theme,
+};
+
+pub mod layers;
+mod sample;
+pub mod trust;
+
+use layers::Layers;
+pub use sample::{check, sample};
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Editor used when `--editor` isn't given, takes precedence over `$VISUAL` and `$EDITOR`
+    pub editor: Option<String>,
+    /// Where tasks are kept, instead of the platform's data directory
+    pub data_dir: Option<PathBuf>,
+    /// Tasks that can't be changed, and their subtasks, e.g. a folder shared with a team
+    pub read_only: Vec<PathBuf>,
+    /// Your name in task directories shared by a team, matched against the tasks' `assignee` by
+    /// `list --mine` and `list.mine`
+    pub identity: Option<String>,
+    /// Append diagnostic logs to this file instead of printing them
+    pub log_file: Option<PathBuf>,
+    /// Language of messages, e.g. `de`, instead of the one from `$LANG`
+    pub locale: Option<String>,
+    /// Glyphs used for urgencies, `nerd` needs a Nerd Font and `ascii` works everywhere
+    pub icons: IconSet,
+    /// When to color the output of commands like `list`
+    pub color: ColorMode,
+    /// Colors and styles used, one of `themes` or the built-in `default` and `monochrome`
+    pub theme: Option<String>,
+    /// Themes to pick from with `theme`, see [`crate::theme`]
+    pub themes: BTreeMap<String, Theme>,
+    /// Urgency levels, lowest first, replacing `low`, `medium` and `high`
+    pub urgencies: Vec<UrgencyConfig>,
+    /// Defaults for `list`, its flags can only add to these
+    pub list: ListConfig,
+    /// Defaults for tasks created with `add` and `capture`, mostly useful in a project config
+    pub add: AddConfig,
+    /// Named views of the tasks shown by `report <name>`, see [`crate::report`]
+    pub reports: BTreeMap<String, Report>,
+    /// Filters picked with `context set <name>` to scope what's listed, see [`crate::context`]
+    pub contexts: BTreeMap<String, String>,
+    /// Endpoints notified when tasks change
+    pub webhooks: Vec<Webhook>,
+    /// Desktop reminders for upcoming tasks
+    pub notify: NotifyConfig,
+    /// Scripts run before tasks are changed
+    pub hooks: Hooks,
+    /// Summary of overdue, upcoming and recently completed tasks
+    pub digest: DigestConfig,
+    /// Moving old completed tasks out of the way
+    pub archive: ArchiveConfig,
+    /// Shorthands for subcommands and their arguments, e.g. `t = "list --overdue"`
+    pub aliases: BTreeMap<String, String>,
+    /// Coefficients of the urgency score used by the `score-*` sort modes
+    pub score: ScoreConfig,
+    /// Recording every change to tasks, shown by `history`
+    pub audit: AuditConfig,
+    /// Recording every change to tasks, taken back by `undo`
+    pub journal: JournalConfig,
+    /// How many tasks can be in progress at once, see [`crate::limits`]
+    pub limits: LimitsConfig,
+    /// Signing the task files with GnuPG, see [`crate::signing`]
+    pub signing: SigningConfig,
+    /// The untrusted project config and the options in it that were ignored, see [`trust`]
+    #[serde(skip)]
+    pub ignored: Option<(PathBuf, Vec<String>)>,
+}
+
+/// An urgency level and its color
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UrgencyConfig {
+    #[serde(flatten)]
+    pub level: UrgencyLevel,
+    /// Color of tasks with this urgency in `list`
+    pub color: Option<Color>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorMode {
+    /// Only when writing to a terminal and `NO_COLOR` isn't set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorMode {
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorMode::Auto => {
+                std::env::var_os("NO_COLOR").is_none() && std::io::stdout().is_terminal()
+            }
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ListConfig {
+    /// Sort mode used when `--sort` isn't given
+    pub default_sort: SortMode,
+    /// Always show completed tasks
+    pub completed: bool,
+    /// Always show overdue tasks
+    pub overdue: bool,
+    /// Only show tasks assigned to `identity`
+    pub mine: bool,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AddConfig {
+    /// Tags given to every new task
+    pub tags: Vec<String>,
+    /// Urgency of new tasks that don't pick one
+    pub urgency: Option<Urgency>,
+    /// Create new tasks under this one, `{project}` is replaced by the name of the directory
+    /// holding `.dooit/config.toml` (or the current directory if there is none)
+    pub parent: Option<String>,
+}
+
+impl AddConfig {
+    /// Fill in the defaults of `task`, keeping its urgency if `urgency_given`
+    pub fn apply(&self, task: &mut Task, urgency_given: bool) -> color_eyre::Result<()> {
+        for tag in &self.tags {
+            if !task.tags.contains(tag) {
+                task.tags.push(tag.clone());
+            }
+        }
+        if let Some(urgency) = self.urgency.as_ref().filter(|_| !urgency_given) {
+            task.urgency = urgency
+                .name()
+                .parse()
+                .wrap_err("invalid `add.urgency` in the config")?;
+        }
+        task.name = self.name(&task.name);
+        Ok(())
+    }
+
+    /// The name a task called `name` gets, under `parent` if it's set
+    pub fn name(&self, name: &Path) -> PathBuf {
+        match &self.parent {
+            Some(parent) => {
+                let parent = PathBuf::from(parent.replace("{project}", &project_name()));
+                match name.starts_with(&parent) {
+                    true => name.to_path_buf(),
+                    false => parent.join(name),
+                }
+            }
+            None => name.to_path_buf(),
+        }
+    }
+}
+
+/// Name of the directory holding the project config, or of the current one
+fn project_name() -> String {
+    let Ok(current) = std::env::current_dir() else {
+        return String::new();
+    };
+    let project = layers::project_path(&current)
+        .and_then(|path| Some(path.parent()?.parent()?.to_path_buf()))
+        .unwrap_or(current);
+    project
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotifyConfig {
+    /// Remind about tasks this long before they're due, one reminder per lead time
+    ///
+    /// Add `"0s"` to also get a reminder once the task is overdue.
+    pub lead_times: Vec<HumanDuration>,
+    /// Where reminders are delivered
+    pub channels: Vec<Channel>,
+    /// Bot chat used by the `telegram` channel
+    pub telegram: Option<TelegramConfig>,
+    /// Room used by the `matrix` channel
+    pub matrix: Option<MatrixConfig>,
+    /// How reminders are delivered depending on the task's urgency
+    pub escalation: EscalationConfig,
+}
+
+impl Default for NotifyConfig {
+    fn default() -> Self {
+        Self {
+            lead_times: vec![HumanDuration(chrono::Duration::hours(1))],
+            channels: vec![Channel::Desktop],
+            telegram: None,
+            matrix: None,
+            escalation: EscalationConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EscalationConfig {
+    pub low: Escalation,
+    pub medium: Escalation,
+    pub high: Escalation,
+}
+
+impl EscalationConfig {
+    /// Custom urgencies escalate like the closest built-in urgency below them
+    pub fn for_urgency(&self, urgency: &Urgency) -> &Escalation {
+        match urgency {
+            Urgency::Low => &self.low,
+            Urgency::Medium => &self.medium,
+            Urgency::High => &self.high,
+            Urgency::Custom(_) => self.for_urgency(&urgency.builtin()),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Escalation {
+    /// How intrusive the notifications are
+    pub priority: Priority,
+    /// Repeat the latest reminder this often until it's acknowledged with `dooit-rs notify ack`
+    pub repeat: Option<HumanDuration>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DigestConfig {
+    /// Have the daemon email the digest this often (e.g. `"1w"`)
+    pub every: Option<HumanDuration>,
+    /// Where to send the digest
+    pub email: Option<EmailConfig>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArchiveConfig {
+    /// Have the daemon archive tasks completed more than this long ago (e.g. `"30d"`)
+    pub after: Option<HumanDuration>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuditConfig {
+    /// Append every change to `<state dir>/audit.jsonl`
+    pub enabled: bool,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JournalConfig {
+    /// Append every change to `<data dir>/.journal.jsonl`, see [`crate::journal`]
+    pub enabled: bool,
+}
+
+impl Default for JournalConfig {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LimitsConfig {
+    /// Most tasks that can be started at once
+    pub started: Option<usize>,
+    /// Most open tasks of each urgency, e.g. `high = 5`
+    pub urgencies: BTreeMap<String, usize>,
+    /// Most open tasks with each tag, e.g. `work = 10`
+    pub tags: BTreeMap<String, usize>,
+    /// Refuse changes going over a limit instead of warning about them
+    pub strict: bool,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SigningConfig {
+    /// Key signing the task files, anything `gpg --local-user` accepts (signing is off if unset)
+    pub key: Option<String>,
+    /// GnuPG command, `gpg` if unset
+    pub program: Option<String>,
+}
+
+impl LimitsConfig {
+    /// Whether no limit is set
+    pub fn is_empty(&self) -> bool {
+        self.started.is_none() && self.urgencies.is_empty() && self.tags.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmailConfig {
+    /// Hostname of the SMTP server
+    pub server: String,
+    /// Port of the SMTP server (defaults to the standard port for `security`)
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub security: SmtpSecurity,
+    /// User to log in as, no authentication is attempted if missing
+    pub username: Option<String>,
+    /// Password of `username`, if it isn't in the keyring (see `dooit-rs auth set smtp`) or the
+    /// `DOOIT_SMTP_PASSWORD` environment variable
+    pub password: Option<String>,
+    /// Sender address, e.g. `"dooit <me@example.com>"`
+    pub from: String,
+    /// Recipient addresses
+    pub to: Vec<String>,
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    /// Connect over TLS
+    #[default]
+    Tls,
+    /// Upgrade a plain connection with STARTTLS
+    StartTls,
+    /// Plain text, only use this for servers on the local machine
+    None,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    /// URL receiving the JSON payload via a POST request
+    pub url: String,
+    /// Events this webhook is interested in (all of them if empty)
+    #[serde(default)]
+    pub events: Vec<EventKind>,
+}
+
+impl Webhook {
+    pub fn wants(&self, event: EventKind) -> bool {
+        self.events.is_empty() || self.events.contains(&event)
+    }
+}
+
+pub fn config_path() -> Option<PathBuf> {
+    dirs::get_config_dir().map(|dir| dir.join("config.toml"))
+}
+
+/// The project config that applies to the current directory, if any
+pub fn project_config_path() -> Option<PathBuf> {
+    std::env::current_dir()
+        .ok()
+        .and_then(|dir| layers::project_path(&dir))
+}
+
+/// Config files in the order they're applied, see [`layers`]
+pub fn config_files() -> Vec<PathBuf> {
+    [layers::system_path(), config_path(), project_config_path()]
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+impl Config {
+    /// Merge the config files, environment variables and `overrides` (`KEY=VALUE` pairs) on top of
+    /// the defaults, see [`layers`] for the order
+    ///
+    /// Options an untrusted project config isn't allowed to set are left out and listed in
+    /// `ignored`.
+    pub fn load(overrides: &[String]) -> color_eyre::Result<Self> {
+        let mut layers = [layers::system_path(), config_path()]
+            .iter()
+            .flatten()
+            .try_fold(Layers::default(), |layers, path| layers.file(path))?;
+
+        let mut ignored = None;
+        if let Some(path) = project_config_path() {
+            if trust::is_trusted(&path)? {
+                layers = layers.file(&path)?;
+            } else {
+                let options;
+                (layers, options) = layers.file_only(&path, trust::PROJECT_OPTIONS)?;
+                ignored = (!options.is_empty()).then_some((path, options));
+            }
+        }
+
+        let mut config: Self = layers
+            .env(layers::ENV_PREFIX)
+            .overrides(overrides)?
+            .build()?;
+        config.ignored = ignored;
+        Ok(config)
+    }
+
+    /// The theme picked with `theme`, the default one if there is no theme with that name
+    pub fn theme(&self) -> Cow<'_, Theme> {
+        let Some(name) = &self.theme else {
+            return Cow::Owned(Theme::default());
+        };
+        if let Some(theme) = self.themes.get(name) {
+            return Cow::Borrowed(theme);
+        }
+        Theme::builtin(name).map(Cow::Owned).unwrap_or_else(|| {
+            static WARNED: Once = Once::new();
+            WARNED.call_once(|| tracing::warn!("no theme named {name}, using the default one"));
+            Cow::Owned(Theme::default())
+        })
+    }
+
+    /// The entry of `read_only` the task called `name` is under, if any
+    pub fn read_only_source(&self, name: &Path) -> Option<&Path> {
+        self.read_only
+            .iter()
+            .map(PathBuf::as_path)
+            .find(|source| name.starts_with(source))
+    }
+
+    /// The configured level of `urgency`, if there is one
+    pub fn urgency(&self, urgency: &Urgency) -> Option<&UrgencyConfig> {
+        self.urgencies
+            .iter()
+            .find(|config| config.level.name.eq_ignore_ascii_case(urgency.name()))
+    }
+
+    /// Make the configured urgencies the ones tasks can have, see [`dooit_tasks::urgency`]
+    pub fn install_urgencies(&self) -> color_eyre::Result<()> {
+        if self.urgencies.is_empty() {
+            return Ok(());
+        }
+        let levels = self.urgencies.iter().map(|urgency| urgency.level.clone());
+        dooit_tasks::urgency::set_levels(levels.collect())
+            .wrap_err("invalid urgencies in the config")
+    }
+}
+
+/// Value of the option at the dotted `key` (e.g. `list.default_sort`), `None` if it isn't set
+fn lookup<'a>(config: &'a toml::Value, key: &str) -> Option<&'a toml::Value> {
+    key.split('.')
+        .try_fold(config, |value, part| value.get(part))
+}
+
+/// Print the value `config` has for the dotted `key`, including defaults
+pub fn get(config: &Config, key: &str) -> color_eyre::Result<()> {
+    let config = toml::Value::try_from(config).wrap_err("serialize the config")?;
+
+    match lookup(&config, key) {
+        Some(toml::Value::String(value)) => println!("{value}"),
+        Some(value) => println!("{value}"),
+        None => bail!("{key} isn't set or isn't a config option"),
+    }
+
+    Ok(())
+}
+
+/// Message for a dry run writing the file at `path`
+pub fn would_write(path: &Path) -> String {
+    match path.exists() {
+        true => tr!("would-modify", path = path.display()),
+        false => tr!("would-create", path = path.display()),
+    }
+}
+
+/// Parse `value` as TOML, taking it as a plain string if it isn't valid TOML (e.g. `1h`)
+fn parse_value(value: &str) -> toml_edit::Value {
+    value
+        .parse()
+        .unwrap_or_else(|_| toml_edit::Value::from(value))
+}
+
+/// Set the dotted `key` to `value` in the config file, keeping its comments and formatting
+///
+/// The file is only written if the result is a valid config where `key` exists, and not at all
+/// with `dry_run`. Giving a single value for a list option sets the list to just that value.
+pub fn set(key: &str, value: &str, dry_run: bool) -> color_eyre::Result<()> {
+    let path = config_path().ok_or_else(|| eyre!("config dir not available"))?;
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err).wrap_err_with(|| format!("read {}", path.display())),
+    };
+    let mut document: toml_edit::DocumentMut = contents
+        .parse()
+        .wrap_err_with(|| format!("parse config file {}", path.display()))?;
+
+    let mut value = parse_value(value);
+    let defaults = toml::Value::try_from(Config::default()).wrap_err("serialize the config")?;
+    if lookup(&defaults, key).is_some_and(toml::Value::is_array) && !value.is_array() {
+        value = toml_edit::Value::Array(std::iter::once(value).collect());
+    }
+
+    let (parents, name) = match key.rsplit_once('.') {
+        Some((parents, name)) => (parents.split('.').collect(), name),
+        None => (Vec::new(), key),
+    };
+    let mut table = document.as_table_mut() as &mut dyn toml_edit::TableLike;
+    for part in parents {
+        table = table
+            .entry(part)
+            .or_insert_with(|| {
+                let mut table = toml_edit::Table::new();
+                table.set_implicit(true);
+                toml_edit::Item::Table(table)
+            })
+            .as_table_like_mut()
+            .ok_or_else(|| eyre!("{part} in {key} isn't a table"))?;
+    }
+    table.insert(name, toml_edit::value(value));
+
+    let contents = document.to_string();
+    let config: Config = toml::from_str(&contents).wrap_err_with(|| format!("invalid {key}"))?;
+    let config = toml::Value::try_from(config).wrap_err("serialize the config")?;
+    if lookup(&config, key).is_none() {
+        bail!("{key} isn't a config option");
+    }
+
+    if dry_run {
+        println!("{}", would_write(&path));
+        return Ok(());
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).wrap_err_with(|| format!("create {}", dir.display()))?;
+    }
+    std::fs::write(&path, contents).wrap_err_with(|| format!("write {}", path.display()))
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use dooit_tasks::{Task, Urgency};
+    use pretty_assertions::assert_eq;
+
+    use super::{AddConfig, Config};
+
+    #[test]
+    fn test_add_defaults() {
+        let defaults = AddConfig {
+            tags: vec!["proj".to_string()],
+            urgency: Some(Urgency::High),
+            parent: Some("proj".to_string()),
+        };
+
+        let mut task = Task::new("notes").with_tags(["docs"]);
+        defaults.apply(&mut task, false).unwrap();
+        assert_eq!(
+            task,
+            Task::new("proj/notes")
+                .with_tags(["docs", "proj"])
+                .with_urgency(Urgency::High)
+        );
+
+        let mut task = Task::new("proj/notes").with_tags(["proj"]);
+        defaults.apply(&mut task, true).unwrap();
+        assert_eq!(task, Task::new("proj/notes").with_tags(["proj"]));
+
+        // What the add wizard checks for an existing task
+        assert_eq!(defaults.name(Path::new("notes")), Path::new("proj/notes"));
+        assert_eq!(
+            defaults.name(Path::new("proj/notes")),
+            Path::new("proj/notes")
+        );
+        assert_eq!(
+            AddConfig::default().name(Path::new("notes")),
+            Path::new("notes")
+        );
+    }
+
+    #[test]
+    fn test_read_only_source() {
+        let config = Config {
+            read_only: vec!["team".into(), "shared/docs".into()],
+            ..Config::default()
+        };
+
+        let source = |name: &str| config.read_only_source(Path::new(name));
+        assert_eq!(source("team"), Some(Path::new("team")));
+        assert_eq!(source("team/sync"), Some(Path::new("team")));
+        assert_eq!(source("shared/docs/intro"), Some(Path::new("shared/docs")));
+        assert_eq!(source("teams"), None);
+        assert_eq!(source("shared"), None);
+    }
+}
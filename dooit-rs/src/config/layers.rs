@@ -0,0 +1,232 @@
+//! Configuration merged from several layers, each one overriding the ones before it:
+//!
+//! 1. The system config, `/etc/dooit/config.toml` (Unix only)
+//! 2. The user config, `config.toml` in the config dir (what `dooit-rs config` edits)
+//! 3. The project config, `.dooit/config.toml` in the current directory or its closest parent
+//!    that has one. Only some options are read from it unless it's trusted, see [`super::trust`]
+//! 4. Environment variables named `DOOIT_CONFIG_` followed by the option, with `__` between
+//!    nested keys, e.g. `DOOIT_CONFIG_LIST__DEFAULT_SORT=name-ascending`
+//! 5. `--config-option KEY=VALUE` flags, with dotted keys, e.g.
+//!    `-C list.default_sort=name-ascending`
+//!
+//! Tables are merged key by key, any other value (lists included) replaces the one below it.
+//! Values from the environment and the command line are read as TOML if possible and as strings
+//! otherwise.
+
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{eyre, WrapErr};
+use serde::de::DeserializeOwned;
+
+/// Prefix of the environment variables setting options
+pub const ENV_PREFIX: &str = "DOOIT_CONFIG_";
+
+/// The system wide config file
+pub fn system_path() -> Option<PathBuf> {
+    cfg!(unix).then(|| PathBuf::from("/etc/dooit/config.toml"))
+}
+
+/// The project config file closest to `dir`, if there is one
+pub fn project_path(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join(".dooit").join("config.toml"))
+        .find(|path| path.is_file())
+}
+
+/// Read `value` as TOML, or as a string if it isn't valid TOML (e.g. `1h` or `list --overdue`)
+pub fn parse_value(value: &str) -> toml::Value {
+    toml::from_str::<toml::value::Table>(&format!("value = {value}"))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(value.to_string()))
+}
+
+/// The TOML file at `path`, `None` if it doesn't exist
+fn read(path: &Path) -> color_eyre::Result<Option<toml::Value>> {
+    let contents = match std::fs::read(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err).wrap_err_with(|| format!("read {}", path.display())),
+    };
+    toml::from_slice(&contents)
+        .map(Some)
+        .wrap_err_with(|| format!("parse config file {}", path.display()))
+}
+
+/// Merge `layer` into `base`, `layer` wins on conflicts
+fn merge(base: &mut toml::Value, layer: toml::Value) {
+    match (base, layer) {
+        (toml::Value::Table(base), toml::Value::Table(layer)) => {
+            for (key, value) in layer {
+                match base.get_mut(&key) {
+                    Some(base) => merge(base, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, layer) => *base = layer,
+    }
+}
+
+/// A configuration built up from layers, see the [module docs](self)
+#[derive(Debug, Clone)]
+pub struct Layers {
+    value: toml::Value,
+}
+
+impl Default for Layers {
+    fn default() -> Self {
+        Self {
+            value: toml::Value::Table(Default::default()),
+        }
+    }
+}
+
+impl Layers {
+    /// Add the options in the TOML file at `path`, if it exists
+    pub fn file(mut self, path: &Path) -> color_eyre::Result<Self> {
+        if let Some(layer) = read(path)? {
+            merge(&mut self.value, layer);
+        }
+        Ok(self)
+    }
+
+    /// Add the top level options in `allowed` from the TOML file at `path`, if it exists, and
+    /// return the ones that were left out
+    pub fn file_only(
+        mut self,
+        path: &Path,
+        allowed: &[&str],
+    ) -> color_eyre::Result<(Self, Vec<String>)> {
+        let Some(mut layer) = read(path)? else {
+            return Ok((self, Vec::new()));
+        };
+
+        let mut ignored = Vec::new();
+        if let toml::Value::Table(table) = &mut layer {
+            ignored = table
+                .keys()
+                .filter(|key| !allowed.contains(&key.as_str()))
+                .cloned()
+                .collect();
+            for key in &ignored {
+                table.remove(key);
+            }
+        }
+
+        merge(&mut self.value, layer);
+        Ok((self, ignored))
+    }
+
+    /// Set the dotted `key` to `value`
+    pub fn set(mut self, key: &str, value: toml::Value) -> Self {
+        let layer = key.rsplit('.').fold(value, |value, key| {
+            toml::Value::Table([(key.to_string(), value)].into_iter().collect())
+        });
+
+        merge(&mut self.value, layer);
+        self
+    }
+
+    /// Add the options in environment variables starting with `prefix`
+    pub fn env(self, prefix: &str) -> Self {
+        std::env::vars()
+            .filter_map(|(name, value)| {
+                let key = name.strip_prefix(prefix)?.to_lowercase().replace("__", ".");
+                Some((key, value))
+            })
+            .fold(self, |layers, (key, value)| {
+                layers.set(&key, parse_value(&value))
+            })
+    }
+
+    /// Add `KEY=VALUE` overrides, with dotted keys
+    pub fn overrides(self, overrides: &[String]) -> color_eyre::Result<Self> {
+        overrides.iter().try_fold(self, |layers, option| {
+            let (key, value) = option
+                .split_once('=')
+                .ok_or_else(|| eyre!("expected KEY=VALUE, got {option:?}"))?;
+            Ok(layers.set(key.trim(), parse_value(value.trim())))
+        })
+    }
+
+    /// Deserialize the merged options
+    pub fn build<T: DeserializeOwned>(self) -> color_eyre::Result<T> {
+        self.value.try_into().wrap_err("invalid configuration")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::{parse_value, Layers};
+
+    #[test]
+    fn test_layers_override() {
+        let layers = Layers::default()
+            .set("list.default_sort", parse_value("name-ascending"))
+            .set("list.completed", parse_value("true"))
+            .set("notify.lead_times", parse_value(r#"["1h", "1d"]"#))
+            .overrides(&[
+                "list.default_sort = score-descending".to_string(),
+                r#"notify.lead_times=["30m"]"#.to_string(),
+            ])
+            .unwrap();
+
+        let expected: toml::Value = toml::from_str(
+            r#"
+[list]
+default_sort = "score-descending"
+completed = true
+
+[notify]
+lead_times = ["30m"]
+"#,
+        )
+        .unwrap();
+        assert_eq!(layers.value, expected);
+    }
+
+    #[test]
+    fn test_file_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            r#"
+data_dir = "/elsewhere"
+
+[list]
+completed = true
+
+[signing]
+program = "./run-me"
+"#,
+        )
+        .unwrap();
+
+        let (layers, ignored) = Layers::default()
+            .set("list.overdue", parse_value("true"))
+            .file_only(&path, &["list", "add"])
+            .unwrap();
+        assert_eq!(ignored, ["data_dir", "signing"]);
+        let expected: toml::Value = toml::from_str(
+            r#"
+[list]
+overdue = true
+completed = true
+"#,
+        )
+        .unwrap();
+        assert_eq!(layers.value, expected);
+
+        let (layers, ignored) = Layers::default()
+            .file_only(&dir.path().join("missing.toml"), &[])
+            .unwrap();
+        assert!(ignored.is_empty());
+        assert_eq!(layers.value, Layers::default().value);
+    }
+}
@@ -0,0 +1,432 @@
+//! The commented sample written by `config init`, and `config check` which compares a config
+//! file against it
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
+
+use color_eyre::eyre::{bail, WrapErr};
+use dooit_tasks::{HumanDuration, ScoreConfig, SortMode, Urgency, UrgencyLevel};
+
+use super::{
+    config_files, AddConfig, ArchiveConfig, Config, DigestConfig, EmailConfig, LimitsConfig,
+    NotifyConfig, SigningConfig, UrgencyConfig, Webhook,
+};
+use crate::{
+    channels::{MatrixConfig, TelegramConfig},
+    color::Color,
+    i18n::tr,
+    report::{Column, GroupBy, Report},
+    theme::{Style, Theme},
+};
+
+/// Options whose keys are picked by the user
+const FREEFORM: [&str; 8] = [
+    "aliases",
+    "themes",
+    "reports",
+    "contexts",
+    "score.tags",
+    "score.urgencies",
+    "limits.urgencies",
+    "limits.tags",
+];
+
+/// Comments placed above options and sections in the sample
+const DOCS: [(&str, &str); 33] = [
+    (
+        "editor",
+        "Editor used when `--editor` isn't given, takes precedence over `$VISUAL` and `$EDITOR`",
+    ),
+    (
+        "data_dir",
+        "Where tasks are kept, instead of the platform's data directory",
+    ),
+    (
+        "read_only",
+        "Tasks that can't be changed, and their subtasks, e.g. a folder shared with a team (`list` \
+         labels them)",
+    ),
+    (
+        "identity",
+        "Your name in a task directory shared by a team, `list --mine` shows the tasks whose \
+         `assignee` it is",
+    ),
+    (
+        "log_file",
+        "Append diagnostic logs to this file instead of printing them",
+    ),
+    (
+        "locale",
+        "Language of messages, e.g. `de`, instead of the one from `$LANG`",
+    ),
+    (
+        "icons",
+        "Glyphs used for urgencies, `nerd` needs a Nerd Font and `ascii` works everywhere",
+    ),
+    (
+        "color",
+        "When to color the output of commands like `list`: `auto`, `always` or `never`",
+    ),
+    (
+        "theme",
+        "Colors and styles used, one of `themes` or the built-in `default` and `monochrome`",
+    ),
+    (
+        "themes.dusk",
+        "Styles like `bold bright-red on black` for overdue, due-soon, completed, header and \
+         selected tasks, and tasks by urgency (unset ones are taken from `default`)",
+    ),
+    (
+        "urgencies",
+        "Urgency levels, lowest first, replacing `low`, `medium` and `high` (tasks keep working if \
+         theirs is removed)",
+    ),
+    (
+        "list",
+        "Defaults for `list`, its flags can only add to these",
+    ),
+    ("list.mine", "Only show the tasks assigned to `identity`"),
+    (
+        "add",
+        "Defaults for tasks created with `add` and `capture`, e.g. in a project's \
+         `.dooit/config.toml` (`{project}` in `parent` is the project's directory name)",
+    ),
+    (
+        "reports.next",
+        "Views of the tasks shown by `report <name>`: a filter like `+work !is:completed \
+         due.before:1w`, a sort mode, columns and `group-by` (urgency, status, tag or parent)",
+    ),
+    (
+        "contexts",
+        "Filters like the ones of reports, `context set <name>` only lists the tasks matching one",
+    ),
+    ("webhooks", "Endpoints notified when tasks change"),
+    ("notify", "Desktop reminders for upcoming tasks"),
+    (
+        "notify.escalation.high",
+        "How reminders are delivered depending on the task's urgency",
+    ),
+    ("hooks", "Scripts run before tasks are changed"),
+    (
+        "digest",
+        "Summary of overdue, upcoming and recently completed tasks",
+    ),
+    ("digest.email", "Where to send the digest"),
+    ("archive", "Moving old completed tasks out of the way"),
+    (
+        "audit.enabled",
+        "Append every change to tasks to `<state dir>/audit.jsonl`, shown by `history <task>`",
+    ),
+    (
+        "journal.enabled",
+        "Append every change to tasks to `<data dir>/.journal.jsonl`, so `undo` can take it back",
+    ),
+    (
+        "aliases",
+        "Shorthands for subcommands and their arguments, e.g. `t = \"list --overdue\"`",
+    ),
+    (
+        "score",
+        "Coefficients of the urgency score used by the `score-*` sort modes",
+    ),
+    (
+        "score.urgencies",
+        "Coefficients of custom urgencies, the closest built-in urgency below them otherwise",
+    ),
+    (
+        "score.tags",
+        "Extra score for tasks with these tags (use negative values to push them down)",
+    ),
+    (
+        "limits",
+        "How many tasks can be started, or open with an urgency or tag, at once (`add` and \
+         changes going over a limit warn, or fail with `strict` or `--strict`)",
+    ),
+    ("limits.urgencies", "Most open tasks of each urgency"),
+    ("limits.tags", "Most open tasks with each tag"),
+    (
+        "signing",
+        "Sign the task files with this GnuPG key and warn about files that aren't signed or \
+         changed since, e.g. when syncing through storage that isn't trusted",
+    ),
+];
+
+/// The default config with an example for every option that is unset by default
+fn example() -> Config {
+    let defaults = Config::default();
+    let mut notify = NotifyConfig {
+        telegram: Some(TelegramConfig {
+            chat_id: "123456789".to_string(),
+            token: None,
+        }),
+        matrix: Some(MatrixConfig {
+            homeserver: "https://matrix.org".to_string(),
+            room_id: "!abcdef:matrix.org".to_string(),
+            token: None,
+        }),
+        ..defaults.notify
+    };
+    notify.escalation.high.repeat = Some(HumanDuration(chrono::Duration::minutes(30)));
+    let dusk = Theme {
+        overdue: Style::try_from("bold bright-red".to_string()).expect("valid style"),
+        urgencies: BTreeMap::from([(
+            "critical".to_string(),
+            Style::try_from("bright-white on red".to_string()).expect("valid style"),
+        )]),
+        ..Theme::default()
+    };
+
+    Config {
+        editor: Some("code --wait".to_string()),
+        data_dir: Some("/home/me/tasks".into()),
+        read_only: vec!["team".into()],
+        identity: Some("alice".to_string()),
+        log_file: Some("/home/me/.local/state/dooit-rs/log".into()),
+        locale: Some("de".to_string()),
+        theme: Some("dusk".to_string()),
+        themes: BTreeMap::from([("dusk".to_string(), dusk)]),
+        urgencies: [
+            ("someday", "󰒲", "z", Some(Color::BrightBlack)),
+            ("low", " ", " ", None),
+            ("medium", "", "!", None),
+            ("high", "", "!!", None),
+            ("critical", "", "!!!", Some(Color::Red)),
+        ]
+        .map(|(name, icon, ascii, color)| UrgencyConfig {
+            level: UrgencyLevel {
+                name: name.to_string(),
+                icon: icon.to_string(),
+                ascii: Some(ascii.to_string()),
+            },
+            color,
+        })
+        .into(),
+        reports: BTreeMap::from([(
+            "next".to_string(),
+            Report {
+                filter: "!is:completed !is:waiting due.before:1w".to_string(),
+                sort: Some(SortMode::ScoreDescending),
+                columns: vec![Column::Urgency, Column::Due, Column::Name, Column::Score],
+                group_by: Some(GroupBy::Parent),
+            },
+        )]),
+        add: AddConfig {
+            tags: vec!["dooit".to_string()],
+            urgency: Some(Urgency::Medium),
+            parent: Some("{project}".to_string()),
+        },
+        contexts: BTreeMap::from([
+            ("work".to_string(), "+work".to_string()),
+            ("home".to_string(), "-work".to_string()),
+        ]),
+        score: ScoreConfig {
+            urgencies: BTreeMap::from([("critical".to_string(), 9.0)]),
+            ..defaults.score.clone()
+        },
+        webhooks: vec![Webhook {
+            url: "https://example.com/dooit".to_string(),
+            events: Vec::new(),
+        }],
+        notify,
+        digest: DigestConfig {
+            every: Some(HumanDuration(chrono::Duration::weeks(1))),
+            email: Some(EmailConfig {
+                server: "smtp.example.com".to_string(),
+                port: Some(465),
+                security: Default::default(),
+                username: Some("me".to_string()),
+                password: Some("hunter2".to_string()),
+                from: "dooit <me@example.com>".to_string(),
+                to: vec!["me@example.com".to_string()],
+            }),
+        },
+        archive: ArchiveConfig {
+            after: Some(HumanDuration(chrono::Duration::weeks(4))),
+        },
+        aliases: BTreeMap::from([("t".to_string(), "list --overdue".to_string())]),
+        limits: LimitsConfig {
+            started: Some(3),
+            urgencies: BTreeMap::from([("high".to_string(), 5)]),
+            tags: BTreeMap::from([("work".to_string(), 10)]),
+            strict: false,
+        },
+        signing: SigningConfig {
+            key: Some("me@example.com".to_string()),
+            program: Some("gpg2".to_string()),
+        },
+        ..defaults
+    }
+}
+
+/// A config file documenting every option, all of them commented out
+pub fn sample() -> color_eyre::Result<String> {
+    let example = toml::Value::try_from(example()).wrap_err("serialize the config")?;
+    let example = toml::to_string(&example).wrap_err("serialize the config")?;
+
+    let mut sample = String::from(
+        "# Configuration of dooit-rs, every option is commented out and set to its default or an \
+         example\n",
+    );
+    let mut section = String::new();
+    // Every element of an array of tables has the same key
+    let mut documented = BTreeSet::new();
+    for line in example.lines() {
+        let key = match line.trim_start_matches('[').split_once(['=', ']']) {
+            Some((name, _)) if line.starts_with('[') => {
+                section = name.to_string();
+                section.clone()
+            }
+            Some((key, _)) if section.is_empty() => key.trim().to_string(),
+            Some((key, _)) => format!("{section}.{}", key.trim()),
+            None => String::new(),
+        };
+        let doc = DOCS
+            .iter()
+            .find(|(name, _)| *name == key)
+            .filter(|_| documented.insert(key.clone()));
+        if doc.is_some() || line.starts_with('[') {
+            sample.push('\n');
+        }
+        if let Some((_, doc)) = doc {
+            sample.push_str(&format!("# {doc}\n"));
+        }
+
+        if !line.is_empty() {
+            sample.push_str("# ");
+            sample.push_str(line);
+            sample.push('\n');
+        }
+    }
+
+    Ok(sample)
+}
+
+/// The known key most similar to `key`, if any is close enough
+fn suggest<'a>(key: &str, known: impl IntoIterator<Item = &'a String>) -> Option<&'a str> {
+    known
+        .into_iter()
+        .map(|candidate| (strsim::jaro(key, candidate), candidate))
+        .filter(|(similarity, _)| *similarity > 0.7)
+        .max_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, candidate)| candidate.as_str())
+}
+
+/// Collect the keys in `value` that aren't in `schema`, with a suggestion for each
+fn unknown_keys(
+    value: &toml::Value,
+    schema: &toml::Value,
+    path: &str,
+    unknown: &mut Vec<(String, Option<String>)>,
+) {
+    if FREEFORM.contains(&path) {
+        return;
+    }
+
+    match (value, schema) {
+        (toml::Value::Table(table), toml::Value::Table(known)) => {
+            for (key, value) in table {
+                let path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                match known.get(key) {
+                    Some(schema) => unknown_keys(value, schema, &path, unknown),
+                    None => {
+                        let suggestion = suggest(key, known.keys()).map(str::to_string);
+                        unknown.push((path, suggestion));
+                    }
+                }
+            }
+        }
+        (toml::Value::Array(values), toml::Value::Array(known)) => {
+            if let Some(schema) = known.first() {
+                for value in values {
+                    unknown_keys(value, schema, path, unknown);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Check every config file parses and only has known options
+pub fn check() -> color_eyre::Result<()> {
+    let files: Vec<_> = config_files()
+        .into_iter()
+        .filter(|path| path.exists())
+        .collect();
+    if files.is_empty() {
+        println!("{}", tr!("config-no-files"));
+    }
+
+    for path in files {
+        check_file(&path)?;
+    }
+    Ok(())
+}
+
+fn check_file(path: &Path) -> color_eyre::Result<()> {
+    let contents =
+        std::fs::read_to_string(path).wrap_err_with(|| format!("read {}", path.display()))?;
+
+    let parse_error = || format!("parse config file {}", path.display());
+    let value: toml::Value = toml::from_str(&contents).wrap_err_with(parse_error)?;
+    let _: Config = toml::from_str(&contents).wrap_err_with(parse_error)?;
+
+    let schema = toml::Value::try_from(example()).wrap_err("serialize the config")?;
+    let mut unknown = Vec::new();
+    unknown_keys(&value, &schema, "", &mut unknown);
+
+    for (key, suggestion) in &unknown {
+        match suggestion {
+            Some(suggestion) => {
+                let message = tr!("config-unknown-suggest", key = key, suggestion = suggestion);
+                eprintln!("{message}");
+            }
+            None => eprintln!("{}", tr!("config-unknown", key = key)),
+        }
+    }
+    if !unknown.is_empty() {
+        bail!("{} has {} unknown option(s)", path.display(), unknown.len());
+    }
+
+    println!("{}", tr!("config-valid", path = path.display()));
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::{example, sample, unknown_keys};
+
+    #[test]
+    fn test_sample_documents_every_option() {
+        let sample = sample().unwrap();
+        let uncommented: String = sample
+            .lines()
+            .filter_map(|line| line.strip_prefix("# "))
+            // Skip the documentation
+            .filter(|line| line.starts_with(|c: char| c == '[' || c.is_ascii_lowercase()))
+            .map(|line| format!("{line}\n"))
+            .collect();
+
+        let value: toml::Value = toml::from_str(&uncommented).unwrap();
+        let schema = toml::Value::try_from(example()).unwrap();
+        assert_eq!(value, schema);
+
+        let typo: toml::Value = toml::from_str("[notify]\nlead_time = [\"1h\"]").unwrap();
+        let mut unknown = Vec::new();
+        unknown_keys(&typo, &schema, "", &mut unknown);
+        assert_eq!(
+            unknown,
+            [(
+                "notify.lead_time".to_string(),
+                Some("lead_times".to_string())
+            )]
+        );
+    }
+}
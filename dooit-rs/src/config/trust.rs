@@ -0,0 +1,128 @@
+//! Project configs trusted with every option
+//!
+//! A `.dooit/config.toml` comes with whatever directory it's in, e.g. a cloned repository, so only
+//! the options in [`PROJECT_OPTIONS`] are read from it until it's trusted with
+//! `dooit-rs config trust`. The others can run commands (`hooks`, `editor`, `signing.program`,
+//! ...), send tasks elsewhere (`webhooks`) or move where they're kept (`data_dir`). Trusted files
+//! are listed one per line in `trusted_projects` in the state dir.
+
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{eyre, WrapErr};
+use dooit_tasks::dirs;
+
+/// Top level options an untrusted project config can set
+pub const PROJECT_OPTIONS: &[&str] = &["list", "add", "contexts", "reports"];
+
+fn list_path() -> color_eyre::Result<PathBuf> {
+    dirs::get_state_dir()
+        .map(|dir| dir.join("trusted_projects"))
+        .ok_or_else(|| eyre!("state dir not available"))
+}
+
+fn read(list: &Path) -> color_eyre::Result<Vec<PathBuf>> {
+    match std::fs::read_to_string(list) {
+        Ok(contents) => Ok(contents
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(PathBuf::from)
+            .collect()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(err).wrap_err_with(|| format!("read {}", list.display())),
+    }
+}
+
+/// The absolute path of the project config, so it's the same from every subdirectory
+fn canonical(config: &Path) -> color_eyre::Result<PathBuf> {
+    config
+        .canonicalize()
+        .wrap_err_with(|| format!("resolve {}", config.display()))
+}
+
+/// Whether the project config at `config` was trusted
+///
+/// Nothing is trusted if the state dir isn't available.
+pub fn is_trusted(config: &Path) -> color_eyre::Result<bool> {
+    let Ok(list) = list_path() else {
+        return Ok(false);
+    };
+    is_trusted_in(&list, config)
+}
+
+fn is_trusted_in(list: &Path, config: &Path) -> color_eyre::Result<bool> {
+    let config = canonical(config)?;
+    Ok(read(list)?.contains(&config))
+}
+
+/// Trust the project config at `config` (or stop trusting it), `false` if nothing changed
+pub fn set(config: &Path, trusted: bool) -> color_eyre::Result<bool> {
+    set_in(&list_path()?, config, trusted)
+}
+
+/// Print the file [`set`] would change, if any
+pub fn show(config: &Path, trusted: bool) -> color_eyre::Result<()> {
+    let list = list_path()?;
+    if is_trusted_in(&list, config)? != trusted {
+        println!("{}", super::would_write(&list));
+    }
+    Ok(())
+}
+
+fn set_in(list: &Path, config: &Path, trusted: bool) -> color_eyre::Result<bool> {
+    let config = canonical(config)?;
+    let mut configs = read(list)?;
+    if configs.contains(&config) == trusted {
+        return Ok(false);
+    }
+    if trusted {
+        configs.push(config);
+    } else {
+        configs.retain(|path| *path != config);
+    }
+
+    if let Some(dir) = list.parent() {
+        std::fs::create_dir_all(dir).wrap_err_with(|| format!("create {}", dir.display()))?;
+    }
+    let contents: String = configs
+        .iter()
+        .map(|path| format!("{}\n", path.display()))
+        .collect();
+    std::fs::write(list, contents).wrap_err_with(|| format!("write {}", list.display()))?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::{is_trusted_in, set_in};
+
+    #[test]
+    fn test_trust() {
+        let dir = tempfile::tempdir().unwrap();
+        let list = dir.path().join("state").join("trusted_projects");
+        let project = dir.path().join("project").join(".dooit");
+        std::fs::create_dir_all(project.join("sub")).unwrap();
+        let config = project.join("config.toml");
+        std::fs::write(&config, "").unwrap();
+
+        assert!(!is_trusted_in(&list, &config).unwrap());
+        assert!(set_in(&list, &config, true).unwrap());
+        assert!(!set_in(&list, &config, true).unwrap());
+
+        // The same file through another path is trusted too, but not its neighbours
+        assert!(is_trusted_in(&list, &project.join("sub/../config.toml")).unwrap());
+        let other = dir.path().join("config.toml");
+        std::fs::write(&other, "").unwrap();
+        assert!(!is_trusted_in(&list, &other).unwrap());
+
+        assert!(set_in(&list, &other, true).unwrap());
+        assert!(set_in(&list, &config, false).unwrap());
+        assert!(!is_trusted_in(&list, &config).unwrap());
+        assert_eq!(
+            std::fs::read_to_string(&list).unwrap(),
+            format!("{}\n", other.canonicalize().unwrap().display())
+        );
+        assert!(!set_in(&list, &config, false).unwrap());
+    }
+}
@@ -0,0 +1,134 @@
+//! The active context: a filter from `contexts` in the config that scopes what's listed
+//!
+//! `dooit-rs context set work` keeps only the tasks matching the `work` filter in `list`,
+//! `report`, `menu`, the shell and the TUI until `dooit-rs context clear`. The filters are written
+//! like the ones of reports, see [`crate::report`].
+
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::{bail, eyre, WrapErr};
+use dooit_tasks::{dirs, Task};
+
+use crate::{color, config::Config, i18n::tr, report::Filter};
+
+fn state_path() -> color_eyre::Result<PathBuf> {
+    dirs::get_state_dir()
+        .map(|dir| dir.join("context"))
+        .ok_or_else(|| eyre!("state dir not available"))
+}
+
+/// The name of the active context, even if it was removed from the config
+fn active_name() -> color_eyre::Result<Option<String>> {
+    let path = state_path()?;
+    match std::fs::read_to_string(&path) {
+        Ok(name) => Ok(Some(name.trim().to_string()).filter(|name| !name.is_empty())),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err).wrap_err_with(|| format!("read {}", path.display())),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Context {
+    pub name: String,
+    filter: Filter,
+}
+
+impl Context {
+    /// The context in `config` called `name`
+    fn new(config: &Config, name: &str) -> color_eyre::Result<Self> {
+        let Some(filter) = config.contexts.get(name) else {
+            let known: Vec<_> = config.contexts.keys().map(String::as_str).collect();
+            bail!(
+                "no context named {name:?} in the config (known contexts: {})",
+                known.join(", ")
+            );
+        };
+        let filter = filter
+            .parse()
+            .wrap_err_with(|| format!("parse the filter of context {name:?}"))?;
+        Ok(Self {
+            name: name.to_string(),
+            filter,
+        })
+    }
+
+    /// The context set with `context set`, if any
+    pub fn active(config: &Config) -> color_eyre::Result<Option<Self>> {
+        let Some(name) = active_name()? else {
+            return Ok(None);
+        };
+        if !config.contexts.contains_key(&name) {
+            tracing::warn!("the active context {name} isn't in the config anymore, ignoring it");
+            return Ok(None);
+        }
+        Self::new(config, &name).map(Some)
+    }
+
+    pub fn matches(&self, task: &Task, now: DateTime<Utc>) -> bool {
+        self.filter.matches(task, now)
+    }
+
+    pub fn filter(&self) -> &Filter {
+        &self.filter
+    }
+
+    /// The task every task in the context is under, see [`Filter::prefix`]
+    pub fn prefix(&self) -> &Path {
+        self.filter.prefix()
+    }
+
+    /// Line shown above the tasks while the context is active
+    pub fn header(&self, config: &Config) -> String {
+        color::header(&tr!("context-active", name = self.name), config)
+    }
+}
+
+/// Make `name` the active context, only printing the file that would change if `dry_run`
+pub fn set(config: &Config, name: &str, dry_run: bool) -> color_eyre::Result<()> {
+    Context::new(config, name)?;
+
+    let path = state_path()?;
+    if dry_run {
+        println!("{}", tr!("would-modify", path = path.display()));
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).wrap_err_with(|| format!("create {}", parent.display()))?;
+    }
+    std::fs::write(&path, name).wrap_err_with(|| format!("write {}", path.display()))
+}
+
+/// Go back to listing every task, only printing the file that would change if `dry_run`
+pub fn clear(dry_run: bool) -> color_eyre::Result<()> {
+    let path = state_path()?;
+    if dry_run {
+        if path.exists() {
+            println!("{}", tr!("would-delete", path = path.display()));
+        }
+        return Ok(());
+    }
+    match std::fs::remove_file(&path) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+            Err(err).wrap_err_with(|| format!("remove {}", path.display()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Print every context, marking the active one
+pub fn show(config: &Config) -> color_eyre::Result<()> {
+    let active = active_name()?;
+    if config.contexts.is_empty() {
+        println!("{}", tr!("no-contexts"));
+    }
+    for (name, filter) in &config.contexts {
+        let marker = if active.as_ref() == Some(name) {
+            '*'
+        } else {
+            ' '
+        };
+        println!("{marker} {name}: {filter}");
+    }
+    Ok(())
+}
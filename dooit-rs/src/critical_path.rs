@@ -0,0 +1,61 @@
+//! `critical-path`: the chain of dependencies that decides when a project can be finished
+
+use std::path::Path;
+
+use chrono::{Duration, Local, Utc};
+use color_eyre::eyre::WrapErr;
+use dooit_tasks::{deps, duration::format_duration, TaskStore};
+
+use crate::{color, config::Config, i18n::tr};
+
+/// Print the critical path of `project` and its subtasks, with when each task ends at the earliest
+pub fn run(config: &Config, store: &impl TaskStore, project: &Path) -> color_eyre::Result<()> {
+    let mut tasks = store.load_under(project).wrap_err("load tasks")?;
+    tasks.sort_by(|a, b| a.name.cmp(&b.name));
+    let Some(path) = deps::critical_path(&tasks)? else {
+        println!("{}", tr!("critical-path-none", project = project.display()));
+        return Ok(());
+    };
+
+    let title = tr!(
+        "critical-path",
+        project = project.display(),
+        duration = format_duration(path.duration)
+    );
+    println!("{}", color::header(&title, config));
+
+    let now = Utc::now();
+    let mut end = Duration::zero();
+    for task in &path.tasks {
+        let remaining = task.remaining();
+        end = end + remaining.unwrap_or_else(Duration::zero);
+        let remaining = remaining.map_or_else(|| "?".to_string(), format_duration);
+        let line = format!(
+            "{:>8} {:>8}  {}",
+            format!("+{}", format_duration(end)),
+            remaining,
+            task.name.display()
+        );
+        match config.color.enabled() {
+            true => println!("{}", color::style(task, config, now).paint(&line)),
+            false => println!("{line}"),
+        }
+    }
+
+    let unestimated = path.unestimated().count();
+    if unestimated > 0 {
+        println!("{}", tr!("critical-path-unestimated", count = unestimated));
+    }
+    let due = tasks
+        .iter()
+        .find(|task| task.name == project)
+        .and_then(|project| project.due);
+    if let Some(due) = due.filter(|due| *due < now + path.duration) {
+        let due = due.with_timezone(&Local).format("%a %F %R").to_string();
+        println!(
+            "{}",
+            tr!("critical-path-late", project = project.display(), due = due)
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,366 @@
+//! Long running process that keeps the task list in memory and sends reminders
+//!
+//! It also archives old completed tasks if `archive.after` is configured, and answers the queries
+//! of read commands like `list` from memory (see [`crate::via_daemon`]).
+
+#[cfg(unix)]
+use std::path::Path;
+use std::{
+    collections::BTreeSet,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::WrapErr;
+use dooit_tasks::{DirStore, EventKind, Task, TaskEvent, TaskStore};
+
+#[cfg(unix)]
+use crate::ipc::{self, Request, Response};
+use crate::{
+    archive,
+    config::Config,
+    digest,
+    i18n::tr,
+    reminders,
+    watch::{self, Watcher},
+    webhooks,
+};
+
+/// Never sleep longer than this, in case the clock jumps (e.g. after a suspend)
+const MAX_SLEEP: Duration = Duration::from_secs(60);
+
+/// How often old completed tasks are archived
+const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Default)]
+struct State {
+    /// Sorted by name, see [`State::sort`]
+    tasks: Vec<Task>,
+    /// When `tasks` were last brought up to date with the store
+    loaded_at: DateTime<Utc>,
+    next_reminder: Option<DateTime<Utc>>,
+}
+
+impl State {
+    /// Replace the tasks with `tasks` as they were at `loaded_at`
+    ///
+    /// They're sorted by name, so queries for the tasks under one find them next to each other.
+    fn update(&mut self, mut tasks: Vec<Task>, loaded_at: DateTime<Utc>) {
+        tasks.sort_by(|a, b| a.name.cmp(&b.name));
+        self.tasks = tasks;
+        self.loaded_at = loaded_at;
+    }
+}
+
+#[cfg(unix)]
+impl State {
+    /// Answer `request` from a command reading the store at `root`
+    fn answer(&self, root: &Path, request: Request) -> Response {
+        match request {
+            Request::Status => Response::Status {
+                pid: std::process::id(),
+                tasks: self.tasks.len(),
+                next_reminder: self.next_reminder,
+            },
+            Request::Tasks => Response::Tasks(self.tasks.clone()),
+            Request::Summaries { root: other, under } if other == root => Response::Summaries {
+                loaded_at: self.loaded_at,
+                tasks: ipc::summaries_under(&self.tasks, &under),
+            },
+            Request::Summaries { root: other, .. } => {
+                Response::Error(format!("not watching {}", other.display()))
+            }
+        }
+    }
+}
+
+/// When the next reminder is due for `tasks` and how long to sleep until something happens
+///
+/// That's the next reminder or the next task becoming overdue, but never more than [`MAX_SLEEP`].
+fn schedule(
+    config: &Config,
+    tasks: &[Task],
+    now: DateTime<Utc>,
+) -> (Option<DateTime<Utc>>, Duration) {
+    let next_due = tasks
+        .iter()
+        .filter(|task| !task.completed)
+        .filter_map(|task| task.due)
+        .filter(|due| *due > now)
+        .min();
+    let next_reminder = reminders::next_reminder(config, tasks, now);
+    let sleep = [next_reminder, next_due]
+        .into_iter()
+        .flatten()
+        .min()
+        .and_then(|wake| (wake - now).to_std().ok())
+        .map_or(MAX_SLEEP, |sleep| sleep.min(MAX_SLEEP));
+    (next_reminder, sleep)
+}
+
+/// Tasks that are overdue at `now`, identified by name and due date
+fn overdue(tasks: &[Task], now: DateTime<Utc>) -> BTreeSet<(String, DateTime<Utc>)> {
+    tasks
+        .iter()
+        .filter(|task| !task.completed)
+        .filter_map(|task| Some((task.name.to_string_lossy().into_owned(), task.due?)))
+        .filter(|(_, due)| *due <= now)
+        .collect()
+}
+
+pub fn run(config: &Config, store: &DirStore) -> color_eyre::Result<()> {
+    std::fs::create_dir_all(store.root())
+        .wrap_err_with(|| format!("create {}", store.root().display()))?;
+
+    let mut state = State::default();
+    let loaded_at = Utc::now();
+    state.update(store.load_all().wrap_err("load tasks")?, loaded_at);
+    let state = Arc::new(Mutex::new(state));
+
+    #[cfg(unix)]
+    {
+        let state = Arc::clone(&state);
+        let root = store.root().to_path_buf();
+        ipc::listen(move |request| {
+            let state = state.lock().expect("daemon state lock poisoned");
+            state.answer(&root, request)
+        })?;
+    }
+
+    let watcher = Watcher::new(store.root())?;
+    // Only tasks that become overdue while we're running trigger webhooks
+    let mut notified_overdue = overdue(
+        &state.lock().expect("daemon state lock poisoned").tasks,
+        Utc::now(),
+    );
+
+    let mut last_maintenance: Option<Instant> = None;
+
+    println!("{}", tr!("daemon-watching", path = store.root().display()));
+
+    // The lock is only held to copy the tasks in and out: reading files, archiving and sending
+    // reminders happen on a copy, so queries don't wait for them
+    loop {
+        let now = Utc::now();
+        let mut tasks = state
+            .lock()
+            .expect("daemon state lock poisoned")
+            .tasks
+            .clone();
+
+        if let Some(after) = config.archive.after {
+            if last_maintenance.is_none_or(|last| last.elapsed() >= MAINTENANCE_INTERVAL) {
+                last_maintenance = Some(Instant::now());
+                // Refresh the cache right away rather than waiting for the watcher
+                match archive::completed(config, store, &tasks, Some(after.0), now, false)
+                    .map(|archived| !archived.is_empty())
+                {
+                    Ok(true) => {
+                        let loaded_at = Utc::now();
+                        match store.load_all() {
+                            Ok(reloaded) => {
+                                let mut state = state.lock().expect("daemon state lock poisoned");
+                                state.update(reloaded, loaded_at);
+                                tasks = state.tasks.clone();
+                            }
+                            Err(err) => tracing::warn!("failed to reload tasks: {err}"),
+                        }
+                    }
+                    Ok(false) => {}
+                    Err(err) => tracing::warn!("failed to archive tasks: {err:#}"),
+                }
+            }
+        }
+
+        if let Err(err) = reminders::remind(config, &tasks, now, Some(store)) {
+            tracing::warn!("failed to send reminders: {err:#}");
+        }
+        if let Err(err) = digest::send_if_due(config, &tasks, now) {
+            tracing::warn!("failed to send the digest: {err:#}");
+        }
+
+        let overdue = overdue(&tasks, now);
+        for task in &tasks {
+            let Some(due) = task.due else { continue };
+            let key = (task.name.to_string_lossy().into_owned(), due);
+            if overdue.contains(&key) && !notified_overdue.contains(&key) {
+                webhooks::fire(
+                    &config.webhooks,
+                    &TaskEvent::new(EventKind::Overdue, task.clone()),
+                );
+            }
+        }
+        notified_overdue = overdue;
+
+        let (next_reminder, sleep) = schedule(config, &tasks, now);
+        state
+            .lock()
+            .expect("daemon state lock poisoned")
+            .next_reminder = next_reminder;
+
+        let changed = watcher.wait(Some(sleep))?;
+        if changed.is_empty() {
+            continue;
+        }
+
+        tracing::info!("reloading {} changed task file(s)", changed.len());
+        let loaded_at = Utc::now();
+        // Only this loop changes the tasks, so the copy is still the latest
+        let applied = watch::apply_changes(store, &mut tasks, &changed);
+        let mut state = state.lock().expect("daemon state lock poisoned");
+        // Whatever wasn't applied is still stale, so it's as old as before
+        let loaded_at = match applied {
+            Ok(()) => loaded_at,
+            Err(err) => {
+                tracing::warn!("{err:#}");
+                state.loaded_at
+            }
+        };
+        state.update(tasks, loaded_at);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{Duration, Utc};
+    use dooit_tasks::{HumanDuration, Task};
+    use pretty_assertions::assert_eq;
+
+    use super::{schedule, MAX_SLEEP};
+    use crate::config::Config;
+
+    #[test]
+    fn test_schedule() {
+        let mut config = Config::default();
+        config.notify.lead_times = vec![HumanDuration(Duration::hours(1))];
+        let now = Utc::now();
+        let due = now + Duration::hours(3);
+        let tasks = [
+            Task::new("report").with_due_date(due),
+            Task::new("taxes")
+                .with_due_date(now + Duration::minutes(30))
+                .complete(),
+            Task::new("late").with_due_date(now - Duration::hours(1)),
+            Task::new("someday"),
+        ];
+
+        // Reminders are due a lead time before the task, completed tasks don't get one
+        assert_eq!(
+            schedule(&config, &tasks, now),
+            (Some(due - Duration::hours(1)), MAX_SLEEP)
+        );
+        let soon = now + Duration::hours(2) - Duration::seconds(10);
+        assert_eq!(
+            schedule(&config, &tasks, soon),
+            (
+                Some(due - Duration::hours(1)),
+                std::time::Duration::from_secs(10)
+            )
+        );
+        // After the last reminder it still wakes up when the task becomes overdue
+        let after = due - Duration::seconds(30);
+        assert_eq!(
+            schedule(&config, &tasks, after),
+            (None, std::time::Duration::from_secs(30))
+        );
+        assert_eq!(schedule(&config, &tasks[2..], now), (None, MAX_SLEEP));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_ipc() {
+        use std::{
+            io::{BufRead, BufReader, Write},
+            os::unix::net::UnixStream,
+            path::Path,
+            sync::{Arc, Mutex},
+        };
+
+        use dooit_tasks::TimeEntry;
+
+        use super::State;
+        use crate::ipc::{self, Request, Response};
+
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("daemon.sock");
+        let root = Path::new("/data/dooit-rs");
+        let loaded_at = Utc::now();
+        let next_reminder = loaded_at + Duration::hours(1);
+        let state = Arc::new(Mutex::new(State {
+            next_reminder: Some(next_reminder),
+            ..State::default()
+        }));
+        let mut tasks = ["release/docs", "taxes", "release"].map(Task::new).to_vec();
+        tasks[2].time_log.push(TimeEntry {
+            start: loaded_at,
+            end: loaded_at,
+        });
+        state.lock().unwrap().update(tasks, loaded_at);
+        let served = Arc::clone(&state);
+        ipc::listen_at(&socket, move |request| {
+            served.lock().unwrap().answer(root, request)
+        })
+        .unwrap();
+        let query = |request| ipc::query_at(&socket, &request).unwrap();
+
+        match query(Request::Status) {
+            Response::Status {
+                pid,
+                tasks,
+                next_reminder: next,
+            } => {
+                assert_eq!(
+                    (pid, tasks, next),
+                    (std::process::id(), 3, Some(next_reminder))
+                );
+            }
+            response => panic!("expected the status, got {response:?}"),
+        }
+        // Summaries are the tasks under a parent sorted by name, without their time logs
+        let summaries = |under: &str| Request::Summaries {
+            root: root.to_path_buf(),
+            under: under.into(),
+        };
+        match query(summaries("release")) {
+            Response::Summaries {
+                loaded_at: at,
+                tasks,
+            } => {
+                assert_eq!(at, loaded_at);
+                assert_eq!(tasks, [Task::new("release"), Task::new("release/docs")]);
+            }
+            response => panic!("expected the summaries, got {response:?}"),
+        }
+        // Stores other than the watched one aren't answered from memory
+        let elsewhere = Request::Summaries {
+            root: "/elsewhere".into(),
+            under: "".into(),
+        };
+        assert!(matches!(query(elsewhere), Response::Error(_)));
+        let Response::Tasks(tasks) = query(Request::Tasks) else {
+            panic!("expected the tasks");
+        };
+        assert_eq!(tasks.len(), 3);
+        assert_eq!(tasks[0].time_log.len(), 1);
+
+        // Changes to the state are seen by the next query
+        state
+            .lock()
+            .unwrap()
+            .update(vec![Task::new("taxes")], Utc::now());
+        assert!(matches!(
+            query(Request::Status),
+            Response::Status { tasks: 1, .. }
+        ));
+
+        let mut stream = UnixStream::connect(&socket).unwrap();
+        stream.write_all(b"{\"method\":\"restart\"}\n").unwrap();
+        let mut line = String::new();
+        BufReader::new(stream).read_line(&mut line).unwrap();
+        assert!(line.starts_with("{\"error\":"), "{line}");
+
+        // A second daemon refuses to take over the socket
+        assert!(ipc::listen_at(&socket, |_| Response::Error(String::new())).is_err());
+    }
+}
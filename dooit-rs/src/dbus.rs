@@ -0,0 +1,164 @@
+//! Session D-Bus service exposing the task list
+//!
+//! The service is registered as [`BUS_NAME`] and serves the `rs.salameme.DooitRs1`
+//! interface at [`OBJECT_PATH`]:
+//!
+//! ```text
+//! ListTasks(b completed, b overdue) -> a(ssssb)   name, description, due, urgency, completed
+//! AddTask(s name, s description, s due, s urgency) empty strings leave the field unset
+//! CompleteTask(s name)
+//! signal TasksChanged()
+//! ```
+//!
+//! `ListTasks` sorts the tasks by `list.default_sort`. `TasksChanged` is emitted whenever the task
+//! files change, whether through the service, the CLI, an editor or a sync tool.
+
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::WrapErr;
+use dooit_tasks::{tasks::parse_date, DirStore, Task};
+use zbus::{fdo, interface, object_server::SignalEmitter};
+
+use crate::{actions, config::Config, i18n::tr, watch::Watcher};
+
+pub const BUS_NAME: &str = "rs.salameme.DooitRs";
+pub const OBJECT_PATH: &str = "/rs/salameme/DooitRs";
+
+type TaskTuple = (String, String, String, String, bool);
+
+struct Tasks {
+    config: Config,
+    store: DirStore,
+}
+
+fn failed(err: impl std::fmt::Display) -> fdo::Error {
+    fdo::Error::Failed(format!("{err:#}"))
+}
+
+fn to_tuple(task: Task) -> TaskTuple {
+    (
+        task.name.to_string_lossy().into_owned(),
+        task.description.unwrap_or_default(),
+        task.due.map(|due| due.to_rfc3339()).unwrap_or_default(),
+        task.urgency.to_string(),
+        task.completed,
+    )
+}
+
+#[interface(name = "rs.salameme.DooitRs1")]
+impl Tasks {
+    async fn list_tasks(&self, completed: bool, overdue: bool) -> fdo::Result<Vec<TaskTuple>> {
+        let tasks = actions::list(
+            &self.config,
+            &self.store,
+            self.config.list.default_sort,
+            completed,
+            overdue,
+        )
+        .map_err(failed)?;
+        Ok(tasks.into_iter().map(to_tuple).collect())
+    }
+
+    async fn add_task(
+        &self,
+        name: String,
+        description: String,
+        due: String,
+        urgency: String,
+    ) -> fdo::Result<()> {
+        let mut task = Task::new(name);
+        if !description.is_empty() {
+            task.description = Some(description);
+        }
+        if !due.is_empty() {
+            task.due =
+                Some(parse_date(&due).map_err(|err| fdo::Error::InvalidArgs(err.to_string()))?);
+        }
+        if !urgency.is_empty() {
+            task.urgency = urgency
+                .parse()
+                .map_err(|err: dooit_tasks::Error| fdo::Error::InvalidArgs(err.to_string()))?;
+        }
+
+        actions::add(&self.config, &self.store, task).map_err(failed)?;
+        Ok(())
+    }
+
+    async fn complete_task(&self, name: String) -> fdo::Result<()> {
+        actions::complete(&self.config, &self.store, name.as_ref()).map_err(failed)?;
+        Ok(())
+    }
+
+    #[zbus(signal)]
+    async fn tasks_changed(emitter: &SignalEmitter<'_>) -> zbus::Result<()>;
+}
+
+/// Whether any of the `changed` paths in the store at `root` hold tasks, rather than e.g. its index
+fn task_files_changed(root: &Path, changed: &[PathBuf]) -> bool {
+    changed.iter().any(|path| {
+        path.strip_prefix(root)
+            .is_ok_and(|relative| !relative.to_string_lossy().starts_with('.'))
+    })
+}
+
+/// Register the service on the session bus and serve requests forever, signaling changes to the
+/// task files
+pub fn serve(config: Config, store: DirStore) -> color_eyre::Result<()> {
+    let root = store.root().to_path_buf();
+    std::fs::create_dir_all(&root).wrap_err_with(|| format!("create {}", root.display()))?;
+    let watcher = Watcher::new(&root)?;
+
+    let connection = zbus::blocking::connection::Builder::session()
+        .and_then(|builder| builder.name(BUS_NAME))
+        .and_then(|builder| builder.serve_at(OBJECT_PATH, Tasks { config, store }))
+        .and_then(|builder| builder.build())
+        .wrap_err("register the D-Bus service on the session bus")?;
+
+    println!("{}", tr!("serve-dbus", name = BUS_NAME));
+
+    let tasks = connection
+        .object_server()
+        .interface::<_, Tasks>(OBJECT_PATH)
+        .wrap_err("find the D-Bus interface")?;
+    loop {
+        let changed = watcher.wait(None)?;
+        if !task_files_changed(&root, &changed) {
+            continue;
+        }
+        if let Err(err) = zbus::block_on(Tasks::tasks_changed(tasks.signal_emitter())) {
+            tracing::warn!("failed to signal the changed tasks: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::{Path, PathBuf};
+
+    use super::task_files_changed;
+
+    #[test]
+    fn test_task_files_changed() {
+        let root = Path::new("/data/dooit-rs");
+        let changed =
+            |paths: &[&str]| -> Vec<PathBuf> { paths.iter().map(|path| root.join(path)).collect() };
+
+        assert!(task_files_changed(
+            root,
+            &changed(&["report.toml", ".index"])
+        ));
+        assert!(task_files_changed(root, &changed(&["release"])));
+        assert!(!task_files_changed(
+            root,
+            &changed(&[".index", ".journal.jsonl"])
+        ));
+        assert!(!task_files_changed(
+            root,
+            &changed(&[".archive/report.toml"])
+        ));
+        assert!(!task_files_changed(
+            root,
+            &[PathBuf::from("/elsewhere/report.toml")]
+        ));
+    }
+}
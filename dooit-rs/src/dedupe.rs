@@ -0,0 +1,265 @@
+//! Finding pending tasks that are likely the same and merging them into one
+//!
+//! Only the last component of the names is compared, ignoring case and punctuation. Tasks with the
+//! same one are duplicates, tasks with nearly the same one only if they are due on the same day.
+
+use std::io::IsTerminal;
+
+use chrono::{Local, Utc};
+use color_eyre::eyre::{bail, WrapErr};
+use dooit_tasks::{Task, TaskStore};
+
+use crate::{
+    actions, color,
+    config::Config,
+    i18n::tr,
+    prompt::{self, Prompt},
+};
+
+/// How close names due on the same day need to be, between 0 and 1
+const SIMILARITY: f64 = 0.8;
+
+/// The last component of the name, lowercased and with words separated by single spaces
+fn normalize(task: &Task) -> String {
+    let leaf = task
+        .name
+        .file_name()
+        .unwrap_or(task.name.as_os_str())
+        .to_string_lossy();
+    leaf.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Whether `a` and `b` are likely the same task, see the [module docs](self)
+fn duplicates(a: &Task, b: &Task) -> bool {
+    let (a_name, b_name) = (normalize(a), normalize(b));
+    if a_name.is_empty() || b_name.is_empty() {
+        return false;
+    }
+    if a_name == b_name {
+        return true;
+    }
+
+    let day = |task: &Task| task.due.map(|due| due.with_timezone(&Local).date_naive());
+    day(a).is_some()
+        && day(a) == day(b)
+        && strsim::normalized_levenshtein(&a_name, &b_name) >= SIMILARITY
+}
+
+/// Indices of the pending `tasks` that are likely duplicates of each other
+pub fn candidates(tasks: &[Task]) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    for (i, a) in tasks.iter().enumerate() {
+        for (j, b) in tasks.iter().enumerate().skip(i + 1) {
+            if !a.completed && !b.completed && duplicates(a, b) {
+                pairs.push((i, j));
+            }
+        }
+    }
+    pairs
+}
+
+/// Add the `items` missing from `into`
+fn extend_unique<T: PartialEq>(into: &mut Vec<T>, items: Vec<T>) {
+    for item in items {
+        if !into.contains(&item) {
+            into.push(item);
+        }
+    }
+}
+
+/// `other` folded into `survivor`, which keeps its name
+///
+/// Descriptions are joined, tags, links, dependencies, logged time and habit occurrences combined,
+/// and whatever `survivor` doesn't have (a due date, an estimate, ...) is taken from `other`. The
+/// merged task gets the higher of both urgencies.
+pub fn merge(mut survivor: Task, other: Task) -> Task {
+    survivor.description = match (survivor.description.take(), other.description) {
+        (Some(ours), Some(theirs)) if ours.trim() != theirs.trim() => {
+            Some(format!("{ours}\n\n{theirs}"))
+        }
+        (ours, theirs) => ours.or(theirs),
+    };
+    survivor.due = survivor.due.or(other.due);
+    survivor.wait = survivor.wait.or(other.wait);
+    survivor.scheduled = survivor.scheduled.or(other.scheduled);
+    survivor.review = survivor.review.or(other.review);
+    survivor.estimate = survivor.estimate.or(other.estimate);
+    survivor.recur = survivor.recur.or(other.recur);
+    survivor.habit |= other.habit;
+    survivor.urgency = survivor.urgency.max(other.urgency);
+
+    extend_unique(&mut survivor.tags, other.tags);
+    extend_unique(&mut survivor.links, other.links);
+    extend_unique(&mut survivor.depends_on, other.depends_on);
+    survivor
+        .depends_on
+        .retain(|name| *name != survivor.name && *name != other.name);
+    extend_unique(&mut survivor.time_log, other.time_log);
+    survivor.time_log.sort_by_key(|entry| entry.start);
+    extend_unique(&mut survivor.occurrences, other.occurrences);
+    survivor
+        .occurrences
+        .sort_by_key(|occurrence| occurrence.due);
+    survivor
+}
+
+/// Which of the two tasks to keep
+fn ask(editor: &mut Prompt) -> color_eyre::Result<Option<bool>> {
+    let choices = ["1", "2", "skip", "quit"].map(String::from);
+    loop {
+        let question = format!("{} ", tr!("dedupe-keep"));
+        let answer = prompt::ask(editor, &question, choices.clone())?;
+        match answer.to_lowercase().as_str() {
+            "1" => return Ok(Some(true)),
+            "2" => return Ok(Some(false)),
+            "" | "s" | "skip" => return Ok(None),
+            "q" | "quit" => bail!("aborted"),
+            _ => eprintln!("{}", tr!("unknown-answer", answer = format!("{answer:?}"))),
+        }
+    }
+}
+
+/// Merge `removed` into `kept`, pointing the tasks that depend on it to `kept` instead
+fn merge_into(
+    config: &Config,
+    store: &impl TaskStore,
+    tasks: &mut [Option<Task>],
+    kept: usize,
+    removed: usize,
+) -> color_eyre::Result<()> {
+    let (Some(survivor), Some(other)) = (tasks[kept].take(), tasks[removed].take()) else {
+        unreachable!("merged tasks are skipped");
+    };
+    let merged = merge(survivor, other.clone());
+
+    for task in tasks.iter_mut().flatten() {
+        if !task.depends_on.contains(&other.name) {
+            continue;
+        }
+        task.depends_on.retain(|name| *name != other.name);
+        if *task.name != merged.name && !task.depends_on.contains(&merged.name) {
+            task.depends_on.push(merged.name.clone());
+        }
+        actions::update(config, store, task.clone())?;
+    }
+
+    actions::update(config, store, merged.clone())?;
+    actions::remove(config, store, &other.name)?;
+    tasks[kept] = Some(merged);
+    Ok(())
+}
+
+/// Go through the likely duplicates, merging them into the task chosen to be kept
+///
+/// With `assume_yes` every duplicate is merged into the task that comes first by name without
+/// asking.
+pub fn run(
+    config: &Config,
+    store: &impl TaskStore,
+    assume_yes: bool,
+    dry_run: bool,
+) -> color_eyre::Result<()> {
+    let mut tasks = store.load_all().wrap_err("load tasks")?;
+    tasks.sort_by(|a, b| a.name.cmp(&b.name));
+    let pairs = candidates(&tasks);
+    if pairs.is_empty() {
+        println!("{}", tr!("dedupe-none"));
+        return Ok(());
+    }
+
+    let found = tr!("dedupe-found", count = pairs.len());
+    let mut editor = match assume_yes {
+        true => None,
+        false if std::io::stdin().is_terminal() => {
+            Some(Prompt::new().wrap_err("open the terminal")?)
+        }
+        false => bail!("{}", tr!("confirm-needs-yes", summary = found)),
+    };
+    println!("{}", color::header(&found, config));
+
+    let now = Utc::now();
+    let mut tasks: Vec<_> = tasks.into_iter().map(Some).collect();
+    let mut merged = 0;
+    for (first, second) in pairs {
+        let (Some(a), Some(b)) = (&tasks[first], &tasks[second]) else {
+            continue;
+        };
+        let (from, into) = (b.name.clone(), a.name.clone());
+
+        let keep_first = match &mut editor {
+            None => true,
+            Some(editor) => {
+                println!("\n[1] {}", color::task(a, config, now));
+                println!("[2] {}", color::task(b, config, now));
+                match ask(editor)? {
+                    Some(keep_first) => keep_first,
+                    None => continue,
+                }
+            }
+        };
+        let (kept, removed, from, into) = match keep_first {
+            true => (first, second, from, into),
+            false => (second, first, into, from),
+        };
+
+        merge_into(config, store, &mut tasks, kept, removed)?;
+        merged += 1;
+        if !dry_run {
+            println!(
+                "{}",
+                tr!(
+                    "dedupe-merged",
+                    from = from.display(),
+                    into = into.display()
+                )
+            );
+        }
+    }
+
+    println!("{}", tr!("dedupe-summary", count = merged));
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{Duration, Utc};
+    use dooit_tasks::{Task, Urgency};
+    use pretty_assertions::assert_eq;
+
+    use super::{candidates, merge};
+
+    #[test]
+    fn test_dedupe() {
+        let now = Utc::now();
+        let tasks = [
+            Task::new("home/Buy milk"),
+            Task::new("buy-milk"),
+            Task::new("write report").with_due_date(now),
+            Task::new("write reports").with_due_date(now),
+            Task::new("write report draft"),
+            Task::new("done/buy milk").complete(),
+        ];
+        assert_eq!(candidates(&tasks), [(0, 1), (2, 3)]);
+
+        let survivor = Task::new("buy-milk")
+            .with_description("2l".to_string())
+            .with_tags(["home"]);
+        let other = Task::new("home/Buy milk")
+            .with_description("oat".to_string())
+            .with_due_date(now + Duration::days(1))
+            .with_urgency(Urgency::High)
+            .with_tags(["errands", "home"]);
+        assert_eq!(
+            merge(survivor, other),
+            Task::new("buy-milk")
+                .with_description("2l\n\noat".to_string())
+                .with_due_date(now + Duration::days(1))
+                .with_urgency(Urgency::High)
+                .with_tags(["home", "errands"])
+        );
+    }
+}
@@ -0,0 +1,199 @@
+//! Summary of overdue, upcoming and recently completed tasks
+//!
+//! Meant to be sent weekly, either by the daemon (see `digest.every` in the configuration) or by
+//! running `dooit-rs digest --email` from a timer.
+
+use std::{fmt::Display, path::PathBuf};
+
+use chrono::{DateTime, Duration, Local, Utc};
+use color_eyre::eyre::{bail, eyre, WrapErr};
+use dooit_tasks::{dirs, Task};
+use lettre::{
+    message::header::ContentType, transport::smtp::authentication::Credentials, Message,
+    SmtpTransport, Transport,
+};
+
+use crate::{
+    config::{Config, EmailConfig, SmtpSecurity},
+    i18n::tr,
+    secrets,
+};
+
+/// How far the digest looks ahead and back
+fn period() -> Duration {
+    Duration::weeks(1)
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Digest {
+    pub overdue: Vec<Task>,
+    pub due_this_week: Vec<Task>,
+    pub completed_last_week: Vec<Task>,
+}
+
+impl Digest {
+    pub fn new(tasks: &[Task], now: DateTime<Utc>) -> Self {
+        let mut digest = Self::default();
+
+        for task in tasks {
+            if task.completed {
+                if task.completed_at.is_some_and(|at| now - at <= period()) {
+                    digest.completed_last_week.push(task.clone());
+                }
+                continue;
+            }
+
+            match task.due {
+                Some(due) if due <= now => digest.overdue.push(task.clone()),
+                Some(due) if due - now <= period() => digest.due_this_week.push(task.clone()),
+                _ => {}
+            }
+        }
+
+        for section in [&mut digest.overdue, &mut digest.due_this_week] {
+            section.sort_by_key(|task| task.due);
+        }
+        digest
+            .completed_last_week
+            .sort_by_key(|task| task.completed_at);
+
+        digest
+    }
+}
+
+impl Display for Digest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sections = [
+            (tr!("digest-overdue"), &self.overdue),
+            (tr!("digest-due-this-week"), &self.due_this_week),
+            (tr!("digest-completed-last-week"), &self.completed_last_week),
+        ];
+
+        for (title, tasks) in sections {
+            writeln!(f, "{title} ({})", tasks.len())?;
+            for task in tasks {
+                write!(f, "  - {}", task.name.display())?;
+                if let Some(due) = task.due.filter(|_| !task.completed) {
+                    write!(f, " (due {})", due.with_timezone(&Local).format("%a %F %R"))?;
+                }
+                writeln!(f)?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub fn send_email(config: &EmailConfig, digest: &Digest) -> color_eyre::Result<()> {
+    if config.to.is_empty() {
+        bail!("no recipients configured in digest.email.to");
+    }
+
+    let mut message = Message::builder()
+        .from(config.from.parse().wrap_err("parse digest.email.from")?)
+        .subject(format!(
+            "dooit digest: {} overdue, {} due this week",
+            digest.overdue.len(),
+            digest.due_this_week.len()
+        ))
+        .header(ContentType::TEXT_PLAIN);
+    for to in &config.to {
+        message = message.to(to
+            .parse()
+            .wrap_err_with(|| format!("parse recipient {to}"))?);
+    }
+    let message = message.body(digest.to_string())?;
+
+    let mut transport = match config.security {
+        SmtpSecurity::Tls => SmtpTransport::relay(&config.server)?,
+        SmtpSecurity::StartTls => SmtpTransport::starttls_relay(&config.server)?,
+        SmtpSecurity::None => SmtpTransport::builder_dangerous(&config.server),
+    };
+    if let Some(port) = config.port {
+        transport = transport.port(port);
+    }
+    if let Some(username) = &config.username {
+        let password = match std::env::var("DOOIT_SMTP_PASSWORD") {
+            Ok(password) => Some(password),
+            Err(_) => secrets::get_or("smtp", config.password.as_deref())?,
+        };
+        let password = password.ok_or_else(|| {
+            eyre!("no password for {username}, add it with `dooit-rs auth set smtp`")
+        })?;
+        transport = transport.credentials(Credentials::new(username.clone(), password));
+    }
+
+    transport
+        .build()
+        .send(&message)
+        .wrap_err_with(|| format!("send the digest through {}", config.server))?;
+
+    Ok(())
+}
+
+fn state_path() -> color_eyre::Result<PathBuf> {
+    dirs::get_state_dir()
+        .map(|dir| dir.join("digest_sent"))
+        .ok_or_else(|| eyre!("state dir not available"))
+}
+
+/// Email the digest if `digest.every` passed since it was last sent
+pub fn send_if_due(config: &Config, tasks: &[Task], now: DateTime<Utc>) -> color_eyre::Result<()> {
+    let (Some(every), Some(email)) = (config.digest.every, &config.digest.email) else {
+        return Ok(());
+    };
+
+    let path = state_path()?;
+    let last_sent = match std::fs::read_to_string(&path) {
+        Ok(last_sent) => last_sent.trim().parse::<DateTime<Utc>>().ok(),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+        Err(err) => return Err(err).wrap_err_with(|| format!("read {}", path.display())),
+    };
+    if last_sent.is_some_and(|last_sent| now - last_sent < every.0) {
+        return Ok(());
+    }
+
+    send_email(email, &Digest::new(tasks, now))?;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).wrap_err_with(|| format!("create {}", parent.display()))?;
+    }
+    std::fs::write(&path, now.to_rfc3339()).wrap_err_with(|| format!("write {}", path.display()))
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{Duration, Utc};
+    use dooit_tasks::Task;
+    use pretty_assertions::assert_eq;
+
+    use super::Digest;
+
+    #[test]
+    fn test_digest_sections() {
+        let now = Utc::now();
+        let completed = |name, days_ago| Task {
+            completed_at: Some(now - Duration::days(days_ago)),
+            ..Task::new(name).complete()
+        };
+        let tasks = [
+            Task::new("later").with_due_date(now + Duration::days(10)),
+            Task::new("soon").with_due_date(now + Duration::days(2)),
+            Task::new("late").with_due_date(now - Duration::hours(1)),
+            Task::new("undated"),
+            completed("recent", 3),
+            completed("old", 30),
+        ];
+
+        let digest = Digest::new(&tasks, now);
+        assert_eq!(
+            digest,
+            Digest {
+                overdue: vec![tasks[2].clone()],
+                due_this_week: vec![tasks[1].clone()],
+                completed_last_week: vec![tasks[4].clone()],
+            }
+        );
+    }
+}
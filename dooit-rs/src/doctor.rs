@@ -0,0 +1,208 @@
+//! `doctor`: task files that don't lead back to their task, see [`dooit_tasks::conflicts`]
+//!
+//! Every conflict is listed, and on a terminal it can be resolved right away: a task in several
+//! files by picking the file to keep (after seeing how each one differs from the first), a task in
+//! the wrong file by moving it to where its name leads or by renaming it after its file. Without a
+//! terminal, or with `--check`, the conflicts are only listed and doctor fails if there are any.
+
+use std::{
+    io::IsTerminal,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::{bail, eyre, WrapErr};
+use dooit_tasks::{conflicts::Conflict, DirStore, Task, TaskStore};
+
+use crate::{color, config::Config, i18n::tr, prompt};
+
+fn read(store: &DirStore, path: &Path) -> color_eyre::Result<Task> {
+    let path = store.root().join(path);
+    let contents = std::fs::read(&path).wrap_err_with(|| format!("read {}", path.display()))?;
+    store
+        .format()
+        .deserialize(&contents)
+        .map_err(|err| eyre!("parse {}: {err}", path.display()))
+}
+
+/// Save `task` where its name leads and delete the `others` files (relative to the root)
+fn replace(
+    store: &DirStore,
+    task: &Task,
+    others: &[PathBuf],
+    dry_run: bool,
+) -> color_eyre::Result<()> {
+    let task_path = store.task_path(&task.name)?;
+    let others = others
+        .iter()
+        .map(|path| store.root().join(path))
+        .filter(|path| *path != task_path);
+    if dry_run {
+        println!("{}", tr!("would-modify", path = task_path.display()));
+        for path in others {
+            println!("{}", tr!("would-delete", path = path.display()));
+        }
+        return Ok(());
+    }
+
+    store
+        .save(task)
+        .wrap_err_with(|| format!("save task {}", task.name.display()))?;
+    for path in others {
+        std::fs::remove_file(&path).wrap_err_with(|| format!("delete {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Keep the file `paths[keep]` of the task in all of `paths`
+fn keep(store: &DirStore, paths: &[PathBuf], keep: usize, dry_run: bool) -> color_eyre::Result<()> {
+    let task = read(store, &paths[keep])?;
+    replace(store, &task, paths, dry_run)
+}
+
+/// Ask how to resolve `conflict`, returns whether it was
+fn resolve(
+    store: &DirStore,
+    editor: &mut prompt::Prompt,
+    conflict: &Conflict,
+    dry_run: bool,
+) -> color_eyre::Result<bool> {
+    match conflict {
+        Conflict::Duplicate { paths, .. } => {
+            let candidates: Vec<_> = (1..=paths.len()).map(|number| number.to_string()).collect();
+            loop {
+                let question = format!("{} ", tr!("doctor-keep-which"));
+                let answer = prompt::ask(editor, &question, candidates.clone())?;
+                if answer.is_empty() {
+                    return Ok(false);
+                }
+                if let Some(index) = answer
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|number| (1..=paths.len()).contains(number))
+                {
+                    keep(store, paths, index - 1, dry_run)?;
+                    return Ok(true);
+                }
+            }
+        }
+        Conflict::Misplaced { path, .. } => {
+            let mut task = read(store, path)?;
+            loop {
+                let answer = prompt::ask(
+                    editor,
+                    &format!("{} ", tr!("doctor-move-or-rename")),
+                    ["move".to_string(), "rename".to_string()],
+                )?;
+                match answer.to_lowercase().as_str() {
+                    "" => return Ok(false),
+                    "m" | "move" => {}
+                    "r" | "rename" => {
+                        let Some(name) = store.task_name(&store.root().join(path)) else {
+                            bail!("{} can't hold a task, move it instead", path.display());
+                        };
+                        task.name = name;
+                    }
+                    _ => continue,
+                }
+                replace(store, &task, std::slice::from_ref(path), dry_run)?;
+                return Ok(true);
+            }
+        }
+    }
+}
+
+/// List the conflicting task files and resolve them on a terminal unless `check`
+pub fn run(
+    config: &Config,
+    store: &DirStore,
+    check: bool,
+    dry_run: bool,
+) -> color_eyre::Result<()> {
+    let conflicts = store
+        .conflicts()
+        .wrap_err("look for conflicting task files")?;
+    if conflicts.is_empty() {
+        println!("{}", color::header(&tr!("doctor-ok"), config));
+        return Ok(());
+    }
+
+    let interactive = !check && std::io::stdin().is_terminal();
+    let mut editor = match interactive {
+        true => Some(prompt::Prompt::new().wrap_err("open the terminal")?),
+        false => None,
+    };
+    let mut unresolved = 0;
+    for conflict in &conflicts {
+        match conflict {
+            Conflict::Duplicate { name, paths } => {
+                println!("{}", tr!("doctor-duplicate", name = name.display()));
+                let first = read(store, &paths[0])?;
+                for (number, path) in paths.iter().enumerate() {
+                    println!("  {}  {}", number + 1, path.display());
+                    for change in first.diff(&read(store, path)?) {
+                        println!("       {change}");
+                    }
+                }
+            }
+            Conflict::Misplaced { name, path } => {
+                let expected = store.task_path(name).map_or_else(
+                    |_| tr!("doctor-invalid-name"),
+                    |expected| expected.display().to_string(),
+                );
+                let path = store.root().join(path);
+                let message = tr!(
+                    "doctor-misplaced",
+                    name = name.display(),
+                    path = path.display(),
+                    expected = expected
+                );
+                println!("{message}");
+            }
+        }
+
+        let resolved = match &mut editor {
+            Some(editor) => resolve(store, editor, conflict, dry_run)?,
+            None => false,
+        };
+        if !resolved {
+            unresolved += 1;
+        }
+    }
+
+    if unresolved > 0 && !interactive {
+        bail!(
+            "{unresolved} task(s) are in conflicting files, run `dooit-rs doctor` on a terminal \
+             to resolve them"
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use dooit_tasks::{DirStore, Task, TaskStore, Urgency};
+    use pretty_assertions::assert_eq;
+
+    use super::keep;
+
+    #[test]
+    fn test_keep() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let store = DirStore::new(root);
+        store.save(&Task::new("report")).unwrap();
+        let copy = Task::new("report").with_urgency(Urgency::High);
+        let contents = store.format().serialize(&copy).unwrap();
+        std::fs::write(root.join("report (conflicted copy).toml"), contents).unwrap();
+        let paths = [
+            PathBuf::from("report.toml"),
+            PathBuf::from("report (conflicted copy).toml"),
+        ];
+
+        keep(&store, &paths, 1, false).unwrap();
+        assert_eq!(store.load_all().unwrap(), [copy]);
+        assert_eq!(store.conflicts().unwrap(), []);
+    }
+}
@@ -0,0 +1,106 @@
+//! `--dry-run`: print the files a command would change instead of changing them
+
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use dooit_tasks::{DirStore, Task, TaskStore};
+
+use crate::i18n::tr;
+
+/// A [`DirStore`] that only reports its changes if `--dry-run` was given
+///
+/// Tasks are still read from disk, so a dry run fails the same way the real one would.
+#[derive(Debug, Clone, Copy)]
+pub struct DryRun<'a> {
+    store: &'a DirStore,
+    enabled: bool,
+}
+
+impl<'a> DryRun<'a> {
+    pub fn new(store: &'a DirStore, enabled: bool) -> Self {
+        Self { store, enabled }
+    }
+
+    /// [`DirStore::unarchive_task`]
+    pub fn unarchive(&self, name: &Path) -> dooit_tasks::Result<bool> {
+        if !self.enabled {
+            return self.store.unarchive_task(name);
+        }
+
+        if !self.store.archive().task_path(name)?.exists() {
+            return Ok(false);
+        }
+        let path = self.store.task_path(name)?;
+        println!("{}", tr!("would-create", path = path.display()));
+        Ok(true)
+    }
+}
+
+impl TaskStore for DryRun<'_> {
+    fn load_all(&self) -> dooit_tasks::Result<Vec<Task>> {
+        self.store.load_all()
+    }
+
+    fn load(&self, name: &Path) -> dooit_tasks::Result<Option<Task>> {
+        self.store.load(name)
+    }
+
+    fn modified(&self, name: &Path) -> dooit_tasks::Result<Option<DateTime<Utc>>> {
+        self.store.modified(name)
+    }
+
+    fn load_under(&self, parent: &Path) -> dooit_tasks::Result<Vec<Task>> {
+        self.store.load_under(parent)
+    }
+
+    fn load_summaries_under(&self, parent: &Path) -> dooit_tasks::Result<Vec<Task>> {
+        self.store.load_summaries_under(parent)
+    }
+
+    fn save(&self, task: &Task) -> dooit_tasks::Result<()> {
+        if !self.enabled {
+            return self.store.save(task);
+        }
+
+        let path = self.store.task_path(&task.name)?;
+        let message = if path.exists() {
+            tr!("would-modify", path = path.display())
+        } else {
+            tr!("would-create", path = path.display())
+        };
+        println!("{message}");
+        Ok(())
+    }
+
+    fn save_all(&self, tasks: &[Task]) -> dooit_tasks::Result<()> {
+        if !self.enabled {
+            return self.store.save_all(tasks);
+        }
+        tasks.iter().try_for_each(|task| self.save(task))
+    }
+
+    fn remove(&self, name: &Path) -> dooit_tasks::Result<bool> {
+        if !self.enabled {
+            return self.store.remove(name);
+        }
+
+        let path = self.store.task_path(name)?;
+        if !path.exists() {
+            return Ok(false);
+        }
+        println!("{}", tr!("would-delete", path = path.display()));
+        Ok(true)
+    }
+
+    fn rename(&self, from: &Path, task: &Task) -> dooit_tasks::Result<()> {
+        if !self.enabled {
+            return self.store.rename(from, task);
+        }
+
+        self.save(task)?;
+        if from != task.name {
+            self.remove(from)?;
+        }
+        Ok(())
+    }
+}
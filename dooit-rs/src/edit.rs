@@ -0,0 +1,116 @@
+//! `edit`: change a task in the editor
+//!
+//! The editor gets a copy of the task's file, which is only saved back once it parses as a valid
+//! task with the same name, through [`actions::update`] like every other change. If it doesn't,
+//! the error is shown and, on a terminal, the editor can be opened again on what was written.
+//! Emptying the file aborts.
+
+use std::{io::IsTerminal, path::Path};
+
+use color_eyre::eyre::{bail, eyre, WrapErr};
+use dooit_tasks::{DirStore, Task, TaskFormat};
+
+use crate::{actions, config::Config, dry_run::DryRun, editor::Editor, i18n::tr, lookup, prompt};
+
+/// The task written in `text`, which has to keep the `name` it was edited under
+fn parse(format: TaskFormat, text: &str, name: &Path) -> color_eyre::Result<Task> {
+    let task = format
+        .deserialize(text.as_bytes())
+        .map_err(|err| eyre!("the task isn't valid: {err}"))?;
+    if task.name != name {
+        bail!(
+            "the name can't be changed by editing the task, it has to stay {}",
+            name.display()
+        );
+    }
+    let task = actions::stamp_completion(None, task);
+    task.validate()?;
+    Ok(task)
+}
+
+/// Edit the task called `name` in `editor` and save it, returns it unless it was left unchanged
+///
+/// Used by the TUI too, which restores the terminal first so the editor and prompts can use it.
+pub fn edit(
+    config: &Config,
+    store: &DirStore,
+    editor: &Editor,
+    name: &Path,
+    dry_run: bool,
+) -> color_eyre::Result<Option<Task>> {
+    actions::writable(config, name)?;
+    let path = store.task_path(name)?;
+    let original =
+        std::fs::read_to_string(&path).wrap_err_with(|| format!("read {}", path.display()))?;
+
+    let mut text = original.clone();
+    let task = loop {
+        text = editor.edit_text(&text, store.format().extension())?;
+        if text.trim().is_empty() || text == original {
+            return Ok(None);
+        }
+        match parse(store.format(), &text, name) {
+            Ok(task) => break task,
+            Err(err) if !std::io::stdin().is_terminal() => {
+                return Err(err.wrap_err(format!("{} is unchanged", name.display())));
+            }
+            Err(err) => {
+                eprintln!("error: {err:#}");
+                if !prompt::confirm(&tr!("edit-again"), false)? {
+                    return Ok(None);
+                }
+            }
+        }
+    };
+
+    actions::update(config, &DryRun::new(store, dry_run), task.clone())?;
+    Ok(Some(task))
+}
+
+/// Edit the task called `name` (or starting like it, see [`lookup`]) in `editor`
+pub fn run(
+    config: &Config,
+    store: &DirStore,
+    editor: &Editor,
+    name: &Path,
+    dry_run: bool,
+) -> color_eyre::Result<()> {
+    let name = lookup::resolve(store, name)?;
+    match edit(config, store, editor, &name, dry_run)? {
+        Some(_) if dry_run => {}
+        Some(_) => println!("{}", tr!("task-modified", name = name.display())),
+        None => println!("{}", tr!("task-unchanged", name = name.display())),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use dooit_tasks::{Task, TaskFormat, Urgency};
+    use pretty_assertions::assert_eq;
+
+    use super::parse;
+
+    #[test]
+    fn test_parse() {
+        let name = Path::new("report");
+        let task = Task::new("report").with_urgency(Urgency::High);
+        let text = String::from_utf8(TaskFormat::Toml.serialize(&task).unwrap()).unwrap();
+
+        assert_eq!(parse(TaskFormat::Toml, &text, name).unwrap(), task);
+        assert!(parse(TaskFormat::Toml, "name = \"report", name).is_err());
+        let renamed = text.replace("\"report\"", "\"other\"");
+        assert!(parse(TaskFormat::Toml, &renamed, name).is_err());
+
+        // Reopening a completed task drops its completion date
+        let completed = Task {
+            completed_at: Some(chrono::Utc::now()),
+            ..task.clone().complete()
+        };
+        let text = String::from_utf8(TaskFormat::Toml.serialize(&completed).unwrap()).unwrap();
+        let reopened = text.replace("completed = true", "completed = false");
+        assert_eq!(parse(TaskFormat::Toml, &reopened, name).unwrap(), task);
+    }
+}
@@ -0,0 +1,125 @@
+//! Finding and running the user's editor
+//!
+//! The editor is the first one set of:
+//!
+//! 1. `--editor`
+//! 2. `editor` in the config
+//! 3. `$VISUAL`
+//! 4. `$EDITOR`
+//! 5. `notepad` on Windows, otherwise `nano` if it's installed and `vi` if not
+//!
+//! Editors can take arguments, quoted like in a shell, e.g. `code --wait`.
+
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use color_eyre::eyre::{bail, eyre, WrapErr};
+
+use crate::{config::Config, failure::Failure};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Editor {
+    program: PathBuf,
+    args: Vec<OsString>,
+}
+
+/// Whether `program` is in one of the directories in `$PATH`
+fn installed(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|path| std::env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+}
+
+fn platform_default() -> &'static str {
+    if cfg!(windows) {
+        "notepad"
+    } else if installed("nano") {
+        "nano"
+    } else {
+        "vi"
+    }
+}
+
+impl Editor {
+    /// Parse a command like `code --wait`
+    pub fn parse(command: &str) -> color_eyre::Result<Self> {
+        let mut words = shlex::split(command)
+            .ok_or_else(|| eyre!("couldn't parse editor command {command:?}"))?
+            .into_iter();
+        let program = words
+            .next()
+            .ok_or_else(|| eyre!("the editor command is empty"))?;
+
+        Ok(Self {
+            program: program.into(),
+            args: words.map(OsString::from).collect(),
+        })
+    }
+
+    /// The editor chosen by `flag` (from `--editor`), the config or the environment, see the
+    /// [module docs](self)
+    pub fn resolve(flag: Option<&str>, config: &Config) -> color_eyre::Result<Self> {
+        let env = |name| {
+            std::env::var(name)
+                .ok()
+                .filter(|value| !value.trim().is_empty())
+        };
+        let command = flag
+            .map(str::to_string)
+            .or_else(|| config.editor.clone())
+            .or_else(|| env("VISUAL"))
+            .or_else(|| env("EDITOR"))
+            .unwrap_or_else(|| platform_default().to_string());
+
+        Self::parse(&command)
+    }
+
+    /// Open `path` in the editor and wait for it to exit
+    pub fn edit(&self, path: &Path) -> color_eyre::Result<()> {
+        let status = Command::new(&self.program)
+            .args(&self.args)
+            .arg(path)
+            .status()
+            .map_err(|err| {
+                Failure::EditorFailed
+                    .report()
+                    .wrap_err(format!("run {}: {err}", self.program.display()))
+            })?;
+
+        if !status.success() {
+            bail!("{} exited with {status}", self.program.display());
+        }
+        Ok(())
+    }
+
+    /// Let the user edit `text` in a temporary file, returning the result
+    pub fn edit_text(&self, text: &str, extension: &str) -> color_eyre::Result<String> {
+        let path = std::env::temp_dir().join(format!("dooit-{}.{extension}", std::process::id()));
+        std::fs::write(&path, text).wrap_err_with(|| format!("write {}", path.display()))?;
+
+        let edited = self.edit(&path).and_then(|()| {
+            std::fs::read_to_string(&path).wrap_err_with(|| format!("read {}", path.display()))
+        });
+        let _ = std::fs::remove_file(&path);
+        edited
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::Editor;
+
+    #[test]
+    fn test_parse_editor() {
+        let editor = Editor::parse("'/opt/My Editor/edit' --wait -n").unwrap();
+        assert_eq!(editor.program.to_str(), Some("/opt/My Editor/edit"));
+        assert_eq!(editor.args, ["--wait", "-n"]);
+
+        assert!(Editor::parse("").is_err());
+        assert!(Editor::parse("code '--wait").is_err());
+    }
+}
@@ -0,0 +1,145 @@
+//! External subcommands: `dooit-rs foo` runs `dooit-foo` from `PATH` if `foo` isn't built in
+//!
+//! The command gets the arguments after its name and these environment variables, so it can find
+//! the tasks without reading the config itself:
+//!
+//! - `DOOIT_DATA_DIR`: where the tasks are stored
+//! - `DOOIT_CONFIG_DIR` and `DOOIT_STATE_DIR`: the config and state directories
+//! - `DOOIT_BIN`: the dooit-rs binary, to call back into it (e.g. `$DOOIT_BIN list`)
+//! - `DOOIT_CONFIG_<OPTION>` for every `--config-option`, so `$DOOIT_BIN` gets them too
+//!
+//! On Unix the command replaces dooit-rs, elsewhere its exit code is passed on.
+
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use clap::{error::ErrorKind, CommandFactory};
+use color_eyre::eyre::{eyre, WrapErr};
+use dooit_tasks::dirs;
+
+use crate::{
+    config::{layers, Config},
+    failure, Cli,
+};
+
+/// Prefix of the executables run as external subcommands
+const PREFIX: &str = "dooit-";
+
+/// The `dooit-<name>` executable in `PATH`, if there is one
+fn find(name: &str) -> Option<PathBuf> {
+    let file = format!("{PREFIX}{name}{}", std::env::consts::EXE_SUFFIX);
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(&file))
+        .find(|path| is_executable(path))
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    path.metadata()
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// The environment variable setting a `KEY=VALUE` config option, see [`layers`]
+fn option_var(option: &str) -> Option<(String, String)> {
+    let (key, value) = option.split_once('=')?;
+    let name = key.trim().to_uppercase().replace('.', "__");
+    Some((
+        format!("{}{name}", layers::ENV_PREFIX),
+        value.trim().to_string(),
+    ))
+}
+
+/// Exit with clap's usage error for an unknown subcommand, suggesting a similar built-in one
+fn unknown(name: &str) -> ! {
+    let mut command = Cli::command();
+    let suggestion = command
+        .get_subcommands()
+        .flat_map(|subcommand| {
+            std::iter::once(subcommand.get_name()).chain(subcommand.get_all_aliases())
+        })
+        .map(|candidate| (strsim::jaro(name, candidate), candidate))
+        .filter(|(similarity, _)| *similarity > 0.7)
+        .max_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, candidate)| format!("\n\n  tip: a similar subcommand exists: '{candidate}'"))
+        .unwrap_or_default();
+    let message = format!(
+        "unrecognized subcommand '{name}', and there is no {PREFIX}{name} in PATH{suggestion}"
+    );
+    command.error(ErrorKind::InvalidSubcommand, message).exit()
+}
+
+/// Run the external subcommand `args[0]` with the rest of `args`, `options` are the
+/// `--config-option`s given to dooit-rs
+pub fn run(config: &Config, options: &[String], args: &[OsString]) -> color_eyre::Result<()> {
+    let (name, args) = args
+        .split_first()
+        .ok_or_else(|| eyre!("no subcommand given"))?;
+    let name = name.to_string_lossy();
+    let Some(path) = find(&name) else {
+        unknown(&name);
+    };
+
+    let mut command = Command::new(&path);
+    command
+        .args(args)
+        .env("DOOIT_DATA_DIR", failure::data_dir(config)?)
+        .env("DOOIT_CONFIG_DIR", failure::config_dir()?)
+        .envs(options.iter().filter_map(|option| option_var(option)));
+    if let Some(state_dir) = dirs::get_state_dir() {
+        command.env("DOOIT_STATE_DIR", state_dir);
+    }
+    if let Ok(bin) = std::env::current_exe() {
+        command.env("DOOIT_BIN", bin);
+    }
+    tracing::debug!("running {}", path.display());
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+
+        // Only returns if it failed
+        let err = command.exec();
+        Err(err).wrap_err_with(|| format!("run {}", path.display()))
+    }
+    #[cfg(not(unix))]
+    {
+        let status = command
+            .status()
+            .wrap_err_with(|| format!("run {}", path.display()))?;
+        std::process::exit(status.code().unwrap_or(1))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::option_var;
+
+    #[test]
+    fn test_option_var() {
+        assert_eq!(
+            option_var("list.default_sort = name-ascending"),
+            Some((
+                "DOOIT_CONFIG_LIST__DEFAULT_SORT".to_string(),
+                "name-ascending".to_string()
+            ))
+        );
+        assert_eq!(
+            option_var("locale=de"),
+            Some(("DOOIT_CONFIG_LOCALE".to_string(), "de".to_string()))
+        );
+        assert_eq!(option_var("nonsense"), None);
+    }
+}
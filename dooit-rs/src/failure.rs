@@ -0,0 +1,118 @@
+//! Failures the user can fix, reported with a suggestion and their own exit code
+//!
+//! Exit codes follow `sysexits.h`: I/O errors exit with 74 and anything else unexpected with 1.
+
+use std::{fmt::Display, path::Path, process::ExitCode};
+
+use color_eyre::{Report, Section};
+use dooit_tasks::{dirs, DirStore};
+
+use crate::{config::Config, signing};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Failure {
+    /// The platform doesn't tell where to keep data (usually because `$HOME` isn't set)
+    NoDirs,
+    /// The configured editor couldn't be run
+    EditorFailed,
+    /// The task is in a [read-only](Config::read_only_source) source
+    ReadOnly,
+    /// A hook exited with an error, rejecting the change
+    HookRejected,
+    /// A full WIP limit with `limits.strict` on, see [`crate::limits`]
+    OverLimit,
+}
+
+impl Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Failure::NoDirs => "couldn't find the data and config directories",
+            Failure::EditorFailed => "couldn't run the editor",
+            Failure::ReadOnly => "the task is read-only",
+            Failure::HookRejected => "a hook rejected the change",
+            Failure::OverLimit => "a WIP limit is full",
+        })
+    }
+}
+
+impl std::error::Error for Failure {}
+
+impl Failure {
+    fn exit_code(self) -> u8 {
+        match self {
+            // EX_OSFILE
+            Failure::NoDirs => 72,
+            // EX_UNAVAILABLE
+            Failure::EditorFailed => 69,
+            // EX_NOPERM
+            Failure::ReadOnly => 77,
+            // EX_DATAERR
+            Failure::HookRejected | Failure::OverLimit => 65,
+        }
+    }
+
+    fn suggestion(self) -> &'static str {
+        match self {
+            Failure::NoDirs => "make sure the HOME environment variable is set",
+            Failure::EditorFailed => "check that the editor is installed and in your PATH",
+            Failure::ReadOnly => "remove its source from `read_only` in the config to change it",
+            Failure::HookRejected => "check the output of the hook above",
+            Failure::OverLimit => {
+                "finish some of the tasks first, or raise the limit in the config"
+            }
+        }
+    }
+
+    /// Turn this failure into a report with a suggestion on how to fix it
+    pub fn report(self) -> Report {
+        Report::new(self).suggestion(self.suggestion())
+    }
+
+    /// The failure behind `report`, if it's one
+    pub fn of(report: &Report) -> Option<Self> {
+        report
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<Failure>())
+            .copied()
+    }
+}
+
+/// Exit code for a report returned from `main`
+pub fn exit_code(report: &Report) -> ExitCode {
+    if let Some(failure) = Failure::of(report) {
+        return failure.exit_code().into();
+    }
+
+    // EX_IOERR
+    let io = report.chain().any(|cause| {
+        cause.is::<std::io::Error>()
+            || matches!(cause.downcast_ref(), Some(dooit_tasks::Error::Io(_)))
+    });
+    ExitCode::from(if io { 74 } else { 1 })
+}
+
+/// The `data_dir` from the config, or the platform's data directory
+pub fn data_dir(config: &Config) -> color_eyre::Result<&Path> {
+    match &config.data_dir {
+        Some(dir) => Ok(dir),
+        None => dirs::get_data_dir().ok_or_else(|| Failure::NoDirs.report()),
+    }
+}
+
+pub fn config_dir() -> color_eyre::Result<&'static Path> {
+    dirs::get_config_dir().ok_or_else(|| Failure::NoDirs.report())
+}
+
+/// The task store in the user's data directory, warning about files that aren't signed if
+/// `signing` is on (see [`crate::signing`])
+pub fn store(config: &Config) -> color_eyre::Result<DirStore> {
+    let store = unchecked_store(config)?;
+    signing::check(config, &store);
+    Ok(store)
+}
+
+/// [`store`] without checking the signature, for helpers that only read from it and have to be
+/// quick, like completions
+pub fn unchecked_store(config: &Config) -> color_eyre::Result<DirStore> {
+    Ok(DirStore::new(data_dir(config)?))
+}
@@ -0,0 +1,120 @@
+//! Atom feed of the tasks due soon, served at `/feed` by `serve`
+//!
+//! Every pending task due in the next `days` days (overdue ones included) is an entry. Its
+//! `updated` date is the due date, so readers order the entries by deadline, and its id includes
+//! the due date, so a task shows up again when it's rescheduled.
+
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use dooit_tasks::Task;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+
+/// Escape `text` for use in XML content and attributes
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn timestamp(date: DateTime<Utc>) -> String {
+    date.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// The Atom feed of the pending `tasks` due within `days` of `now`, soonest first
+pub fn atom(tasks: &[Task], days: u32, now: DateTime<Utc>) -> String {
+    let until = now + Duration::days(days.into());
+    let mut due: Vec<_> = tasks
+        .iter()
+        .filter(|task| !task.completed)
+        .filter_map(|task| Some((task.due.filter(|due| *due <= until)?, task)))
+        .collect();
+    due.sort_by_key(|(due, _)| *due);
+
+    let mut feed = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    feed.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    feed.push_str("  <title>dooit-rs: upcoming tasks</title>\n");
+    feed.push_str("  <id>urn:dooit-rs:feed</id>\n");
+    feed.push_str(&format!("  <updated>{}</updated>\n", timestamp(now)));
+    feed.push_str("  <author><name>dooit-rs</name></author>\n");
+    for (due, task) in due {
+        let name = task.name.to_string_lossy();
+        let id = utf8_percent_encode(&name, NON_ALPHANUMERIC);
+        let due = timestamp(due);
+        feed.push_str("  <entry>\n");
+        feed.push_str(&format!("    <title>{}</title>\n", escape(&name)));
+        feed.push_str(&format!("    <id>urn:dooit-rs:task:{id}:{due}</id>\n"));
+        feed.push_str(&format!("    <updated>{due}</updated>\n"));
+        for tag in &task.tags {
+            feed.push_str(&format!("    <category term=\"{}\"/>\n", escape(tag)));
+        }
+        let summary = format!("{} ({})", task.urgency, due);
+        feed.push_str(&format!("    <summary>{}</summary>\n", escape(&summary)));
+        if let Some(description) = &task.description {
+            feed.push_str(&format!(
+                "    <content type=\"text\">{}</content>\n",
+                escape(description)
+            ));
+        }
+        feed.push_str("  </entry>\n");
+    }
+    feed.push_str("</feed>\n");
+    feed
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{Duration, TimeZone, Utc};
+    use dooit_tasks::Task;
+    use pretty_assertions::assert_eq;
+
+    use super::atom;
+
+    #[test]
+    fn test_atom() {
+        let now = Utc.with_ymd_and_hms(2024, 5, 1, 9, 0, 0).unwrap();
+        let tasks = [
+            Task::new("later").with_due_date(now + Duration::days(10)),
+            Task::new("done").with_due_date(now).complete(),
+            Task::new("undated"),
+            Task {
+                description: Some("Q&A slides".to_string()),
+                tags: vec!["work".to_string()],
+                ..Task::new("talk/<prep>").with_due_date(now + Duration::days(2))
+            },
+            Task::new("overdue").with_due_date(now - Duration::days(1)),
+        ];
+
+        assert_eq!(
+            atom(&tasks, 7, now),
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+             <feed xmlns=\"http://www.w3.org/2005/Atom\">\n  \
+               <title>dooit-rs: upcoming tasks</title>\n  \
+               <id>urn:dooit-rs:feed</id>\n  \
+               <updated>2024-05-01T09:00:00Z</updated>\n  \
+               <author><name>dooit-rs</name></author>\n  \
+               <entry>\n    \
+                 <title>overdue</title>\n    \
+                 <id>urn:dooit-rs:task:overdue:2024-04-30T09:00:00Z</id>\n    \
+                 <updated>2024-04-30T09:00:00Z</updated>\n    \
+                 <summary>Low (2024-04-30T09:00:00Z)</summary>\n  \
+               </entry>\n  \
+               <entry>\n    \
+                 <title>talk/&lt;prep&gt;</title>\n    \
+                 <id>urn:dooit-rs:task:talk%2F%3Cprep%3E:2024-05-03T09:00:00Z</id>\n    \
+                 <updated>2024-05-03T09:00:00Z</updated>\n    \
+                 <category term=\"work\"/>\n    \
+                 <summary>Low (2024-05-03T09:00:00Z)</summary>\n    \
+                 <content type=\"text\">Q&amp;A slides</content>\n  \
+               </entry>\n\
+             </feed>\n"
+        );
+    }
+}
@@ -0,0 +1,126 @@
+//! `forecast`: when the tasks matching a filter will be done, going by how many tasks get
+//! completed every week
+//!
+//! The velocity is the number of tasks completed per week over the last few weeks, counting every
+//! completed task, not just the ones matching the filter. The expected finish uses the average,
+//! the optimistic and pessimistic ones the weeks at the upper and lower quartile.
+
+use chrono::{DateTime, Duration, Local, Utc};
+use color_eyre::eyre::WrapErr;
+use dooit_tasks::{Task, TaskStore};
+
+use crate::{color, config::Config, i18n::tr, report::Filter};
+
+/// Tasks completed in each of the last `weeks` weeks before `now`, the most recent first
+fn weekly_completions(tasks: &[Task], weeks: usize, now: DateTime<Utc>) -> Vec<usize> {
+    let mut counts = vec![0; weeks];
+    for completed_at in tasks.iter().filter_map(|task| task.completed_at) {
+        if completed_at > now {
+            continue;
+        }
+        let week = (now - completed_at).num_weeks() as usize;
+        if let Some(count) = counts.get_mut(week) {
+            *count += 1;
+        }
+    }
+    counts
+}
+
+/// The value at the `quantile` (between 0 and 1) of `weekly`, interpolating between weeks
+fn quantile(weekly: &[usize], quantile: f64) -> f64 {
+    let mut sorted = weekly.to_vec();
+    sorted.sort_unstable();
+    let position = quantile * (sorted.len() - 1) as f64;
+    let (below, above) = (position.floor() as usize, position.ceil() as usize);
+    let fraction = position - below as f64;
+    sorted[below] as f64 * (1.0 - fraction) + sorted[above] as f64 * fraction
+}
+
+/// When `remaining` tasks are done at `per_week`, `None` if never
+fn finish(remaining: usize, per_week: f64, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    if per_week <= 0.0 {
+        return None;
+    }
+    let weeks = remaining as f64 / per_week;
+    Some(now + Duration::seconds((weeks * Duration::weeks(1).num_seconds() as f64) as i64))
+}
+
+/// Print the velocity over the last `weeks` weeks and when the tasks matching `filter` are done
+pub fn run(
+    config: &Config,
+    store: &impl TaskStore,
+    filter: &str,
+    weeks: usize,
+) -> color_eyre::Result<()> {
+    let filter: Filter = filter.parse().wrap_err("parse the filter")?;
+    let tasks = store.load_all().wrap_err("load tasks")?;
+    let now = Utc::now();
+
+    let weekly = weekly_completions(&tasks, weeks.max(1), now);
+    let average = weekly.iter().sum::<usize>() as f64 / weekly.len() as f64;
+    let title = tr!(
+        "forecast-velocity",
+        velocity = format!("{average:.1}"),
+        weeks = weekly.len()
+    );
+    println!("{}", color::header(&title, config));
+
+    let remaining = tasks
+        .iter()
+        .filter(|task| !task.completed && filter.matches(task, now))
+        .count();
+    if remaining == 0 {
+        println!("{}", tr!("forecast-done"));
+        return Ok(());
+    }
+    println!("{}", tr!("forecast-remaining", count = remaining));
+
+    let date = |per_week: f64| match finish(remaining, per_week, now) {
+        Some(date) => date.with_timezone(&Local).format("%a %F").to_string(),
+        None => tr!("forecast-never"),
+    };
+    println!(
+        "{}",
+        tr!("forecast-optimistic", date = date(quantile(&weekly, 0.75)))
+    );
+    println!("{}", tr!("forecast-expected", date = date(average)));
+    println!(
+        "{}",
+        tr!("forecast-pessimistic", date = date(quantile(&weekly, 0.25)))
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{Duration, Utc};
+    use dooit_tasks::Task;
+    use pretty_assertions::assert_eq;
+
+    use super::{finish, quantile, weekly_completions};
+
+    #[test]
+    fn test_forecast() {
+        let now = Utc::now();
+        let completed = |days: i64| Task {
+            completed_at: Some(now - Duration::days(days)),
+            ..Task::new("done").complete()
+        };
+        let tasks = [
+            completed(1),
+            completed(2),
+            completed(8),
+            completed(20),
+            completed(30),
+            Task::new("pending"),
+        ];
+
+        let weekly = weekly_completions(&tasks, 4, now);
+        assert_eq!(weekly, [2, 1, 1, 0]);
+        assert_eq!(quantile(&weekly, 0.75), 1.25);
+        assert_eq!(quantile(&weekly, 0.25), 0.75);
+
+        assert_eq!(finish(4, 2.0, now), Some(now + Duration::weeks(2)));
+        assert_eq!(finish(4, 0.0, now), None);
+    }
+}
@@ -0,0 +1,332 @@
+//! The dependency graph of the tasks, for Graphviz or Mermaid
+//!
+//! Every task is a node with an edge to each task that depends on it, so edges point in the order
+//! tasks can be done. Nodes get the color `list` would show them in, and their outline shows the
+//! status: bold once started, dotted while waiting and dashed once completed.
+//!
+//! The Mermaid Gantt chart is a timeline instead: tasks are bars from their scheduled date to their
+//! due date, taking their estimate when only one of them is set, and milestones when they only have
+//! a due date. Subtasks are grouped in a section per project (the first part of their name).
+
+use std::{collections::BTreeMap, fmt::Write, path::Path};
+
+use chrono::{DateTime, Local, Utc};
+use clap::ValueEnum;
+use dooit_tasks::{Status, Task};
+
+use crate::{color, config::Config};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GraphFormat {
+    /// Graphviz, e.g. `dooit-rs graph | dot -Tsvg > tasks.svg`
+    #[default]
+    Dot,
+    /// A Mermaid flowchart, for Markdown documents
+    Mermaid,
+    /// A Mermaid Gantt chart of the tasks with dates, grouped by project
+    MermaidGantt,
+}
+
+/// Line around a node, showing the status of the task
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outline {
+    Bold,
+    Dotted,
+    Dashed,
+}
+
+impl Outline {
+    fn dot(self) -> &'static str {
+        match self {
+            Outline::Bold => "bold",
+            Outline::Dotted => "dotted",
+            Outline::Dashed => "dashed",
+        }
+    }
+
+    fn mermaid(self) -> &'static str {
+        match self {
+            Outline::Bold => "stroke-width:3px",
+            Outline::Dotted => "stroke-dasharray:2 2",
+            Outline::Dashed => "stroke-dasharray:5 5",
+        }
+    }
+}
+
+/// How a task is drawn
+struct Node {
+    label: String,
+    color: Option<&'static str>,
+    outline: Option<Outline>,
+}
+
+impl Node {
+    fn new(task: &Task, config: &Config, now: DateTime<Utc>) -> Self {
+        let outline = match task.status {
+            _ if task.completed => Some(Outline::Dashed),
+            Status::Started => Some(Outline::Bold),
+            Status::Waiting => Some(Outline::Dotted),
+            Status::Pending => None,
+        };
+        let color = match task.completed {
+            true => Some(color::Color::BrightBlack),
+            false => color::style(task, config, now).fg,
+        };
+        Self {
+            label: task.name.display().to_string(),
+            color: color.map(color::Color::hex),
+            outline,
+        }
+    }
+
+    /// A dependency that isn't one of the tasks, e.g. a completed one
+    fn missing(name: &Path) -> Self {
+        Self {
+            label: name.display().to_string(),
+            color: None,
+            outline: Some(Outline::Dashed),
+        }
+    }
+}
+
+/// The nodes, numbered by position, and the edges between them
+fn graph(tasks: &[Task], config: &Config, now: DateTime<Utc>) -> (Vec<Node>, Vec<(usize, usize)>) {
+    let mut ids: BTreeMap<&Path, usize> = BTreeMap::new();
+    let mut nodes = Vec::new();
+    for task in tasks {
+        ids.insert(&task.name, nodes.len());
+        nodes.push(Node::new(task, config, now));
+    }
+
+    let mut edges = Vec::new();
+    for (id, task) in tasks.iter().enumerate() {
+        for dependency in &task.depends_on {
+            let from = *ids.entry(dependency).or_insert_with(|| {
+                nodes.push(Node::missing(dependency));
+                nodes.len() - 1
+            });
+            edges.push((from, id));
+        }
+    }
+    (nodes, edges)
+}
+
+fn dot(nodes: &[Node], edges: &[(usize, usize)]) -> String {
+    let mut out = String::from("digraph tasks {\n    rankdir=LR;\n    node [shape=box];\n");
+    for (id, node) in nodes.iter().enumerate() {
+        let label = node.label.replace('\\', "\\\\").replace('"', "\\\"");
+        let mut attributes = vec![format!("label=\"{label}\"")];
+        if let Some(color) = node.color {
+            attributes.push(format!("color=\"{color}\", fontcolor=\"{color}\""));
+        }
+        if let Some(outline) = node.outline {
+            attributes.push(format!("style={}", outline.dot()));
+        }
+        writeln!(out, "    n{id} [{}];", attributes.join(", ")).expect("writing to a String");
+    }
+    for (from, to) in edges {
+        writeln!(out, "    n{from} -> n{to};").expect("writing to a String");
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn mermaid(nodes: &[Node], edges: &[(usize, usize)]) -> String {
+    let mut out = String::from("flowchart LR\n");
+    for (id, node) in nodes.iter().enumerate() {
+        let label = node.label.replace('"', "#quot;");
+        writeln!(out, "    n{id}[\"{label}\"]").expect("writing to a String");
+    }
+    for (from, to) in edges {
+        writeln!(out, "    n{from} --> n{to}").expect("writing to a String");
+    }
+    for (id, node) in nodes.iter().enumerate() {
+        let mut styles = Vec::new();
+        if let Some(color) = node.color {
+            styles.push(format!("stroke:{color},color:{color}"));
+        }
+        styles.extend(node.outline.map(|outline| outline.mermaid().to_string()));
+        if !styles.is_empty() {
+            writeln!(out, "    style n{id} {}", styles.join(",")).expect("writing to a String");
+        }
+    }
+    out
+}
+
+/// Where a task is on the timeline: its start and end, `None` for a milestone
+fn span(task: &Task) -> Option<(DateTime<Utc>, Option<DateTime<Utc>>)> {
+    let estimate = task.estimate.map(|estimate| estimate.0);
+    match (task.scheduled, task.due, estimate) {
+        (Some(start), Some(end), _) => Some((start, Some(end.max(start)))),
+        (Some(start), None, Some(estimate)) => Some((start, Some(start + estimate))),
+        (None, Some(end), Some(estimate)) => Some((end - estimate, Some(end))),
+        (None, Some(due), None) => Some((due, None)),
+        _ => None,
+    }
+}
+
+fn gantt(tasks: &[Task], now: DateTime<Utc>) -> String {
+    const FORMAT: &str = "%Y-%m-%d %H:%M";
+    let date = |date: DateTime<Utc>| date.with_timezone(&Local).format(FORMAT).to_string();
+    // `:` ends the label and `;` the line
+    let escape = |label: &str| label.replace(';', "#59;").replace(':', "#58;");
+
+    // The tasks that aren't in a project come before the first section
+    let mut sections: BTreeMap<Option<String>, Vec<String>> = BTreeMap::new();
+    for (id, task) in tasks.iter().enumerate() {
+        let Some((start, end)) = span(task) else {
+            continue;
+        };
+        let mut components = task.name.components();
+        let first = components.next().map(|first| first.as_os_str());
+        let rest = components.as_path();
+        let is_project = tasks
+            .iter()
+            .any(|other| other.name != task.name && other.name.starts_with(&task.name));
+        let (section, label) = match first {
+            Some(first) if !rest.as_os_str().is_empty() => (
+                Some(first.to_string_lossy().into_owned()),
+                rest.display().to_string(),
+            ),
+            _ if is_project => (
+                Some(task.name.display().to_string()),
+                task.name.display().to_string(),
+            ),
+            _ => (None, task.name.display().to_string()),
+        };
+
+        let mut tags = Vec::new();
+        if task.completed {
+            tags.push("done");
+        } else if task.status == Status::Started {
+            tags.push("active");
+        }
+        if !task.completed && task.due.is_some_and(|due| due < now) {
+            tags.push("crit");
+        }
+        let end = match end {
+            Some(end) => date(end),
+            None => {
+                tags.push("milestone");
+                "0d".to_string()
+            }
+        };
+        tags.push("");
+        let line = format!(
+            "{} :{}t{id}, {}, {end}",
+            escape(&label),
+            tags.join(", "),
+            date(start)
+        );
+        sections.entry(section).or_default().push(line);
+    }
+
+    let mut out = String::from("gantt\n    dateFormat YYYY-MM-DD HH:mm\n");
+    for (section, lines) in sections {
+        if let Some(section) = section {
+            writeln!(out, "    section {}", escape(&section)).expect("writing to a String");
+        }
+        for line in lines {
+            writeln!(out, "    {line}").expect("writing to a String");
+        }
+    }
+    out
+}
+
+/// The graph of `tasks` in `format`
+pub fn render(tasks: &[Task], format: GraphFormat, config: &Config, now: DateTime<Utc>) -> String {
+    match format {
+        GraphFormat::Dot => {
+            let (nodes, edges) = graph(tasks, config, now);
+            dot(&nodes, &edges)
+        }
+        GraphFormat::Mermaid => {
+            let (nodes, edges) = graph(tasks, config, now);
+            mermaid(&nodes, &edges)
+        }
+        GraphFormat::MermaidGantt => gantt(tasks, now),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{Duration, Local, TimeZone, Utc};
+    use dooit_tasks::{Status, Task};
+    use pretty_assertions::assert_eq;
+
+    use super::{render, GraphFormat};
+    use crate::config::Config;
+
+    #[test]
+    fn test_render() {
+        let tasks = [
+            Task::new("design"),
+            Task {
+                status: Status::Started,
+                depends_on: vec!["design".into(), "hire".into()],
+                ..Task::new("build \"v1\"")
+            },
+        ];
+        let config = Config::default();
+        let now = Utc::now();
+
+        assert_eq!(
+            render(&tasks, GraphFormat::Dot, &config, now),
+            "digraph tasks {
+    rankdir=LR;
+    node [shape=box];
+    n0 [label=\"design\"];
+    n1 [label=\"build \\\"v1\\\"\", style=bold];
+    n2 [label=\"hire\", style=dashed];
+    n0 -> n1;
+    n2 -> n1;
+}
+"
+        );
+        assert_eq!(
+            render(&tasks, GraphFormat::Mermaid, &config, now),
+            "flowchart LR
+    n0[\"design\"]
+    n1[\"build #quot;v1#quot;\"]
+    n2[\"hire\"]
+    n0 --> n1
+    n2 --> n1
+    style n1 stroke-width:3px
+    style n2 stroke-dasharray:5 5
+"
+        );
+
+        let now = Local
+            .with_ymd_and_hms(2024, 5, 1, 9, 0, 0)
+            .unwrap()
+            .with_timezone(&Utc);
+        let tasks = [
+            Task::new("errands")
+                .with_due_date(now - Duration::days(1))
+                .with_estimate(Duration::hours(1)),
+            Task::new("thesis").with_due_date(now + Duration::days(30)),
+            Task::new("thesis/draft: intro")
+                .with_scheduled(now)
+                .with_estimate(Duration::days(2))
+                .complete(),
+            Task {
+                status: Status::Started,
+                ..Task::new("thesis/write")
+                    .with_scheduled(now + Duration::days(2))
+                    .with_due_date(now + Duration::days(20))
+            },
+            Task::new("someday"),
+        ];
+        assert_eq!(
+            render(&tasks, GraphFormat::MermaidGantt, &config, now),
+            "gantt
+    dateFormat YYYY-MM-DD HH:mm
+    errands :crit, t0, 2024-04-30 08:00, 2024-04-30 09:00
+    section thesis
+    thesis :milestone, t1, 2024-05-31 09:00, 0d
+    draft#58; intro :done, t2, 2024-05-01 09:00, 2024-05-03 09:00
+    write :active, t3, 2024-05-03 09:00, 2024-05-21 09:00
+"
+        );
+    }
+}
@@ -0,0 +1,214 @@
+//! `habits`: the recurring tasks tracked as habits (see `add --habit`), with their last few days,
+//! streak and completion rate
+//!
+//! Every day shows whether an occurrence was due that day and if it got completed or missed. The
+//! pending occurrence counts as missed once the next one is due, like completing it late would
+//! record it (see [`Task::next_occurrence`]). The streak is the number of occurrences completed in
+//! a row up to the latest one, the rate the share of the occurrences in the window that were
+//! completed.
+
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
+use color_eyre::eyre::WrapErr;
+use dooit_tasks::{HumanDuration, IconSet, Occurrence, Task, TaskStore};
+
+use crate::{color, config::Config, i18n::tr};
+
+/// Days `habits` and `show` go back by default
+pub const DAYS: u32 = 30;
+
+/// What happened to a habit on a day
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Day {
+    Done,
+    Missed,
+    /// The pending occurrence is due that day
+    Due,
+    /// Nothing was due that day
+    Free,
+}
+
+impl Day {
+    fn glyph(self, icons: IconSet) -> &'static str {
+        match (icons, self) {
+            (IconSet::Nerd, Day::Done) => "■",
+            (IconSet::Nerd, Day::Missed) => "✗",
+            (IconSet::Nerd, Day::Due) => "□",
+            (IconSet::Nerd, Day::Free) => "·",
+            (IconSet::Ascii, Day::Done) => "#",
+            (IconSet::Ascii, Day::Missed) => "x",
+            (IconSet::Ascii, Day::Due) => "o",
+            (IconSet::Ascii, Day::Free) => ".",
+        }
+    }
+}
+
+/// The recorded occurrences of `task` and the overdue pending ones as missed, oldest first, and
+/// when the pending one is due
+///
+/// Pending occurrences due before `since` are skipped except for the latest, which is enough to
+/// break the streak.
+fn occurrences(
+    task: &Task,
+    since: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> (Vec<Occurrence>, Option<DateTime<Utc>>) {
+    let mut occurrences = task.occurrences.clone();
+    let (Some(HumanDuration(every)), Some(mut due)) = (task.recur, task.due) else {
+        return (occurrences, task.due);
+    };
+    if every <= Duration::zero() {
+        return (occurrences, task.due);
+    }
+
+    let behind = (since - due).num_seconds() / every.num_seconds() - 1;
+    if behind > 0 {
+        due += every * i32::try_from(behind).unwrap_or(i32::MAX);
+    }
+    while due + every <= now {
+        occurrences.push(Occurrence {
+            due,
+            completed_at: None,
+        });
+        due += every;
+    }
+    (occurrences, Some(due))
+}
+
+/// The last `days` days of `task` up to `now`, the oldest first, with its streak and completion
+/// rate in percent (none if nothing was due in those days)
+fn history(task: &Task, days: u32, now: DateTime<Utc>) -> (Vec<Day>, usize, Option<u32>) {
+    let today = now.with_timezone(&Local).date_naive();
+    let first = today - Duration::days(i64::from(days.max(1)) - 1);
+    let date = |at: DateTime<Utc>| at.with_timezone(&Local).date_naive();
+    let since = first
+        .and_hms_opt(0, 0, 0)
+        .and_then(|start| start.and_local_timezone(Local).earliest())
+        .map_or(now, Into::into);
+
+    let (occurrences, pending) = occurrences(task, since, now);
+    let streak = occurrences
+        .iter()
+        .rev()
+        .take_while(|occurrence| occurrence.completed_at.is_some())
+        .count();
+
+    let in_window: Vec<_> = occurrences
+        .iter()
+        .filter(|occurrence| date(occurrence.due) >= first)
+        .collect();
+    let done = in_window
+        .iter()
+        .filter(|occurrence| occurrence.completed_at.is_some())
+        .count();
+    let rate = (!in_window.is_empty()).then(|| (done * 100 / in_window.len()) as u32);
+
+    let day = |day: NaiveDate| {
+        let mut due = in_window
+            .iter()
+            .filter(|occurrence| date(occurrence.due) == day);
+        match due.clone().next() {
+            Some(_) if due.any(|occurrence| occurrence.completed_at.is_none()) => Day::Missed,
+            Some(_) => Day::Done,
+            None if pending.is_some_and(|due| date(due) == day) => Day::Due,
+            None => Day::Free,
+        }
+    };
+    let days = first
+        .iter_days()
+        .take_while(|day| *day <= today)
+        .map(day)
+        .collect();
+    (days, streak, rate)
+}
+
+/// Print the last `days` days of the habit `task` and its streak, indented to go under the task
+pub fn print(config: &Config, task: &Task, days: u32, now: DateTime<Utc>) {
+    let (history, streak, rate) = history(task, days, now);
+    let theme = config.theme();
+    let grid: String = history
+        .into_iter()
+        .map(|day| {
+            let glyph = day.glyph(config.icons);
+            match day {
+                _ if !config.color.enabled() => glyph.to_string(),
+                Day::Done => theme.completed.paint(glyph),
+                Day::Missed => theme.overdue.paint(glyph),
+                Day::Due | Day::Free => glyph.to_string(),
+            }
+        })
+        .collect();
+    let rate = rate.map_or_else(|| "-".to_string(), |rate| format!("{rate}%"));
+    println!("    {grid}");
+    println!(
+        "    {}",
+        tr!("habit-stats", streak = streak, rate = rate, days = days)
+    );
+}
+
+/// Print every habit with its last `days` days
+pub fn run(config: &Config, store: &impl TaskStore, days: u32) -> color_eyre::Result<()> {
+    let mut tasks = store.load_all().wrap_err("load tasks")?;
+    tasks.retain(|task| task.habit);
+    tasks.sort_by(|a, b| a.name.cmp(&b.name));
+    if tasks.is_empty() {
+        println!("{}", tr!("no-habits"));
+        return Ok(());
+    }
+
+    let now = Utc::now();
+    for task in &tasks {
+        println!("{}", color::task(task, config, now));
+        print(config, task, days, now);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{Duration, Local, TimeZone, Utc};
+    use dooit_tasks::{Occurrence, Task};
+    use pretty_assertions::assert_eq;
+
+    use super::{history, Day};
+
+    #[test]
+    fn test_history() {
+        let day = Duration::days(1);
+        let due = Local.with_ymd_and_hms(2024, 5, 1, 9, 0, 0).unwrap();
+        let due = due.with_timezone(&Utc);
+        let occurrence = |days: i32, done: bool| Occurrence {
+            due: due + day * days,
+            completed_at: done.then_some(due + day * days),
+        };
+        let task = Task {
+            occurrences: vec![
+                occurrence(0, true),
+                occurrence(1, false),
+                occurrence(2, true),
+                occurrence(3, true),
+            ],
+            ..Task::new("stretch")
+                .with_due_date(due + day * 4)
+                .with_habit(day)
+        };
+
+        // The occurrence due on the 5th is still pending on the 6th, then it's missed
+        let (days, streak, rate) = history(&task, 6, due + day * 5 - Duration::hours(1));
+        assert_eq!(
+            days,
+            [
+                Day::Done,
+                Day::Missed,
+                Day::Done,
+                Day::Done,
+                Day::Due,
+                Day::Free
+            ]
+        );
+        assert_eq!((streak, rate), (2, Some(75)));
+
+        let (days, streak, rate) = history(&task, 3, due + day * 5);
+        assert_eq!(days, [Day::Done, Day::Missed, Day::Due]);
+        assert_eq!((streak, rate), (0, Some(50)));
+    }
+}
@@ -0,0 +1,224 @@
+//! User scripts run around task mutations, similar to Taskwarrior hooks
+//!
+//! A hook receives the task as a single line of JSON on stdin (`on-modify` hooks get the original
+//! task on the first line and the modified one on the second). Exiting with a non-zero status
+//! vetoes the operation, printing a task as JSON on stdout replaces the task that will be saved.
+//! Output of `on-delete` hooks is ignored.
+
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use color_eyre::eyre::{bail, WrapErr};
+use dooit_tasks::Task;
+use serde::{Deserialize, Serialize};
+
+use crate::failure::Failure;
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Hooks {
+    /// Run before a task is created
+    pub on_add: Vec<PathBuf>,
+    /// Run before a task is changed (except for completing it)
+    pub on_modify: Vec<PathBuf>,
+    /// Run before a task is marked as completed
+    pub on_complete: Vec<PathBuf>,
+    /// Run before a task is deleted
+    pub on_delete: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Hook {
+    Add,
+    Modify,
+    Complete,
+    Delete,
+}
+
+impl Hooks {
+    fn scripts(&self, hook: Hook) -> &[PathBuf] {
+        match hook {
+            Hook::Add => &self.on_add,
+            Hook::Modify => &self.on_modify,
+            Hook::Complete => &self.on_complete,
+            Hook::Delete => &self.on_delete,
+        }
+    }
+
+    /// Run every script configured for `hook`, returns the task as modified by the scripts
+    pub fn run(&self, hook: Hook, old: Option<&Task>, mut task: Task) -> color_eyre::Result<Task> {
+        for script in self.scripts(hook) {
+            let Some(modified) = run_script(script, old, &task)? else {
+                continue;
+            };
+            if matches!(hook, Hook::Delete) {
+                continue;
+            }
+
+            if !matches!(hook, Hook::Add) && modified.name != task.name {
+                bail!(
+                    "hook {} tried to rename {} to {}",
+                    script.display(),
+                    task.name.display(),
+                    modified.name.display()
+                );
+            }
+            task = modified;
+        }
+
+        Ok(task)
+    }
+}
+
+fn run_script(script: &Path, old: Option<&Task>, task: &Task) -> color_eyre::Result<Option<Task>> {
+    tracing::debug!(
+        "running hook {} on {}",
+        script.display(),
+        task.name.display()
+    );
+    let start = std::time::Instant::now();
+    let mut child = Command::new(script)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .wrap_err_with(|| format!("run hook {}", script.display()))?;
+
+    {
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        for task in old.into_iter().chain([task]) {
+            serde_json::to_writer(&mut stdin, task)?;
+            stdin.write_all(b"\n")?;
+        }
+    }
+
+    let output = child
+        .wait_with_output()
+        .wrap_err_with(|| format!("run hook {}", script.display()))?;
+    tracing::debug!(
+        "hook {} exited with {} after {:?}",
+        script.display(),
+        output.status,
+        start.elapsed()
+    );
+
+    if !output.status.success() {
+        return Err(Failure::HookRejected.report().wrap_err(format!(
+            "hook {} rejected the change ({})",
+            script.display(),
+            output.status
+        )));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if stdout.trim().is_empty() {
+        return Ok(None);
+    }
+
+    serde_json::from_str(stdout.trim())
+        .map(Some)
+        .wrap_err_with(|| format!("parse task printed by hook {}", script.display()))
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use std::{
+        os::unix::fs::PermissionsExt,
+        path::{Path, PathBuf},
+    };
+
+    use dooit_tasks::{DirStore, Task, TaskStore, Urgency};
+    use pretty_assertions::assert_eq;
+
+    use super::Hooks;
+    use crate::{
+        actions,
+        config::{AuditConfig, Config, JournalConfig},
+        failure::Failure,
+    };
+
+    #[test]
+    fn test_hooks() {
+        let dir = tempfile::tempdir().unwrap();
+        let scripts = dir.path().join("hooks");
+        std::fs::create_dir(&scripts).unwrap();
+        let script = |name: &str, body: &str| -> PathBuf {
+            let path = scripts.join(name);
+            std::fs::write(&path, format!("#!/bin/sh\n{body}\n")).unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+            path
+        };
+        // Save what the script got on stdin next to it, then print `task` if there's one
+        let record = |name: &str, task: Option<&Task>| {
+            let input = scripts.join(format!("{name}.in"));
+            let print = task.map_or(String::new(), |task| {
+                format!("echo '{}'", serde_json::to_string(task).unwrap())
+            });
+            script(name, &format!("cat > '{}'\n{print}", input.display()))
+        };
+        let input = |name: &str| -> Vec<Task> {
+            let input = std::fs::read_to_string(scripts.join(format!("{name}.in"))).unwrap();
+            input
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect()
+        };
+
+        let tagged = Task::new("report").with_tags(["hooked"]);
+        let config = Config {
+            hooks: Hooks {
+                on_add: vec![record("tag", Some(&tagged)), record("after-tag", None)],
+                on_modify: vec![record("modify", None)],
+                on_complete: vec![script("veto", "cat >/dev/null\nexit 3")],
+                on_delete: vec![record("delete", Some(&Task::new("kept")))],
+            },
+            audit: AuditConfig { enabled: false },
+            journal: JournalConfig { enabled: false },
+            ..Config::default()
+        };
+        let store = DirStore::new(dir.path().join("tasks"));
+        let load = |name: &str| store.load(Path::new(name)).unwrap();
+
+        // on-add hooks get the new task, run in order, and what they print is saved instead
+        actions::add(&config, &store, Task::new("report")).unwrap();
+        assert_eq!(input("tag"), [Task::new("report")]);
+        assert_eq!(input("after-tag"), std::slice::from_ref(&tagged));
+        assert_eq!(load("report"), Some(tagged.clone()));
+
+        // on-modify hooks get the original task and the modified one
+        let urgent = tagged.clone().with_urgency(Urgency::High);
+        actions::update(&config, &store, urgent.clone()).unwrap();
+        assert_eq!(input("modify"), [tagged, urgent.clone()]);
+
+        // A failing hook vetoes the change
+        let vetoed = actions::complete(&config, &store, Path::new("report")).unwrap_err();
+        assert_eq!(Failure::of(&vetoed), Some(Failure::HookRejected));
+        assert!(vetoed.to_string().contains("veto"), "{vetoed}");
+        assert_eq!(load("report"), Some(urgent.clone()));
+
+        // on-delete hooks can veto but not replace the task
+        actions::remove(&config, &store, Path::new("report")).unwrap();
+        assert_eq!(input("delete"), [urgent]);
+        assert_eq!((load("report"), load("kept")), (None, None));
+
+        // Hooks can't rename the task they're run on
+        let renaming = Config {
+            hooks: Hooks {
+                on_modify: vec![record("rename", Some(&Task::new("taxes")))],
+                ..Hooks::default()
+            },
+            ..config
+        };
+        actions::add(&renaming, &store, Task::new("report")).unwrap();
+        let renamed = Task::new("report").with_urgency(Urgency::Medium);
+        let err = actions::update(&renaming, &store, renamed).unwrap_err();
+        assert!(
+            err.to_string().contains("tried to rename report to taxes"),
+            "{err}"
+        );
+        assert_eq!(load("report"), Some(Task::new("report")));
+    }
+}
@@ -0,0 +1,205 @@
+//! Translations of the messages printed by dooit-rs
+//!
+//! Catalogs are written in a subset of [Fluent](https://projectfluent.org): `id = message` lines,
+//! where `{ $name }` is replaced by the argument called `name`, and `#` comments. The language is
+//! `locale` from the config, or the one in `$LC_ALL`, `$LC_MESSAGES` or `$LANG` (e.g.
+//! `de_AT.UTF-8`, which tries `de-AT` and then `de`). Messages are looked up in:
+//!
+//! 1. `<config dir>/locales/<language>.ftl`, for translations that don't ship with dooit-rs
+//! 2. The built-in translation, if there is one
+//! 3. English, which has every message
+
+use std::{collections::HashMap, sync::OnceLock};
+
+use color_eyre::eyre::{bail, WrapErr};
+use dooit_tasks::dirs;
+
+/// Translations that ship with dooit-rs, English first
+const BUILTIN: [(&str, &str); 2] = [
+    ("en", include_str!("../locales/en.ftl")),
+    ("de", include_str!("../locales/de.ftl")),
+];
+
+static CATALOGS: OnceLock<Vec<Catalog>> = OnceLock::new();
+
+/// The messages of a single language, by id
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct Catalog(HashMap<String, String>);
+
+impl Catalog {
+    fn parse(source: &str) -> color_eyre::Result<Self> {
+        let mut messages = HashMap::new();
+        for (number, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((id, message)) = line.split_once('=') else {
+                bail!("line {}: expected `id = message`", number + 1);
+            };
+            messages.insert(id.trim().to_string(), message.trim().to_string());
+        }
+        Ok(Self(messages))
+    }
+
+    fn english() -> Self {
+        Self::parse(BUILTIN[0].1).expect("the English catalog is valid")
+    }
+
+    /// The user's catalog for `language`, skipped with a warning if it's broken
+    fn user(language: &str) -> Option<Self> {
+        let path = dirs::get_config_dir()?
+            .join("locales")
+            .join(format!("{language}.ftl"));
+        let source = std::fs::read_to_string(&path).ok()?;
+
+        Self::parse(&source)
+            .wrap_err_with(|| format!("parse {}", path.display()))
+            .inspect_err(|err| tracing::warn!("{err:#}"))
+            .ok()
+    }
+}
+
+/// Languages to try for `locale`, most specific first
+fn languages(locale: &str) -> Vec<String> {
+    let locale = locale
+        .split(['.', '@'])
+        .next()
+        .unwrap_or_default()
+        .replace('_', "-");
+    if locale.is_empty() || locale == "C" || locale == "POSIX" {
+        return Vec::new();
+    }
+
+    let mut languages = vec![locale.clone()];
+    if let Some((language, _)) = locale.split_once('-') {
+        languages.push(language.to_string());
+    }
+    languages
+}
+
+/// The locale set in the environment
+fn detect() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .into_iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+}
+
+/// Pick the language of messages, `locale` overrides the one from the environment
+///
+/// Messages are in English until this is called.
+pub fn init(locale: Option<&str>) {
+    let locale = locale.map(str::to_string).or_else(detect);
+    let mut catalogs = Vec::new();
+    for language in languages(locale.as_deref().unwrap_or_default()) {
+        catalogs.extend(Catalog::user(&language));
+        if let Some((_, source)) = BUILTIN.iter().find(|(name, _)| *name == language) {
+            catalogs.push(Catalog::parse(source).expect("built-in catalogs are valid"));
+        }
+    }
+    catalogs.push(Catalog::english());
+
+    tracing::debug!("using locale {locale:?}");
+    let _ = CATALOGS.set(catalogs);
+}
+
+/// Replace the `{ $name }` placeholders in `message`
+fn format(message: &str, args: &[(&str, String)]) -> String {
+    args.iter()
+        .fold(message.to_string(), |message, (name, value)| {
+            message.replace(&format!("{{ ${name} }}"), value)
+        })
+}
+
+/// The message `id` in the user's language, see [`tr`]
+pub fn message(id: &str, args: &[(&str, String)]) -> String {
+    let catalogs = CATALOGS.get_or_init(|| vec![Catalog::english()]);
+    let message = catalogs
+        .iter()
+        .find_map(|catalog| catalog.0.get(id))
+        .map_or(id, String::as_str);
+    format(message, args)
+}
+
+/// Translate a message, e.g. `tr!("task-added", name = task.name.display())`
+macro_rules! tr {
+    ($id:expr $(, $name:ident = $value:expr)* $(,)?) => {
+        $crate::i18n::message($id, &[$((stringify!($name), $value.to_string())),*])
+    };
+}
+pub(crate) use tr;
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use pretty_assertions::assert_eq;
+
+    use super::{format, languages, Catalog, BUILTIN};
+
+    #[test]
+    fn test_catalogs() {
+        let english = Catalog::english();
+        for (language, source) in BUILTIN {
+            let catalog = Catalog::parse(source).unwrap();
+            let missing: Vec<_> = english
+                .0
+                .keys()
+                .filter(|id| !catalog.0.contains_key(*id))
+                .collect();
+            assert!(missing.is_empty(), "{language} is missing {missing:?}");
+        }
+
+        assert_eq!(
+            format(
+                &english.0["task-added"],
+                &[("name", "groceries".to_string())]
+            ),
+            "Added groceries"
+        );
+        assert_eq!(languages("de_AT.UTF-8"), ["de-AT", "de"]);
+        assert_eq!(languages("C"), Vec::<String>::new());
+        assert!(Catalog::parse("no equals sign").is_err());
+    }
+
+    /// The ids passed to `tr!` as literals in the `.rs` files under `dir`
+    fn used_ids(dir: &Path, ids: &mut Vec<String>) {
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                used_ids(&path, ids);
+            } else if path.extension().is_some_and(|ext| ext == "rs") {
+                let source = std::fs::read_to_string(&path).unwrap();
+                for (start, _) in source.match_indices("tr!(") {
+                    // Not `include_str!(` or this pattern
+                    let before = source[..start].chars().next_back();
+                    if before.is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '"') {
+                        continue;
+                    }
+                    let call = &source[start + "tr!(".len()..];
+                    let Some(call) = call.trim_start().strip_prefix('"') else {
+                        continue;
+                    };
+                    if let Some((id, _)) = call.split_once('"') {
+                        ids.push(id.to_string());
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_used_ids() {
+        let mut ids = Vec::new();
+        used_ids(&Path::new(env!("CARGO_MANIFEST_DIR")).join("src"), &mut ids);
+        assert!(ids.contains(&"task-added".to_string()));
+
+        let english = Catalog::english();
+        let missing: Vec<_> = ids
+            .iter()
+            .filter(|id| !english.0.contains_key(*id))
+            .collect();
+        assert!(missing.is_empty(), "en is missing {missing:?}");
+    }
+}
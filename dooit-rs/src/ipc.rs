@@ -0,0 +1,170 @@
+//! Communication with the daemon over a Unix socket
+//!
+//! Every connection carries a single newline terminated JSON [`Request`] answered by a single
+//! JSON [`Response`].
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::{Path, PathBuf},
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::{bail, eyre, WrapErr};
+use dooit_tasks::{dirs, Task};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum Request {
+    /// Information about the daemon itself
+    Status,
+    /// Every task known to the daemon
+    Tasks,
+    /// The tasks under `under` in the store at `root` without their time logs, see
+    /// [`TaskStore::load_summaries_under`](dooit_tasks::TaskStore::load_summaries_under)
+    Summaries { root: PathBuf, under: PathBuf },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Response {
+    Status {
+        pid: u32,
+        tasks: usize,
+        next_reminder: Option<DateTime<Utc>>,
+    },
+    Tasks(Vec<Task>),
+    /// Sorted by name, `loaded_at` is when the daemon last read the store
+    Summaries {
+        loaded_at: DateTime<Utc>,
+        tasks: Vec<Task>,
+    },
+    Error(String),
+}
+
+/// How long to wait for the daemon to answer, so a stuck daemon doesn't hang the command
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+pub fn socket_path() -> color_eyre::Result<PathBuf> {
+    dirs::get_state_dir()
+        .map(|dir| dir.join("daemon.sock"))
+        .ok_or_else(|| eyre!("state dir not available"))
+}
+
+/// Send `request` to the running daemon
+pub fn query(request: &Request) -> color_eyre::Result<Response> {
+    query_at(&socket_path()?, request)
+}
+
+/// Send `request` to the daemon listening on `path`
+pub fn query_at(path: &Path, request: &Request) -> color_eyre::Result<Response> {
+    let mut stream = UnixStream::connect(path)
+        .wrap_err_with(|| format!("connect to the daemon at {}", path.display()))?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+
+    serde_json::to_writer(&mut stream, request)?;
+    stream.write_all(b"\n")?;
+
+    let mut response = String::new();
+    BufReader::new(stream).read_line(&mut response)?;
+    serde_json::from_str(&response).wrap_err("parse daemon response")
+}
+
+/// The summaries of the tasks under `parent` (all of them if it's empty) in `tasks` sorted by name
+///
+/// A task and its subtasks are next to each other in `tasks`, so they're found by a binary search
+/// instead of going through all of them.
+pub fn summaries_under(tasks: &[Task], parent: &Path) -> Vec<Task> {
+    let start = tasks.partition_point(|task| task.name.as_path() < parent);
+    tasks[start..]
+        .iter()
+        .take_while(|task| task.name.starts_with(parent))
+        .map(|task| Task {
+            time_log: Vec::new(),
+            ..task.clone()
+        })
+        .collect()
+}
+
+/// Answer requests with `handler` on a background thread
+pub fn listen(
+    handler: impl Fn(Request) -> Response + Send + 'static,
+) -> color_eyre::Result<JoinHandle<()>> {
+    listen_at(&socket_path()?, handler)
+}
+
+/// [`listen`] on the socket at `path`
+pub fn listen_at(
+    path: &Path,
+    handler: impl Fn(Request) -> Response + Send + 'static,
+) -> color_eyre::Result<JoinHandle<()>> {
+    if path.exists() {
+        if UnixStream::connect(path).is_ok() {
+            bail!("another daemon is already listening on {}", path.display());
+        }
+        // Left behind by a daemon that didn't shut down cleanly
+        std::fs::remove_file(path).wrap_err_with(|| format!("remove {}", path.display()))?;
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).wrap_err_with(|| format!("create {}", parent.display()))?;
+    }
+
+    let listener =
+        UnixListener::bind(path).wrap_err_with(|| format!("listen on {}", path.display()))?;
+
+    Ok(std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let result = stream.and_then(|stream| {
+                let mut line = String::new();
+                BufReader::new(&stream).read_line(&mut line)?;
+
+                let response = match serde_json::from_str(&line) {
+                    Ok(request) => handler(request),
+                    Err(err) => Response::Error(err.to_string()),
+                };
+
+                let mut stream = &stream;
+                serde_json::to_writer(&mut stream, &response)?;
+                stream.write_all(b"\n")
+            });
+
+            if let Err(err) = result {
+                tracing::warn!("failed to answer IPC request: {err}");
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use dooit_tasks::Task;
+    use pretty_assertions::assert_eq;
+
+    use super::summaries_under;
+
+    #[test]
+    fn test_summaries_under() {
+        let mut tasks: Vec<_> = ["release-notes", "release/docs", "release", "taxes", "a"]
+            .into_iter()
+            .map(Task::new)
+            .collect();
+        tasks.sort_by(|a, b| a.name.cmp(&b.name));
+        let names = |parent: &str| -> Vec<_> {
+            summaries_under(&tasks, Path::new(parent))
+                .into_iter()
+                .map(|task| task.name.display().to_string())
+                .collect()
+        };
+
+        assert_eq!(names("release"), ["release", "release/docs"]);
+        assert_eq!(names("release/docs"), ["release/docs"]);
+        assert_eq!(names("").len(), 5);
+        assert_eq!(names("zzz"), Vec::<String>::new());
+    }
+}
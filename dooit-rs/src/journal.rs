@@ -0,0 +1,361 @@
+//! Journal of the changes made to tasks, so `dooit-rs undo` can take them back
+//!
+//! Every change is appended as a line of JSON to `<data dir>/.journal.jsonl`, with the task before
+//! and after it (none if it didn't exist) and the operation it was part of: one run of a command,
+//! like an `rm -r` deleting several tasks. `undo` reverts the last operations and drops them from
+//! the journal. It refuses to if one of their tasks changed since (e.g. by a sync or by hand), and
+//! attachments of deleted tasks are gone for good. Archived tasks are moved back out of the archive
+//! with their attachments.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs::OpenOptions,
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use chrono::{DateTime, Local, Utc};
+use color_eyre::eyre::{bail, WrapErr};
+use dooit_tasks::{Task, TaskStore};
+use serde::{Deserialize, Serialize};
+
+use crate::{audit, config::Config, dry_run::DryRun, failure, i18n::tr};
+
+/// Name of the journal inside the data dir
+const JOURNAL_FILE: &str = ".journal.jsonl";
+
+/// The ID and command of the operation of this run, see [`begin`]
+static OPERATION: OnceLock<(String, String)> = OnceLock::new();
+
+fn operation(command: &str) -> (String, String) {
+    let id = format!("{}-{}", Utc::now().timestamp_micros(), std::process::id());
+    (id, command.to_string())
+}
+
+/// Start the operation the changes of this run belong to, `command` names it in `undo`
+pub fn begin(command: &str) {
+    let _ = OPERATION.set(operation(command));
+}
+
+/// A change to a task, one line of the journal
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    /// Same for every change made by one run of a command
+    pub operation: String,
+    /// The command that made the change, e.g. `done`
+    pub command: String,
+    pub at: DateTime<Utc>,
+    pub old: Option<Task>,
+    pub new: Option<Task>,
+    /// The task was moved to the archive rather than deleted
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub archived: bool,
+}
+
+fn journal_path(config: &Config) -> color_eyre::Result<PathBuf> {
+    failure::data_dir(config).map(|dir| dir.join(JOURNAL_FILE))
+}
+
+/// Append the change from `old` to `new` to the journal, unless disabled in `config`
+///
+/// Failing to write the journal doesn't undo the change, it's only logged.
+pub fn record(config: &Config, old: Option<&Task>, new: Option<&Task>) {
+    record_entry(config, old, new, false);
+}
+
+/// Append the move of `task` to the archive to the journal, unless disabled in `config`
+pub fn record_archived(config: &Config, task: &Task) {
+    record_entry(config, Some(task), None, true);
+}
+
+fn record_entry(config: &Config, old: Option<&Task>, new: Option<&Task>, archived: bool) {
+    if !config.journal.enabled || old == new {
+        return;
+    }
+    let (id, command) = OPERATION.get_or_init(|| operation("unknown"));
+    let entry = Entry {
+        operation: id.clone(),
+        command: command.clone(),
+        at: Utc::now(),
+        old: old.cloned(),
+        new: new.cloned(),
+        archived,
+    };
+    if let Err(err) = append(config, &entry) {
+        let name = new.or(old).map(|task| task.name.display().to_string());
+        tracing::warn!(
+            "failed to journal the change to {}: {err:?}",
+            name.unwrap_or_default()
+        );
+    }
+}
+
+fn append(config: &Config, entry: &Entry) -> color_eyre::Result<()> {
+    let path = journal_path(config)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).wrap_err_with(|| format!("create {}", parent.display()))?;
+    }
+    let mut line = serde_json::to_string(entry).wrap_err("serialize the entry")?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .wrap_err_with(|| format!("write {}", path.display()))
+}
+
+/// The entries of the journal at `path`, oldest first
+fn entries(path: &Path) -> color_eyre::Result<Vec<Entry>> {
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err).wrap_err_with(|| format!("read {}", path.display())),
+    };
+    let mut entries = Vec::new();
+    for (number, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.wrap_err_with(|| format!("read {}", path.display()))?;
+        // A line cut short by a crash shouldn't hide the rest of the journal
+        match serde_json::from_str::<Entry>(&line) {
+            Ok(entry) => entries.push(entry),
+            Err(err) => {
+                let line = number + 1;
+                tracing::warn!("skipping line {line} of {}: {err}", path.display());
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// `entries` grouped by operation, in the order the operations started
+fn operations(entries: Vec<Entry>) -> Vec<Vec<Entry>> {
+    let mut operations: Vec<Vec<Entry>> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for entry in entries {
+        match index.get(&entry.operation) {
+            Some(&at) => operations[at].push(entry),
+            None => {
+                index.insert(entry.operation.clone(), operations.len());
+                operations.push(vec![entry]);
+            }
+        }
+    }
+    operations
+}
+
+/// Fail unless the tasks changed by `operation` are still as it left them
+fn check(store: &impl TaskStore, operation: &[Entry]) -> color_eyre::Result<()> {
+    // The state after the last change to every task, none if it was deleted or renamed away
+    let mut expected: HashMap<&Path, Option<&Task>> = HashMap::new();
+    for entry in operation.iter().rev() {
+        if let Some(new) = &entry.new {
+            expected.entry(&new.name).or_insert(Some(new));
+        }
+        if let Some(old) = &entry.old {
+            expected.entry(&old.name).or_insert(None);
+        }
+    }
+
+    for (name, task) in expected {
+        let current = store
+            .load(name)
+            .wrap_err_with(|| format!("load task {}", name.display()))?;
+        if current.as_ref() != task {
+            bail!(
+                "{} changed since, so the changes to it can't be undone anymore",
+                name.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Revert the changes of `operation`, latest first
+///
+/// Archived tasks are saved as the journal has them if they're not in the archive anymore.
+fn revert(config: &Config, store: &DryRun, operation: &[Entry]) -> color_eyre::Result<()> {
+    for entry in operation.iter().rev() {
+        match (&entry.old, &entry.new) {
+            (None, Some(new)) => store.remove(&new.name).map(drop),
+            (Some(old), Some(new)) => store.rename(&new.name, old),
+            (Some(old), None) if entry.archived => match store.unarchive(&old.name) {
+                Ok(false) => store.save(old),
+                unarchived => unarchived.map(drop),
+            },
+            (Some(old), None) => store.save(old),
+            (None, None) => Ok(()),
+        }
+        .wrap_err("revert the change")?;
+        audit::record(config, entry.new.as_ref(), entry.old.as_ref());
+    }
+    Ok(())
+}
+
+/// Revert the last `count` of `operations`, latest first, adding their IDs to `undone`
+fn revert_last(
+    config: &Config,
+    store: &DryRun,
+    operations: &[Vec<Entry>],
+    count: usize,
+    dry_run: bool,
+    undone: &mut HashSet<String>,
+) -> color_eyre::Result<()> {
+    for operation in operations.iter().rev().take(count) {
+        // A dry run doesn't revert the operations the earlier ones are checked against
+        if !dry_run || undone.is_empty() {
+            check(store, operation)?;
+        }
+        revert(config, store, operation)?;
+        undone.insert(operation[0].operation.clone());
+        if !dry_run {
+            let at = operation[0].at.with_timezone(&Local).format("%a %F %T");
+            let message = tr!(
+                "undone",
+                command = &operation[0].command,
+                at = at.to_string()
+            );
+            println!("{message}");
+        }
+    }
+    Ok(())
+}
+
+/// Revert the last `count` operations in the journal, latest first, and drop them from it
+pub fn undo(
+    config: &Config,
+    store: &DryRun,
+    count: usize,
+    dry_run: bool,
+) -> color_eyre::Result<()> {
+    let path = journal_path(config)?;
+    let mut operations = operations(entries(&path)?);
+    if operations.is_empty() {
+        println!("{}", tr!("undo-nothing"));
+        return Ok(());
+    }
+
+    let mut undone = HashSet::new();
+    let reverted = revert_last(config, store, &operations, count, dry_run, &mut undone);
+    if dry_run || undone.is_empty() {
+        return reverted;
+    }
+
+    operations.retain(|operation| !undone.contains(&operation[0].operation));
+    let mut contents = String::new();
+    for entry in operations.into_iter().flatten() {
+        contents.push_str(&serde_json::to_string(&entry).wrap_err("serialize the entry")?);
+        contents.push('\n');
+    }
+    let tmp = path.with_extension(format!("jsonl.{}", std::process::id()));
+    std::fs::write(&tmp, contents)
+        .and_then(|()| std::fs::rename(&tmp, &path))
+        .wrap_err_with(|| format!("write {}", path.display()))?;
+    reverted
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use chrono::Utc;
+    use dooit_tasks::{DirStore, Task, TaskStore, Urgency};
+    use pretty_assertions::assert_eq;
+
+    use super::{check, entries, journal_path, operations, revert, undo, Entry};
+    use crate::{
+        actions,
+        config::{AuditConfig, Config, JournalConfig},
+        dry_run::DryRun,
+    };
+
+    #[test]
+    fn test_revert() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let dir_store = DirStore::new(root);
+        let store = DryRun::new(&dir_store, false);
+        let config = Config {
+            audit: AuditConfig { enabled: false },
+            ..Config::default()
+        };
+        let entry = |operation: &str, old: Option<&Task>, new: Option<&Task>| Entry {
+            operation: operation.to_string(),
+            command: "test".to_string(),
+            at: Utc::now(),
+            old: old.cloned(),
+            new: new.cloned(),
+            archived: false,
+        };
+        let report = Task::new("report");
+        let urgent = report.clone().with_urgency(Urgency::High);
+        let renamed = Task::new("taxes").with_urgency(Urgency::High);
+        let journal = operations(vec![
+            entry("1", None, Some(&report)),
+            entry("2", Some(&report), Some(&urgent)),
+            entry("3", Some(&urgent), Some(&renamed)),
+            entry("2", None, Some(&Task::new("notes"))),
+        ]);
+        assert_eq!(journal.len(), 3);
+        assert_eq!(journal[1].len(), 2);
+
+        store.save(&renamed).unwrap();
+        check(&store, &journal[2]).unwrap();
+        revert(&config, &store, &journal[2]).unwrap();
+        assert_eq!(store.load_all().unwrap(), [urgent]);
+
+        // `notes` was deleted since
+        assert!(check(&store, &journal[1]).is_err());
+        store.save(&Task::new("notes")).unwrap();
+        check(&store, &journal[1]).unwrap();
+        revert(&config, &store, &journal[1]).unwrap();
+        assert_eq!(store.load_all().unwrap(), [report]);
+        revert(&config, &store, &journal[0]).unwrap();
+        assert_eq!(store.load(Path::new("report")).unwrap(), None);
+
+        // Archived tasks are moved back
+        let done = Task::new("done").complete();
+        store.save(&done).unwrap();
+        dir_store.archive_task(&done.name).unwrap();
+        let archived = Entry {
+            archived: true,
+            ..entry("4", Some(&done), None)
+        };
+        check(&store, std::slice::from_ref(&archived)).unwrap();
+        revert(&config, &store, &[archived]).unwrap();
+        assert_eq!(store.load_all().unwrap(), [done]);
+        assert_eq!(dir_store.archive().load_all().unwrap(), []);
+    }
+
+    #[test]
+    fn test_undo_add_existing() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir_store = DirStore::new(dir.path());
+        let store = DryRun::new(&dir_store, false);
+        let config = Config {
+            data_dir: Some(dir.path().to_path_buf()),
+            audit: AuditConfig { enabled: false },
+            journal: JournalConfig { enabled: true },
+            ..Config::default()
+        };
+        let mut report = Task::new("report");
+        report.description = Some("made before the journal".to_string());
+        dir_store.save(&report).unwrap();
+
+        // Adding over it would have been journaled as a new task, and undone by deleting it
+        let err = actions::add(&config, &store, Task::new("report")).unwrap_err();
+        assert_eq!(err.to_string(), "a task named report already exists");
+        let batch = vec![Task::new("notes"), Task::new("report")];
+        assert!(actions::add_all(&config, &store, batch).is_err());
+        let batch = vec![Task::new("notes"), Task::new("notes")];
+        assert!(actions::add_all(&config, &store, batch).is_err());
+        assert_eq!(store.load_all().unwrap(), [report.clone()]);
+        let path = journal_path(&config).unwrap();
+        assert_eq!(entries(&path).unwrap(), []);
+
+        actions::add(&config, &store, Task::new("notes")).unwrap();
+        undo(&config, &store, 1, false).unwrap();
+        assert_eq!(store.load_all().unwrap(), [report]);
+        assert_eq!(entries(&path).unwrap(), []);
+    }
+}
@@ -0,0 +1,223 @@
+//! Work-in-progress limits from `limits` in the config
+//!
+//! Only open tasks count: a task is in the `started` group while its status is started, and in
+//! the group of its urgency and of each of its tags until it's completed. Adding a task to a group
+//! that is already full warns, or fails if `limits.strict` is set (e.g. with `--strict`). `list`
+//! warns about every group over its limit.
+
+use std::{collections::BTreeMap, fmt::Display, path::Path};
+
+use color_eyre::eyre::WrapErr;
+use dooit_tasks::{Status, Task, TaskStore};
+
+use crate::{
+    config::{Config, LimitsConfig},
+    failure::Failure,
+    i18n::tr,
+};
+
+/// Tasks sharing a limit
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Group {
+    Started,
+    Urgency(String),
+    Tag(String),
+}
+
+impl Display for Group {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Group::Started => f.write_str(&tr!("limit-started")),
+            Group::Urgency(urgency) => f.write_str(&tr!("limit-urgency", urgency = urgency)),
+            Group::Tag(tag) => write!(f, "+{tag}"),
+        }
+    }
+}
+
+/// The limited groups `task` counts towards, with their limits
+fn groups(limits: &LimitsConfig, task: &Task) -> Vec<(Group, usize)> {
+    if task.completed {
+        return Vec::new();
+    }
+
+    let started = limits
+        .started
+        .filter(|_| task.status == Status::Started)
+        .map(|limit| (Group::Started, limit));
+    let urgency = limits
+        .urgencies
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(task.urgency.name()))
+        .map(|(name, &limit)| (Group::Urgency(name.clone()), limit));
+    let tags = task.tags.iter().filter_map(|tag| {
+        let limit = limits.tags.get(tag)?;
+        Some((Group::Tag(tag.clone()), *limit))
+    });
+    started.into_iter().chain(urgency).chain(tags).collect()
+}
+
+/// The groups with more of `tasks` than their limit, with how many they have and the limit
+pub fn exceeded(limits: &LimitsConfig, tasks: &[Task]) -> Vec<(Group, usize, usize)> {
+    let mut counts = BTreeMap::new();
+    for (group, limit) in tasks.iter().flat_map(|task| groups(limits, task)) {
+        counts.entry(group).or_insert((0, limit)).0 += 1;
+    }
+    counts
+        .into_iter()
+        .filter(|(_, (count, limit))| count > limit)
+        .map(|(group, (count, limit))| (group, count, limit))
+        .collect()
+}
+
+/// Warn about, or refuse if strict, saving `task` (which was `old` before) if that puts it in a
+/// group that is already full
+pub fn check(
+    config: &Config,
+    store: &impl TaskStore,
+    old: Option<&Task>,
+    task: &Task,
+) -> color_eyre::Result<()> {
+    let joined = joined(&config.limits, old, task);
+    check_joined(config, store, &joined, std::slice::from_ref(task))
+}
+
+/// [`check`] for saving all of `changes` (the tasks with what they were before) at once
+pub fn check_changes(
+    config: &Config,
+    store: &impl TaskStore,
+    changes: &[(Option<Task>, Task)],
+) -> color_eyre::Result<()> {
+    let joined: Vec<_> = changes
+        .iter()
+        .flat_map(|(old, task)| joined(&config.limits, old.as_ref(), task))
+        .collect();
+    let tasks: Vec<_> = changes.iter().map(|(_, task)| task.clone()).collect();
+    check_joined(config, store, &joined, &tasks)
+}
+
+/// The groups `task` is in that it wasn't in as `old`
+fn joined(limits: &LimitsConfig, old: Option<&Task>, task: &Task) -> Vec<(Group, usize)> {
+    let before = old.map(|old| groups(limits, old)).unwrap_or_default();
+    groups(limits, task)
+        .into_iter()
+        .filter(|group| !before.contains(group))
+        .collect()
+}
+
+/// [`check`] for adding all of `tasks` at once, counting them along with each other
+pub fn check_all(
+    config: &Config,
+    store: &impl TaskStore,
+    tasks: &[Task],
+) -> color_eyre::Result<()> {
+    let joined: Vec<_> = tasks
+        .iter()
+        .flat_map(|task| groups(&config.limits, task))
+        .collect();
+    check_joined(config, store, &joined, tasks)
+}
+
+/// Warn about, or refuse if strict, the `joined` groups that saving `tasks` puts over their limit
+fn check_joined(
+    config: &Config,
+    store: &impl TaskStore,
+    joined: &[(Group, usize)],
+    tasks: &[Task],
+) -> color_eyre::Result<()> {
+    if joined.is_empty() {
+        return Ok(());
+    }
+
+    let limits = &config.limits;
+    let tasks: Vec<_> = store
+        .load_summaries_under(Path::new(""))
+        .wrap_err("load tasks")?
+        .into_iter()
+        .filter(|other| !tasks.iter().any(|task| task.name == other.name))
+        .chain(tasks.iter().cloned())
+        .collect();
+    for (group, count, limit) in exceeded(limits, &tasks) {
+        if !joined.iter().any(|(joined, _)| *joined == group) {
+            continue;
+        }
+        let message = tr!(
+            "limit-exceeded",
+            group = group,
+            count = count,
+            limit = limit
+        );
+        if limits.strict {
+            return Err(Failure::OverLimit.report().wrap_err(message));
+        }
+        eprintln!("warning: {message}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use dooit_tasks::{DirStore, Status, Task, TaskStore, Urgency};
+    use pretty_assertions::assert_eq;
+
+    use super::{check_all, exceeded, Group};
+    use crate::config::{Config, LimitsConfig};
+
+    #[test]
+    fn test_exceeded() {
+        let limits = LimitsConfig {
+            started: Some(1),
+            urgencies: BTreeMap::from([("high".to_string(), 1)]),
+            tags: BTreeMap::from([("work".to_string(), 1)]),
+            strict: false,
+        };
+        let task = |name: &str, status, urgency, tags: &[&str], completed| Task {
+            status,
+            urgency,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            completed,
+            ..Task::new(name)
+        };
+        let tasks = [
+            task("a", Status::Started, Urgency::High, &["work"], false),
+            task("b", Status::Started, Urgency::High, &[], false),
+            task("c", Status::Pending, Urgency::Low, &["work"], false),
+            // Completed tasks don't count
+            task("d", Status::Started, Urgency::High, &["work"], true),
+        ];
+
+        assert_eq!(
+            exceeded(&limits, &tasks),
+            [
+                (Group::Started, 2, 1),
+                (Group::Urgency("high".to_string()), 2, 1),
+                (Group::Tag("work".to_string()), 2, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_all() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let store = DirStore::new(root);
+        let config = Config {
+            limits: LimitsConfig {
+                tags: BTreeMap::from([("work".to_string(), 2)]),
+                strict: true,
+                ..LimitsConfig::default()
+            },
+            ..Config::default()
+        };
+        store.save(&Task::new("a").with_tags(["work"])).unwrap();
+
+        let batch = [
+            Task::new("b").with_tags(["work"]),
+            Task::new("c").with_tags(["work"]),
+        ];
+        // Each fits next to the stored task, but not both of them
+        assert!(check_all(&config, &store, &batch[..1]).is_ok());
+        assert!(check_all(&config, &store, &batch).is_err());
+    }
+}
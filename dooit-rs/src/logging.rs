@@ -0,0 +1,60 @@
+//! Diagnostic logs, written to stderr or to `log_file` from the config
+//!
+//! Only warnings and errors are shown by default, `-v` adds informational messages (e.g. reminders
+//! sent by the daemon), `-vv` debug messages (e.g. how long loading tasks took and which hooks
+//! ran) and `-vvv` everything. `-q` only shows errors.
+
+use std::{fs::OpenOptions, path::Path, sync::Mutex};
+
+use color_eyre::eyre::WrapErr;
+use tracing_subscriber::{
+    filter::{LevelFilter, Targets},
+    fmt::writer::BoxMakeWriter,
+    layer::SubscriberExt,
+    util::SubscriberInitExt,
+    Layer,
+};
+
+/// Level of our own logs for the `-v` and `-q` flags given
+fn level(verbose: u8, quiet: bool) -> LevelFilter {
+    match (quiet, verbose) {
+        (true, _) => LevelFilter::ERROR,
+        (false, 0) => LevelFilter::WARN,
+        (false, 1) => LevelFilter::INFO,
+        (false, 2) => LevelFilter::DEBUG,
+        (false, _) => LevelFilter::TRACE,
+    }
+}
+
+pub fn init(verbose: u8, quiet: bool, file: Option<&Path>) -> color_eyre::Result<()> {
+    let level = level(verbose, quiet);
+    // Dependencies are noisy, only show their warnings
+    let filter = Targets::new()
+        .with_target("dooit_rs", level)
+        .with_target("dooit_tasks", level)
+        .with_default(level.min(LevelFilter::WARN));
+
+    let layer = tracing_subscriber::fmt::layer();
+    let layer = match file {
+        Some(path) => {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .wrap_err_with(|| format!("open log file {}", path.display()))?;
+            layer
+                .with_writer(BoxMakeWriter::new(Mutex::new(file)))
+                .boxed()
+        }
+        None => layer
+            .without_time()
+            .with_writer(BoxMakeWriter::new(std::io::stderr))
+            .boxed(),
+    };
+
+    tracing_subscriber::registry()
+        .with(layer)
+        .with(filter)
+        .try_init()
+        .wrap_err("set up logging")
+}
@@ -0,0 +1,135 @@
+//! Finding a task from a name typed on the command line
+//!
+//! A name that isn't a task is taken as the start of one: of its whole name or of its last part,
+//! so `done tax` finds `admin/taxes`. When several tasks start like that they are listed (and one
+//! can be picked on a terminal), when none does the most similar name is suggested.
+
+use std::{
+    io::IsTerminal,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::{bail, WrapErr};
+use dooit_tasks::TaskStore;
+
+use crate::{i18n::tr, prompt};
+
+/// How similar a name has to be to be suggested, see [`strsim::jaro`]
+const SIMILARITY: f64 = 0.8;
+
+#[derive(Debug, PartialEq, Eq)]
+enum Match<'a> {
+    /// The one task starting with the name
+    One(&'a str),
+    /// The tasks starting with the name, when there are several
+    Several(Vec<&'a str>),
+    /// The most similar name, if any is similar enough
+    Suggestion(Option<&'a str>),
+}
+
+/// The last part of a task's name
+fn last(name: &str) -> &str {
+    name.rsplit('/').next().unwrap_or(name)
+}
+
+/// The `names` `query` stands for
+fn find<'a>(names: &'a [String], query: &str) -> Match<'a> {
+    let query = query.to_lowercase();
+    let found: Vec<_> = names
+        .iter()
+        .map(String::as_str)
+        .filter(|name| {
+            name.to_lowercase().starts_with(&query) || last(name).to_lowercase().starts_with(&query)
+        })
+        .collect();
+    match found[..] {
+        [name] => return Match::One(name),
+        [_, _, ..] => return Match::Several(found),
+        [] => {}
+    }
+
+    let similarity = |name: &str| {
+        let name = name.to_lowercase();
+        strsim::jaro(&query, &name).max(strsim::jaro(&query, last(&name)))
+    };
+    let suggestion = names
+        .iter()
+        .map(|name| (similarity(name), name.as_str()))
+        .filter(|(similarity, _)| *similarity > SIMILARITY)
+        .max_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, name)| name);
+    Match::Suggestion(suggestion)
+}
+
+/// The name of the task `name` stands for, see the [module docs](self)
+pub fn resolve(store: &impl TaskStore, name: &Path) -> color_eyre::Result<PathBuf> {
+    if store
+        .load(name)
+        .wrap_err_with(|| format!("load task {}", name.display()))?
+        .is_some()
+    {
+        return Ok(name.to_path_buf());
+    }
+
+    let mut names: Vec<_> = store
+        .load_summaries_under(Path::new(""))
+        .wrap_err("load tasks")?
+        .into_iter()
+        .map(|task| task.name.to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    let query = name.to_string_lossy();
+    match find(&names, &query) {
+        Match::One(name) => Ok(name.into()),
+        Match::Several(found) if std::io::stdin().is_terminal() => {
+            for name in &found {
+                eprintln!("  {name}");
+            }
+            let mut editor = prompt::Prompt::new().wrap_err("open the terminal")?;
+            loop {
+                let candidates = found.iter().map(|name| name.to_string());
+                let chosen = prompt::ask(
+                    &mut editor,
+                    &format!("{} ", tr!("lookup-which")),
+                    candidates,
+                )?;
+                if found.contains(&chosen.as_str()) {
+                    return Ok(chosen.into());
+                }
+            }
+        }
+        Match::Several(found) => bail!(
+            "{query} could be any of {}, give more of the name",
+            found.join(", ")
+        ),
+        Match::Suggestion(Some(suggestion)) => {
+            bail!("no task named {query}, did you mean {suggestion}?")
+        }
+        Match::Suggestion(None) => bail!("no task named {query}"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::{find, Match};
+
+    #[test]
+    fn test_find() {
+        let names = ["admin/taxes", "admin/tidy", "groceries", "talk"].map(String::from);
+
+        assert_eq!(find(&names, "gro"), Match::One("groceries"));
+        assert_eq!(find(&names, "TAX"), Match::One("admin/taxes"));
+        assert_eq!(find(&names, "admin/ti"), Match::One("admin/tidy"));
+        assert_eq!(
+            find(&names, "t"),
+            Match::Several(vec!["admin/taxes", "admin/tidy", "talk"])
+        );
+        assert_eq!(
+            find(&names, "grocerise"),
+            Match::Suggestion(Some("groceries"))
+        );
+        assert_eq!(find(&names, "zzz"), Match::Suggestion(None));
+    }
+}
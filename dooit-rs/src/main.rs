@@ -1,35 +1,813 @@
-use std::{path::PathBuf, process::Command};
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
 
-use clap::{Parser, Subcommand};
-use dooit_tasks::{dirs, dirs::get_tasks, tasks::sort_tasks, SortMode, Task};
+use clap::{parser::ValueSource, CommandFactory, FromArgMatches, Parser, Subcommand};
+use color_eyre::eyre::WrapErr;
+use config::Config;
+use context::Context;
+use dooit_tasks::{HumanDuration, SortMode, Task, TaskStore};
+use dry_run::DryRun;
+use editor::Editor;
+use failure::Failure;
+use i18n::tr;
+use via_daemon::ViaDaemon;
+
+mod accuracy;
+mod actions;
+mod archive;
+mod attachments;
+mod audit;
+mod capture;
+mod channels;
+mod color;
+mod complete;
+mod config;
+mod context;
+mod critical_path;
+mod daemon;
+#[cfg(target_os = "linux")]
+mod dbus;
+mod dedupe;
+mod digest;
+mod doctor;
+mod dry_run;
+mod edit;
+mod editor;
+mod external;
+mod failure;
+mod feed;
+mod forecast;
+mod graph;
+mod habits;
+mod hooks;
+mod i18n;
+#[cfg(unix)]
+mod ipc;
+mod journal;
+mod limits;
+mod logging;
+mod lookup;
+mod mcp;
+mod menu;
+mod modify;
+mod plan;
+#[cfg(feature = "wasm-plugins")]
+mod plugins;
+mod pomo;
+mod prompt;
+mod reminders;
+mod report;
+mod review;
+mod schedule;
+#[cfg(feature = "scripting")]
+mod scripting;
+mod search;
+mod secrets;
+mod server;
+mod shell;
+mod signing;
+mod stats;
+mod sync;
+mod templates;
+mod theme;
+mod timesheet;
+mod tui;
+mod verify;
+mod via_daemon;
+mod watch;
+mod webhooks;
 
 #[derive(Parser, Debug)]
 struct Cli {
-    /// Editor to use when modifying files
-    #[arg(short, long, env)]
-    editor: Option<PathBuf>,
+    /// Editor to use when modifying files, e.g. `code --wait` [default: $VISUAL or $EDITOR]
+    #[arg(short, long)]
+    editor: Option<String>,
+    /// Override a config option, e.g. `-C list.default_sort=name-ascending` (can be repeated)
+    #[arg(
+        short = 'C',
+        long = "config-option",
+        value_name = "KEY=VALUE",
+        global = true
+    )]
+    config_options: Vec<String>,
+    /// Show more diagnostic logs (can be repeated, e.g. `-vv` for debug logs)
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    verbose: u8,
+    /// Only log errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+    /// Don't ask for confirmation before deleting or changing several tasks
+    #[arg(short, long, global = true)]
+    yes: bool,
+    /// Print which task files would be created, modified or deleted instead of changing them
+    ///
+    /// Interactive and long-running commands, and those sending messages, refuse it.
+    #[arg(long, global = true)]
+    dry_run: bool,
+    /// Refuse changes that go over a limit from `limits` in the config instead of warning
+    #[arg(long, global = true)]
+    strict: bool,
     #[command(subcommand)]
     mode: Mode,
 }
 
+// Only one is ever created, and clap can't flatten a boxed `Option<Task>`
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand, Debug)]
 enum Mode {
     /// List tasks
     List {
-        /// Sort tasks
-        #[arg(short, long, value_enum, default_value_t)]
-        sort: SortMode,
+        /// Sort tasks [default: `list.default_sort` in the config]
+        #[arg(short, long, value_enum)]
+        sort: Option<SortMode>,
         /// Show completed items
         #[arg(short, long)]
         completed: bool,
         /// Show overdue items
         #[arg(short, long)]
         overdue: bool,
+        /// Redraw the list whenever a task changes
+        #[arg(short, long)]
+        watch: bool,
+        /// Also redraw the list every this many seconds while watching
+        #[arg(long, requires = "watch")]
+        interval: Option<u64>,
+        /// Filter, sort and annotate tasks with `<config dir>/scripts/<SCRIPT>.rhai`
+        #[arg(long)]
+        script: Option<String>,
+        /// Only list this task and its subtasks
+        #[arg(long, value_name = "TASK")]
+        under: Option<PathBuf>,
+        /// Only list tasks assigned to you (`identity` in the config)
+        #[arg(short, long)]
+        mine: bool,
+        /// Only list tasks assigned to this person
+        #[arg(long, value_name = "NAME", conflicts_with = "mine")]
+        assignee: Option<String>,
+        /// List the archived tasks instead, see `archive`
+        #[arg(long)]
+        archived: bool,
+    },
+    /// Show a report from `reports` in the config, or list them if no name is given
+    #[command(args_conflicts_with_subcommands = true)]
+    Report {
+        name: Option<String>,
+        #[command(subcommand)]
+        command: Option<ReportCommand>,
+    },
+    /// Show the contexts, or pick the one that scopes what's listed
+    Context {
+        #[command(subcommand)]
+        command: Option<ContextCommand>,
+    },
+    /// Add a task (asks for its fields interactively if no name is given)
+    Add {
+        /// Create the task from a template in the config dir, filling in its variables
+        ///
+        /// Example: `--template release version=1.4`
+        #[arg(
+            long,
+            num_args = 1..,
+            value_names = ["TEMPLATE", "VAR=VALUE"],
+            conflicts_with = "name"
+        )]
+        template: Vec<String>,
+        #[command(flatten)]
+        task: Option<Task>,
+    },
+    /// Create a task and its subtasks from a blueprint (a TOML or YAML template)
+    Scaffold {
+        /// Template describing the tasks, see `add --template`
+        blueprint: PathBuf,
+        /// Values of the template's `{{variables}}`
+        #[arg(value_name = "VAR=VALUE")]
+        vars: Vec<String>,
+    },
+    /// Create a task from text read from stdin (first line as name, rest as description)
+    Capture {
+        /// Read the clipboard instead of stdin
+        #[arg(long)]
+        clipboard: bool,
+    },
+    /// Print the dependency graph of the tasks, e.g. `dooit-rs graph | dot -Tsvg > tasks.svg`, or
+    /// their timeline with `--format mermaid-gantt`
+    Graph {
+        #[arg(short, long, value_enum, default_value_t)]
+        format: graph::GraphFormat,
+        /// Include completed tasks (otherwise they only show up as dependencies)
+        #[arg(short, long)]
+        completed: bool,
+    },
+    /// Show the longest chain of dependencies under a project and how long it takes to finish
+    ///
+    /// Tasks take the part of their estimate that's left, tasks outside the project or completed
+    /// ones don't hold anything up.
+    CriticalPath { project: PathBuf },
+    /// Forecast when the tasks matching a filter are done, going by the tasks completed per week
+    Forecast {
+        /// Tasks to forecast, written like the filters of reports (e.g. `project:thesis`)
+        #[arg(short, long, default_value = "")]
+        filter: String,
+        /// Number of past weeks the velocity is averaged over
+        #[arg(short, long, default_value_t = 8)]
+        weeks: usize,
+    },
+    /// Sum the estimates of the tasks due or scheduled today and suggest what to defer if they
+    /// don't fit
+    Plan {
+        /// Time there is for the tasks today
+        #[arg(short, long, default_value = "6h")]
+        capacity: HumanDuration,
+        /// Print a Markdown sheet for the day instead: the tasks scheduled by time, a checklist of
+        /// the rest and room for notes
+        #[arg(short, long)]
+        print: bool,
+    },
+    /// Spread the unscheduled tasks over the coming days, setting their scheduled date
+    ///
+    /// Tasks due soonest are placed first, each on the first day with room for its estimate that
+    /// comes after the tasks it depends on.
+    Schedule {
+        /// How far ahead to schedule
+        #[arg(long, default_value = "2w")]
+        horizon: HumanDuration,
+        /// Time there is for tasks every day
+        #[arg(short, long, default_value = "6h")]
+        capacity: HumanDuration,
+    },
+    /// Show the habits (tasks added with `--habit`) with the days they were done or missed
+    Habits {
+        /// How many days to go back
+        #[arg(short, long, default_value_t = habits::DAYS)]
+        days: u32,
+    },
+    /// Count the pending, overdue and completed tasks
+    Stats {
+        /// Also show the days in a row tasks were completed and the points earned
+        #[arg(short, long)]
+        streaks: bool,
+        /// Only print the current streak and today's points, for a shell prompt
+        #[arg(short, long)]
+        prompt: bool,
+    },
+    /// Show every recorded change to a task, see `audit` in the config
+    History { name: PathBuf },
+    /// Check that the task files weren't changed or corrupted since dooit-rs last wrote them
+    ///
+    /// Compares every task file to the hash recorded in `.index` at the root of the data directory
+    /// and lists the ones modified, missing or not in the index.
+    Verify {
+        /// Trust the task files as they are now, recording their hashes
+        #[arg(long)]
+        accept: bool,
+    },
+    /// Print a script completing subcommands, task names and tags for a shell
+    ///
+    /// Example: `source <(dooit-rs completions bash)` in `~/.bashrc`, or
+    /// `dooit-rs completions fish > ~/.config/fish/completions/dooit-rs.fish`
+    Completions {
+        #[arg(value_enum)]
+        shell: complete::Shell,
+    },
+    /// Print the completions of the last word, used by the completion scripts
+    #[command(name = "__complete", hide = true)]
+    Complete {
+        #[arg(allow_hyphen_values = true)]
+        words: Vec<String>,
+    },
+    /// Print a line for every task added, modified, completed or deleted until interrupted
+    ///
+    /// Also notices changes made by other programs, like a sync tool or an editor.
+    Watch {
+        /// Print every change as a line of JSON with the event and the task (the old one if
+        /// deleted), e.g. `{"event":"completed","timestamp":"...","task":{...}}`
+        #[arg(long)]
+        json: bool,
+    },
+    /// Mark a task as completed
+    ///
+    /// The name can be the start of the task's name or of its last part (e.g. `tax` for
+    /// `admin/taxes`), if several tasks start like that you are asked which one.
+    Done { name: PathBuf },
+    /// Delete a task, asking first unless `--force` is given
+    Rm {
+        name: PathBuf,
+        /// Also delete its subtasks, tasks that have some aren't deleted otherwise
+        #[arg(short, long)]
+        recursive: bool,
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        force: bool,
+    },
+    /// Copy files into a task's attachments
+    Attach {
+        name: PathBuf,
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Show a task with its links and attachments
+    Show { name: PathBuf },
+    /// Open the links of a task, or one of its attachments
+    Open {
+        name: PathBuf,
+        /// Open this attachment instead, e.g. `spec.pdf`
+        #[arg(short, long, value_name = "FILE")]
+        attachment: Option<String>,
+    },
+    /// Edit a task in the editor, it's saved once it is valid
+    Edit { name: PathBuf },
+    /// Find tasks by their name (fuzzily) or description, best match first
+    Search { query: String },
+    /// Find task files that don't lead back to their task (e.g. sync conflicts) and resolve them
+    Doctor {
+        /// Only list them, failing if there are any
+        #[arg(long)]
+        check: bool,
+    },
+    /// Rename a task, moving its subtasks along
+    Mv { from: PathBuf, to: PathBuf },
+    /// Move completed tasks to the archive, `list --archived` shows them
+    Archive {
+        /// Only the ones completed more than this long ago, e.g. `30d`
+        #[arg(long, value_name = "DURATION")]
+        older_than: Option<HumanDuration>,
+    },
+    /// Push the due date of a task forward (from now if it has none)
+    ///
+    /// The name is looked up like for `done`.
+    Snooze {
+        name: PathBuf,
+        /// How far to push it, e.g. `1d` or `2w`
+        by: HumanDuration,
+    },
+    /// Take back the changes made by the last commands, see `journal` in the config
+    Undo {
+        /// How many commands to take back
+        #[arg(default_value_t = 1)]
+        count: usize,
+    },
+    /// Delete completed tasks for good, asking first unless `--yes` is given
+    ///
+    /// Tasks with subtasks that aren't deleted are kept.
+    Purge {
+        /// Only the ones completed more than this long ago, e.g. `30d`
+        #[arg(long, value_name = "DURATION")]
+        completed_before: HumanDuration,
+    },
+    /// Sync the tasks with another task directory, e.g. on a shared drive
+    ///
+    /// Fields changed on both sides since the last sync are shown side by side to pick the value
+    /// to keep, scripts pick a side with `--ours`, `--theirs` or `--newest`.
+    Sync {
+        /// The other task directory
+        dir: PathBuf,
+        /// Keep the other directory's tasks under this task
+        #[arg(long)]
+        prefix: Option<PathBuf>,
+        /// Keep the local values of the fields both sides changed
+        #[arg(long, group = "policy")]
+        ours: bool,
+        /// Take the other directory's values of the fields both sides changed
+        #[arg(long, group = "policy")]
+        theirs: bool,
+        /// Take the values of the side that changed the task last
+        #[arg(long, group = "policy")]
+        newest: bool,
+    },
+    /// Add or remove tags of a task
+    Tag {
+        #[command(subcommand)]
+        command: TagCommand,
+    },
+    /// Raise the urgency of a task to the next level
+    Bump { name: PathBuf },
+    /// Lower the urgency of a task to the previous level
+    Lower { name: PathBuf },
+    /// Serve the task list to other programs (REST API over HTTP by default)
+    Serve {
+        /// Speak the Model Context Protocol over stdio instead
+        #[arg(long)]
+        mcp: bool,
+        /// Register a service on the D-Bus session bus instead (Linux only)
+        #[arg(long, conflicts_with = "mcp")]
+        dbus: bool,
+        /// Address to listen on for HTTP requests
+        #[arg(short, long, default_value = "127.0.0.1:8080")]
+        listen: SocketAddr,
+        /// Require this bearer token on every HTTP request
+        #[arg(long, env = "DOOIT_TOKEN", hide_env_values = true)]
+        token: Option<String>,
+        /// Also accept this token as `/feed?token=`, for feed readers that can't send headers
+        ///
+        /// It only gives access to the feed, since it ends up in URLs and logs.
+        #[arg(
+            long,
+            env = "DOOIT_FEED_TOKEN",
+            hide_env_values = true,
+            requires = "token"
+        )]
+        feed_token: Option<String>,
+    },
+    /// Send desktop notifications for tasks that are due soon or overdue
+    ///
+    /// Reminders are only sent once per task and lead time, so this is safe to run periodically
+    /// from cron or a systemd timer.
+    Notify {
+        #[command(subcommand)]
+        command: Option<NotifyCommand>,
+    },
+    /// Summarize overdue, upcoming and recently completed tasks
+    Digest {
+        /// Send the digest to the addresses in `digest.email` instead of printing it
+        #[arg(long)]
+        email: bool,
+    },
+    /// Work on a task in pomodoros, logging the time spent into it
+    Pomo {
+        /// Task to work on
+        name: PathBuf,
+        /// Length of a pomodoro
+        #[arg(short, long, default_value = "25m")]
+        work: HumanDuration,
+        /// Length of the breaks between pomodoros
+        #[arg(short = 'b', long = "break", default_value = "5m")]
+        rest: HumanDuration,
+        /// Number of pomodoros
+        #[arg(short, long, default_value_t = 4)]
+        count: u32,
+    },
+    /// Go through the tasks up for review, stale, undated and waiting tasks one by one, deciding
+    /// what to do with each
+    ///
+    /// Set a task's `--review` date (or pick `later` here) to put it out of the way until then.
+    Review,
+    /// Change fields of every task matching a filter, e.g. `--filter +sprint12 urgency:high due:+1w`
+    ///
+    /// Changes are `+tag`, `-tag`, `urgency:`, `status:`, `due:`, `wait:`, `scheduled:`,
+    /// `review:`, `estimate:`, `assignee:` and `recur:`. Dates can be moved with `due:+1w` and
+    /// cleared with `due:none`.
+    Modify {
+        /// Only change the tasks matching this filter, written like the ones of reports
+        #[arg(short, long)]
+        filter: String,
+        /// Changes to apply to the matching tasks
+        #[arg(required = true, allow_hyphen_values = true)]
+        changes: Vec<String>,
+    },
+    /// Find pending tasks that are likely duplicates and merge each pair into the one kept
+    ///
+    /// With `--yes` every duplicate is merged into the task that comes first by name.
+    Dedupe,
+    /// Show a single task full screen, with its checklist and a timer
+    Focus {
+        /// Task to focus on
+        name: PathBuf,
+    },
+    /// Run in the background, sending reminders and caching tasks for other commands
+    Daemon {
+        #[command(subcommand)]
+        command: Option<DaemonCommand>,
+    },
+    /// Manage the tokens and passwords of services in the keyring (`telegram`, `matrix` or `smtp`)
+    #[command(alias = "secret")]
+    Auth {
+        #[command(subcommand)]
+        command: AuthCommand,
+    },
+    /// Browse and manage tasks interactively
+    Tui,
+    /// Run commands like `add` or `done` interactively
+    Shell,
+    /// Print tasks for dmenu or rofi, or act on the task chosen from them
+    ///
+    /// Example: `dooit-rs menu | rofi -dmenu | dooit-rs menu complete`
+    Menu {
+        /// Perform this action on the task read from stdin instead of printing tasks
+        #[arg(value_enum)]
+        action: Option<menu::Action>,
+        /// Include completed tasks
+        #[arg(short, long)]
+        completed: bool,
+    },
+    /// Run the WebAssembly plugin `<config dir>/plugins/<NAME>.wasm`
+    Plugin {
+        name: String,
+        #[command(subcommand)]
+        command: PluginCommand,
     },
-    /// Add a task
-    Add(Task),
     /// Edit the Configuration
-    Config,
+    Config {
+        #[command(subcommand)]
+        command: Option<ConfigCommand>,
+    },
+    /// Any other subcommand runs `dooit-<NAME>` from `PATH`
+    #[command(external_subcommand)]
+    External(Vec<OsString>),
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigCommand {
+    /// Print the value of an option, e.g. `list.default_sort`
+    Get { key: String },
+    /// Change an option in the config file, e.g. `notify.lead_times 1h`
+    ///
+    /// The value is read as TOML if possible and as a string otherwise.
+    Set { key: String, value: String },
+    /// Check the config file for errors and unknown options
+    Check,
+    /// Write a sample config file documenting every option
+    Init {
+        /// Replace the existing config file
+        #[arg(short, long)]
+        force: bool,
+    },
+    /// Read every option from the project config (`.dooit/config.toml`) that applies here
+    ///
+    /// Until then it can only set `list`, `add`, `contexts` and `reports`, the other options can
+    /// run commands or move where tasks are kept.
+    Trust,
+    /// Go back to reading only the harmless options from the project config that applies here
+    Untrust,
+}
+
+#[derive(Subcommand, Debug)]
+enum ReportCommand {
+    /// Sum the time logged on tasks, e.g. `report time --by tag --since 1w --csv`
+    Time {
+        #[arg(short, long, value_enum, default_value_t)]
+        by: timesheet::TimeGroup,
+        /// Only count the time logged this long ago or later
+        #[arg(short, long)]
+        since: Option<HumanDuration>,
+        /// Print comma separated values with the hours as a decimal number
+        #[arg(long)]
+        csv: bool,
+    },
+    /// Compare the estimates of completed tasks to the time logged on them
+    Accuracy {
+        #[arg(short, long, value_enum, default_value_t)]
+        by: accuracy::AccuracyGroup,
+        /// Only count the tasks completed this long ago or later
+        #[arg(short, long)]
+        since: Option<HumanDuration>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ContextCommand {
+    /// Only list the tasks matching a filter from `contexts` in the config
+    Set { name: String },
+    /// List every task again
+    Clear,
+}
+
+#[derive(Subcommand, Debug)]
+enum TagCommand {
+    /// Tag a task, e.g. `tag add report +work urgent`
+    Add {
+        name: PathBuf,
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+    /// Remove tags from a task
+    Remove {
+        name: PathBuf,
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum DaemonCommand {
+    /// Show the state of the running daemon
+    Status,
+}
+
+#[derive(Subcommand, Debug)]
+enum NotifyCommand {
+    /// Stop repeating the reminder for a task
+    Ack { name: String },
+}
+
+#[derive(Subcommand, Debug)]
+enum AuthCommand {
+    /// Store the token or password of a service, read from stdin or typed in
+    Set { service: String },
+    /// Remove the token or password of a service
+    #[command(alias = "delete")]
+    Remove { service: String },
+}
+
+#[derive(Subcommand, Debug)]
+enum PluginCommand {
+    /// Add the tasks the plugin parses from a file
+    Import { file: PathBuf },
+    /// Write every task in the plugin's format
+    Export {
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Print the plugin's report of every task
+    Report,
+}
+
+/// What `--dry-run` does with a command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DryRunSupport {
+    /// Nothing, the command doesn't change anything anyway
+    ReadOnly,
+    /// The command prints the files it would change instead of changing them
+    Shown,
+    /// The command can't run without its side effects (it's interactive, keeps running, sends
+    /// messages or runs other programs), so it fails instead
+    Refused,
+}
+
+impl Mode {
+    fn dry_run(&self) -> DryRunSupport {
+        match self {
+            Mode::List { .. }
+            | Mode::Report { .. }
+            | Mode::Context { command: None }
+            | Mode::Graph { .. }
+            | Mode::CriticalPath { .. }
+            | Mode::Forecast { .. }
+            | Mode::Plan { .. }
+            | Mode::Habits { .. }
+            | Mode::Stats { .. }
+            | Mode::History { .. }
+            | Mode::Verify { accept: false }
+            | Mode::Completions { .. }
+            | Mode::Complete { .. }
+            | Mode::Watch { .. }
+            | Mode::Show { .. }
+            | Mode::Open { .. }
+            | Mode::Search { .. }
+            | Mode::Digest { email: false }
+            | Mode::Daemon {
+                command: Some(DaemonCommand::Status),
+            }
+            | Mode::Menu { action: None, .. }
+            | Mode::Plugin {
+                command: PluginCommand::Export { .. } | PluginCommand::Report,
+                ..
+            }
+            | Mode::Config {
+                command: Some(ConfigCommand::Get { .. } | ConfigCommand::Check),
+            } => DryRunSupport::ReadOnly,
+            Mode::Context { command: Some(_) }
+            | Mode::Add { .. }
+            | Mode::Scaffold { .. }
+            | Mode::Capture { .. }
+            | Mode::Schedule { .. }
+            | Mode::Verify { accept: true }
+            | Mode::Done { .. }
+            | Mode::Rm { .. }
+            | Mode::Attach { .. }
+            | Mode::Edit { .. }
+            | Mode::Doctor { .. }
+            | Mode::Mv { .. }
+            | Mode::Archive { .. }
+            | Mode::Snooze { .. }
+            | Mode::Undo { .. }
+            | Mode::Purge { .. }
+            | Mode::Sync { .. }
+            | Mode::Tag { .. }
+            | Mode::Bump { .. }
+            | Mode::Lower { .. }
+            | Mode::Notify {
+                command: Some(NotifyCommand::Ack { .. }),
+            }
+            | Mode::Pomo { .. }
+            | Mode::Modify { .. }
+            | Mode::Dedupe
+            | Mode::Menu {
+                action: Some(_), ..
+            }
+            | Mode::Plugin {
+                command: PluginCommand::Import { .. },
+                ..
+            }
+            | Mode::Config {
+                command:
+                    Some(
+                        ConfigCommand::Set { .. }
+                        | ConfigCommand::Init { .. }
+                        | ConfigCommand::Trust
+                        | ConfigCommand::Untrust,
+                    ),
+            } => DryRunSupport::Shown,
+            Mode::Serve { .. }
+            | Mode::Notify { command: None }
+            | Mode::Digest { email: true }
+            | Mode::Review
+            | Mode::Focus { .. }
+            | Mode::Daemon { command: None }
+            | Mode::Auth { .. }
+            | Mode::Tui
+            | Mode::Shell
+            | Mode::Config { command: None }
+            | Mode::External(_) => DryRunSupport::Refused,
+        }
+    }
+}
+
+/// The subcommands in `matches`, e.g. `config set`
+fn command_name(matches: &clap::ArgMatches) -> String {
+    let mut names = Vec::new();
+    let mut matches = matches;
+    while let Some((name, subcommand)) = matches.subcommand() {
+        names.push(name);
+        matches = subcommand;
+    }
+    names.join(" ")
+}
+
+/// Replace the subcommand in `args` with its expansion if it's one of `aliases`
+///
+/// Built-in subcommands can't be shadowed, and aliases aren't expanded recursively.
+fn expand_aliases(args: Vec<OsString>, aliases: &BTreeMap<String, String>) -> Vec<OsString> {
+    // Skip the binary and the global options to find the subcommand
+    let mut index = 1;
+    while let Some(arg) = args.get(index) {
+        match arg.to_str() {
+            Some("-e" | "--editor" | "-C" | "--config-option") => index += 2,
+            Some(arg) if arg.starts_with('-') => index += 1,
+            _ => break,
+        }
+    }
+
+    let Some(expansion) = args
+        .get(index)
+        .and_then(|arg| arg.to_str())
+        .filter(|name| Cli::command().find_subcommand(name).is_none())
+        .and_then(|name| aliases.get(name))
+    else {
+        return args;
+    };
+
+    let mut args = args;
+    args.splice(
+        index..=index,
+        expansion.split_whitespace().map(OsString::from),
+    );
+    args
+}
+
+/// Move a task to the next or previous urgency level for `bump` and `lower`
+fn bump(config: &Config, name: &Path, up: bool, dry_run: bool) -> color_eyre::Result<()> {
+    let dir_store = failure::store(config)?;
+    let store = DryRun::new(&dir_store, dry_run);
+    let task = actions::bump(config, &store, name, up)?;
+    if !dry_run {
+        println!("{}", color::task(&task, config, chrono::Utc::now()));
+    }
+    Ok(())
+}
+
+/// Delete the task called `name`, and its subtasks if `recursive`
+fn rm(
+    config: &Config,
+    name: &Path,
+    recursive: bool,
+    force: bool,
+    dry_run: bool,
+) -> color_eyre::Result<()> {
+    let dir_store = failure::store(config)?;
+    let store = DryRun::new(&dir_store, dry_run);
+    let mut tasks = store
+        .load_summaries_under(name)
+        .wrap_err_with(|| format!("load task {}", name.display()))?;
+    if !tasks.iter().any(|task| task.name == name) {
+        color_eyre::eyre::bail!("no task named {}", name.display());
+    }
+    let subtasks = tasks.len() - 1;
+    if subtasks > 0 && !recursive {
+        color_eyre::eyre::bail!(
+            "{} has {subtasks} subtask(s), pass --recursive to delete them too",
+            name.display()
+        );
+    }
+
+    let summary = tr!("tasks-to-delete", count = tasks.len());
+    if !prompt::confirm(&summary, force || dry_run)? {
+        return Ok(());
+    }
+    // Subtasks go before their parents
+    tasks.sort_by_key(|task| std::cmp::Reverse(task.name.components().count()));
+    for task in tasks {
+        actions::remove(config, &store, &task.name)?;
+        if !dry_run {
+            println!("{}", tr!("task-deleted", name = task.name.display()));
+        }
+    }
+    Ok(())
 }
 
 /// Returns Ok(false) if the path already exists
@@ -43,96 +821,876 @@ fn create_dir_all_if_missing(path: impl AsRef<std::path::Path>) -> std::io::Resu
     std::fs::create_dir_all(path).map(|_| true)
 }
 
-fn main() -> color_eyre::Result<()> {
-    color_eyre::install()?;
+fn write_sample_config(path: &std::path::Path) -> color_eyre::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).wrap_err_with(|| format!("create {}", dir.display()))?;
+    }
+    std::fs::write(path, config::sample()?).wrap_err_with(|| format!("create {}", path.display()))
+}
+
+/// Trust the project config that applies here, or stop trusting it
+fn trust_project_config(trusted: bool, dry_run: bool) -> color_eyre::Result<()> {
+    let path = config::project_config_path()
+        .ok_or_else(|| color_eyre::eyre::eyre!("{}", tr!("no-project-config")))?;
+    if dry_run {
+        return config::trust::show(&path, trusted);
+    }
+    let message = match (config::trust::set(&path, trusted)?, trusted) {
+        (true, true) => "config-trusted",
+        (false, true) => "config-already-trusted",
+        (true, false) => "config-untrusted-now",
+        (false, false) => "config-not-trusted",
+    };
+    println!("{}", tr!(message, path = path.display()));
+    Ok(())
+}
 
-    let args = Cli::parse();
+fn main() -> ExitCode {
+    if let Err(err) = color_eyre::install() {
+        eprintln!("Error: {err:?}");
+        return ExitCode::FAILURE;
+    }
+
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error: {err:?}");
+            failure::exit_code(&err)
+        }
+    }
+}
+
+fn run() -> color_eyre::Result<()> {
+    // Aliases and urgencies can't come from `--config-option`, it's only known once the arguments
+    // are parsed, and that needs both of them
+    let mut config = Config::load(&[])?;
+    config.install_urgencies()?;
+
+    // `add` without a name starts the wizard instead of failing (`mut_arg` moves the argument to
+    // the end, so the positional arguments need explicit indices)
+    let matches = Cli::command()
+        .mut_subcommand("add", |add| {
+            add.mut_arg("name", |name| name.required(false).index(1))
+                .mut_arg("description", |description| description.index(2))
+        })
+        .get_matches_from(expand_aliases(
+            std::env::args_os().collect(),
+            &config.aliases,
+        ));
+    let args = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    if !args.config_options.is_empty() {
+        config = Config::load(&args.config_options)?;
+    }
+    logging::init(args.verbose, args.quiet, config.log_file.as_deref())?;
+    i18n::init(config.locale.as_deref());
+    if let Some((path, options)) = &config.ignored {
+        tracing::warn!(
+            "{}",
+            tr!(
+                "config-untrusted",
+                path = path.display(),
+                options = options.join(", ")
+            )
+        );
+    }
+    if args.strict {
+        config.limits.strict = true;
+    }
+    if args.dry_run && args.mode.dry_run() == DryRunSupport::Refused {
+        let command = command_name(&matches);
+        color_eyre::eyre::bail!("{}", tr!("dry-run-refused", command = command));
+    }
+    if args.dry_run {
+        // Hooks and webhooks could have side effects of their own
+        config.hooks = Default::default();
+        config.webhooks.clear();
+        config.audit.enabled = false;
+        config.journal.enabled = false;
+    }
+    let _signing = signing::Guard::new(&config);
+    if let Some(command) = matches.subcommand_name() {
+        journal::begin(command);
+    }
 
     match args.mode {
         Mode::List {
             sort,
             completed,
             overdue,
+            watch,
+            interval,
+            script,
+            under,
+            mine,
+            assignee,
+            archived,
         } => {
-            let data_dir = dirs::get_data_dir().expect("data dir");
+            let data_dir = failure::data_dir(&config)?;
+            let sort = sort.unwrap_or(config.list.default_sort);
+            // Archived tasks are all completed
+            let completed = completed || archived || config.list.completed;
+            let overdue = overdue || config.list.overdue;
+            let assignee = match assignee {
+                Some(assignee) => Some(assignee),
+                None if mine || config.list.mine => match &config.identity {
+                    Some(identity) => Some(identity.clone()),
+                    None => color_eyre::eyre::bail!(
+                        "set `identity` in the config to list the tasks assigned to you"
+                    ),
+                },
+                None => None,
+            };
 
             if !data_dir.exists() {
-                panic!(
-                    "The task directory is empty, add some by running:\n\t`{} add`",
-                    std::env::args().next().unwrap_or_else(|| "dooit-rs".into())
-                );
+                if !watch {
+                    println!("{}", tr!("no-tasks-yet"));
+                    return Ok(());
+                }
+                std::fs::create_dir_all(data_dir)
+                    .wrap_err_with(|| format!("create {}", data_dir.display()))?;
             }
 
-            let tasks = get_tasks()?;
-            let today = chrono::Utc::now();
-            let filtered = tasks
-                .into_iter()
-                .filter(|task| {
-                    (!task.completed || completed)
-                        && (task.due.map(|date| date >= today).unwrap_or(true) || overdue)
-                })
-                .collect::<Vec<_>>();
-
-            if filtered.is_empty() {
-                println!("No tasks to do!");
+            #[cfg(feature = "scripting")]
+            let script = script.as_deref().map(scripting::Script::load).transpose()?;
+            #[cfg(not(feature = "scripting"))]
+            if script.is_some() {
+                color_eyre::eyre::bail!("dooit-rs was built without scripting support");
+            }
+
+            let store = failure::store(&config)?;
+            let store = match archived {
+                true => store.archive(),
+                false => store,
+            };
+            let store = ViaDaemon::new(&store);
+            let print = || -> color_eyre::Result<()> {
+                let context = Context::active(&config)?;
+                let parent = match (&under, &context) {
+                    (Some(under), _) => under.as_path(),
+                    (None, Some(context)) => context.prefix(),
+                    (None, None) => Path::new(""),
+                };
+                let mut sorted =
+                    actions::list_under(&config, &store, parent, sort, completed, overdue)?;
+                let now = chrono::Utc::now();
+                if let Some(context) = &context {
+                    println!("{}", context.header(&config));
+                    sorted.retain(|task| context.matches(task, now));
+                }
+                if let Some(assignee) = &assignee {
+                    sorted.retain(|task| task.assignee.as_ref() == Some(assignee));
+                }
+
+                #[cfg(feature = "scripting")]
+                let rows = match &script {
+                    Some(script) => script.apply(sorted)?,
+                    None => sorted.into_iter().map(|task| (task, None)).collect(),
+                };
+                #[cfg(not(feature = "scripting"))]
+                let rows: Vec<(Task, Option<String>)> =
+                    sorted.into_iter().map(|task| (task, None)).collect();
+
+                if rows.is_empty() {
+                    println!("{}", tr!("no-tasks"));
+                }
+                if !config.limits.is_empty() {
+                    let tasks = store
+                        .load_summaries_under(Path::new(""))
+                        .wrap_err("load tasks")?;
+                    for (group, count, limit) in limits::exceeded(&config.limits, &tasks) {
+                        let message = tr!(
+                            "limit-exceeded",
+                            group = group,
+                            count = count,
+                            limit = limit
+                        );
+                        eprintln!("warning: {message}");
+                    }
+                }
+
+                for (task, column) in rows {
+                    let read_only = config
+                        .read_only_source(&task.name)
+                        .map(|_| tr!("read-only-label"));
+                    let columns: Vec<_> = column.into_iter().chain(read_only).collect();
+                    let task = color::task(&task, &config, now);
+                    if columns.is_empty() {
+                        println!("{task}");
+                        continue;
+                    }
+                    let column = columns.join(" ");
+
+                    // Keep the column on the task's line, above the description
+                    match task.split_once('\n') {
+                        Some((line, description)) => println!("{line} {column}\n{description}"),
+                        None => println!("{task} {column}"),
+                    }
+                }
+
+                Ok(())
+            };
+
+            if !watch {
+                return print();
+            }
+
+            let watcher = watch::Watcher::new(data_dir)?;
+            let interval = interval.map(std::time::Duration::from_secs);
+            loop {
+                // Clear the screen and move the cursor to the top left corner
+                print!("\x1b[2J\x1b[H");
+                print()?;
+                watcher.wait(interval)?;
+            }
+        }
+        Mode::Report {
+            command: Some(ReportCommand::Time { by, since, csv }),
+            ..
+        } => {
+            let store = failure::store(&config)?;
+            timesheet::run(&config, &store, by, since.map(|since| since.0), csv)?;
+        }
+        Mode::Report {
+            command: Some(ReportCommand::Accuracy { by, since }),
+            ..
+        } => {
+            let store = failure::store(&config)?;
+            accuracy::run(&config, &store, by, since.map(|since| since.0))?;
+        }
+        Mode::Report { name: None, .. } => {
+            for name in config.reports.keys() {
+                println!("{name}");
+            }
+        }
+        Mode::Report {
+            name: Some(name), ..
+        } => {
+            let data_dir = failure::data_dir(&config)?;
+            if !data_dir.exists() {
+                println!("{}", tr!("no-tasks-yet"));
                 return Ok(());
             }
+            let store = failure::store(&config)?;
+            report::run(&config, &store, &name)?;
+        }
+        Mode::Context { command: None } => context::show(&config)?,
+        Mode::Context {
+            command: Some(ContextCommand::Set { name }),
+        } => {
+            context::set(&config, &name, args.dry_run)?;
+            if !args.dry_run {
+                println!("{}", tr!("context-set", name = name));
+            }
+        }
+        Mode::Context {
+            command: Some(ContextCommand::Clear),
+        } => {
+            context::clear(args.dry_run)?;
+            if !args.dry_run {
+                println!("{}", tr!("context-cleared"));
+            }
+        }
+        Mode::Add { template, task } => {
+            let data_dir = failure::data_dir(&config)?;
 
-            let sorted = sort_tasks(filtered, sort);
+            if args.dry_run {
+                if !data_dir.exists() {
+                    println!("{}", tr!("would-create", path = data_dir.display()));
+                }
+            } else {
+                let created = create_dir_all_if_missing(data_dir).wrap_err_with(|| {
+                    format!("create the task directory {}", data_dir.display())
+                })?;
+                if created {
+                    println!("{}", tr!("creating-task-dir"));
+                }
+            }
 
-            for task in sorted {
-                println!("{task}");
+            let dir_store = failure::store(&config)?;
+            let store = DryRun::new(&dir_store, args.dry_run);
+            let tasks = match (template.split_first(), task) {
+                (Some((template, vars)), _) => templates::instantiate(template, vars)?,
+                (None, Some(mut task)) => {
+                    let urgency = matches
+                        .subcommand_matches("add")
+                        .and_then(|add| add.value_source("urgency"));
+                    let urgency_given = urgency == Some(ValueSource::CommandLine);
+                    config.add.apply(&mut task, urgency_given)?;
+                    vec![task]
+                }
+                (None, None) => {
+                    let editor = Editor::resolve(args.editor.as_deref(), &config)?;
+                    let mut task = prompt::add_task(&store, &editor, &config.add)?;
+                    // The urgency was picked in the wizard
+                    config.add.apply(&mut task, true)?;
+                    vec![task]
+                }
+            };
+            actions::add_all(&config, &store, tasks)?;
+        }
+        Mode::Scaffold { blueprint, vars } => {
+            let dir_store = failure::store(&config)?;
+            let store = DryRun::new(&dir_store, args.dry_run);
+            let tasks = templates::scaffold(&blueprint, &vars)?;
+            // Don't leave half a tree behind
+            for task in &tasks {
+                if store.load(&task.name)?.is_some() {
+                    color_eyre::eyre::bail!("a task named {} already exists", task.name.display());
+                }
+            }
+            let summary = tr!("tasks-to-create", count = tasks.len());
+            if tasks.len() > 1 && !prompt::confirm(&summary, args.yes || args.dry_run)? {
+                color_eyre::eyre::bail!("aborted");
+            }
+            let names: Vec<_> = tasks.iter().map(|task| task.name.clone()).collect();
+            actions::add_all(&config, &store, tasks)?;
+            if !args.dry_run {
+                for name in names {
+                    println!("{}", tr!("task-created", name = name.display()));
+                }
             }
         }
-        Mode::Add(task) => {
-            let data_dir = dirs::get_data_dir().expect("data dir");
+        Mode::Capture { clipboard } => {
+            let text = if clipboard {
+                capture::read_clipboard()?
+            } else {
+                capture::read_stdin()?
+            };
+            let Some(mut task) = capture::parse(&text) else {
+                color_eyre::eyre::bail!("nothing to capture");
+            };
+            config.add.apply(&mut task, false)?;
 
-            create_dir_all_if_missing(data_dir)
-                .map(|created| {
-                    if created {
-                        println!("The task directory doesn't exist, creating it...");
+            let dir_store = failure::store(&config)?;
+            let store = DryRun::new(&dir_store, args.dry_run);
+            let name = task.name.clone();
+            actions::add(&config, &store, task)?;
+            if !args.dry_run {
+                println!("{}", tr!("task-captured", name = name.display()));
+            }
+        }
+        Mode::Graph { format, completed } => {
+            let store = failure::store(&config)?;
+            let mut tasks = store.load_all().wrap_err("load tasks")?;
+            tasks.retain(|task| completed || !task.completed);
+            tasks.sort_by(|a, b| a.name.cmp(&b.name));
+            print!(
+                "{}",
+                graph::render(&tasks, format, &config, chrono::Utc::now())
+            );
+        }
+        Mode::CriticalPath { project } => {
+            let store = failure::store(&config)?;
+            critical_path::run(&config, &store, &project)?;
+        }
+        Mode::Forecast { filter, weeks } => {
+            let store = failure::store(&config)?;
+            forecast::run(&config, &store, &filter, weeks)?;
+        }
+        Mode::Plan { capacity, print } => {
+            let store = failure::store(&config)?;
+            plan::run(&config, &store, capacity.0, print)?;
+        }
+        Mode::Schedule { horizon, capacity } => {
+            let dir_store = failure::store(&config)?;
+            let store = DryRun::new(&dir_store, args.dry_run);
+            let (yes, dry_run) = (args.yes, args.dry_run);
+            schedule::run(&config, &store, horizon.0, capacity.0, yes, dry_run)?;
+        }
+        Mode::Habits { days } => {
+            let store = failure::store(&config)?;
+            habits::run(&config, &store, days)?;
+        }
+        Mode::Stats { streaks, prompt } => {
+            let store = failure::store(&config)?;
+            stats::run(&config, &ViaDaemon::new(&store), streaks, prompt)?;
+        }
+        Mode::History { name } => audit::run(&config, &name)?,
+        Mode::Verify { accept } => {
+            let store = failure::store(&config)?;
+            verify::run(&config, &store, accept, args.dry_run)?;
+        }
+        Mode::Completions { shell } => complete::script(shell),
+        Mode::Complete { words } => complete::run(&config, &words)?,
+        Mode::Watch { json } => {
+            let store = failure::store(&config)?;
+            watch::run(&config, &store, json)?;
+        }
+        Mode::Done { name } => {
+            let dir_store = failure::store(&config)?;
+            let store = DryRun::new(&dir_store, args.dry_run);
+            let name = lookup::resolve(&store, &name)?;
+            let task = actions::complete(&config, &store, &name)?;
+            if !args.dry_run {
+                let message = match (task.completed, task.due) {
+                    (false, Some(due)) => {
+                        let due = due.with_timezone(&chrono::Local).format("%a %F %R");
+                        tr!("task-recurs", name = task.name.display(), due = due)
                     }
-                })
-                .expect("create task directory");
-
-            let task_path = {
-                let mut task_path = data_dir.join(task.name.as_path());
-                task_path.set_extension("toml");
-                task_path
+                    _ => tr!("task-completed", name = task.name.display()),
+                };
+                println!("{message}");
+            }
+        }
+        Mode::Rm {
+            name,
+            recursive,
+            force,
+        } => rm(&config, &name, recursive, force || args.yes, args.dry_run)?,
+        Mode::Attach { name, files } => {
+            let store = failure::store(&config)?;
+            attachments::attach(&config, &store, &name, &files, args.dry_run)?;
+        }
+        Mode::Show { name } => {
+            let store = failure::store(&config)?;
+            attachments::show(&config, &store, &name)?;
+        }
+        Mode::Open { name, attachment } => {
+            let store = failure::store(&config)?;
+            attachments::open(&store, &name, attachment.as_deref())?;
+        }
+        Mode::Edit { name } => {
+            let store = failure::store(&config)?;
+            let editor = Editor::resolve(args.editor.as_deref(), &config)?;
+            edit::run(&config, &store, &editor, &name, args.dry_run)?;
+        }
+        Mode::Search { query } => {
+            let store = failure::store(&config)?;
+            search::run(&config, &store, &query)?;
+        }
+        Mode::Doctor { check } => {
+            let store = failure::store(&config)?;
+            doctor::run(&config, &store, check, args.dry_run)?;
+        }
+        Mode::Mv { from, to } => {
+            let dir_store = failure::store(&config)?;
+            let store = DryRun::new(&dir_store, args.dry_run);
+            let count = actions::rename(&config, &store, &from, &to)?;
+            if !args.dry_run {
+                let message = tr!(
+                    "task-moved",
+                    from = from.display(),
+                    to = to.display(),
+                    count = count
+                );
+                println!("{message}");
+            }
+        }
+        Mode::Archive { older_than } => {
+            let store = failure::store(&config)?;
+            archive::run(
+                &config,
+                &store,
+                older_than.map(|older_than| older_than.0),
+                args.dry_run,
+            )?;
+        }
+        Mode::Snooze { name, by } => {
+            let dir_store = failure::store(&config)?;
+            let store = DryRun::new(&dir_store, args.dry_run);
+            let name = lookup::resolve(&store, &name)?;
+            let task = actions::snooze(&config, &store, &name, by.0)?;
+            if !args.dry_run {
+                let due = task.due.map(|due| due.with_timezone(&chrono::Local));
+                let due = due.map(|due| due.format("%a %F %R").to_string());
+                let message = tr!(
+                    "task-snoozed",
+                    name = task.name.display(),
+                    due = due.unwrap_or_default()
+                );
+                println!("{message}");
+            }
+        }
+        Mode::Undo { count } => {
+            let dir_store = failure::store(&config)?;
+            let store = DryRun::new(&dir_store, args.dry_run);
+            journal::undo(&config, &store, count, args.dry_run)?;
+        }
+        Mode::Purge { completed_before } => {
+            let dir_store = failure::store(&config)?;
+            let store = DryRun::new(&dir_store, args.dry_run);
+            let (yes, dry_run) = (args.yes, args.dry_run);
+            archive::purge(&config, &store, completed_before.0, yes, dry_run)?;
+        }
+        Mode::Sync {
+            dir,
+            prefix,
+            ours,
+            theirs,
+            newest,
+        } => {
+            let store = failure::store(&config)?;
+            let policy = sync::policy(ours, theirs, newest);
+            sync::run(
+                &config,
+                &store,
+                &dir,
+                &prefix.unwrap_or_default(),
+                policy,
+                args.dry_run,
+            )?;
+        }
+        Mode::Tag { command } => {
+            let (name, tags, add) = match command {
+                TagCommand::Add { name, tags } => (name, tags, true),
+                TagCommand::Remove { name, tags } => (name, tags, false),
+            };
+            let tags: Vec<_> = tags
+                .iter()
+                .map(|tag| tag.trim_start_matches('+').to_string())
+                .collect();
+            let (added, removed) = match add {
+                true => (tags.as_slice(), [].as_slice()),
+                false => ([].as_slice(), tags.as_slice()),
             };
 
-            create_dir_all_if_missing(task_path.parent().expect("valid parent"))
-                .expect("create subtask folder");
+            let dir_store = failure::store(&config)?;
+            let store = DryRun::new(&dir_store, args.dry_run);
+            let task = actions::retag(&config, &store, &name, added, removed)?;
+            if !args.dry_run {
+                println!("{}", color::task(&task, &config, chrono::Utc::now()));
+            }
+        }
+        Mode::Bump { name } => bump(&config, &name, true, args.dry_run)?,
+        Mode::Lower { name } => bump(&config, &name, false, args.dry_run)?,
+        Mode::Serve {
+            mcp,
+            dbus,
+            listen,
+            token,
+            feed_token,
+        } => {
+            let store = failure::store(&config)?;
+            if mcp {
+                mcp::serve(&config, &store)?;
+            } else if dbus {
+                #[cfg(target_os = "linux")]
+                dbus::serve(config, store)?;
+                #[cfg(not(target_os = "linux"))]
+                color_eyre::eyre::bail!("the D-Bus service is only available on Linux");
+            } else {
+                server::serve(
+                    &config,
+                    &store,
+                    listen,
+                    token.as_deref(),
+                    feed_token.as_deref(),
+                )?;
+            }
+        }
+        Mode::Notify {
+            command: Some(NotifyCommand::Ack { name }),
+        } => {
+            if !reminders::acknowledge(&name, args.dry_run)? {
+                color_eyre::eyre::bail!("no repeating reminder for {name}");
+            }
+        }
+        Mode::Notify { command: None } => {
+            let store = failure::store(&config)?;
+            reminders::notify(&config, &store)?;
+        }
+        Mode::Digest { email } => {
+            let store = failure::store(&config)?;
+            let digest = digest::Digest::new(&store.load_all()?, chrono::Utc::now());
+            if !email {
+                print!("{digest}");
+            } else if let Some(email) = &config.digest.email {
+                digest::send_email(email, &digest)?;
+            } else {
+                color_eyre::eyre::bail!("no email configured in the digest.email section");
+            }
+        }
+        Mode::Pomo {
+            name,
+            work,
+            rest,
+            count,
+        } => {
+            let dir_store = failure::store(&config)?;
+            let store = DryRun::new(&dir_store, args.dry_run);
+            pomo::run(&config, &store, &name, work.0, rest.0, count)?;
+        }
+        Mode::Review => {
+            let store = failure::store(&config)?;
+            review::run(&config, &store)?;
+        }
+        Mode::Modify { filter, changes } => {
+            let dir_store = failure::store(&config)?;
+            let store = DryRun::new(&dir_store, args.dry_run);
+            modify::run(&config, &store, &filter, &changes, args.yes, args.dry_run)?;
+        }
+        Mode::Dedupe => {
+            let dir_store = failure::store(&config)?;
+            let store = DryRun::new(&dir_store, args.dry_run);
+            dedupe::run(&config, &store, args.yes, args.dry_run)?;
+        }
+        Mode::Focus { name } => {
+            let store = failure::store(&config)?;
+            tui::focus::run(config, store, &name)?;
+        }
+        Mode::Daemon { command: None } => {
+            let store = failure::store(&config)?;
+            daemon::run(&config, &store)?;
+        }
+        Mode::Daemon {
+            command: Some(DaemonCommand::Status),
+        } => {
+            #[cfg(unix)]
+            match ipc::query(&ipc::Request::Status)? {
+                ipc::Response::Status {
+                    pid,
+                    tasks,
+                    next_reminder,
+                } => {
+                    println!("{}", tr!("daemon-running", pid = pid, tasks = tasks));
+                    if let Some(next) = next_reminder {
+                        let next = next.with_timezone(&chrono::Local).format("%a %F %R");
+                        println!("{}", tr!("daemon-next-reminder", at = next));
+                    }
+                }
+                response => color_eyre::eyre::bail!("unexpected response {response:?}"),
+            }
+            #[cfg(not(unix))]
+            color_eyre::eyre::bail!("querying the daemon is only supported on Unix");
+        }
+        Mode::Shell => {
+            let store = failure::store(&config)?;
+            shell::run(&config, &store, args.yes)?;
+        }
+        Mode::Menu { action, completed } => {
+            let store = failure::store(&config)?;
+            match action {
+                Some(action) => menu::pick(&config, &DryRun::new(&store, args.dry_run), action)?,
+                None => menu::print(&config, &store, completed)?,
+            }
+        }
+        Mode::Auth {
+            command: AuthCommand::Set { service },
+        } => {
+            let prompt = format!("{service}: ");
+            let secret = match prompt::read_line(&mut prompt::Prompt::new()?, &prompt, [])? {
+                Some(secret) if !secret.is_empty() => secret,
+                _ => color_eyre::eyre::bail!("no secret given"),
+            };
+            secrets::set(&service, &secret)?;
+        }
+        Mode::Auth {
+            command: AuthCommand::Remove { service },
+        } => {
+            if !secrets::delete(&service)? {
+                color_eyre::eyre::bail!("no secret stored for {service}");
+            }
+        }
+        Mode::Tui => {
+            let store = failure::store(&config)?;
+            let editor = Editor::resolve(args.editor.as_deref(), &config)?;
+            tui::run(config, store, editor)?;
+        }
+        #[cfg(feature = "wasm-plugins")]
+        Mode::Plugin { name, command } => {
+            let store = failure::store(&config)?;
+            let mut plugin = plugins::Plugin::load(&name)?;
+            match command {
+                PluginCommand::Import { file } => {
+                    let store = DryRun::new(&store, args.dry_run);
+                    let contents = std::fs::read(&file)
+                        .wrap_err_with(|| format!("read {}", file.display()))?;
+                    let mut tasks = Vec::new();
+                    for task in plugin.import(&contents)? {
+                        if store.load(&task.name)?.is_some() {
+                            let name = task.name.display();
+                            eprintln!("warning: {}", tr!("import-skipped", name = name));
+                            continue;
+                        }
+                        tasks.push(task);
+                    }
 
-            std::fs::write(task_path, toml::to_vec(&task).expect("valid toml"))
-                .expect("write task to file");
+                    let summary = tr!("tasks-to-import", count = tasks.len());
+                    if tasks.len() > 1 && !prompt::confirm(&summary, args.yes || args.dry_run)? {
+                        color_eyre::eyre::bail!("aborted");
+                    }
+                    actions::add_all(&config, &store, tasks)?;
+                }
+                PluginCommand::Export { output } => {
+                    let contents = plugin.export(&store.load_all()?)?;
+                    match output {
+                        Some(path) => std::fs::write(&path, contents)
+                            .wrap_err_with(|| format!("write {}", path.display()))?,
+                        None => std::io::Write::write_all(&mut std::io::stdout(), &contents)?,
+                    }
+                }
+                PluginCommand::Report => print!("{}", plugin.report(&store.load_all()?)?),
+            }
         }
-        Mode::Config => {
-            let config_dir = dirs::get_config_dir().expect("data dir");
-            if !config_dir.exists() {
-                std::fs::create_dir_all(config_dir).expect("create config dir");
+        #[cfg(not(feature = "wasm-plugins"))]
+        Mode::Plugin { .. } => {
+            color_eyre::eyre::bail!("dooit-rs was built without plugin support");
+        }
+        Mode::Config {
+            command: Some(ConfigCommand::Get { key }),
+        } => config::get(&config, &key)?,
+        Mode::Config {
+            command: Some(ConfigCommand::Set { key, value }),
+        } => config::set(&key, &value, args.dry_run)?,
+        Mode::Config {
+            command: Some(ConfigCommand::Check),
+        } => config::check()?,
+        Mode::Config {
+            command: Some(ConfigCommand::Init { force }),
+        } => {
+            let config_path = config::config_path().ok_or_else(|| Failure::NoDirs.report())?;
+            if config_path.exists() && !force {
+                color_eyre::eyre::bail!(
+                    "{} already exists, pass --force to replace it",
+                    config_path.display()
+                );
+            }
+            if args.dry_run {
+                println!("{}", config::would_write(&config_path));
+            } else {
+                write_sample_config(&config_path)?;
+                println!("{}", tr!("config-written", path = config_path.display()));
             }
+        }
+        Mode::Config {
+            command: Some(ConfigCommand::Trust),
+        } => trust_project_config(true, args.dry_run)?,
+        Mode::Config {
+            command: Some(ConfigCommand::Untrust),
+        } => trust_project_config(false, args.dry_run)?,
+        Mode::Config { command: None } => {
+            let config_dir = failure::config_dir()?;
+            std::fs::create_dir_all(config_dir)
+                .wrap_err_with(|| format!("create {}", config_dir.display()))?;
 
             let config_path = config_dir.join("config.toml");
             if !config_path.exists() {
-                std::fs::write(
-                    &config_path,
-                    "# This is the sample config
-",
-                )
-                .expect("create sample config");
-            }
-
-            if let Some(editor) = args.editor {
-                Command::new(&editor)
-                    .arg(&config_path)
-                    .status()
-                    .unwrap_or_else(|_| panic!("edit {config_path:?} with {editor:?}"));
-            } else {
-                panic!("No editor configured, set the EDITOR environment variable or pass it as an argument with --editor")
+                write_sample_config(&config_path)?;
             }
+
+            Editor::resolve(args.editor.as_deref(), &config)?.edit(&config_path)?;
         }
+        Mode::External(external) => external::run(&config, &args.config_options, &external)?,
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use clap::{CommandFactory, Parser};
+    use pretty_assertions::assert_eq;
+
+    use super::{Cli, DryRunSupport};
+
+    /// Every subcommand under `command` (named `name`) with arguments it parses, e.g.
+    /// `("config set", ["config", "set", "1d", "1d"])`
+    fn invocations(
+        command: &clap::Command,
+        name: &str,
+        mut args: Vec<String>,
+        all: &mut Vec<(String, Vec<String>)>,
+    ) {
+        for arg in command.get_arguments().filter(|arg| arg.is_required_set()) {
+            let value = match arg.get_possible_values().first() {
+                Some(value) => value.get_name().to_string(),
+                None => "1d".to_string(),
+            };
+            args.extend(arg.get_long().map(|long| format!("--{long}")));
+            args.push(value);
+        }
+        if !command.is_subcommand_required_set() {
+            all.push((name.to_string(), args.clone()));
+        }
+        for subcommand in command.get_subcommands() {
+            let name = format!("{name} {}", subcommand.get_name());
+            let mut args = args.clone();
+            args.push(subcommand.get_name().to_string());
+            invocations(subcommand, name.trim_start(), args, all);
+        }
+    }
+
+    #[test]
+    fn test_dry_run_support() {
+        use DryRunSupport::{ReadOnly, Refused, Shown};
+
+        let mut all = Vec::new();
+        invocations(&Cli::command(), "", Vec::new(), &mut all);
+        all.retain(|(name, _)| !name.is_empty() && name != "help" && !name.ends_with(" help"));
+        // Flags changing what a command does, and external subcommands
+        let with = |name: &str, args: &[&str]| {
+            let args = args.iter().map(|arg| arg.to_string()).collect();
+            (name.to_string(), args)
+        };
+        all.extend([
+            with("verify --accept", &["verify", "--accept"]),
+            with("digest --email", &["digest", "--email"]),
+            with("menu complete", &["menu", "complete"]),
+            with("frobnicate", &["frobnicate"]),
+        ]);
+
+        // Everything else is read-only
+        let changing = [
+            ("context set", Shown),
+            ("context clear", Shown),
+            ("add", Shown),
+            ("scaffold", Shown),
+            ("capture", Shown),
+            ("schedule", Shown),
+            ("verify --accept", Shown),
+            ("done", Shown),
+            ("rm", Shown),
+            ("attach", Shown),
+            ("edit", Shown),
+            ("doctor", Shown),
+            ("mv", Shown),
+            ("archive", Shown),
+            ("snooze", Shown),
+            ("undo", Shown),
+            ("purge", Shown),
+            ("sync", Shown),
+            ("tag add", Shown),
+            ("tag remove", Shown),
+            ("bump", Shown),
+            ("lower", Shown),
+            ("serve", Refused),
+            ("notify", Refused),
+            ("notify ack", Shown),
+            ("digest --email", Refused),
+            ("pomo", Shown),
+            ("review", Refused),
+            ("modify", Shown),
+            ("dedupe", Shown),
+            ("focus", Refused),
+            ("daemon", Refused),
+            ("auth set", Refused),
+            ("auth remove", Refused),
+            ("tui", Refused),
+            ("shell", Refused),
+            ("menu complete", Shown),
+            ("plugin import", Shown),
+            ("config", Refused),
+            ("config set", Shown),
+            ("config init", Shown),
+            ("config trust", Shown),
+            ("config untrust", Shown),
+            ("frobnicate", Refused),
+        ];
+
+        for (name, args) in &all {
+            let args = ["dooit-rs", "--dry-run"]
+                .into_iter()
+                .chain(args.iter().map(String::as_str));
+            let cli = Cli::try_parse_from(args).unwrap_or_else(|err| panic!("{name}: {err}"));
+            let expected = changing
+                .iter()
+                .find(|(changing, _)| changing == name)
+                .map_or(ReadOnly, |(_, support)| *support);
+            assert_eq!((name, cli.mode.dry_run()), (name, expected));
+        }
+        for (changing, _) in changing {
+            assert!(all.iter().any(|(name, _)| name == changing), "{changing}");
+        }
+        assert!(all.iter().any(|(name, _)| name == "__complete"));
+    }
+}
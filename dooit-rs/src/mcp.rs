@@ -0,0 +1,344 @@
+//! [Model Context Protocol](https://modelcontextprotocol.io) server over stdio
+//!
+//! Messages are newline delimited JSON-RPC 2.0 requests, only the `tools` capability is
+//! implemented.
+
+use std::{
+    io::{BufRead, Write},
+    path::PathBuf,
+};
+
+use clap::ValueEnum;
+use color_eyre::eyre::{eyre, WrapErr};
+use dooit_tasks::{tasks::parse_date, urgency, SortMode, Task, TaskStore};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{actions, config::Config};
+
+const PROTOCOL_VERSION: &str = "2024-11-05";
+
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    /// Notifications don't carry an id and never get a response
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct ToolCall {
+    name: String,
+    #[serde(default)]
+    arguments: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListArgs {
+    #[serde(default)]
+    completed: bool,
+    #[serde(default)]
+    overdue: bool,
+    sort: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AddArgs {
+    name: PathBuf,
+    description: Option<String>,
+    due: Option<String>,
+    urgency: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CompleteArgs {
+    name: PathBuf,
+}
+
+/// Answer requests from stdin until it is closed
+pub fn serve(config: &Config, store: &impl TaskStore) -> color_eyre::Result<()> {
+    let mut stdout = std::io::stdout().lock();
+
+    for line in std::io::stdin().lock().lines() {
+        let line = line.wrap_err("read request")?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => handle(config, store, request),
+            Err(err) => Some(error(Value::Null, PARSE_ERROR, err.to_string())),
+        };
+
+        if let Some(response) = response {
+            serde_json::to_writer(&mut stdout, &response).wrap_err("write response")?;
+            writeln!(stdout).wrap_err("write response")?;
+            stdout.flush().wrap_err("write response")?;
+        }
+    }
+
+    Ok(())
+}
+
+fn handle(config: &Config, store: &impl TaskStore, request: Request) -> Option<Value> {
+    let id = request.id?;
+
+    let result = match request.method.as_str() {
+        "initialize" => json!({
+            "protocolVersion": PROTOCOL_VERSION,
+            "capabilities": { "tools": {} },
+            "serverInfo": {
+                "name": env!("CARGO_PKG_NAME"),
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+        }),
+        "ping" => json!({}),
+        "tools/list" => json!({ "tools": tools() }),
+        "tools/call" => match serde_json::from_value::<ToolCall>(request.params) {
+            Ok(call) => match call_tool(config, store, call) {
+                Ok(text) => json!({ "content": [{ "type": "text", "text": text }] }),
+                Err(err) => json!({
+                    "content": [{ "type": "text", "text": format!("{err:#}") }],
+                    "isError": true,
+                }),
+            },
+            Err(err) => return Some(error(id, INVALID_PARAMS, err.to_string())),
+        },
+        method => {
+            return Some(error(
+                id,
+                METHOD_NOT_FOUND,
+                format!("unknown method {method}"),
+            ))
+        }
+    };
+
+    Some(json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+}
+
+fn error(id: Value, code: i64, message: String) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+fn tools() -> Value {
+    let sort_modes: Vec<_> = SortMode::value_variants()
+        .iter()
+        .filter_map(|mode| mode.to_possible_value())
+        .map(|value| value.get_name().to_string())
+        .collect();
+    let urgencies: Vec<_> = urgency::levels()
+        .iter()
+        .map(|level| level.name.clone())
+        .collect();
+
+    json!([
+        {
+            "name": "list_tasks",
+            "description": "List the user's tasks, by default only pending tasks that aren't overdue",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "completed": { "type": "boolean", "description": "Include completed tasks" },
+                    "overdue": { "type": "boolean", "description": "Include overdue tasks" },
+                    "sort": { "type": "string", "enum": sort_modes },
+                },
+            },
+        },
+        {
+            "name": "add_task",
+            "description": "Add a task, subtasks are created by naming them parent/subtask",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "description": { "type": "string" },
+                    "due": {
+                        "type": "string",
+                        "description": "Due date as YYYY-MM-DD, HH:MM:SS or YYYY-MM-DDTHH:MM:SS",
+                    },
+                    "urgency": { "type": "string", "enum": urgencies },
+                },
+                "required": ["name"],
+            },
+        },
+        {
+            "name": "complete_task",
+            "description": "Mark a task as completed",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "name": { "type": "string" } },
+                "required": ["name"],
+            },
+        },
+    ])
+}
+
+fn call_tool(
+    config: &Config,
+    store: &impl TaskStore,
+    call: ToolCall,
+) -> color_eyre::Result<String> {
+    match call.name.as_str() {
+        "list_tasks" => {
+            let args: ListArgs = serde_json::from_value(call.arguments)?;
+            let sort = args
+                .sort
+                .map(|sort| SortMode::from_str(&sort, true))
+                .transpose()
+                .map_err(|err| eyre!(err))?
+                .unwrap_or_default();
+            let tasks = actions::list(config, store, sort, args.completed, args.overdue)?;
+
+            if tasks.is_empty() {
+                return Ok("No tasks to do!".to_string());
+            }
+
+            Ok(tasks
+                .iter()
+                .map(Task::to_string)
+                .collect::<Vec<_>>()
+                .join("\n"))
+        }
+        "add_task" => {
+            let args: AddArgs = serde_json::from_value(call.arguments)?;
+            let mut task = Task::new(&args.name);
+            task.description = args.description;
+            task.due = args.due.as_deref().map(parse_date).transpose()?;
+            if let Some(urgency) = args.urgency {
+                task.urgency = urgency.parse()?;
+            }
+
+            actions::add(config, store, task.clone())?;
+            Ok(format!("Added {task}"))
+        }
+        "complete_task" => {
+            let args: CompleteArgs = serde_json::from_value(call.arguments)?;
+            let task = actions::complete(config, store, &args.name)?;
+            Ok(format!("Completed {task}"))
+        }
+        tool => Err(eyre!("unknown tool {tool}")),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use dooit_tasks::{tasks::parse_date, DirStore, TaskStore, Urgency};
+    use pretty_assertions::assert_eq;
+    use serde_json::{json, Value};
+
+    use super::{handle, Request, INVALID_PARAMS, METHOD_NOT_FOUND, PROTOCOL_VERSION};
+    use crate::config::{AuditConfig, Config, JournalConfig};
+
+    #[test]
+    fn test_handle() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = DirStore::new(dir.path());
+        let config = Config {
+            audit: AuditConfig { enabled: false },
+            journal: JournalConfig { enabled: false },
+            ..Config::default()
+        };
+        let call = |request: Value| {
+            let request: Request = serde_json::from_value(request).unwrap();
+            handle(&config, &store, request)
+        };
+        let tool = |id: u32, name: &str, arguments: Value| {
+            let response = call(json!({
+                "id": id,
+                "method": "tools/call",
+                "params": { "name": name, "arguments": arguments },
+            }))
+            .unwrap();
+            assert_eq!(response["id"], id);
+            let result = &response["result"];
+            let text = result["content"][0]["text"].as_str().unwrap().to_string();
+            (text, result["isError"] == true)
+        };
+
+        let initialize =
+            call(json!({ "jsonrpc": "2.0", "id": 0, "method": "initialize" })).unwrap();
+        assert_eq!(initialize["result"]["protocolVersion"], PROTOCOL_VERSION);
+        assert_eq!(initialize["result"]["capabilities"], json!({ "tools": {} }));
+        assert_eq!(call(json!({ "method": "notifications/initialized" })), None);
+
+        let tools = call(json!({ "id": 1, "method": "tools/list" })).unwrap();
+        let tools = tools["result"]["tools"].as_array().unwrap();
+        let names: Vec<_> = tools
+            .iter()
+            .map(|tool| tool["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["list_tasks", "add_task", "complete_task"]);
+        assert_eq!(tools[1]["inputSchema"]["required"], json!(["name"]));
+
+        let unknown = call(json!({ "id": 2, "method": "resources/list" })).unwrap();
+        assert_eq!(
+            (&unknown["id"], &unknown["error"]["code"]),
+            (&json!(2), &json!(METHOD_NOT_FOUND))
+        );
+        let invalid = call(json!({ "id": 3, "method": "tools/call", "params": {} })).unwrap();
+        assert_eq!(invalid["error"]["code"], INVALID_PARAMS);
+
+        // add_task stores the task with the given fields
+        let added = tool(
+            4,
+            "add_task",
+            json!({
+                "name": "release/notes",
+                "description": "for 1.0",
+                "due": "2099-01-01",
+                "urgency": "high",
+            }),
+        );
+        assert!(!added.1 && added.0.starts_with("Added "), "{added:?}");
+        let notes = store.load(Path::new("release/notes")).unwrap().unwrap();
+        assert_eq!(
+            (notes.description.as_deref(), notes.due, notes.urgency),
+            (
+                Some("for 1.0"),
+                Some(parse_date("2099-01-01").unwrap()),
+                Urgency::High
+            )
+        );
+        assert!(!tool(5, "add_task", json!({ "name": "report" })).1);
+        let (text, failed) = tool(6, "add_task", json!({ "name": "x", "urgency": "whenever" }));
+        assert!(failed, "{text}");
+        assert!(tool(7, "add_task", json!({})).1);
+
+        // list_tasks lists the pending tasks, sorted as asked
+        let (text, failed) = tool(8, "list_tasks", json!({ "sort": "name-descending" }));
+        assert!(!failed);
+        let position = |name| {
+            text.find(name)
+                .unwrap_or_else(|| panic!("{name} in {text}"))
+        };
+        assert!(position("report") < position("release/notes"), "{text}");
+        assert!(tool(9, "list_tasks", json!({ "sort": "random" })).1);
+
+        // complete_task completes it, listings then leave it out unless asked for
+        let (text, failed) = tool(10, "complete_task", json!({ "name": "report" }));
+        assert!(!failed && text.starts_with("Completed "), "{text}");
+        assert!(store.load(Path::new("report")).unwrap().unwrap().completed);
+        assert!(!tool(11, "list_tasks", json!({})).0.contains("report"));
+        assert!(tool(12, "list_tasks", json!({ "completed": true }))
+            .0
+            .contains("report"));
+        assert_eq!(
+            tool(13, "complete_task", json!({ "name": "missing" })).1,
+            true
+        );
+
+        let (text, failed) = tool(14, "delete_task", json!({ "name": "report" }));
+        assert_eq!((text.as_str(), failed), ("unknown tool delete_task", true));
+    }
+}
@@ -0,0 +1,105 @@
+//! Integration with launchers like dmenu or rofi
+//!
+//! `dooit-rs menu` prints one line per task, piping the chosen line back into `dooit-rs menu
+//! <ACTION>` performs the action on that task:
+//!
+//! ```sh
+//! dooit-rs menu | rofi -dmenu | dooit-rs menu complete
+//! ```
+
+use std::{io::BufRead, path::Path};
+
+use chrono::{Duration, Local};
+use clap::ValueEnum;
+use color_eyre::eyre::{bail, WrapErr};
+use dooit_tasks::{SortMode, Task, TaskStore};
+
+use crate::{actions, config::Config, context::Context, i18n::tr};
+
+/// Separates the fields of a line, the task's name is always the first one
+const SEPARATOR: &str = " | ";
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Action {
+    /// Mark the task as completed
+    Complete,
+    /// Print the whole task
+    Show,
+    /// Push the due date back by a day
+    Snooze,
+}
+
+fn line(task: &Task) -> String {
+    let mut fields = vec![
+        task.name.to_string_lossy().into_owned(),
+        task.urgency.to_string(),
+    ];
+    if task.completed {
+        fields.push("done".to_string());
+    }
+    if let Some(due) = task.due {
+        fields.push(format!("due {}", due.with_timezone(&Local).format("%F %R")));
+    }
+    if let Some(description) = &task.description {
+        fields.push(description.lines().next().unwrap_or_default().to_string());
+    }
+
+    fields.join(SEPARATOR)
+}
+
+/// Print a line for every task
+pub fn print(config: &Config, store: &impl TaskStore, completed: bool) -> color_eyre::Result<()> {
+    let context = Context::active(config)?;
+    let now = chrono::Utc::now();
+    let parent = context.as_ref().map_or(Path::new(""), Context::prefix);
+    for task in actions::list_under(config, store, parent, SortMode::default(), completed, true)? {
+        if context
+            .as_ref()
+            .is_none_or(|context| context.matches(&task, now))
+        {
+            println!("{}", line(&task));
+        }
+    }
+
+    Ok(())
+}
+
+/// Perform `action` on the task from the line chosen in the menu, read from stdin
+pub fn pick(config: &Config, store: &impl TaskStore, action: Action) -> color_eyre::Result<()> {
+    let mut chosen = String::new();
+    std::io::stdin()
+        .lock()
+        .read_line(&mut chosen)
+        .wrap_err("read the chosen task")?;
+
+    // Nothing was chosen (e.g. the menu was closed)
+    let Some(name) = chosen.trim_end_matches('\n').split(SEPARATOR).next() else {
+        return Ok(());
+    };
+    if name.is_empty() {
+        return Ok(());
+    }
+    let name = Path::new(name);
+
+    match action {
+        Action::Complete => {
+            let task = actions::complete(config, store, name)?;
+            println!("{}", tr!("task-completed", name = task.name.display()));
+        }
+        Action::Show => match store.load(name)? {
+            Some(task) => println!("{task}"),
+            None => bail!("no task named {}", name.display()),
+        },
+        Action::Snooze => {
+            let task = actions::snooze(config, store, name, Duration::days(1))?;
+            let due = task.due.expect("snoozed tasks have a due date");
+            let due = due.with_timezone(&Local).format("%F %R");
+            println!(
+                "{}",
+                tr!("task-snoozed", name = task.name.display(), due = due)
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,245 @@
+//! Changing fields of every task matching a filter at once
+//!
+//! `dooit-rs modify --filter 'tag:sprint12' urgency:high due:+1w +blocked` takes a filter like the
+//! ones of reports (see [`crate::report`]) and a list of changes:
+//!
+//! - `+tag` and `-tag`: add or remove a tag
+//! - `urgency:<level>` and `status:<status>`
+//! - `due:<date>`, `wait:<date>`, `scheduled:<date>` and `review:<date>`, e.g. `due:friday` or `wait:"in 3d"`, `none` clears them
+//! - `due:+<duration>` and `due:-<duration>`: move the due date (from now if there's none)
+//! - `estimate:<duration>`, `none` clears it
+//! - `assignee:<name>`, `none` clears it
+//! - `recur:<duration>`, `none` stops the task (and a habit) from recurring
+
+use std::str::FromStr;
+
+use chrono::{DateTime, Duration, Utc};
+use clap::ValueEnum;
+use color_eyre::eyre::{bail, eyre, WrapErr};
+use dooit_tasks::{
+    duration::parse_duration, tasks::parse_date, HumanDuration, Status, Task, TaskStore, Urgency,
+};
+
+use crate::{actions, color, config::Config, i18n::tr, prompt, report::Filter};
+
+/// A date given to `due:`, `wait:`, `scheduled:` or `review:`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DateChange {
+    Set(Option<DateTime<Utc>>),
+    Shift(Duration),
+}
+
+impl DateChange {
+    fn apply(self, date: Option<DateTime<Utc>>, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            DateChange::Set(date) => date,
+            DateChange::Shift(duration) => Some(date.unwrap_or(now) + duration),
+        }
+    }
+}
+
+impl FromStr for DateChange {
+    type Err = color_eyre::Report;
+
+    fn from_str(date: &str) -> Result<Self, Self::Err> {
+        Ok(match date {
+            "none" => DateChange::Set(None),
+            shift if shift.starts_with(['+', '-']) => {
+                let duration = parse_duration(shift.trim_start_matches('+'))?;
+                DateChange::Shift(duration)
+            }
+            date => DateChange::Set(Some(parse_date(date)?)),
+        })
+    }
+}
+
+/// One of the changes, see the [module docs](self)
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Change {
+    AddTag(String),
+    RemoveTag(String),
+    Urgency(Urgency),
+    Status(Status),
+    Due(DateChange),
+    Wait(DateChange),
+    Scheduled(DateChange),
+    Review(DateChange),
+    Estimate(Option<HumanDuration>),
+    Assignee(Option<String>),
+    Recur(Option<HumanDuration>),
+}
+
+impl Change {
+    fn apply(&self, task: &mut Task, now: DateTime<Utc>) {
+        match self {
+            Change::AddTag(tag) => {
+                if !task.tags.contains(tag) {
+                    task.tags.push(tag.clone());
+                }
+            }
+            Change::RemoveTag(tag) => task.tags.retain(|other| other != tag),
+            Change::Urgency(urgency) => task.urgency = urgency.clone(),
+            Change::Status(status) => task.status = *status,
+            Change::Due(change) => task.due = change.apply(task.due, now),
+            Change::Wait(change) => task.wait = change.apply(task.wait, now),
+            Change::Scheduled(change) => task.scheduled = change.apply(task.scheduled, now),
+            Change::Review(change) => task.review = change.apply(task.review, now),
+            Change::Estimate(estimate) => task.estimate = *estimate,
+            Change::Assignee(assignee) => task.assignee = assignee.clone(),
+            Change::Recur(recur) => {
+                task.recur = *recur;
+                task.habit &= recur.is_some();
+            }
+        }
+    }
+}
+
+impl FromStr for Change {
+    type Err = color_eyre::Report;
+
+    fn from_str(change: &str) -> Result<Self, Self::Err> {
+        if let Some(tag) = change.strip_prefix('+') {
+            return Ok(Change::AddTag(tag.to_string()));
+        }
+        if let Some(tag) = change.strip_prefix('-') {
+            return Ok(Change::RemoveTag(tag.to_string()));
+        }
+        let Some((field, value)) = change.split_once(':') else {
+            bail!("expected `+tag`, `-tag` or `field:value`, got {change:?}");
+        };
+        let parsed = match field {
+            "urgency" => value.parse().map(Change::Urgency).map_err(Into::into),
+            "status" => Status::from_str(value, true)
+                .map(Change::Status)
+                .map_err(|err| eyre!(err)),
+            "due" => value.parse().map(Change::Due),
+            "wait" => value.parse().map(Change::Wait),
+            "scheduled" => value.parse().map(Change::Scheduled),
+            "review" => value.parse().map(Change::Review),
+            "estimate" if value == "none" => Ok(Change::Estimate(None)),
+            "estimate" => value
+                .parse()
+                .map(|estimate| Change::Estimate(Some(estimate)))
+                .map_err(Into::into),
+            "assignee" if value == "none" => Ok(Change::Assignee(None)),
+            "assignee" => Ok(Change::Assignee(Some(value.to_string()))),
+            "recur" if value == "none" => Ok(Change::Recur(None)),
+            "recur" => value
+                .parse()
+                .map(|recur| Change::Recur(Some(recur)))
+                .map_err(Into::into),
+            field => bail!("unknown field {field:?} in {change:?}"),
+        };
+        parsed.wrap_err_with(|| format!("parse {change:?}"))
+    }
+}
+
+/// The tasks matching `filter` that `changes` modify, changed
+fn modified(
+    tasks: Vec<Task>,
+    filter: &Filter,
+    changes: &[Change],
+    now: DateTime<Utc>,
+) -> Vec<(Task, Task)> {
+    tasks
+        .into_iter()
+        .filter(|task| filter.matches(task, now))
+        .filter_map(|old| {
+            let mut new = old.clone();
+            for change in changes {
+                change.apply(&mut new, now);
+            }
+            (new != old).then_some((old, new))
+        })
+        .collect()
+}
+
+/// Apply `changes` to every task matching `filter`, after showing what changes and asking
+pub fn run(
+    config: &Config,
+    store: &impl TaskStore,
+    filter: &str,
+    changes: &[String],
+    assume_yes: bool,
+    dry_run: bool,
+) -> color_eyre::Result<()> {
+    let filter: Filter = filter.parse().wrap_err("parse the filter")?;
+    let changes = changes
+        .iter()
+        .map(|change| change.parse())
+        .collect::<color_eyre::Result<Vec<Change>>>()?;
+    if changes.is_empty() {
+        bail!("nothing to change, pass changes like `urgency:high` or `+tag`");
+    }
+
+    let tasks = store.load_under(filter.prefix()).wrap_err("load tasks")?;
+    let modified = modified(tasks, &filter, &changes, Utc::now());
+    if modified.is_empty() {
+        println!("{}", tr!("no-tasks-modified"));
+        return Ok(());
+    }
+
+    for (old, new) in &modified {
+        println!("{}", color::header(&old.name.display().to_string(), config));
+        for change in old.diff(new) {
+            println!("  {change}");
+        }
+    }
+    let summary = tr!("tasks-to-modify", count = modified.len());
+    if modified.len() > 1 && !prompt::confirm(&summary, assume_yes || dry_run)? {
+        bail!("aborted");
+    }
+
+    for (_, task) in modified {
+        let name = task.name.clone();
+        actions::update(config, store, task)?;
+        if !dry_run {
+            println!("{}", tr!("task-modified", name = name.display()));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{Duration, Utc};
+    use dooit_tasks::{Task, Urgency};
+    use pretty_assertions::assert_eq;
+
+    use super::{modified, Change};
+
+    #[test]
+    fn test_modify() {
+        let now = Utc::now();
+        let tasks = vec![
+            Task::new("a").with_tags(["sprint12", "blocked"]),
+            Task::new("b").with_tags(["sprint12"]).with_due_date(now),
+            Task::new("c"),
+        ];
+        let changes: Vec<Change> = ["urgency:high", "due:+1w", "-blocked", "+review"]
+            .iter()
+            .map(|change| change.parse().unwrap())
+            .collect();
+        let filter = "tag:sprint12".parse().unwrap();
+
+        let new: Vec<_> = modified(tasks, &filter, &changes, now)
+            .into_iter()
+            .map(|(_, new)| new)
+            .collect();
+        assert_eq!(
+            new,
+            [
+                Task::new("a")
+                    .with_tags(["sprint12", "review"])
+                    .with_urgency(Urgency::High)
+                    .with_due_date(now + Duration::weeks(1)),
+                Task::new("b")
+                    .with_tags(["sprint12", "review"])
+                    .with_urgency(Urgency::High)
+                    .with_due_date(now + Duration::weeks(1)),
+            ]
+        );
+        assert!("due:someday".parse::<Change>().is_err());
+        assert!("color:red".parse::<Change>().is_err());
+    }
+}
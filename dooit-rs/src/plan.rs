@@ -0,0 +1,338 @@
+//! `plan`: the work for a day against the time there is for it
+//!
+//! The day's tasks are the pending ones scheduled for it (or earlier) and the ones due by its end,
+//! overdue ones included. Each takes the part of its estimate that's left. When they add up to more
+//! than the capacity, the least urgent tasks that aren't due yet are suggested to be deferred until
+//! the rest fits.
+//!
+//! Tasks whose review date has come are listed apart, they don't take any of the capacity (see
+//! [`crate::review`]).
+
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
+use color_eyre::eyre::WrapErr;
+use dooit_tasks::{duration::format_duration, Task, TaskStore};
+
+use crate::{color, config::Config, i18n::tr, report};
+
+/// Whether `date` falls on `day` or before it, in local time
+fn by(date: Option<DateTime<Utc>>, day: NaiveDate) -> bool {
+    date.is_some_and(|date| date.with_timezone(&Local).date_naive() <= day)
+}
+
+/// The tasks of a day and which of them to defer
+#[derive(Debug)]
+struct Plan<'a> {
+    /// The tasks, most urgent first
+    tasks: Vec<&'a Task>,
+    /// Remaining estimates of `tasks` summed
+    load: Duration,
+    /// Tasks to defer so the rest fits the capacity, least urgent first
+    defer: Vec<&'a Task>,
+    /// Remaining estimates of the tasks not deferred summed
+    kept: Duration,
+    /// Pending tasks up for review by the day, not among `tasks`
+    review: Vec<&'a Task>,
+}
+
+/// Plan the pending `tasks` due or scheduled by `day` into `capacity`
+fn plan(tasks: &[Task], day: NaiveDate, capacity: Duration, now: DateTime<Utc>) -> Plan<'_> {
+    let mut review: Vec<_> = tasks
+        .iter()
+        .filter(|task| !task.completed && by(task.review, day))
+        .filter(|task| !by(task.scheduled, day) && !by(task.due, day))
+        .collect();
+    review.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut tasks: Vec<_> = tasks
+        .iter()
+        .filter(|task| !task.completed && !task.is_waiting(now))
+        .filter(|task| by(task.scheduled, day) || by(task.due, day))
+        .collect();
+    tasks.sort_by(|a, b| {
+        let due = |task: &Task| (task.due.is_none(), task.due);
+        b.urgency.cmp(&a.urgency).then_with(|| due(a).cmp(&due(b)))
+    });
+    let remaining = |task: &Task| task.remaining().unwrap_or_else(Duration::zero);
+    let load = tasks
+        .iter()
+        .fold(Duration::zero(), |load, task| load + remaining(task));
+
+    // Bigger tasks go first among equally urgent ones, so fewer have to move
+    let mut deferrable: Vec<_> = tasks
+        .iter()
+        .copied()
+        .filter(|task| !by(task.due, day) && remaining(task) > Duration::zero())
+        .collect();
+    deferrable.sort_by(|a, b| {
+        a.urgency
+            .cmp(&b.urgency)
+            .then_with(|| remaining(b).cmp(&remaining(a)))
+    });
+    let mut defer = Vec::new();
+    let mut kept = load;
+    for task in deferrable {
+        if kept <= capacity {
+            break;
+        }
+        kept = kept - remaining(task);
+        defer.push(task);
+    }
+
+    Plan {
+        tasks,
+        load,
+        defer,
+        kept,
+        review,
+    }
+}
+
+/// Blank lines left for notes at the end of the printable sheet
+const NOTES_LINES: usize = 8;
+
+/// `plan` as a Markdown sheet to print or paste into a journal: the tasks scheduled for `day` by
+/// time, a checklist of the others and room for notes
+fn sheet(plan: &Plan, day: NaiveDate, capacity: Duration) -> String {
+    let title = tr!(
+        "plan-title",
+        day = day.format("%a %F").to_string(),
+        load = format_duration(plan.load),
+        capacity = format_duration(capacity)
+    );
+    let mut sheet = format!("# {title}\n");
+
+    let on_day = |date: DateTime<Utc>| date.with_timezone(&Local).date_naive() == day;
+    let (mut timeline, checklist): (Vec<&Task>, Vec<_>) = plan
+        .tasks
+        .iter()
+        .partition(|task| task.scheduled.is_some_and(on_day));
+    timeline.sort_by_key(|task| task.scheduled);
+
+    if !timeline.is_empty() {
+        sheet.push_str(&format!("\n## {}\n\n", tr!("plan-sheet-schedule")));
+    }
+    for task in timeline {
+        let start = task.scheduled.expect("on the timeline");
+        let time = match task.remaining().filter(|left| *left > Duration::zero()) {
+            Some(left) => format!(
+                "{}–{}",
+                start.with_timezone(&Local).format("%R"),
+                (start + left).with_timezone(&Local).format("%R")
+            ),
+            None => start.with_timezone(&Local).format("%R").to_string(),
+        };
+        sheet.push_str(&format!("- [ ] {time} {}\n", task.name.display()));
+    }
+
+    if !checklist.is_empty() {
+        sheet.push_str(&format!("\n## {}\n\n", tr!("plan-sheet-todo")));
+    }
+    for task in checklist {
+        sheet.push_str(&format!("- [ ] {}", task.name.display()));
+        if let Some(due) = task.due {
+            let format = if on_day(due) { "%R" } else { "%a %F %R" };
+            let due = due.with_timezone(&Local).format(format).to_string();
+            sheet.push_str(&format!(" ({})", tr!("plan-sheet-due", due = due)));
+        }
+        sheet.push('\n');
+    }
+
+    if !plan.defer.is_empty() {
+        let over = format_duration(plan.load - capacity);
+        sheet.push_str(&format!("\n{}\n\n", tr!("plan-over", over = over)));
+        for task in &plan.defer {
+            sheet.push_str(&format!("- {}\n", task.name.display()));
+        }
+    }
+
+    if !plan.review.is_empty() {
+        sheet.push_str(&format!("\n## {}\n\n", tr!("review-up")));
+    }
+    for task in &plan.review {
+        sheet.push_str(&format!("- [ ] {}\n", task.name.display()));
+    }
+
+    sheet.push_str(&format!("\n## {}\n", tr!("plan-sheet-notes")));
+    sheet.push_str(&"\n".repeat(NOTES_LINES));
+    sheet
+}
+
+/// Print today's tasks, how much of `capacity` they take and what to defer if it's too much,
+/// as a [`sheet`] if `printable`
+pub fn run(
+    config: &Config,
+    store: &impl TaskStore,
+    capacity: Duration,
+    printable: bool,
+) -> color_eyre::Result<()> {
+    let tasks = store.load_all().wrap_err("load tasks")?;
+    let now = Utc::now();
+    let today = now.with_timezone(&Local).date_naive();
+    let plan = plan(&tasks, today, capacity, now);
+    if printable {
+        print!("{}", sheet(&plan, today, capacity));
+        return Ok(());
+    }
+    if plan.tasks.is_empty() && plan.review.is_empty() {
+        println!("{}", tr!("plan-empty"));
+        return Ok(());
+    }
+    if !plan.review.is_empty() {
+        println!("{}", color::header(&tr!("review-up"), config));
+        for task in &plan.review {
+            println!("{}", color::task(task, config, now));
+        }
+        println!();
+    }
+    if plan.tasks.is_empty() {
+        return Ok(());
+    }
+
+    let title = tr!(
+        "plan-title",
+        day = today.format("%a %F").to_string(),
+        load = format_duration(plan.load),
+        capacity = format_duration(capacity)
+    );
+    println!("{}", color::header(&title, config));
+
+    let row = |task: &Task| {
+        vec![
+            task.urgency.name().to_string(),
+            task.remaining()
+                .map_or_else(|| "?".to_string(), format_duration),
+            task.due.map_or_else(String::new, |due| {
+                due.with_timezone(&Local).format("%a %F %R").to_string()
+            }),
+            task.name.display().to_string(),
+        ]
+    };
+    let header = vec![
+        tr!("column-urgency"),
+        tr!("column-estimate"),
+        tr!("column-due"),
+        tr!("column-name"),
+    ];
+    let print = |tasks: &[&Task]| {
+        let rows: Vec<_> = std::iter::once(header.clone())
+            .chain(tasks.iter().map(|task| row(task)))
+            .collect();
+        for (index, line) in report::table(&rows).into_iter().enumerate() {
+            match index.checked_sub(1).map(|index| tasks[index]) {
+                None => println!("{}", color::header(&line, config)),
+                Some(task) if config.color.enabled() => {
+                    println!("{}", color::style(task, config, now).paint(&line))
+                }
+                Some(_) => println!("{line}"),
+            }
+        }
+    };
+    print(&plan.tasks);
+
+    let unestimated = plan
+        .tasks
+        .iter()
+        .filter(|task| task.remaining().is_none())
+        .count();
+    if unestimated > 0 {
+        println!("{}", tr!("plan-unestimated", count = unestimated));
+    }
+    if plan.load <= capacity {
+        return Ok(());
+    }
+
+    let over = format_duration(plan.load - capacity);
+    println!();
+    if !plan.defer.is_empty() {
+        println!("{}", tr!("plan-over", over = over));
+        print(&plan.defer);
+    }
+    if plan.kept > capacity {
+        let over = format_duration(plan.kept - capacity);
+        println!("{}", tr!("plan-over-due", over = over));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{Duration, Local, TimeZone, Utc};
+    use dooit_tasks::{Task, Urgency};
+    use pretty_assertions::assert_eq;
+
+    use super::{plan, sheet};
+
+    #[test]
+    fn test_plan() {
+        let now = Local
+            .with_ymd_and_hms(2024, 5, 1, 9, 0, 0)
+            .unwrap()
+            .with_timezone(&Utc);
+        let today = now.with_timezone(&Local).date_naive();
+        let task = |name: &str, urgency: Urgency, hours: i64| {
+            Task::new(name)
+                .with_urgency(urgency)
+                .with_estimate(Duration::hours(hours))
+                .with_scheduled(now)
+        };
+        let tasks = [
+            task("report", Urgency::High, 3).with_due_date(now + Duration::hours(8)),
+            task("email", Urgency::Low, 1),
+            task("refactor", Urgency::Low, 2),
+            task("review", Urgency::Medium, 2),
+            task("tomorrow", Urgency::High, 5).with_scheduled(now + Duration::days(1)),
+            task("done", Urgency::High, 5).complete(),
+            Task::new("overdue").with_due_date(now - Duration::days(2)),
+            Task::new("someday").with_review(now),
+            Task::new("later").with_review(now + Duration::days(1)),
+        ];
+
+        let plan = plan(&tasks, today, Duration::hours(5), now);
+        let names = |tasks: &[&Task]| -> Vec<_> {
+            tasks
+                .iter()
+                .map(|task| task.name.display().to_string())
+                .collect()
+        };
+        assert_eq!(
+            names(&plan.tasks),
+            ["report", "review", "overdue", "email", "refactor"]
+        );
+        assert_eq!(plan.load, Duration::hours(8));
+        assert_eq!(names(&plan.defer), ["refactor", "email"]);
+        assert_eq!(plan.kept, Duration::hours(5));
+        assert_eq!(names(&plan.review), ["someday"]);
+
+        let sheet = sheet(&plan, today, Duration::hours(5));
+        let overdue = (now - Duration::days(2))
+            .with_timezone(&Local)
+            .format("%a %F %R");
+        let expected = format!(
+            "# Plan for Wed 2024-05-01: 8h of 5h\n\
+             \n\
+             ## Schedule\n\
+             \n\
+             - [ ] 09:00–12:00 report\n\
+             - [ ] 09:00–11:00 review\n\
+             - [ ] 09:00–10:00 email\n\
+             - [ ] 09:00–11:00 refactor\n\
+             \n\
+             ## To do\n\
+             \n\
+             - [ ] overdue (due {overdue})\n\
+             \n\
+             Over capacity by 3h, consider deferring:\n\
+             \n\
+             - refactor\n\
+             - email\n\
+             \n\
+             ## Up for review\n\
+             \n\
+             - [ ] someday\n\
+             \n\
+             ## Notes\n\
+             \n\n\n\n\n\n\n\n"
+        );
+        assert_eq!(sheet, expected);
+    }
+}
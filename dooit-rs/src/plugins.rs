@@ -0,0 +1,131 @@
+//! Importers, exporters and reports compiled to WebAssembly
+//!
+//! Plugins live in `<config dir>/plugins/<name>.wasm` and run sandboxed: they get no host imports
+//! and a limited amount of fuel per call. A plugin must export its `memory` and an
+//! `alloc(len: i32) -> i32` function returning a buffer of `len` bytes, which the host fills with
+//! the input before calling any of these (optional) entry points:
+//!
+//! - `import(ptr: i32, len: i32) -> i64`: parse the contents of a file into a JSON array of tasks
+//! - `export(ptr: i32, len: i32) -> i64`: turn a JSON array of tasks into the contents of a file
+//! - `report(ptr: i32, len: i32) -> i64`: turn a JSON array of tasks into text for the terminal
+//!
+//! Entry points return their output as a buffer in the plugin's memory, packed into a single
+//! integer as `(ptr << 32) | len`.
+
+use std::path::PathBuf;
+
+use color_eyre::eyre::{bail, eyre, WrapErr};
+use dooit_tasks::{dirs, Task};
+use wasmi::{Config, Engine, Instance, Linker, Memory, Module, Store};
+
+/// Instructions a single call may execute before it's aborted
+const FUEL: u64 = 1_000_000_000;
+
+pub struct Plugin {
+    name: String,
+    store: Store<()>,
+    instance: Instance,
+    memory: Memory,
+}
+
+fn plugin_path(name: &str) -> color_eyre::Result<PathBuf> {
+    dirs::get_config_dir()
+        .map(|dir| dir.join("plugins").join(name).with_extension("wasm"))
+        .ok_or_else(|| eyre!("config dir not available"))
+}
+
+impl Plugin {
+    pub fn load(name: &str) -> color_eyre::Result<Self> {
+        let path = plugin_path(name)?;
+        let wasm = std::fs::read(&path).wrap_err_with(|| format!("read {}", path.display()))?;
+
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, &wasm)
+            .map_err(|err| eyre!("{err}"))
+            .wrap_err_with(|| format!("compile plugin {}", path.display()))?;
+
+        let mut store = Store::new(&engine, ());
+        store.set_fuel(FUEL).map_err(|err| eyre!("{err}"))?;
+        let instance = Linker::new(&engine)
+            .instantiate(&mut store, &module)
+            .and_then(|instance| instance.start(&mut store))
+            .map_err(|err| eyre!("{err}"))
+            .wrap_err_with(|| format!("instantiate plugin {name}"))?;
+        let memory = instance
+            .get_memory(&store, "memory")
+            .ok_or_else(|| eyre!("plugin {name} doesn't export its memory"))?;
+
+        Ok(Self {
+            name: name.to_string(),
+            store,
+            instance,
+            memory,
+        })
+    }
+
+    /// Call `function` with `input`, returns `Ok(None)` if the plugin doesn't export it
+    fn call(&mut self, function: &str, input: &[u8]) -> color_eyre::Result<Option<Vec<u8>>> {
+        let Ok(entry) = self
+            .instance
+            .get_typed_func::<(i32, i32), i64>(&self.store, function)
+        else {
+            return Ok(None);
+        };
+        let alloc = self
+            .instance
+            .get_typed_func::<i32, i32>(&self.store, "alloc")
+            .map_err(|err| eyre!("plugin {} has no usable alloc: {err}", self.name))?;
+
+        self.store.set_fuel(FUEL).map_err(|err| eyre!("{err}"))?;
+
+        let len = i32::try_from(input.len()).wrap_err("plugin input too large")?;
+        let ptr = alloc
+            .call(&mut self.store, len)
+            .map_err(|err| eyre!("{err}"))
+            .wrap_err_with(|| format!("run alloc from plugin {}", self.name))?;
+        self.memory
+            .write(&mut self.store, ptr as u32 as usize, input)
+            .map_err(|err| eyre!("plugin {} allocated an invalid buffer: {err}", self.name))?;
+
+        let packed = entry
+            .call(&mut self.store, (ptr, len))
+            .map_err(|err| eyre!("{err}"))
+            .wrap_err_with(|| format!("run {function} from plugin {}", self.name))?;
+
+        let (ptr, len) = ((packed as u64 >> 32) as usize, packed as u32 as usize);
+        let mut output = vec![0; len];
+        self.memory
+            .read(&self.store, ptr, &mut output)
+            .map_err(|err| eyre!("plugin {} returned an invalid buffer: {err}", self.name))?;
+
+        Ok(Some(output))
+    }
+
+    fn call_required(&mut self, function: &str, input: &[u8]) -> color_eyre::Result<Vec<u8>> {
+        match self.call(function, input)? {
+            Some(output) => Ok(output),
+            None => bail!("plugin {} doesn't provide {function}", self.name),
+        }
+    }
+
+    /// Parse the contents of a file into tasks
+    pub fn import(&mut self, contents: &[u8]) -> color_eyre::Result<Vec<Task>> {
+        let output = self.call_required("import", contents)?;
+        serde_json::from_slice(&output)
+            .wrap_err_with(|| format!("parse tasks returned by plugin {}", self.name))
+    }
+
+    /// Render tasks into the contents of a file
+    pub fn export(&mut self, tasks: &[Task]) -> color_eyre::Result<Vec<u8>> {
+        self.call_required("export", &serde_json::to_vec(tasks)?)
+    }
+
+    /// Render tasks into a report for the terminal
+    pub fn report(&mut self, tasks: &[Task]) -> color_eyre::Result<String> {
+        let output = self.call_required("report", &serde_json::to_vec(tasks)?)?;
+        String::from_utf8(output)
+            .wrap_err_with(|| format!("plugin {} returned a report that isn't UTF-8", self.name))
+    }
+}
@@ -0,0 +1,204 @@
+//! Pomodoro timer logging the time spent into the task
+//!
+//! Only completed work periods are logged, stopping the timer (e.g. with Ctrl-C) discards the one
+//! in progress.
+
+use std::{io::Write, path::Path, thread, time::Duration as StdDuration};
+
+use chrono::{Duration, Utc};
+use color_eyre::eyre::{eyre, WrapErr};
+use dooit_tasks::{duration::format_duration, TaskStore, TimeEntry};
+
+use crate::{
+    actions,
+    channels::{self, Priority},
+    config::Config,
+};
+
+/// Show the time left in `phase` until it's over
+fn countdown(phase: &str, length: Duration) -> color_eyre::Result<()> {
+    let end = Utc::now() + length;
+    let mut stdout = std::io::stdout();
+
+    loop {
+        let left = end - Utc::now();
+        if left <= Duration::zero() {
+            break;
+        }
+
+        // Erase the line and print the time left rounded up to the second
+        let seconds = (left.num_milliseconds() + 999) / 1000;
+        write!(
+            stdout,
+            "\r\x1b[2K{phase} {:02}:{:02}",
+            seconds / 60,
+            seconds % 60
+        )?;
+        stdout.flush()?;
+
+        // Wake up right when the displayed second changes
+        let tick = match left.num_milliseconds() {
+            0 => 1,
+            millis if millis % 1000 == 0 => 1000,
+            millis => millis % 1000,
+        };
+        thread::sleep(StdDuration::from_millis(tick as u64));
+    }
+
+    writeln!(stdout, "\r\x1b[2K{phase} done")?;
+    Ok(())
+}
+
+fn notify(config: &Config, summary: &str) {
+    if let Err(err) = channels::broadcast(&config.notify, Priority::Normal, summary, "", None) {
+        tracing::warn!("{err:#}");
+    }
+}
+
+/// Run `count` pomodoros of `work` separated by breaks of `rest`
+pub fn run(
+    config: &Config,
+    store: &impl TaskStore,
+    name: &Path,
+    work: Duration,
+    rest: Duration,
+    count: u32,
+) -> color_eyre::Result<()> {
+    if store.load(name)?.is_none() {
+        return Err(eyre!("no task named {}", name.display()));
+    }
+
+    for pomodoro in 1..=count {
+        let start = Utc::now();
+        countdown(
+            &format!("Pomodoro {pomodoro}/{count} on {}", name.display()),
+            work,
+        )?;
+
+        // Reload in case the task changed while we were counting down
+        let mut task = store
+            .load(name)?
+            .ok_or_else(|| eyre!("{} was deleted", name.display()))?;
+        task.time_log.push(TimeEntry {
+            start,
+            end: Utc::now(),
+        });
+        let spent = task.time_spent();
+        actions::update(config, store, task).wrap_err("log the pomodoro")?;
+        println!(
+            "Logged, {} spent on {} so far",
+            format_duration(spent),
+            name.display()
+        );
+
+        if pomodoro == count {
+            notify(config, &format!("Done with {}", name.display()));
+            break;
+        }
+
+        notify(
+            config,
+            &format!("Pomodoro done, take a {} break", format_duration(rest)),
+        );
+        countdown("Break", rest)?;
+        notify(config, &format!("Break over, back to {}", name.display()));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use chrono::{Duration, Utc};
+    use dooit_tasks::{DirStore, Task, TaskStore, TimeEntry};
+    use pretty_assertions::assert_eq;
+    use serde_json::Value;
+    use tiny_http::{Response, Server};
+
+    use super::run;
+    use crate::{
+        channels::{Channel, MatrixConfig},
+        config::{AuditConfig, Config, JournalConfig, NotifyConfig},
+    };
+
+    #[test]
+    fn test_run() {
+        // Collect the notifications sent at each transition
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let homeserver = format!("http://{}/", server.server_addr().to_ip().unwrap());
+        let (sent, received) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            for mut request in server.incoming_requests() {
+                let mut body = String::new();
+                request.as_reader().read_to_string(&mut body).unwrap();
+                let body: Value = serde_json::from_str(&body).unwrap();
+                sent.send(body["body"].as_str().unwrap().to_string())
+                    .unwrap();
+                request.respond(Response::from_string("{}")).unwrap();
+            }
+        });
+        std::env::set_var("DOOIT_MATRIX_TOKEN", "secret");
+
+        let dir = tempfile::tempdir().unwrap();
+        let store = DirStore::new(dir.path());
+        let config = Config {
+            audit: AuditConfig { enabled: false },
+            journal: JournalConfig { enabled: false },
+            notify: NotifyConfig {
+                channels: vec![Channel::Matrix],
+                matrix: Some(MatrixConfig {
+                    homeserver,
+                    room_id: "!room:example.org".to_string(),
+                    token: None,
+                }),
+                ..NotifyConfig::default()
+            },
+            ..Config::default()
+        };
+        let name = Path::new("report");
+        let work = Duration::milliseconds(50);
+        let rest = Duration::milliseconds(30);
+
+        let err = run(&config, &store, name, work, rest, 2).unwrap_err();
+        assert_eq!(err.to_string(), "no task named report");
+        assert!(received.try_recv().is_err());
+
+        // Time already tracked is kept
+        let earlier = TimeEntry {
+            start: Utc::now() - Duration::hours(2),
+            end: Utc::now() - Duration::hours(1),
+        };
+        let mut task = Task::new("report");
+        task.time_log.push(earlier);
+        store.save(&task).unwrap();
+
+        let started = Utc::now();
+        run(&config, &store, name, work, rest, 2).unwrap();
+        let task = store.load(name).unwrap().unwrap();
+        let [first, logged @ ..] = task.time_log.as_slice() else {
+            panic!("nothing logged: {task:?}");
+        };
+        assert_eq!(first, &earlier);
+        let [one, two] = logged else {
+            panic!("expected two pomodoros: {logged:?}");
+        };
+
+        // Each pomodoro lasts a full work period, and they're separated by the break
+        assert!(one.start >= started);
+        assert!(one.end - one.start >= work);
+        assert!(two.start - one.end >= rest);
+        assert!(two.end - two.start >= work);
+
+        let notifications: Vec<String> = received.try_iter().collect();
+        assert_eq!(
+            notifications,
+            [
+                "Pomodoro done, take a 0s break",
+                "Break over, back to report",
+                "Done with report",
+            ]
+        );
+    }
+}
@@ -0,0 +1,210 @@
+//! Interactive prompts on the terminal
+
+use std::{collections::BTreeSet, io::IsTerminal, path::Path};
+
+use color_eyre::eyre::{bail, WrapErr};
+use dooit_tasks::{tasks::parse_date, urgency, Task, TaskStore, Urgency};
+use rustyline::{
+    completion::Completer, error::ReadlineError, highlight::Highlighter, hint::Hinter,
+    history::DefaultHistory, validate::Validator, Context, Editor, Helper,
+};
+
+use crate::{config::AddConfig, editor::Editor as TextEditor, i18n::tr};
+
+/// Complete the word under the cursor with the `candidates` it's a prefix of
+pub fn complete_word<'a>(
+    line: &str,
+    pos: usize,
+    candidates: impl IntoIterator<Item = &'a String>,
+) -> (usize, Vec<String>) {
+    let start = line[..pos]
+        .rfind(|c: char| c.is_whitespace() || c == ',')
+        .map_or(0, |separator| separator + 1);
+    let word = &line[start..pos];
+
+    let matches = candidates
+        .into_iter()
+        .filter(|candidate| candidate.starts_with(word))
+        .cloned()
+        .collect();
+    (start, matches)
+}
+
+/// Completes the word under the cursor from a list of candidates
+#[derive(Debug, Default)]
+pub struct Candidates(pub Vec<String>);
+
+impl Completer for Candidates {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(complete_word(line, pos, &self.0))
+    }
+}
+
+impl Hinter for Candidates {
+    type Hint = String;
+}
+
+impl Highlighter for Candidates {}
+
+impl Validator for Candidates {}
+
+impl Helper for Candidates {}
+
+pub type Prompt = Editor<Candidates, DefaultHistory>;
+
+/// Read a line completing words from `candidates`, returns `Ok(None)` on Ctrl-D
+pub fn read_line(
+    editor: &mut Prompt,
+    prompt: &str,
+    candidates: impl IntoIterator<Item = String>,
+) -> color_eyre::Result<Option<String>> {
+    editor.set_helper(Some(Candidates(candidates.into_iter().collect())));
+
+    match editor.readline(prompt) {
+        Ok(line) => Ok(Some(line.trim().to_string())),
+        Err(ReadlineError::Eof) => Ok(None),
+        Err(ReadlineError::Interrupted) => bail!("interrupted"),
+        Err(err) => Err(err).wrap_err("read from the terminal"),
+    }
+}
+
+/// Like [`read_line`] but Ctrl-D aborts
+pub fn ask(
+    editor: &mut Prompt,
+    prompt: &str,
+    candidates: impl IntoIterator<Item = String>,
+) -> color_eyre::Result<String> {
+    match read_line(editor, prompt, candidates)? {
+        Some(line) => Ok(line),
+        None => bail!("aborted"),
+    }
+}
+
+/// Ask whether to go ahead with `summary` (e.g. "3 task(s) will be deleted")
+///
+/// `assume_yes` (from `--yes`) skips the question. Without a terminal there is nobody to ask, so
+/// scripts have to pass `--yes`.
+pub fn confirm(summary: &str, assume_yes: bool) -> color_eyre::Result<bool> {
+    if assume_yes {
+        return Ok(true);
+    }
+    if !std::io::stdin().is_terminal() {
+        bail!("{}", tr!("confirm-needs-yes", summary = summary));
+    }
+
+    let mut editor = Prompt::new().wrap_err("open the terminal")?;
+    let answer = read_line(
+        &mut editor,
+        &format!("{} ", tr!("confirm", summary = summary)),
+        ["yes".to_string(), "no".to_string()],
+    )?;
+    Ok(answer.is_some_and(|answer| matches!(answer.to_lowercase().as_str(), "y" | "yes")))
+}
+
+/// Ask for every field of a new task, suggesting names and tags of the tasks in `store`
+///
+/// The name is checked against the existing tasks as it'll be once `add` is applied.
+pub fn add_task(
+    store: &impl TaskStore,
+    text_editor: &TextEditor,
+    add: &AddConfig,
+) -> color_eyre::Result<Task> {
+    let tasks = store.load_all().wrap_err("load tasks")?;
+    let names: BTreeSet<_> = tasks
+        .iter()
+        .map(|task| task.name.to_string_lossy().into_owned())
+        .collect();
+    let tags: BTreeSet<_> = tasks.iter().flat_map(|task| task.tags.clone()).collect();
+
+    let mut editor = Prompt::new().wrap_err("open the terminal")?;
+
+    let name = loop {
+        let name = ask(
+            &mut editor,
+            &format!("{} ", tr!("add-name")),
+            names.iter().cloned(),
+        )?;
+        if name.is_empty() {
+            continue;
+        }
+        let added = add.name(Path::new(&name));
+        if names.contains(added.to_string_lossy().as_ref()) {
+            eprintln!("{}", tr!("task-exists", name = added.display()));
+            continue;
+        }
+        break name;
+    };
+    let mut task = Task::new(name);
+
+    let mut description = ask(&mut editor, &format!("{} ", tr!("add-description")), [])?;
+    if description == ":e" {
+        description = text_editor.edit_text("", "md")?.trim().to_string();
+    }
+    if !description.is_empty() {
+        task = task.with_description(description);
+    }
+
+    let suggestions = ["today", "tomorrow", "next", "monday", "friday", "in"];
+    loop {
+        let due = ask(
+            &mut editor,
+            &format!("{} ", tr!("add-due")),
+            suggestions.map(String::from),
+        )?;
+        if due.is_empty() {
+            break;
+        }
+        match parse_date(&due) {
+            Ok(due) => {
+                eprintln!(
+                    "{}",
+                    tr!("add-due-at", due = due.with_timezone(&chrono::Local))
+                );
+                task = task.with_due_date(due);
+                break;
+            }
+            Err(err) => eprintln!("{err}"),
+        }
+    }
+
+    let urgencies: Vec<_> = urgency::levels()
+        .iter()
+        .map(|level| level.name.clone())
+        .collect();
+    loop {
+        let prompt = format!(
+            "{} ",
+            tr!(
+                "add-urgency",
+                levels = urgencies.join("/"),
+                default = Urgency::default()
+            )
+        );
+        let urgency = ask(&mut editor, &prompt, urgencies.clone())?;
+        if urgency.is_empty() {
+            break;
+        }
+        match urgency.parse() {
+            Ok(urgency) => {
+                task = task.with_urgency(urgency);
+                break;
+            }
+            Err(err) => eprintln!("{err}"),
+        }
+    }
+
+    let new_tags = ask(&mut editor, &format!("{} ", tr!("add-tags")), tags)?;
+    Ok(task.with_tags(
+        new_tags
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|tag| !tag.is_empty())
+            .map(|tag| tag.trim_start_matches('+')),
+    ))
+}
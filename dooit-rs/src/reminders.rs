@@ -0,0 +1,300 @@
+//! Reminders for upcoming and overdue tasks, delivered through the configured [`channels`]
+//!
+//! Which reminders were sent is persisted between runs, so `dooit-rs notify` can be run as often
+//! as wanted (e.g. every minute from cron or a systemd timer) without repeating notifications.
+//! Unless the task's urgency is configured to repeat them (see
+//! [`Escalation`](crate::config::Escalation)), then the latest reminder is sent again until it's
+//! acknowledged.
+//!
+//! Reminders sent by the daemon offer to complete or snooze the task from the notification.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+};
+
+use chrono::{DateTime, Duration, Utc};
+use color_eyre::eyre::{eyre, WrapErr};
+use dooit_tasks::{dirs, duration::format_duration, DirStore, HumanDuration, Task, TaskStore};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    actions,
+    channels::{self, Action, ActionHandler},
+    config::Config,
+    i18n::tr,
+};
+
+/// A reminder for `task` sent `lead_time` before `due`
+///
+/// The due date is part of the reminder so rescheduling a task re-arms its reminders.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+struct Reminder {
+    task: String,
+    due: DateTime<Utc>,
+    lead_time: HumanDuration,
+}
+
+impl Reminder {
+    fn new(task: &Task, due: DateTime<Utc>, lead_time: HumanDuration) -> Self {
+        Self {
+            task: task.name.to_string_lossy().into_owned(),
+            due,
+            lead_time,
+        }
+    }
+}
+
+/// A reminder that's sent again until acknowledged
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Repeating {
+    due: DateTime<Utc>,
+    last_sent: DateTime<Utc>,
+    acknowledged: bool,
+}
+
+/// Reminders that were already sent
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct Sent {
+    reminders: BTreeSet<Reminder>,
+    /// Indexed by task name
+    #[serde(default)]
+    repeating: BTreeMap<String, Repeating>,
+}
+
+fn state_path() -> color_eyre::Result<PathBuf> {
+    dirs::get_state_dir()
+        .map(|dir| dir.join("reminders.json"))
+        .ok_or_else(|| eyre!("state dir not available"))
+}
+
+impl Sent {
+    fn load() -> color_eyre::Result<Self> {
+        let path = state_path()?;
+
+        match std::fs::read(&path) {
+            Ok(contents) => Ok(serde_json::from_slice(&contents).unwrap_or_else(|err| {
+                // Better to repeat a few notifications than to stop sending them altogether
+                tracing::warn!("resetting corrupted {}: {err}", path.display());
+                Self::default()
+            })),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err).wrap_err_with(|| format!("read {}", path.display())),
+        }
+    }
+
+    /// Atomically replace the state file, so concurrent runs never see a partial file
+    fn save(&self) -> color_eyre::Result<()> {
+        let path = state_path()?;
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .wrap_err_with(|| format!("create {}", parent.display()))?;
+        }
+
+        let tmp = path.with_extension(format!("json.{}", std::process::id()));
+        std::fs::write(&tmp, serde_json::to_vec(self)?)
+            .wrap_err_with(|| format!("write {}", tmp.display()))?;
+        std::fs::rename(&tmp, &path).wrap_err_with(|| format!("replace {}", path.display()))
+    }
+}
+
+/// Reminders for `task` that are due at `now`, the most imminent one last
+fn reached(config: &Config, task: &Task, now: DateTime<Utc>) -> Vec<Reminder> {
+    let Some(due) = task.due.filter(|_| !task.completed) else {
+        return Vec::new();
+    };
+
+    let mut lead_times = config.notify.lead_times.clone();
+    lead_times.sort_unstable_by(|a, b| b.cmp(a));
+
+    lead_times
+        .into_iter()
+        .filter(|lead_time| due - lead_time.0 <= now)
+        .map(|lead_time| Reminder::new(task, due, lead_time))
+        .collect()
+}
+
+/// Apply the action chosen on a reminder about `task` to `store`
+fn action_handler(config: &Config, store: &DirStore, task: &Task) -> ActionHandler {
+    let (config, store, name) = (config.clone(), store.clone(), task.name.clone());
+    Box::new(move |action| {
+        let result = match action {
+            Action::Done => actions::complete(&config, &store, &name).map(drop),
+            Action::Snooze => actions::snooze(&config, &store, &name, Duration::hours(1)).map(drop),
+        };
+        if let Err(err) = result {
+            tracing::warn!("{err:#}");
+        }
+    })
+}
+
+/// Deliver a reminder about `task` through every configured channel
+///
+/// Desktop notifications offer to complete or snooze the task in `store` if it's given.
+pub fn send(
+    config: &Config,
+    task: &Task,
+    now: DateTime<Utc>,
+    store: Option<&DirStore>,
+) -> color_eyre::Result<()> {
+    let due = task.due.expect("only tasks with a due date get reminders");
+    let summary = if due <= now {
+        format!("Overdue: {}", task.name.display())
+    } else {
+        format!(
+            "Due in {}: {}",
+            format_duration(due - now),
+            task.name.display()
+        )
+    };
+    let body = task.description.as_deref().unwrap_or_default();
+    let priority = config.notify.escalation.for_urgency(&task.urgency).priority;
+
+    let on_action = store.map(|store| action_handler(config, store, task));
+
+    tracing::info!("sending reminder: {summary}");
+    channels::broadcast(&config.notify, priority, &summary, body, on_action)
+        .wrap_err_with(|| format!("send reminder for {}", task.name.display()))
+}
+
+/// Send a notification for every upcoming task that wasn't notified about already
+pub fn notify(config: &Config, store: &impl TaskStore) -> color_eyre::Result<()> {
+    let tasks = store.load_all().wrap_err("load tasks")?;
+    remind(config, &tasks, Utc::now(), None)
+}
+
+/// Like [`notify`] but on already loaded tasks
+///
+/// Pass the `store` the tasks come from to act on them from the notifications, the process must
+/// keep running for that to work.
+pub fn remind(
+    config: &Config,
+    tasks: &[Task],
+    now: DateTime<Utc>,
+    store: Option<&DirStore>,
+) -> color_eyre::Result<()> {
+    let sent = Sent::load()?;
+    let mut still_relevant = Sent::default();
+
+    for task in tasks {
+        let reached = reached(config, task, now);
+        let Some(latest) = reached.last() else {
+            continue;
+        };
+
+        let repeat = config.notify.escalation.for_urgency(&task.urgency).repeat;
+        let repeating = sent
+            .repeating
+            .get(&latest.task)
+            .filter(|repeating| repeat.is_some() && repeating.due == latest.due);
+        let repeat_due = repeat.zip(repeating).is_some_and(|(every, repeating)| {
+            !repeating.acknowledged && now - repeating.last_sent >= every.0
+        });
+
+        // If several lead times passed since the last run only the most imminent one is sent
+        if !sent.reminders.contains(latest) || repeat_due {
+            if let Err(err) = send(config, task, now, store) {
+                tracing::warn!("{err:#}");
+                still_relevant.reminders.extend(
+                    reached
+                        .iter()
+                        .filter(|r| sent.reminders.contains(r))
+                        .cloned(),
+                );
+                still_relevant
+                    .repeating
+                    .extend(repeating.map(|r| (latest.task.clone(), r.clone())));
+                continue;
+            }
+
+            if repeat.is_some() {
+                let repeating = Repeating {
+                    due: latest.due,
+                    last_sent: now,
+                    acknowledged: false,
+                };
+                still_relevant
+                    .repeating
+                    .insert(latest.task.clone(), repeating);
+            }
+        } else if let Some(repeating) = repeating {
+            still_relevant
+                .repeating
+                .insert(latest.task.clone(), repeating.clone());
+        }
+
+        still_relevant.reminders.extend(reached);
+    }
+
+    // Reminders for completed, deleted or rescheduled tasks are dropped here
+    if still_relevant != sent {
+        still_relevant.save()?;
+    }
+
+    Ok(())
+}
+
+/// Stop repeating the reminder for the task called `name`, returns `Ok(false)` if there is none
+///
+/// With `dry_run` the file that would change is printed instead.
+pub fn acknowledge(name: &str, dry_run: bool) -> color_eyre::Result<bool> {
+    let mut sent = Sent::load()?;
+    let Some(repeating) = sent.repeating.get_mut(name) else {
+        return Ok(false);
+    };
+
+    if dry_run {
+        println!("{}", tr!("would-modify", path = state_path()?.display()));
+        return Ok(true);
+    }
+    repeating.acknowledged = true;
+    sent.save()?;
+    Ok(true)
+}
+
+/// When the next reminder should be sent
+pub fn next_reminder(config: &Config, tasks: &[Task], now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    tasks
+        .iter()
+        .filter(|task| !task.completed)
+        .filter_map(|task| task.due)
+        .flat_map(|due| {
+            config
+                .notify
+                .lead_times
+                .iter()
+                .map(move |lead_time| due - lead_time.0)
+        })
+        .filter(|remind_at| *remind_at > now)
+        .min()
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{Duration, Utc};
+    use dooit_tasks::{HumanDuration, Task};
+    use pretty_assertions::assert_eq;
+
+    use super::reached;
+    use crate::config::Config;
+
+    #[test]
+    fn test_reached_lead_times() {
+        let mut config = Config::default();
+        config.notify.lead_times = [Duration::hours(1), Duration::days(1), Duration::zero()]
+            .into_iter()
+            .map(HumanDuration)
+            .collect();
+
+        let now = Utc::now();
+        let task = Task::new("a").with_due_date(now + Duration::minutes(30));
+
+        let lead_times: Vec<_> = reached(&config, &task, now)
+            .into_iter()
+            .map(|reminder| reminder.lead_time.0)
+            .collect();
+        assert_eq!(lead_times, vec![Duration::days(1), Duration::hours(1)]);
+        assert!(reached(&config, &task.complete(), now).is_empty());
+    }
+}
@@ -0,0 +1,443 @@
+//! Custom reports: named views of the tasks defined under `reports` in the config
+//!
+//! `dooit-rs report <name>` shows the tasks matching the report's filter as a table, sorted,
+//! optionally grouped, and with the columns picked in the config. A filter is a list of terms
+//! separated by spaces, and a task is shown if it matches every term:
+//!
+//! - `+tag` and `-tag`: tasks with and without a tag, `tag:<tag>` is the same as `+tag`
+//! - `urgency:<level>` and `status:<status>`, e.g. `urgency:high` or `status:started`
+//! - `assignee:<name>`: tasks assigned to someone, `assignee:none` for unassigned ones
+//! - `under:<task>`: the task and its subtasks, `project:<task>` is the same
+//! - `due.before:<duration>`: pending tasks due within the duration from now, e.g. `due.before:1w`
+//! - `is:completed`, `is:overdue`, `is:dated` and `is:waiting` (hidden by `wait`)
+//!
+//! Any term can be negated with a leading `!`, e.g. `!is:completed`. Unlike `list`, nothing is
+//! hidden unless the filter says so.
+
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use chrono::{DateTime, Local, Utc};
+use clap::ValueEnum;
+use color_eyre::eyre::{bail, eyre, WrapErr};
+use dooit_tasks::{
+    duration::format_duration, tasks::sort_tasks_with, urgency_score, HumanDuration, SortMode,
+    Status, Task, TaskStore,
+};
+use serde::{Deserialize, Serialize};
+use unicode_width::UnicodeWidthStr;
+
+use crate::{color, config::Config, context::Context, i18n::tr};
+
+/// A report, see the [module docs](self)
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Report {
+    /// Terms a task has to match to be shown, e.g. `+work !is:completed`
+    pub filter: String,
+    /// Sort mode, `list.default_sort` if unset
+    pub sort: Option<SortMode>,
+    /// Columns shown, in order
+    pub columns: Vec<Column>,
+    /// Show the tasks in groups with a title each
+    pub group_by: Option<GroupBy>,
+}
+
+/// Something shown about each task in a report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Column {
+    /// `[x]` for completed tasks
+    Done,
+    Urgency,
+    Due,
+    Name,
+    Tags,
+    Status,
+    Estimate,
+    /// Time logged working on the task
+    Spent,
+    /// Urgency score, see `score` in the config
+    Score,
+    Description,
+}
+
+/// How tasks are grouped in a report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GroupBy {
+    Urgency,
+    Status,
+    /// One group per tag, so tasks with several tags are shown more than once
+    Tag,
+    /// The task the tasks are under, e.g. `release` for `release/notes`
+    Parent,
+}
+
+/// A single term of a [`Filter`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Term {
+    Tag(String),
+    Urgency(String),
+    Status(Status),
+    /// `None` for unassigned tasks
+    Assignee(Option<String>),
+    Under(PathBuf),
+    DueBefore(HumanDuration),
+    Completed,
+    Overdue,
+    Dated,
+    Waiting,
+}
+
+impl Term {
+    fn matches(&self, task: &Task, now: DateTime<Utc>) -> bool {
+        match self {
+            Term::Tag(tag) => task.tags.contains(tag),
+            Term::Urgency(name) => task.urgency.name().eq_ignore_ascii_case(name),
+            Term::Status(status) => !task.completed && task.status == *status,
+            Term::Assignee(assignee) => task.assignee == *assignee,
+            Term::Under(parent) => task.name.starts_with(parent),
+            Term::DueBefore(duration) => task.is_due_within(duration.0, now),
+            Term::Completed => task.completed,
+            Term::Overdue => task.is_overdue(now),
+            Term::Dated => task.due.is_some(),
+            Term::Waiting => task.is_waiting(now),
+        }
+    }
+}
+
+impl FromStr for Term {
+    type Err = color_eyre::Report;
+
+    fn from_str(term: &str) -> Result<Self, Self::Err> {
+        if let Some(tag) = term.strip_prefix('+') {
+            return Ok(Term::Tag(tag.to_string()));
+        }
+        let Some((key, value)) = term.split_once(':') else {
+            bail!("expected `+tag`, `-tag` or `key:value`, got {term:?}");
+        };
+        Ok(match (key, value) {
+            ("urgency", name) => {
+                let urgency: dooit_tasks::Urgency = name.parse()?;
+                Term::Urgency(urgency.name().to_string())
+            }
+            ("status", status) => {
+                Term::Status(Status::from_str(status, true).map_err(|err| eyre!(err))?)
+            }
+            ("assignee", "none") => Term::Assignee(None),
+            ("assignee", assignee) => Term::Assignee(Some(assignee.to_string())),
+            ("tag", tag) => Term::Tag(tag.to_string()),
+            ("under" | "project", parent) => Term::Under(parent.into()),
+            ("due.before", duration) => Term::DueBefore(duration.parse()?),
+            ("is", "completed") => Term::Completed,
+            ("is", "overdue") => Term::Overdue,
+            ("is", "dated") => Term::Dated,
+            ("is", "waiting") => Term::Waiting,
+            ("is", other) => {
+                bail!("expected completed, overdue, dated or waiting after `is:`, got {other:?}")
+            }
+            (key, _) => bail!("unknown filter {key:?} in {term:?}"),
+        })
+    }
+}
+
+/// Terms a task has to match, each of them negated or not
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Filter(Vec<(bool, Term)>);
+
+impl Filter {
+    /// The task every match is under (see `under:`), empty if there isn't one
+    pub fn prefix(&self) -> &Path {
+        self.0
+            .iter()
+            .find_map(|term| match term {
+                (false, Term::Under(parent)) => Some(parent.as_path()),
+                _ => None,
+            })
+            .unwrap_or(Path::new(""))
+    }
+
+    pub fn matches(&self, task: &Task, now: DateTime<Utc>) -> bool {
+        self.0
+            .iter()
+            .all(|(negated, term)| term.matches(task, now) != *negated)
+    }
+}
+
+impl FromStr for Filter {
+    type Err = color_eyre::Report;
+
+    fn from_str(filter: &str) -> Result<Self, Self::Err> {
+        filter
+            .split_whitespace()
+            .map(|term| match term.strip_prefix('!') {
+                Some(term) => Ok((true, term.parse()?)),
+                None => match term.strip_prefix('-') {
+                    Some(tag) => Ok((true, Term::Tag(tag.to_string()))),
+                    None => Ok((false, term.parse()?)),
+                },
+            })
+            .collect::<color_eyre::Result<_>>()
+            .map(Self)
+    }
+}
+
+impl Column {
+    fn title(self) -> String {
+        match self {
+            Column::Done => String::new(),
+            Column::Urgency => tr!("column-urgency"),
+            Column::Due => tr!("column-due"),
+            Column::Name => tr!("column-name"),
+            Column::Tags => tr!("column-tags"),
+            Column::Status => tr!("column-status"),
+            Column::Estimate => tr!("column-estimate"),
+            Column::Spent => tr!("column-spent"),
+            Column::Score => tr!("column-score"),
+            Column::Description => tr!("column-description"),
+        }
+    }
+
+    fn value(self, task: &Task, config: &Config) -> String {
+        match self {
+            Column::Done => if task.completed { "[x]" } else { "[ ]" }.to_string(),
+            Column::Urgency => task.urgency.icon(config.icons).to_string(),
+            Column::Due => task
+                .due
+                .map(|due| due.with_timezone(&Local).format("%F %R").to_string())
+                .unwrap_or_default(),
+            Column::Name => task.name.display().to_string(),
+            Column::Tags => task
+                .tags
+                .iter()
+                .map(|tag| format!("+{tag}"))
+                .collect::<Vec<_>>()
+                .join(" "),
+            Column::Status if task.completed => String::new(),
+            Column::Status => task.status.to_string(),
+            Column::Estimate => task
+                .estimate
+                .map(|estimate| format_duration(estimate.0))
+                .unwrap_or_default(),
+            Column::Spent if task.time_log.is_empty() => String::new(),
+            Column::Spent => format_duration(task.time_spent()),
+            Column::Score => format!("{:.1}", urgency_score(task, &config.score)),
+            Column::Description => task
+                .description
+                .as_deref()
+                .and_then(|description| description.lines().next())
+                .unwrap_or_default()
+                .to_string(),
+        }
+    }
+}
+
+impl GroupBy {
+    /// Titles of the groups `task` is in
+    fn keys(self, task: &Task) -> Vec<String> {
+        match self {
+            GroupBy::Urgency => vec![task.urgency.to_string()],
+            GroupBy::Status if task.completed => vec![tr!("report-completed")],
+            GroupBy::Status => vec![task.status.to_string()],
+            GroupBy::Tag if task.tags.is_empty() => vec![tr!("report-no-group")],
+            GroupBy::Tag => task.tags.clone(),
+            GroupBy::Parent => vec![task
+                .name
+                .parent()
+                .filter(|parent| !parent.as_os_str().is_empty())
+                .map_or_else(
+                    || tr!("report-no-group"),
+                    |parent| parent.display().to_string(),
+                )],
+        }
+    }
+}
+
+/// Columns shown by reports that don't pick any, like the lines of `list`
+const DEFAULT_COLUMNS: [Column; 5] = [
+    Column::Done,
+    Column::Urgency,
+    Column::Due,
+    Column::Name,
+    Column::Tags,
+];
+
+/// Group `tasks` in the order their groups first appear
+fn group(tasks: &[Task], group_by: Option<GroupBy>) -> Vec<(Option<String>, Vec<&Task>)> {
+    let Some(group_by) = group_by else {
+        return vec![(None, tasks.iter().collect())];
+    };
+
+    let mut groups: Vec<(Option<String>, Vec<&Task>)> = Vec::new();
+    for task in tasks {
+        for key in group_by.keys(task) {
+            match groups
+                .iter_mut()
+                .find(|(other, _)| other.as_ref() == Some(&key))
+            {
+                Some((_, group)) => group.push(task),
+                None => groups.push((Some(key), vec![task])),
+            }
+        }
+    }
+    groups
+}
+
+/// Lay out `rows` in columns separated by two spaces, without trailing whitespace
+///
+/// Cells are measured in terminal columns, so wide characters (CJK, emoji) still line up.
+pub fn table(rows: &[Vec<String>]) -> Vec<String> {
+    let columns = rows.first().map_or(0, Vec::len);
+    let widths: Vec<_> = (0..columns)
+        .map(|column| {
+            rows.iter()
+                .map(|row| row[column].width())
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{cell}{}", " ".repeat(width - cell.width())))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect()
+}
+
+/// Print the report called `name`
+pub fn run(config: &Config, store: &impl TaskStore, name: &str) -> color_eyre::Result<()> {
+    let Some(report) = config.reports.get(name) else {
+        let known: Vec<_> = config.reports.keys().map(String::as_str).collect();
+        bail!(
+            "no report named {name:?} in the config (known reports: {})",
+            known.join(", ")
+        );
+    };
+    let filter: Filter = report
+        .filter
+        .parse()
+        .wrap_err_with(|| format!("parse the filter of report {name:?}"))?;
+
+    let context = Context::active(config)?;
+    if let Some(context) = &context {
+        println!("{}", context.header(config));
+    }
+
+    let now = Utc::now();
+    // Only read the tasks under the longest prefix, both have to match anyway
+    let prefix = [Some(&filter), context.as_ref().map(Context::filter)]
+        .into_iter()
+        .flatten()
+        .map(Filter::prefix)
+        .max_by_key(|prefix| prefix.components().count())
+        .unwrap_or(Path::new(""));
+    let tasks: Vec<_> = store
+        .load_under(prefix)
+        .wrap_err("load tasks")?
+        .into_iter()
+        .filter(|task| filter.matches(task, now))
+        .filter(|task| {
+            context
+                .as_ref()
+                .is_none_or(|context| context.matches(task, now))
+        })
+        .collect();
+    let tasks = sort_tasks_with(
+        tasks,
+        report.sort.unwrap_or(config.list.default_sort),
+        &config.score,
+    );
+    if tasks.is_empty() {
+        println!("{}", tr!("no-tasks"));
+        return Ok(());
+    }
+
+    let columns = match report.columns.as_slice() {
+        [] => DEFAULT_COLUMNS.as_slice(),
+        columns => columns,
+    };
+    for (index, (title, tasks)) in group(&tasks, report.group_by).into_iter().enumerate() {
+        if let Some(title) = title {
+            let separator = if index == 0 { "" } else { "\n" };
+            println!("{separator}{}", color::header(&title, config));
+        }
+
+        let rows: Vec<_> = std::iter::once(columns.iter().map(|column| column.title()).collect())
+            .chain(tasks.iter().map(|task| {
+                columns
+                    .iter()
+                    .map(|column| column.value(task, config))
+                    .collect()
+            }))
+            .collect();
+        let lines = table(&rows);
+        println!("{}", color::header(&lines[0], config));
+        for (task, line) in tasks.iter().zip(&lines[1..]) {
+            if config.color.enabled() {
+                println!("{}", color::style(task, config, now).paint(line));
+            } else {
+                println!("{line}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{Duration, Utc};
+    use dooit_tasks::{Status, Task};
+    use pretty_assertions::assert_eq;
+
+    use super::{table, Filter};
+
+    #[test]
+    fn test_filter() {
+        let now = Utc::now();
+        let filter: Filter = "+work -someday !is:completed under:release due.before:1w"
+            .parse()
+            .unwrap();
+        let task = Task::new("release/notes")
+            .with_tags(["work"])
+            .with_due_date(now + Duration::days(2));
+        assert!(filter.matches(&task, now));
+        assert!(!filter.matches(&task.clone().with_tags(["someday"]), now));
+        assert!(!filter.matches(&task.clone().complete(), now));
+        assert!(!filter.matches(&Task::new("notes").with_tags(["work"]), now));
+
+        let started: Filter = "status:started".parse().unwrap();
+        assert!(started.matches(&Task::new("a").with_status(Status::Started), now));
+        let assigned = Task {
+            assignee: Some("alice".to_string()),
+            ..Task::new("a")
+        };
+        let alice: Filter = "assignee:alice".parse().unwrap();
+        assert!(alice.matches(&assigned, now));
+        assert!(!alice.matches(&Task::new("a"), now));
+        let unassigned: Filter = "assignee:none".parse().unwrap();
+        assert!(unassigned.matches(&Task::new("a"), now));
+        assert!("is:someday".parse::<Filter>().is_err());
+        assert!("urgency".parse::<Filter>().is_err());
+
+        let rows = [
+            vec!["Name".to_string(), "Tags".to_string()],
+            vec!["groceries".to_string(), String::new()],
+        ];
+        assert_eq!(table(&rows), ["Name       Tags", "groceries"]);
+        let rows = [
+            vec!["買い物".to_string(), "x".to_string()],
+            vec!["groceries".to_string(), "y".to_string()],
+        ];
+        assert_eq!(table(&rows), ["買い物     x", "groceries  y"]);
+    }
+}
@@ -0,0 +1,323 @@
+//! Guided weekly review, going through the tasks that need attention one at a time
+//!
+//! Tasks are reviewed in four rounds: tasks whose review date has come, stale tasks (overdue or
+//! untouched for two weeks), tasks without a due date and tasks waiting on someone else. Every task
+//! shows up in the first round it belongs to only.
+//!
+//! The review date works like a tickler file: a task that's not for now (someday/maybe) gets one,
+//! either with `--review` or by answering `later` here, and stays out of the review until then (unless
+//! it's overdue). Once it's up, reviewing it clears the date, or sets a new one with `later`.
+
+use std::time::SystemTime;
+
+use chrono::{DateTime, Duration, Local, Utc};
+use color_eyre::eyre::WrapErr;
+use dooit_tasks::{tasks::parse_date, DirStore, Status, Task, TaskStore};
+
+use crate::{
+    actions, color,
+    config::Config,
+    i18n::tr,
+    prompt::{self, Prompt},
+};
+
+/// Tasks that weren't modified for this long are stale
+fn stale_after() -> Duration {
+    Duration::weeks(2)
+}
+
+/// What to do with the task being reviewed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Choice {
+    Keep,
+    Reschedule,
+    Delegate,
+    Later,
+    Delete,
+    Complete,
+    Quit,
+}
+
+impl Choice {
+    const ALL: [Choice; 7] = [
+        Choice::Keep,
+        Choice::Reschedule,
+        Choice::Delegate,
+        Choice::Later,
+        Choice::Delete,
+        Choice::Complete,
+        Choice::Quit,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Choice::Keep => "keep",
+            Choice::Reschedule => "reschedule",
+            Choice::Delegate => "delegate",
+            Choice::Later => "later",
+            Choice::Delete => "delete",
+            Choice::Complete => "complete",
+            Choice::Quit => "quit",
+        }
+    }
+
+    /// Accepts the full name or its first letter, an empty answer keeps the task
+    fn parse(answer: &str) -> Option<Self> {
+        if answer.is_empty() {
+            return Some(Choice::Keep);
+        }
+        Choice::ALL
+            .into_iter()
+            .find(|choice| choice.name() == answer || choice.name()[..1] == *answer)
+    }
+}
+
+/// The tasks to review, grouped by round
+///
+/// `tasks` are paired with the last time they were modified.
+fn rounds(
+    tasks: Vec<(Task, Option<DateTime<Utc>>)>,
+    now: DateTime<Utc>,
+) -> [(&'static str, Vec<Task>); 4] {
+    let mut up = Vec::new();
+    let mut stale = Vec::new();
+    let mut undated = Vec::new();
+    let mut waiting = Vec::new();
+
+    for (task, modified) in tasks {
+        if task.completed {
+            continue;
+        }
+
+        let overdue = task.due.is_some_and(|due| due <= now);
+        let untouched = modified.is_some_and(|modified| now - modified >= stale_after());
+        let later = task.review.is_some_and(|review| review > now);
+        if task.is_up_for_review(now) {
+            up.push(task);
+        } else if overdue || (untouched && task.status != Status::Waiting && !later) {
+            stale.push(task);
+        } else if later {
+            continue;
+        } else if task.due.is_none() && task.status != Status::Waiting {
+            undated.push(task);
+        } else if task.status == Status::Waiting {
+            waiting.push(task);
+        }
+    }
+
+    for round in [&mut up, &mut stale, &mut undated, &mut waiting] {
+        round.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    [
+        ("review-up", up),
+        ("review-stale", stale),
+        ("review-undated", undated),
+        ("review-waiting", waiting),
+    ]
+}
+
+fn modified(store: &DirStore, task: &Task) -> Option<DateTime<Utc>> {
+    let path = store.task_path(&task.name).ok()?;
+    let modified: SystemTime = std::fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.into())
+}
+
+fn show(task: &Task) {
+    println!("\n{}", task.name.display());
+    if let Some(due) = task.due {
+        let due = due.with_timezone(&Local).format("%a %F %R");
+        println!("  {}", tr!("review-show-due", due = due));
+    }
+    if let Some(review) = task.review {
+        let review = review.with_timezone(&Local).format("%a %F");
+        println!("  {}", tr!("review-show-review", review = review));
+    }
+    let urgency = tr!(
+        "review-show-urgency",
+        urgency = task.urgency,
+        status = task.status
+    );
+    println!("  {urgency}");
+    if !task.tags.is_empty() {
+        println!("  {}", tr!("review-show-tags", tags = task.tags.join(", ")));
+    }
+    for line in task.description.iter().flat_map(|desc| desc.lines()) {
+        println!("  {line}");
+    }
+}
+
+/// Ask what to do with `task` and do it, returns the choice that was carried out
+fn review_task(
+    config: &Config,
+    store: &DirStore,
+    editor: &mut Prompt,
+    task: Task,
+) -> color_eyre::Result<Choice> {
+    // It's been reviewed, whatever happens to it
+    let up = task.is_up_for_review(Utc::now());
+    let task = match up {
+        true => Task {
+            review: None,
+            ..task
+        },
+        false => task,
+    };
+
+    let names = || Choice::ALL.map(|choice| choice.name().to_string());
+    let choice = loop {
+        let answer = prompt::ask(editor, &format!("{} ", tr!("review-choice")), names())?;
+        // `d` is taken by delegate, deleting needs a capital or the full word
+        let choice = match answer.as_str() {
+            "D" => Some(Choice::Delete),
+            answer => Choice::parse(&answer.to_lowercase()),
+        };
+        match choice {
+            Some(choice) => break choice,
+            None => eprintln!("{}", tr!("unknown-answer", answer = format!("{answer:?}"))),
+        }
+    };
+
+    match choice {
+        Choice::Keep if up => {
+            actions::update(config, store, task)?;
+        }
+        Choice::Keep | Choice::Quit => {}
+        Choice::Reschedule => loop {
+            let due = prompt::ask(editor, &format!("{} ", tr!("review-due")), [])?;
+            match parse_date(&due) {
+                Ok(due) => {
+                    actions::update(config, store, task.with_due_date(due))?;
+                    break;
+                }
+                Err(err) => eprintln!("{err}"),
+            }
+        },
+        Choice::Later => loop {
+            let review = prompt::ask(editor, &format!("{} ", tr!("review-again")), [])?;
+            match parse_date(&review) {
+                Ok(review) => {
+                    actions::update(config, store, task.with_review(review))?;
+                    break;
+                }
+                Err(err) => eprintln!("{err}"),
+            }
+        },
+        Choice::Delegate => {
+            let who = prompt::ask(editor, &format!("{} ", tr!("review-waiting-on")), [])?;
+            let note = tr!("review-waiting-note", who = who);
+            let description = match task.description.clone() {
+                Some(description) => format!("{description}\n{note}"),
+                None => note,
+            };
+            let task = Task {
+                status: Status::Waiting,
+                ..task.with_description(description)
+            };
+            actions::update(config, store, task)?;
+        }
+        Choice::Delete => {
+            actions::remove(config, store, &task.name)?;
+        }
+        Choice::Complete => {
+            actions::complete(config, store, &task.name)?;
+        }
+    }
+
+    Ok(choice)
+}
+
+pub fn run(config: &Config, store: &DirStore) -> color_eyre::Result<()> {
+    let tasks = store
+        .load_all()
+        .wrap_err("load tasks")?
+        .into_iter()
+        .map(|task| {
+            let modified = modified(store, &task);
+            (task, modified)
+        })
+        .collect();
+
+    let mut editor = Prompt::new().wrap_err("open the terminal")?;
+    let mut reviewed = 0;
+    let mut changed = 0;
+
+    'review: for (title, tasks) in rounds(tasks, Utc::now()) {
+        if tasks.is_empty() {
+            continue;
+        }
+        let title = format!("== {} ({}) ==", tr!(title), tasks.len());
+        println!("\n{}", color::header(&title, config));
+
+        for task in tasks {
+            show(&task);
+            match review_task(config, store, &mut editor, task)? {
+                Choice::Quit => break 'review,
+                Choice::Keep => {}
+                _ => changed += 1,
+            }
+            reviewed += 1;
+        }
+    }
+
+    println!(
+        "\n{}",
+        tr!("review-summary", reviewed = reviewed, changed = changed)
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{Duration, Utc};
+    use dooit_tasks::{Status, Task};
+    use pretty_assertions::assert_eq;
+
+    use super::rounds;
+
+    #[test]
+    fn test_review_rounds() {
+        let now = Utc::now();
+        let waiting = Task {
+            status: Status::Waiting,
+            ..Task::new("waiting")
+        };
+        let tasks = vec![
+            (
+                Task::new("fresh").with_due_date(now + Duration::days(1)),
+                Some(now),
+            ),
+            (
+                Task::new("old").with_due_date(now + Duration::days(1)),
+                Some(now - Duration::weeks(3)),
+            ),
+            (
+                Task::new("overdue").with_due_date(now - Duration::days(1)),
+                Some(now),
+            ),
+            (Task::new("undated"), Some(now)),
+            (Task::new("done").complete(), Some(now - Duration::weeks(3))),
+            (waiting, Some(now - Duration::weeks(3))),
+            (
+                Task::new("someday").with_review(now - Duration::days(1)),
+                Some(now - Duration::weeks(3)),
+            ),
+            (
+                Task::new("maybe").with_review(now + Duration::weeks(4)),
+                Some(now - Duration::weeks(3)),
+            ),
+        ];
+
+        let names = |tasks: &[Task]| -> Vec<String> {
+            tasks
+                .iter()
+                .map(|task| task.name.display().to_string())
+                .collect()
+        };
+        let [(_, up), (_, stale), (_, undated), (_, waiting)] = rounds(tasks, now);
+        assert_eq!(names(&up), ["someday"]);
+        assert_eq!(names(&stale), ["old", "overdue"]);
+        assert_eq!(names(&undated), ["undated"]);
+        assert_eq!(names(&waiting), ["waiting"]);
+    }
+}
@@ -0,0 +1,260 @@
+//! `schedule`: spreading unscheduled tasks over the coming days
+//!
+//! Tasks are placed one at a time, those due soonest first (then the most urgent), each on the
+//! first day with room left for its remaining estimate. A task never goes before the tasks it
+//! depends on or before its wait date. Tasks that are already scheduled keep their day and take up
+//! its capacity, ones scheduled in the past count for today. Tasks that don't fit anywhere in the
+//! horizon are left unscheduled.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+};
+
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveTime, Utc};
+use color_eyre::eyre::{bail, WrapErr};
+use dooit_tasks::{duration::format_duration, Error, Task, TaskStore};
+
+use crate::{actions, color, config::Config, i18n::tr, prompt, report};
+
+fn local_day(date: DateTime<Utc>) -> NaiveDate {
+    date.with_timezone(&Local).date_naive()
+}
+
+/// Where the tasks went
+#[derive(Debug, Default)]
+struct Schedule<'a> {
+    /// Tasks and the day each was put on, in the order they were placed
+    placed: Vec<(&'a Task, NaiveDate)>,
+    /// Tasks that didn't fit in the horizon
+    unplaced: Vec<&'a Task>,
+}
+
+/// Place the pending unscheduled `tasks` on the `days` starting at `today`, see the
+/// [module docs](self)
+fn schedule(
+    tasks: &[Task],
+    today: NaiveDate,
+    days: u64,
+    capacity: Duration,
+) -> color_eyre::Result<Schedule<'_>> {
+    let pending: HashMap<&Path, &Task> = tasks
+        .iter()
+        .filter(|task| !task.completed)
+        .map(|task| (task.name.as_path(), task))
+        .collect();
+    let remaining = |task: &Task| task.remaining().unwrap_or_else(Duration::zero);
+
+    let mut load: BTreeMap<NaiveDate, Duration> = BTreeMap::new();
+    // The day of every scheduled task, `None` for the ones that couldn't be placed
+    let mut days_of: HashMap<&Path, Option<NaiveDate>> = HashMap::new();
+    let mut left = Vec::new();
+    for task in tasks.iter().filter(|task| !task.completed) {
+        match task.scheduled {
+            Some(scheduled) => {
+                let day = local_day(scheduled).max(today);
+                let day_load = load.entry(day).or_insert_with(Duration::zero);
+                *day_load = *day_load + remaining(task);
+                days_of.insert(&task.name, Some(day));
+            }
+            None => left.push(task),
+        }
+    }
+    left.sort_by(|a, b| {
+        let due = |task: &Task| (task.due.is_none(), task.due);
+        due(a)
+            .cmp(&due(b))
+            .then_with(|| b.urgency.cmp(&a.urgency))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+
+    let last = today + chrono::Days::new(days);
+    let mut schedule = Schedule::default();
+    while !left.is_empty() {
+        let ready = left.iter().position(|task| {
+            task.depends_on.iter().all(|dependency| {
+                !pending.contains_key(dependency.as_path()) || days_of.contains_key(&**dependency)
+            })
+        });
+        let Some(index) = ready else {
+            return Err(Error::DependencyCycle(left[0].name.clone()).into());
+        };
+        let task = left.remove(index);
+
+        let mut earliest = Some(task.wait.map_or(today, local_day).max(today));
+        for dependency in &task.depends_on {
+            if let Some(day) = days_of.get(dependency.as_path()) {
+                earliest = earliest.zip(*day).map(|(earliest, day)| earliest.max(day));
+            }
+        }
+        let need = remaining(task);
+        let day = earliest.and_then(|earliest| {
+            earliest
+                .iter_days()
+                .take_while(|day| *day < last)
+                .find(|day| {
+                    let day_load = load.get(day).copied().unwrap_or_else(Duration::zero);
+                    day_load + need <= capacity || day_load.is_zero()
+                })
+        });
+
+        days_of.insert(&task.name, day);
+        match day {
+            Some(day) => {
+                let day_load = load.entry(day).or_insert_with(Duration::zero);
+                *day_load = *day_load + need;
+                schedule.placed.push((task, day));
+            }
+            None => schedule.unplaced.push(task),
+        }
+    }
+    Ok(schedule)
+}
+
+/// Schedule the pending tasks over the next `horizon` with `capacity` a day, after showing the
+/// schedule and asking
+pub fn run(
+    config: &Config,
+    store: &impl TaskStore,
+    horizon: Duration,
+    capacity: Duration,
+    assume_yes: bool,
+    dry_run: bool,
+) -> color_eyre::Result<()> {
+    let tasks = store.load_all().wrap_err("load tasks")?;
+    let now = Utc::now();
+    let today = local_day(now);
+    let days = horizon.num_days().max(1) as u64;
+    let schedule = schedule(&tasks, today, days, capacity)?;
+    if schedule.placed.is_empty() && schedule.unplaced.is_empty() {
+        println!("{}", tr!("schedule-none"));
+        return Ok(());
+    }
+
+    let header = vec![
+        tr!("column-day"),
+        tr!("column-estimate"),
+        tr!("column-name"),
+    ];
+    let mut placed = schedule.placed.clone();
+    placed.sort_by_key(|(_, day)| *day);
+    let rows: Vec<_> = std::iter::once(header)
+        .chain(placed.iter().map(|(task, day)| {
+            vec![
+                day.format("%a %F").to_string(),
+                task.remaining()
+                    .map_or_else(|| "?".to_string(), format_duration),
+                task.name.display().to_string(),
+            ]
+        }))
+        .collect();
+    for (index, line) in report::table(&rows).into_iter().enumerate() {
+        match index.checked_sub(1).map(|index| placed[index].0) {
+            None => println!("{}", color::header(&line, config)),
+            Some(task) if config.color.enabled() => {
+                println!("{}", color::style(task, config, now).paint(&line))
+            }
+            Some(_) => println!("{line}"),
+        }
+    }
+
+    for (task, day) in &placed {
+        if let Some(due) = task.due.filter(|due| local_day(*due) < *day) {
+            let due = due.with_timezone(&Local).format("%a %F %R").to_string();
+            println!(
+                "{}",
+                tr!("schedule-late", name = task.name.display(), due = due)
+            );
+        }
+    }
+    if !schedule.unplaced.is_empty() {
+        let names: Vec<_> = schedule
+            .unplaced
+            .iter()
+            .map(|task| task.name.display().to_string())
+            .collect();
+        println!(
+            "{}",
+            tr!(
+                "schedule-unplaced",
+                count = names.len(),
+                names = names.join(", ")
+            )
+        );
+    }
+    if placed.is_empty() {
+        return Ok(());
+    }
+
+    let summary = tr!("tasks-to-schedule", count = placed.len());
+    if !prompt::confirm(&summary, assume_yes || dry_run)? {
+        bail!("aborted");
+    }
+    for (task, day) in &placed {
+        let scheduled = day
+            .and_time(NaiveTime::from_hms_opt(0, 0, 0).expect("valid time"))
+            .and_local_timezone(Local)
+            .earliest()
+            .expect("valid date");
+        let task = Task {
+            scheduled: Some(scheduled.into()),
+            ..(*task).clone()
+        };
+        actions::update(config, store, task)?;
+    }
+    if !dry_run {
+        println!("{}", tr!("tasks-scheduled", count = placed.len()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{Datelike, Duration, NaiveDate, TimeZone, Utc};
+    use dooit_tasks::{Task, Urgency};
+    use pretty_assertions::assert_eq;
+
+    use super::schedule;
+
+    #[test]
+    fn test_schedule() {
+        let today = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+        let noon = |day: u32| Utc.with_ymd_and_hms(2024, 5, day, 12, 0, 0).unwrap();
+        let task = |name: &str, hours: i64| Task::new(name).with_estimate(Duration::hours(hours));
+        let tasks = [
+            task("meeting", 2).with_scheduled(noon(1)),
+            task("slides", 3).with_due_date(noon(2)),
+            task("talk", 1)
+                .with_due_date(noon(2))
+                .with_dependencies(["slides"]),
+            task("blog", 4).with_urgency(Urgency::High),
+            task("later", 1).with_wait(noon(3)),
+            task("huge", 20),
+            task("done", 4).complete(),
+        ];
+
+        let result = schedule(&tasks, today, 4, Duration::hours(6)).unwrap();
+        let placed: Vec<_> = result
+            .placed
+            .iter()
+            .map(|(task, day)| (task.name.to_str().unwrap(), day.day()))
+            .collect();
+        assert_eq!(
+            placed,
+            [
+                ("slides", 1),
+                ("talk", 1),
+                ("blog", 2),
+                ("huge", 3),
+                ("later", 4),
+            ]
+        );
+        assert_eq!(result.unplaced.len(), 0);
+
+        let cycle = [
+            task("a", 1).with_dependencies(["b"]),
+            task("b", 1).with_dependencies(["a"]),
+        ];
+        assert!(schedule(&cycle, today, 3, Duration::hours(6)).is_err());
+    }
+}
@@ -0,0 +1,129 @@
+//! User scripts written in [rhai](https://rhai.rs) extending `list`
+//!
+//! Scripts live in `<config dir>/scripts/<name>.rhai` and may define any of these functions, all
+//! of which receive the task as an object map:
+//!
+//! ```rhai
+//! // Only show tasks for which this returns true
+//! fn filter(task) { task.urgency == "High" }
+//! // Sort tasks by this score (highest first) instead of the `--sort` mode
+//! fn score(task) { if task.hours_left == () { 0.0 } else { 100.0 - task.hours_left } }
+//! // Text appended to the task's line
+//! fn column(task) { task.status }
+//! ```
+//!
+//! Besides the task's fields the map contains `hours_left`, the hours until the task is due (or
+//! `()` if it doesn't have a due date).
+
+use std::path::PathBuf;
+
+use color_eyre::eyre::{eyre, WrapErr};
+use dooit_tasks::{dirs, Task};
+use rhai::{Dynamic, Engine, Scope, AST};
+
+pub struct Script {
+    name: String,
+    engine: Engine,
+    ast: AST,
+}
+
+fn script_path(name: &str) -> color_eyre::Result<PathBuf> {
+    dirs::get_config_dir()
+        .map(|dir| dir.join("scripts").join(name).with_extension("rhai"))
+        .ok_or_else(|| eyre!("config dir not available"))
+}
+
+fn to_dynamic(task: &Task) -> color_eyre::Result<Dynamic> {
+    let mut map = rhai::serde::to_dynamic(task)
+        .map_err(|err| eyre!("{err}"))?
+        .try_cast::<rhai::Map>()
+        .expect("tasks serialize to maps");
+
+    let hours_left = task.due.map_or(Dynamic::UNIT, |due| {
+        Dynamic::from_float((due - chrono::Utc::now()).num_minutes() as f64 / 60.0)
+    });
+    map.insert("hours_left".into(), hours_left);
+
+    Ok(map.into())
+}
+
+impl Script {
+    pub fn load(name: &str) -> color_eyre::Result<Self> {
+        let path = script_path(name)?;
+        let engine = Engine::new();
+        let ast = engine
+            .compile_file(path.clone())
+            .map_err(|err| eyre!("{err}"))
+            .wrap_err_with(|| format!("compile script {}", path.display()))?;
+
+        Ok(Self {
+            name: name.to_string(),
+            engine,
+            ast,
+        })
+    }
+
+    fn defines(&self, function: &str) -> bool {
+        self.ast
+            .iter_functions()
+            .any(|f| f.name == function && f.params.len() == 1)
+    }
+
+    /// Call `function` on `task`, returns `Ok(None)` if the script doesn't define it
+    fn call(&self, function: &str, task: &Task) -> color_eyre::Result<Option<Dynamic>> {
+        if !self.defines(function) {
+            return Ok(None);
+        }
+
+        self.engine
+            .call_fn(&mut Scope::new(), &self.ast, function, (to_dynamic(task)?,))
+            .map(Some)
+            .map_err(|err| eyre!("{err}"))
+            .wrap_err_with(|| format!("run {function} from script {}", self.name))
+    }
+
+    pub fn filter(&self, task: &Task) -> color_eyre::Result<bool> {
+        match self.call("filter", task)? {
+            Some(keep) => keep
+                .as_bool()
+                .map_err(|ty| eyre!("filter in script {} returned {ty}, not a bool", self.name)),
+            None => Ok(true),
+        }
+    }
+
+    pub fn score(&self, task: &Task) -> color_eyre::Result<Option<f64>> {
+        let Some(score) = self.call("score", task)? else {
+            return Ok(None);
+        };
+
+        score
+            .as_float()
+            .or_else(|_| score.as_int().map(|score| score as f64))
+            .map(Some)
+            .map_err(|ty| eyre!("score in script {} returned {ty}, not a number", self.name))
+    }
+
+    pub fn column(&self, task: &Task) -> color_eyre::Result<Option<String>> {
+        Ok(self.call("column", task)?.map(|column| column.to_string()))
+    }
+
+    /// Apply the script's filter, score and column to the output of `list`
+    pub fn apply(&self, tasks: Vec<Task>) -> color_eyre::Result<Vec<(Task, Option<String>)>> {
+        let mut scored = Vec::with_capacity(tasks.len());
+        for task in tasks {
+            if self.filter(&task)? {
+                let score = self.score(&task)?;
+                let column = self.column(&task)?;
+                scored.push((score, task, column));
+            }
+        }
+
+        // Stable sort so tasks with equal scores keep the `--sort` order
+        scored.sort_by(|(a, ..), (b, ..)| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
+
+        Ok(scored
+            .into_iter()
+            .map(|(_, task, column)| (task, column))
+            .collect())
+    }
+}
@@ -0,0 +1,119 @@
+//! `search`: find tasks by (part of) their name or description
+//!
+//! Names match fuzzily: a name containing the query ranks highest, then one containing its letters
+//! in order (`rlsnts` finds `release/notes`, the closer together the better), then one similar to
+//! it (to forgive typos). Descriptions only match if they contain the query, and rank below names.
+//! Case is ignored everywhere, and completed tasks are included.
+
+use std::path::Path;
+
+use color_eyre::eyre::WrapErr;
+use dooit_tasks::{Task, TaskStore};
+
+use crate::{color, config::Config, i18n::tr};
+
+/// How similar the last part of a name has to be to match, see [`strsim::jaro_winkler`]
+const SIMILARITY: f64 = 0.8;
+
+/// How well `name` (lowercase) matches `query` (lowercase), if it does
+fn name_score(name: &str, query: &str) -> Option<f64> {
+    let last = name.rsplit('/').next().unwrap_or(name);
+    if last.starts_with(query) || name.starts_with(query) {
+        return Some(1.0);
+    }
+    if name.contains(query) {
+        return Some(0.9);
+    }
+
+    // The letters of the query in order, scored by how spread out they are
+    let mut chars = name.char_indices();
+    let mut span = None;
+    for wanted in query.chars() {
+        let (index, _) = chars.by_ref().find(|(_, c)| *c == wanted)?;
+        let (start, _) = span.get_or_insert((index, index));
+        span = Some((*start, index));
+    }
+    if let Some((start, end)) = span {
+        let spread = query.chars().count() as f64 / name[start..=end].chars().count() as f64;
+        return Some(0.5 + 0.3 * spread);
+    }
+    None
+}
+
+/// How well `task` matches `query` (lowercase), if it does
+fn score(task: &Task, query: &str) -> Option<f64> {
+    let name = task.name.to_string_lossy().to_lowercase();
+    let last = name.rsplit('/').next().unwrap_or(&name);
+    let similar = Some(strsim::jaro_winkler(query, last))
+        .filter(|similarity| *similarity > SIMILARITY)
+        .map(|similarity| similarity * 0.4);
+    let description = task
+        .description
+        .as_ref()
+        .filter(|description| description.to_lowercase().contains(query))
+        .map(|_| 0.3);
+    [name_score(&name, query), similar, description]
+        .into_iter()
+        .flatten()
+        .max_by(f64::total_cmp)
+}
+
+/// The `tasks` matching `query`, best match first
+fn search(tasks: Vec<Task>, query: &str) -> Vec<Task> {
+    let query = query.to_lowercase();
+    let mut found: Vec<_> = tasks
+        .into_iter()
+        .filter_map(|task| Some((score(&task, &query)?, task)))
+        .collect();
+    found.sort_by(|(a, a_task), (b, b_task)| b.total_cmp(a).then(a_task.name.cmp(&b_task.name)));
+    found.into_iter().map(|(_, task)| task).collect()
+}
+
+/// Print the tasks matching `query`, best match first
+pub fn run(config: &Config, store: &impl TaskStore, query: &str) -> color_eyre::Result<()> {
+    let tasks = store
+        .load_summaries_under(Path::new(""))
+        .wrap_err("load tasks")?;
+    let found = search(tasks, query);
+    if found.is_empty() {
+        println!("{}", tr!("search-no-match", query = query));
+    }
+    let now = chrono::Utc::now();
+    for task in found {
+        println!("{}", color::task(&task, config, now));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use dooit_tasks::Task;
+    use pretty_assertions::assert_eq;
+
+    use super::search;
+
+    #[test]
+    fn test_search() {
+        let tasks = vec![
+            Task::new("groceries").with_description("Notes for the party".to_string()),
+            Task::new("release/notes"),
+            Task::new("notes"),
+            Task::new("nitro/tests"),
+            Task::new("taxes"),
+        ];
+        let names = |query| -> Vec<_> {
+            search(tasks.clone(), query)
+                .into_iter()
+                .map(|task| task.name.to_string_lossy().into_owned())
+                .collect()
+        };
+
+        assert_eq!(
+            names("NOTES"),
+            ["notes", "release/notes", "nitro/tests", "groceries"]
+        );
+        assert_eq!(names("rlsnts"), ["release/notes"]);
+        assert_eq!(names("taxs"), ["taxes"]);
+        assert!(names("zzz").is_empty());
+    }
+}
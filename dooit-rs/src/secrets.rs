@@ -0,0 +1,82 @@
+//! Tokens and passwords kept in the operating system's keyring
+//!
+//! Secrets are stored with `dooit-rs auth set <service>`, where the service is `telegram`,
+//! `matrix` or `smtp`. Every secret can be overridden with an environment variable,
+//! `DOOIT_<NAME>_TOKEN` (e.g. `DOOIT_TELEGRAM_TOKEN`), and falls back to the one in the config
+//! (e.g. `notify.telegram.token`) for machines without a keyring.
+
+use color_eyre::eyre::WrapErr;
+use keyring::Entry;
+
+const SERVICE: &str = env!("CARGO_PKG_NAME");
+
+fn env_var(name: &str) -> String {
+    format!("DOOIT_{}_TOKEN", name.to_uppercase().replace('-', "_"))
+}
+
+fn entry(name: &str) -> color_eyre::Result<Entry> {
+    Entry::new(SERVICE, name).wrap_err_with(|| format!("open keyring entry {name}"))
+}
+
+/// Look up the secret called `name`, returns `Ok(None)` if it was never stored
+pub fn get(name: &str) -> color_eyre::Result<Option<String>> {
+    if let Ok(secret) = std::env::var(env_var(name)) {
+        return Ok(Some(secret));
+    }
+
+    match entry(name)?.get_password() {
+        Ok(secret) => Ok(Some(secret)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(err) => Err(err).wrap_err_with(|| format!("read {name} from the keyring")),
+    }
+}
+
+/// [`get`] the secret called `name`, or `fallback` from the config if it isn't stored
+///
+/// The fallback is also used if the keyring can't be read at all, e.g. on a server without one.
+pub fn get_or(name: &str, fallback: Option<&str>) -> color_eyre::Result<Option<String>> {
+    match (get(name), fallback) {
+        (Ok(Some(secret)), _) => Ok(Some(secret)),
+        (Ok(None), fallback) => Ok(fallback.map(str::to_string)),
+        (Err(err), Some(fallback)) => {
+            tracing::warn!("using {name} from the config: {err:?}");
+            Ok(Some(fallback.to_string()))
+        }
+        (Err(err), None) => Err(err),
+    }
+}
+
+pub fn set(name: &str, secret: &str) -> color_eyre::Result<()> {
+    entry(name)?
+        .set_password(secret)
+        .wrap_err_with(|| format!("store {name} in the keyring"))
+}
+
+/// Remove the secret called `name`, returns `Ok(false)` if it wasn't stored
+pub fn delete(name: &str) -> color_eyre::Result<bool> {
+    match entry(name)?.delete_credential() {
+        Ok(()) => Ok(true),
+        Err(keyring::Error::NoEntry) => Ok(false),
+        Err(err) => Err(err).wrap_err_with(|| format!("remove {name} from the keyring")),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::{env_var, get_or};
+
+    #[test]
+    fn test_env_override() {
+        assert_eq!(env_var("smtp"), "DOOIT_SMTP_TOKEN");
+        assert_eq!(env_var("test-only"), "DOOIT_TEST_ONLY_TOKEN");
+
+        // The variable is only read by this test, and it keeps the keyring out of it
+        std::env::set_var(env_var("test-only"), "from the environment");
+        assert_eq!(
+            get_or("test-only", Some("from the config")).unwrap(),
+            Some("from the environment".to_string())
+        );
+    }
+}
@@ -0,0 +1,523 @@
+//! Minimal REST API over HTTP
+//!
+//! | Method   | Path            | Description                                          |
+//! |----------|-----------------|------------------------------------------------------|
+//! | `GET`    | `/tasks`        | List tasks (`?completed=true&overdue=true&sort=...`) |
+//! | `GET`    | `/tasks?limit=` | List a page of tasks (`&offset=...`), see below      |
+//! | `POST`   | `/tasks`        | Create a task from the JSON body                     |
+//! | `GET`    | `/tasks/{name}` | Get a single task                                    |
+//! | `PUT`    | `/tasks/{name}` | Create or replace a task                             |
+//! | `DELETE` | `/tasks/{name}` | Delete a task                                        |
+//! | `GET`    | `/feed`         | Atom feed of the tasks due soon (`?days=7`)          |
+//!
+//! Subtask names contain slashes, they can be used as-is (`/tasks/parent/child`) or
+//! percent-encoded.
+//!
+//! With `limit` or `offset` only that page of the tasks is returned, without their time logs, and
+//! the `X-Total-Count` header has the number of tasks on all pages.
+//!
+//! Changes refused because the task is read-only, a WIP limit is full or a hook rejected them
+//! answer 403, 409 and 422 respectively, with the reason in `error`.
+//!
+//! Bodies over [`MAX_BODY`] bytes are refused with 413.
+//!
+//! The token has to be sent as `Authorization: Bearer ...`. Feed readers can't always send
+//! headers, so `GET /feed` also takes a separate feed token as `?token=`. That one ends up in
+//! URLs, and with them in logs and browser histories, so it only gives access to the feed.
+
+use std::{
+    io::Read,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+};
+
+use clap::ValueEnum;
+use color_eyre::eyre::{bail, eyre};
+use dooit_tasks::{ListFilter, SortMode, Task, TaskStore};
+use percent_encoding::percent_decode_str;
+use serde::Serialize;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::{actions, config::Config, failure::Failure, feed, i18n::tr};
+
+type HttpResponse = Response<std::io::Cursor<Vec<u8>>>;
+
+/// Largest request body read, in bytes
+pub const MAX_BODY: u64 = 1024 * 1024;
+
+pub fn serve(
+    config: &Config,
+    store: &impl TaskStore,
+    listen: SocketAddr,
+    token: Option<&str>,
+    feed_token: Option<&str>,
+) -> color_eyre::Result<()> {
+    if feed_token.is_some() && feed_token == token {
+        bail!("the feed token ends up in URLs, it has to differ from the token");
+    }
+    let server = Server::http(listen).map_err(|err| eyre!("listen on {listen}: {err}"))?;
+    println!("{}", tr!("serve-http", listen = listen));
+
+    for request in server.incoming_requests() {
+        respond(config, store, token, feed_token, request);
+    }
+
+    Ok(())
+}
+
+/// Answer `request` if it carries `token` (or `feed_token` for the feed)
+fn respond(
+    config: &Config,
+    store: &impl TaskStore,
+    token: Option<&str>,
+    feed_token: Option<&str>,
+    mut request: Request,
+) {
+    let response = if authorized(&request, token, feed_token) {
+        handle(config, store, &mut request)
+    } else {
+        error(401, "missing or invalid bearer token")
+    };
+
+    if let Err(err) = request.respond(response) {
+        tracing::warn!("failed to send response: {err}");
+    }
+}
+
+fn authorized(request: &Request, token: Option<&str>, feed_token: Option<&str>) -> bool {
+    let Some(token) = token else {
+        return true;
+    };
+
+    let bearer = request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Authorization"))
+        .and_then(|header| header.value.as_str().strip_prefix("Bearer "))
+        .is_some_and(|given| same_token(given, token));
+    if bearer {
+        return true;
+    }
+
+    let (Some(feed_token), Method::Get) = (feed_token, request.method()) else {
+        return false;
+    };
+    let query = match request.url().split_once('?') {
+        Some(("/feed", query)) => query,
+        _ => return false,
+    };
+    form_urlencoded::parse(query.as_bytes())
+        .any(|(key, given)| key == "token" && same_token(&given, feed_token))
+}
+
+/// Whether `given` is `token`, taking as long wherever they differ so the token can't be guessed
+/// byte by byte from how long requests take
+fn same_token(given: &str, token: &str) -> bool {
+    let difference = given
+        .bytes()
+        .zip(token.bytes())
+        .fold(0, |difference, (a, b)| difference | (a ^ b));
+    given.len() == token.len() && std::hint::black_box(difference) == 0
+}
+
+fn handle(config: &Config, store: &impl TaskStore, request: &mut Request) -> HttpResponse {
+    let (path, query) = request.url().split_once('?').unwrap_or((request.url(), ""));
+    let path = percent_decode_str(path).decode_utf8_lossy().into_owned();
+    let query = query.to_string();
+
+    if path == "/feed" {
+        let result = match request.method() {
+            Method::Get => feed(store, &query),
+            _ => Err(error(405, "method not allowed")),
+        };
+        return result.unwrap_or_else(|response| response);
+    }
+    let name = match path.strip_prefix("/tasks/") {
+        Some(name) => name.trim_start_matches('/'),
+        None if path == "/tasks" => "",
+        None => return error(404, "not found"),
+    };
+
+    let result = match (request.method(), name) {
+        (Method::Get, "") => list(config, store, &query),
+        (Method::Post, "") => read_task(request).and_then(|task| create(config, store, task)),
+        (Method::Get, name) => get(store, name),
+        (Method::Put, name) => {
+            let name = PathBuf::from(name);
+            read_task(request).and_then(|task| replace(config, store, name, task))
+        }
+        (Method::Delete, name) => delete(config, store, name),
+        _ => Err(error(405, "method not allowed")),
+    };
+
+    result.unwrap_or_else(|response| response)
+}
+
+fn list(
+    config: &Config,
+    store: &impl TaskStore,
+    query: &str,
+) -> Result<HttpResponse, HttpResponse> {
+    let mut completed = false;
+    let mut overdue = false;
+    let mut sort = SortMode::default();
+    let mut offset = None;
+    let mut limit = None;
+
+    for (key, value) in form_urlencoded::parse(query.as_bytes()) {
+        let number = || {
+            value
+                .parse::<usize>()
+                .map_err(|_| error(400, &format!("{key} must be a number")))
+        };
+        match key.as_ref() {
+            "completed" => completed = value == "true",
+            "overdue" => overdue = value == "true",
+            "sort" => sort = SortMode::from_str(&value, true).map_err(|err| error(400, &err))?,
+            "offset" => offset = Some(number()?),
+            "limit" => limit = Some(number()?),
+            _ => return Err(error(400, &format!("unknown query parameter {key}"))),
+        }
+    }
+
+    if offset.is_none() && limit.is_none() {
+        let tasks = actions::list(config, store, sort, completed, overdue).map_err(internal)?;
+        return Ok(json(200, &tasks));
+    }
+    let filter = ListFilter {
+        completed,
+        overdue,
+        score: config.score.clone(),
+        ..ListFilter::default()
+    };
+    let offset = offset.unwrap_or(0);
+    let limit = limit.unwrap_or(usize::MAX);
+    let page = store
+        .list_page(&filter, sort, offset, limit)
+        .map_err(|err| internal(err.into()))?;
+    let total = Header::from_bytes("X-Total-Count", page.total.to_string()).expect("valid header");
+    Ok(json(200, &page.tasks).with_header(total))
+}
+
+fn feed(store: &impl TaskStore, query: &str) -> Result<HttpResponse, HttpResponse> {
+    let mut days = 7;
+    for (key, value) in form_urlencoded::parse(query.as_bytes()) {
+        match key.as_ref() {
+            "days" => {
+                days = value
+                    .parse()
+                    .map_err(|_| error(400, "days must be a number"))?
+            }
+            "token" => {}
+            _ => return Err(error(400, &format!("unknown query parameter {key}"))),
+        }
+    }
+
+    let tasks = store
+        .load_summaries_under(Path::new(""))
+        .map_err(|err| internal(err.into()))?;
+    let body = feed::atom(&tasks, days, chrono::Utc::now());
+    let content_type = Header::from_bytes("Content-Type", "application/atom+xml; charset=utf-8")
+        .expect("valid header");
+    Ok(Response::from_data(body.into_bytes()).with_header(content_type))
+}
+
+fn create(
+    config: &Config,
+    store: &impl TaskStore,
+    task: Task,
+) -> Result<HttpResponse, HttpResponse> {
+    task.validate().map_err(bad_request)?;
+    if store.load(&task.name).map_err(bad_request)?.is_some() {
+        return Err(error(409, "a task with that name already exists"));
+    }
+
+    actions::add(config, store, task.clone()).map_err(refused)?;
+    Ok(json(201, &task))
+}
+
+fn get(store: &impl TaskStore, name: &str) -> Result<HttpResponse, HttpResponse> {
+    match store.load(name.as_ref()).map_err(bad_request)? {
+        Some(task) => Ok(json(200, &task)),
+        None => Err(error(404, "no such task")),
+    }
+}
+
+fn replace(
+    config: &Config,
+    store: &impl TaskStore,
+    name: PathBuf,
+    mut task: Task,
+) -> Result<HttpResponse, HttpResponse> {
+    task.name = name;
+    let task = actions::stamp_completion(None, task);
+    task.validate().map_err(bad_request)?;
+
+    let existed = actions::update(config, store, task.clone()).map_err(refused)?;
+    Ok(json(if existed { 200 } else { 201 }, &task))
+}
+
+fn delete(
+    config: &Config,
+    store: &impl TaskStore,
+    name: &str,
+) -> Result<HttpResponse, HttpResponse> {
+    if store.load(name.as_ref()).map_err(bad_request)?.is_none() {
+        return Err(error(404, "no such task"));
+    }
+
+    actions::remove(config, store, name.as_ref()).map_err(refused)?;
+    Ok(Response::from_data(Vec::new()).with_status_code(204))
+}
+
+fn read_task(request: &mut Request) -> Result<Task, HttpResponse> {
+    let too_large = || error(413, &format!("the body is over {MAX_BODY} bytes"));
+    if request
+        .body_length()
+        .is_some_and(|length| length as u64 > MAX_BODY)
+    {
+        return Err(too_large());
+    }
+    let mut body = Vec::new();
+    request
+        .as_reader()
+        .take(MAX_BODY + 1)
+        .read_to_end(&mut body)
+        .map_err(|err| error(400, &err.to_string()))?;
+    if body.len() as u64 > MAX_BODY {
+        return Err(too_large());
+    }
+
+    serde_json::from_slice(&body).map_err(|err| error(400, &format!("invalid task: {err}")))
+}
+
+fn json(status: u16, body: &impl Serialize) -> HttpResponse {
+    let body = serde_json::to_vec(body).expect("tasks serialize to json");
+    Response::from_data(body)
+        .with_status_code(status)
+        .with_header(Header::from_bytes("Content-Type", "application/json").expect("valid header"))
+}
+
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+    error: &'a str,
+}
+
+fn error(status: u16, message: &str) -> HttpResponse {
+    json(status, &ErrorBody { error: message })
+}
+
+fn bad_request(err: dooit_tasks::Error) -> HttpResponse {
+    match err {
+        dooit_tasks::Error::InvalidName(_) | dooit_tasks::Error::InvalidTask { .. } => {
+            error(400, &err.to_string())
+        }
+        _ => internal(err.into()),
+    }
+}
+
+/// The response to a change that failed, telling the client why if it refused it
+fn refused(err: color_eyre::Report) -> HttpResponse {
+    let status = match Failure::of(&err) {
+        Some(Failure::ReadOnly) => 403,
+        Some(Failure::OverLimit) => 409,
+        Some(Failure::HookRejected) => 422,
+        _ => {
+            let invalid = err.chain().any(|cause| {
+                matches!(
+                    cause.downcast_ref(),
+                    Some(
+                        dooit_tasks::Error::InvalidName(_) | dooit_tasks::Error::InvalidTask { .. }
+                    )
+                )
+            });
+            if !invalid {
+                return internal(err);
+            }
+            400
+        }
+    };
+    error(status, &err.to_string())
+}
+
+fn internal(err: color_eyre::Report) -> HttpResponse {
+    tracing::error!("{err:#}");
+    error(500, "internal server error")
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{Read, Write};
+
+    use chrono::{Duration, Utc};
+    use dooit_tasks::{DirStore, Task, TaskStore, Urgency};
+    use pretty_assertions::assert_eq;
+    use serde_json::{json, Value};
+    use tiny_http::Server;
+
+    use super::{respond, MAX_BODY};
+    use crate::config::{AuditConfig, Config, JournalConfig};
+
+    /// Status, `X-Total-Count` and body of a response
+    type Answer = (u16, Option<String>, String);
+
+    fn names(body: &str) -> Vec<String> {
+        let tasks: Vec<Task> = serde_json::from_str(body).unwrap();
+        let names = tasks
+            .into_iter()
+            .map(|task| task.name.display().to_string());
+        names.collect()
+    }
+
+    #[test]
+    fn test_routes() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = DirStore::new(dir.path());
+        let config = Config {
+            read_only: vec!["team".into()],
+            audit: AuditConfig { enabled: false },
+            journal: JournalConfig { enabled: false },
+            ..Config::default()
+        };
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", server.server_addr().to_ip().unwrap());
+        let served = store.clone();
+        std::thread::spawn(move || {
+            for request in server.incoming_requests() {
+                respond(&config, &served, Some("secret"), Some("feeds"), request);
+            }
+        });
+
+        let agent = ureq::agent();
+        let send = |method: &str, path: &str, token: &str, body: Option<Value>| -> Answer {
+            let request = agent
+                .request(method, &format!("{url}{path}"))
+                .set("Authorization", &format!("Bearer {token}"));
+            let response = match body {
+                Some(body) => request.send_string(&body.to_string()),
+                None => request.call(),
+            };
+            let response = match response {
+                Ok(response) | Err(ureq::Error::Status(_, response)) => response,
+                Err(err) => panic!("{method} {path}: {err}"),
+            };
+            let total = response.header("X-Total-Count").map(str::to_string);
+            (response.status(), total, response.into_string().unwrap())
+        };
+        let call =
+            |method: &str, path: &str, body: Option<Value>| send(method, path, "secret", body);
+        let status = |method: &str, path: &str, body: Option<Value>| call(method, path, body).0;
+        let task = |task: &Task| Some(serde_json::to_value(task).unwrap());
+
+        // The token is required on every route
+        assert_eq!(send("GET", "/tasks", "guess", None).0, 401);
+        assert_eq!(send("GET", "/feed", "", None).0, 401);
+
+        // Only the feed token is taken from the URL, and only for reading the feed
+        assert_eq!(send("GET", "/feed?token=secret", "", None).0, 401);
+        assert_eq!(send("GET", "/feed?token=feeds", "", None).0, 200);
+        assert_eq!(send("GET", "/tasks?token=feeds", "", None).0, 401);
+        assert_eq!(send("GET", "/tasks", "feeds", None).0, 401);
+        assert_eq!(send("POST", "/feed?token=feeds", "", None).0, 401);
+
+        // Large bodies aren't read
+        let large = json!({ "name": "large", "description": "x".repeat(MAX_BODY as usize) });
+        assert_eq!(status("POST", "/tasks", Some(large)), 413);
+        assert_eq!(store.load("large".as_ref()).unwrap(), None);
+        // Even without a length up front
+        let mut stream = std::net::TcpStream::connect(url.trim_start_matches("http://")).unwrap();
+        let chunk = "x".repeat(MAX_BODY as usize + 1);
+        let request = format!(
+            "POST /tasks HTTP/1.1\r\nHost: dooit\r\nAuthorization: Bearer secret\r\n\
+             Transfer-Encoding: chunked\r\nConnection: close\r\n\r\n{:x}\r\n{chunk}\r\n0\r\n\r\n",
+            chunk.len()
+        );
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 413"), "{response}");
+
+        // POST creates a task from the body and answers with it, once
+        let now = Utc::now();
+        let report = Task::new("report").with_due_date(now + Duration::days(1));
+        let (code, _, body) = call("POST", "/tasks", task(&report));
+        assert_eq!(
+            (code, serde_json::from_str::<Task>(&body).unwrap()),
+            (201, report.clone())
+        );
+        assert_eq!(status("POST", "/tasks", task(&report)), 409);
+        assert_eq!(
+            status("POST", "/tasks", Some(json!({ "due": "soon" }))),
+            400
+        );
+        assert_eq!(status("POST", "/tasks", task(&Task::new("../escape"))), 400);
+
+        // PUT names the task after the path, subtasks can be percent-encoded
+        let (code, _, body) = call("PUT", "/tasks/release%2Fnotes", task(&Task::new("ignored")));
+        assert_eq!(
+            (code, names(&format!("[{body}]"))),
+            (201, vec!["release/notes".into()])
+        );
+        let late = Task::new("late").with_due_date(now - Duration::days(1));
+        assert_eq!(status("PUT", "/tasks/late", task(&late)), 201);
+        let urgent = late.clone().with_urgency(Urgency::High);
+        assert_eq!(status("PUT", "/tasks/late", task(&urgent)), 200);
+        let done = Task {
+            completed: true,
+            ..Task::new("done")
+        };
+        assert_eq!(status("PUT", "/tasks/done", task(&done)), 201);
+        assert_eq!(
+            status("PUT", "/tasks/team/notes", task(&Task::new("notes"))),
+            403
+        );
+
+        // GET returns single tasks as stored
+        let (code, _, body) = call("GET", "/tasks/release/notes", None);
+        assert_eq!(
+            (code, serde_json::from_str::<Task>(&body).unwrap()),
+            (200, Task::new("release/notes"))
+        );
+        let (_, _, body) = call("GET", "/tasks/late", None);
+        assert_eq!(
+            serde_json::from_str::<Task>(&body).unwrap().urgency,
+            Urgency::High
+        );
+        assert_eq!(status("GET", "/tasks/missing", None), 404);
+
+        // Listing hides completed and overdue tasks unless asked for, and sorts them
+        let list = |query: &str| names(&call("GET", &format!("/tasks?{query}"), None).2);
+        assert_eq!(list("sort=name-ascending"), ["release/notes", "report"]);
+        assert_eq!(
+            list("sort=name-descending&overdue=true"),
+            ["report", "release/notes", "late"]
+        );
+        assert_eq!(
+            list("sort=name-ascending&overdue=true&completed=true"),
+            ["done", "late", "release/notes", "report"]
+        );
+        let (code, total, body) = call("GET", "/tasks?sort=name-ascending&limit=1&offset=1", None);
+        assert_eq!(
+            (code, total, names(&body)),
+            (200, Some("2".into()), vec!["report".into()])
+        );
+        assert_eq!(status("GET", "/tasks?limit=one", None), 400);
+        assert_eq!(status("GET", "/tasks?sort=random", None), 400);
+        assert_eq!(status("GET", "/tasks?color=blue", None), 400);
+
+        // The feed lists the tasks due soon
+        let (code, _, body) = call("GET", "/feed?days=2", None);
+        assert_eq!(code, 200);
+        assert!(body.contains("<feed") && body.contains("report"), "{body}");
+        assert!(!call("GET", "/feed?days=0", None).2.contains("report"));
+
+        // DELETE removes the task
+        assert_eq!(status("DELETE", "/tasks/report", None), 204);
+        assert_eq!(status("DELETE", "/tasks/report", None), 404);
+        assert_eq!(store.load("report".as_ref()).unwrap(), None);
+
+        assert_eq!(status("PATCH", "/tasks/late", None), 405);
+        assert_eq!(status("POST", "/feed", None), 405);
+        assert_eq!(status("GET", "/tasksets", None), 404);
+    }
+}
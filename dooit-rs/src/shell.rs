@@ -0,0 +1,340 @@
+//! Interactive shell running commands on the task list without restarting dooit-rs
+//!
+//! ```text
+//! dooit> add groceries due:tomorrow urgency:high +home buy milk
+//! dooit> done groceries
+//! ```
+
+use std::{
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+};
+
+use clap::ValueEnum;
+use color_eyre::eyre::{bail, eyre, WrapErr};
+use dooit_tasks::{dirs, tasks::parse_date, DirStore, SortMode, Status, Task, TaskStore};
+use rustyline::{
+    completion::Completer, error::ReadlineError, highlight::Highlighter, hint::Hinter,
+    history::DefaultHistory, validate::Validator, Context, Editor, Helper,
+};
+
+use crate::{
+    actions, color,
+    config::Config,
+    context,
+    i18n::tr,
+    lookup,
+    prompt::{complete_word, confirm},
+};
+
+/// Name, arguments and description of every command
+const COMMANDS: [(&str, &str, &str); 7] = [
+    ("list", "[completed] [overdue] [all]", "List tasks"),
+    (
+        "add",
+        "NAME [due:DATE] [urgency:URGENCY] [status:STATUS] [+TAG]... [DESCRIPTION]",
+        "Add a task",
+    ),
+    ("done", "NAME...", "Mark tasks as completed"),
+    ("show", "NAME...", "Show tasks"),
+    ("rm", "NAME...", "Delete tasks"),
+    ("help", "", "Show this help"),
+    ("exit", "", "Leave the shell"),
+];
+
+/// Completes commands, task names and tags
+#[derive(Debug, Default)]
+struct ShellHelper {
+    commands: Vec<String>,
+    names: Vec<String>,
+    tags: Vec<String>,
+}
+
+impl ShellHelper {
+    fn load(store: &impl TaskStore) -> Self {
+        let tasks = store.load_all().unwrap_or_default();
+        let mut tags: Vec<_> = tasks
+            .iter()
+            .flat_map(|task| task.tags.iter().map(|tag| format!("+{tag}")))
+            .collect();
+        tags.sort_unstable();
+        tags.dedup();
+
+        Self {
+            commands: COMMANDS.iter().map(|(name, ..)| name.to_string()).collect(),
+            names: tasks
+                .iter()
+                .map(|task| task.name.to_string_lossy().into_owned())
+                .collect(),
+            tags,
+        }
+    }
+}
+
+impl Completer for ShellHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let before = &line[..pos];
+        let word = before
+            .rsplit(char::is_whitespace)
+            .next()
+            .unwrap_or_default();
+
+        let candidates = if !before.contains(char::is_whitespace) {
+            &self.commands
+        } else if word.starts_with('+') {
+            &self.tags
+        } else {
+            &self.names
+        };
+        Ok(complete_word(line, pos, candidates))
+    }
+}
+
+impl Hinter for ShellHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ShellHelper {}
+
+impl Validator for ShellHelper {}
+
+impl Helper for ShellHelper {}
+
+/// Split `line` into words, single or double quotes group words containing spaces
+fn split_words(line: &str) -> color_eyre::Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word = None::<String>;
+    let mut quote = None;
+
+    for c in line.chars() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some(_), c) => word.get_or_insert_with(String::new).push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    if let Some(quote) = quote {
+        bail!("unterminated {quote}");
+    }
+    words.extend(word);
+
+    Ok(words)
+}
+
+/// Build a task from the arguments of `add`
+fn parse_add(words: &[String]) -> color_eyre::Result<Task> {
+    let mut name = None;
+    let mut description = Vec::new();
+    let mut task = Task::new("");
+
+    for word in words {
+        if let Some(tag) = word.strip_prefix('+') {
+            task.tags.push(tag.to_string());
+        } else if let Some(due) = word.strip_prefix("due:") {
+            task.due = Some(parse_date(due)?);
+        } else if let Some(urgency) = word.strip_prefix("urgency:") {
+            task.urgency = urgency.parse()?;
+        } else if let Some(status) = word.strip_prefix("status:") {
+            task.status = Status::from_str(status, true).map_err(|err| eyre!("{err}"))?;
+        } else if name.is_none() {
+            name = Some(PathBuf::from(word));
+        } else {
+            description.push(word.as_str());
+        }
+    }
+
+    task.name = name.ok_or_else(|| eyre!("usage: add {}", COMMANDS[1].1))?;
+    if !description.is_empty() {
+        task.description = Some(description.join(" "));
+    }
+
+    Ok(task)
+}
+
+fn run_command(
+    config: &Config,
+    store: &impl TaskStore,
+    line: &str,
+    assume_yes: bool,
+) -> color_eyre::Result<ControlFlow<()>> {
+    let words = split_words(line)?;
+    let Some((command, args)) = words.split_first() else {
+        return Ok(ControlFlow::Continue(()));
+    };
+
+    match command.as_str() {
+        "list" | "ls" => {
+            let all = args.iter().any(|arg| arg == "all");
+            let completed = all || args.iter().any(|arg| arg == "completed");
+            let overdue = all || args.iter().any(|arg| arg == "overdue");
+
+            let context = context::Context::active(config)?;
+            let parent = context
+                .as_ref()
+                .map_or(Path::new(""), context::Context::prefix);
+            let mut tasks = actions::list_under(
+                config,
+                store,
+                parent,
+                SortMode::default(),
+                completed,
+                overdue,
+            )?;
+            let now = chrono::Utc::now();
+            if let Some(context) = &context {
+                println!("{}", context.header(config));
+                tasks.retain(|task| context.matches(task, now));
+            }
+            if tasks.is_empty() {
+                println!("{}", tr!("no-tasks"));
+            }
+            for task in tasks {
+                println!("{}", color::task(&task, config, now));
+            }
+        }
+        "add" => {
+            let task = parse_add(args)?;
+            let name = task.name.clone();
+            actions::add(config, store, task)?;
+            println!("{}", tr!("task-added", name = name.display()));
+        }
+        "done" => {
+            for name in args {
+                let name = lookup::resolve(store, Path::new(name))?;
+                let task = actions::complete(config, store, &name)?;
+                println!("{}", tr!("task-completed", name = task.name.display()));
+            }
+        }
+        "show" => {
+            for name in args {
+                let name = lookup::resolve(store, Path::new(name))?;
+                match store.load(&name)? {
+                    Some(task) => println!("{task}"),
+                    None => bail!("no task named {}", name.display()),
+                }
+            }
+        }
+        "rm" => {
+            let names = args
+                .iter()
+                .map(|name| lookup::resolve(store, Path::new(name)))
+                .collect::<color_eyre::Result<Vec<_>>>()?;
+            let summary = tr!("tasks-to-delete", count = names.len());
+            if !confirm(&summary, assume_yes)? {
+                return Ok(ControlFlow::Continue(()));
+            }
+            for name in names {
+                let task = actions::remove(config, store, &name)?;
+                println!("{}", tr!("task-deleted", name = task.name.display()));
+            }
+        }
+        "help" => {
+            for (command, args, description) in COMMANDS {
+                println!(
+                    "{}\n    {description}",
+                    format!("{command} {args}").trim_end()
+                );
+            }
+        }
+        "exit" | "quit" => return Ok(ControlFlow::Break(())),
+        command => bail!("unknown command {command}, try `help`"),
+    }
+
+    Ok(ControlFlow::Continue(()))
+}
+
+fn history_path() -> Option<PathBuf> {
+    dirs::get_state_dir().map(|dir| dir.join("shell_history"))
+}
+
+/// Run the shell until the user leaves, `assume_yes` skips confirming deletions
+pub fn run(config: &Config, store: &DirStore, assume_yes: bool) -> color_eyre::Result<()> {
+    let mut editor = Editor::<ShellHelper, DefaultHistory>::new().wrap_err("open the terminal")?;
+
+    let history = history_path();
+    if let Some(history) = &history {
+        // There is no history the first time the shell runs
+        let _ = editor.load_history(history);
+    }
+
+    loop {
+        // Reload every time so completions include changes made outside the shell
+        editor.set_helper(Some(ShellHelper::load(store)));
+
+        let line = match editor.readline("dooit> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(err) => return Err(err).wrap_err("read from the terminal"),
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        editor.add_history_entry(line.as_str())?;
+
+        match run_command(config, store, &line, assume_yes) {
+            Ok(ControlFlow::Continue(())) => {}
+            Ok(ControlFlow::Break(())) => break,
+            Err(err) => eprintln!("error: {err:#}"),
+        }
+    }
+
+    if let Some(history) = history {
+        if let Some(parent) = history.parent() {
+            std::fs::create_dir_all(parent)
+                .wrap_err_with(|| format!("create {}", parent.display()))?;
+        }
+        editor
+            .save_history(&history)
+            .wrap_err_with(|| format!("save {}", history.display()))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use dooit_tasks::{Task, Urgency};
+    use pretty_assertions::assert_eq;
+
+    use super::{parse_add, split_words};
+
+    #[test]
+    fn test_parse_add() {
+        let words = split_words(r#"add "weekly shop" +home urgency:high buy 'oat milk'"#).unwrap();
+        assert_eq!(
+            words,
+            [
+                "add",
+                "weekly shop",
+                "+home",
+                "urgency:high",
+                "buy",
+                "oat milk"
+            ]
+        );
+
+        let task = parse_add(&words[1..]).unwrap();
+        let expect = Task::new("weekly shop")
+            .with_urgency(Urgency::High)
+            .with_tags(["home"])
+            .with_description("buy oat milk".to_string());
+        assert_eq!(task, expect);
+
+        assert!(split_words("add 'unterminated").is_err());
+        assert!(parse_add(&[]).is_err());
+    }
+}
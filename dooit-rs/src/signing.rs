@@ -0,0 +1,407 @@
+//! Signing the task files with GnuPG, for task directories synced through storage that isn't
+//! trusted
+//!
+//! With `signing.key` set, the index of the task files (see [`dooit_tasks::index`]) is signed with
+//! that key into `.index.sig` whenever a command that changed it exits. Commands loading the tasks
+//! check the signature first and then the files against the index, warning about the files that
+//! aren't signed or changed since. An index without a good signature isn't signed again, that
+//! would vouch for whatever was tampered with, until `dooit-rs verify --accept` rebuilds it. Signing the index rather than every file keeps it to one `gpg`
+//! call per command, two when the key is given by user id, which is looked up to check the
+//! signature was made by that exact key. age can't sign files, so only GnuPG is supported.
+
+use std::{
+    path::PathBuf,
+    process::{Command, Stdio},
+    sync::Mutex,
+};
+
+use color_eyre::eyre::{bail, WrapErr};
+use dooit_tasks::{
+    index::{Difference, INDEX_FILE},
+    DirStore,
+};
+
+use crate::{config::Config, failure, i18n::tr};
+
+/// Name of the signature of the index inside a store's root
+pub const SIGNATURE_FILE: &str = ".index.sig";
+
+/// `key` as written in `gpg` status output if it's a fingerprint or a long key id
+fn key_id(key: &str) -> Option<String> {
+    let key = key.trim_start_matches("0x").to_uppercase();
+    let hex = key.chars().all(|char| char.is_ascii_hexdigit());
+    (hex && matches!(key.len(), 16 | 40)).then_some(key)
+}
+
+/// Whether the `--status-fd` output of `gpg --verify` has a good signature made by one of `keys`
+///
+/// `keys` are fingerprints and long key ids as [`key_id`] writes them. They're compared exactly to
+/// the key id of the good signature and to the fingerprints of the key that made it and of its
+/// primary key, so a key can't pass for another by sharing part of its id.
+fn signed_by(status: &str, keys: &[String]) -> bool {
+    let mut good = false;
+    let mut matches = false;
+    for line in status.lines() {
+        let Some(line) = line.strip_prefix("[GNUPG:] ") else {
+            continue;
+        };
+        let fields: Vec<_> = line.split(' ').collect();
+        let ids: &[usize] = match fields[0] {
+            "GOODSIG" => {
+                good = true;
+                &[1]
+            }
+            // The fingerprint of the signing key and, last, the one of its primary key
+            "VALIDSIG" => &[1, 10],
+            _ => continue,
+        };
+        matches |= ids
+            .iter()
+            .filter_map(|&id| fields.get(id))
+            .any(|id| keys.iter().any(|key| key.eq_ignore_ascii_case(id)));
+    }
+    good && matches
+}
+
+/// The `gpg` command and key from `signing` in the config
+#[derive(Debug, Clone)]
+struct Signer {
+    program: String,
+    key: String,
+}
+
+impl Signer {
+    fn new(config: &Config) -> Option<Self> {
+        let key = config.signing.key.clone()?;
+        let program = config
+            .signing
+            .program
+            .clone()
+            .unwrap_or_else(|| "gpg".to_string());
+        Some(Self { program, key })
+    }
+
+    fn paths(store: &DirStore) -> (PathBuf, PathBuf) {
+        (
+            store.root().join(INDEX_FILE),
+            store.root().join(SIGNATURE_FILE),
+        )
+    }
+
+    /// Sign the index of `store`
+    fn sign(&self, store: &DirStore) -> color_eyre::Result<()> {
+        let (index, signature) = Self::paths(store);
+        let status = Command::new(&self.program)
+            .args([
+                "--batch",
+                "--yes",
+                "--local-user",
+                &self.key,
+                "--detach-sign",
+            ])
+            .arg("--output")
+            .arg(&signature)
+            .arg(&index)
+            .stdin(Stdio::null())
+            .status()
+            .wrap_err_with(|| format!("run {}", self.program))?;
+        if !status.success() {
+            bail!(
+                "signing {} with {} failed: {status}",
+                index.display(),
+                self.key
+            );
+        }
+        Ok(())
+    }
+
+    /// The fingerprints and key ids the signature has to match, see [`signed_by`]
+    ///
+    /// Other keys are looked up in the keyring, so the fingerprints of every key they stand for
+    /// count. Email addresses are looked up as `<address>`, which GnuPG matches exactly instead of
+    /// taking every user id containing them.
+    fn key_ids(&self) -> color_eyre::Result<Vec<String>> {
+        if let Some(id) = key_id(&self.key) {
+            return Ok(vec![id]);
+        }
+        let user_id = match self.key.contains('@') && !self.key.contains('<') {
+            true => format!("<{}>", self.key),
+            false => self.key.clone(),
+        };
+        let output = Command::new(&self.program)
+            .args(["--batch", "--with-colons", "--fingerprint", &user_id])
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .wrap_err_with(|| format!("run {}", self.program))?;
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter(|line| line.starts_with("fpr:"))
+            .filter_map(|line| line.split(':').nth(9))
+            .map(str::to_string)
+            .collect())
+    }
+
+    /// Whether the index of `store` has a good signature by the key
+    fn verify(&self, store: &DirStore) -> color_eyre::Result<bool> {
+        let (index, signature) = Self::paths(store);
+        let output = Command::new(&self.program)
+            .args(["--batch", "--status-fd", "1", "--verify"])
+            .arg(&signature)
+            .arg(&index)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .wrap_err_with(|| format!("run {}", self.program))?;
+        Ok(output.status.success()
+            && signed_by(&String::from_utf8_lossy(&output.stdout), &self.key_ids()?))
+    }
+}
+
+/// What [`check`] found, for the [`Guard`] to decide whether to sign the index again
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Verification {
+    /// The signature is good, or there is no index to sign yet
+    Good,
+    /// The signature is missing, bad or couldn't be checked
+    Failed,
+    /// The index was rebuilt from the task files as they are with `verify --accept`
+    Accepted,
+}
+
+/// The [`Verification`] of this run, set by the first [`check`]
+static VERIFICATION: Mutex<Option<Verification>> = Mutex::new(None);
+
+fn verification() -> Option<Verification> {
+    *VERIFICATION.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+fn set_verification(verification: Verification) {
+    *VERIFICATION.lock().unwrap_or_else(|err| err.into_inner()) = Some(verification);
+}
+
+/// Sign the index when this run exits even if its signature didn't check out, after `verify
+/// --accept` rebuilt it
+pub fn accept() {
+    set_verification(Verification::Accepted);
+}
+
+/// Warn about the task files of `store` that aren't signed or changed since, if signing is on
+///
+/// Only the first call of a run checks, the others would find the same.
+pub fn check(config: &Config, store: &DirStore) {
+    let Some(signer) = Signer::new(config) else {
+        return;
+    };
+    if verification().is_some() {
+        return;
+    }
+    let verification = verify(&signer, store);
+    set_verification(verification);
+    if verification != Verification::Good {
+        return;
+    }
+
+    let differences = match store.verify() {
+        Ok(differences) => differences,
+        Err(err) => {
+            eprintln!("warning: {}", tr!("signing-check-failed", error = err));
+            return;
+        }
+    };
+    for (path, difference) in differences {
+        let path = path.display();
+        let message = match difference {
+            Difference::Modified => tr!("signing-modified", path = path),
+            Difference::Missing => tr!("signing-missing", path = path),
+            Difference::Unindexed => tr!("signing-unindexed", path = path),
+        };
+        eprintln!("warning: {message}");
+    }
+}
+
+/// Check the signature of the index of `store`, warning if it isn't good
+fn verify(signer: &Signer, store: &DirStore) -> Verification {
+    let (index, signature) = Signer::paths(store);
+    if !index.exists() {
+        return Verification::Good;
+    }
+    if !signature.exists() {
+        eprintln!("warning: {}", tr!("signing-unsigned"));
+        return Verification::Failed;
+    }
+
+    match signer.verify(store) {
+        Ok(true) => Verification::Good,
+        Ok(false) => {
+            let message = tr!(
+                "signing-invalid",
+                path = signature.display(),
+                key = &signer.key
+            );
+            eprintln!("warning: {message}");
+            Verification::Failed
+        }
+        Err(err) => {
+            eprintln!(
+                "warning: {}",
+                tr!("signing-verify-failed", error = format!("{err:#}"))
+            );
+            Verification::Failed
+        }
+    }
+}
+
+/// Signs the index of the store when dropped if it changed since the guard was created, so every
+/// command that changes tasks signs them as it exits (however it exits)
+///
+/// Only an index whose signature [`check`] found good is signed again, or one `verify --accept`
+/// rebuilt.
+pub struct Guard {
+    signing: Option<(Signer, DirStore, Option<Vec<u8>>)>,
+}
+
+impl Guard {
+    pub fn new(config: &Config) -> Self {
+        let signing = Signer::new(config).zip(failure::data_dir(config).ok());
+        let signing = signing.map(|(signer, data_dir)| {
+            let store = DirStore::new(data_dir);
+            let index = std::fs::read(Signer::paths(&store).0).ok();
+            (signer, store, index)
+        });
+        Self { signing }
+    }
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        let Some((signer, store, before)) = &self.signing else {
+            return;
+        };
+        let after = std::fs::read(Signer::paths(store).0).ok();
+        let changed = after.is_some() && after != *before;
+        match verification() {
+            Some(Verification::Accepted) if after.is_some() => {}
+            _ if !changed => return,
+            Some(Verification::Good) => {}
+            // A new store, there was nothing to check
+            None if before.is_none() => {}
+            Some(_) | None => {
+                eprintln!("warning: {}", tr!("signing-refused"));
+                return;
+            }
+        }
+        if let Err(err) = signer.sign(store) {
+            eprintln!(
+                "warning: {}",
+                tr!("signing-failed", error = format!("{err:#}"))
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(unix)]
+    use dooit_tasks::{DirStore, Task, TaskStore};
+
+    #[cfg(unix)]
+    use super::{accept, check, Guard, Signer, VERIFICATION};
+    use super::{key_id, signed_by};
+    #[cfg(unix)]
+    use crate::config::{Config, SigningConfig};
+
+    #[test]
+    fn test_signed_by() {
+        let status = "[GNUPG:] NEWSIG\n\
+                      [GNUPG:] GOODSIG 0123456789ABCDEF Me <me@example.com>\n\
+                      [GNUPG:] VALIDSIG AAAABBBBCCCCDDDDEEEEFFFF0123456789ABCDEF 2024-05-01 \
+                      1714521600 0 4 0 22 10 00 1111222233334444555566667777888899990000\n";
+        let keys = |key: &str| vec![key_id(key).unwrap()];
+
+        assert!(signed_by(status, &keys("0x0123456789abcdef")));
+        assert!(signed_by(
+            status,
+            &keys("AAAABBBBCCCCDDDDEEEEFFFF0123456789ABCDEF")
+        ));
+        // The primary key of the subkey that signed
+        assert!(signed_by(
+            status,
+            &keys("1111222233334444555566667777888899990000")
+        ));
+        // Parts of the ids don't count
+        assert!(!signed_by(status, &keys("AAAABBBBCCCCDDDD")));
+        assert!(!signed_by(status, &["89ABCDEF".to_string()]));
+        assert_eq!(key_id("me@example.com"), None);
+        let bad = "[GNUPG:] BADSIG 0123456789ABCDEF Me <me@example.com>\n";
+        assert!(!signed_by(bad, &keys("0123456789ABCDEF")));
+    }
+
+    /// A `gpg` "signing" the index by copying it, and finding the signature good if it's a copy
+    #[cfg(unix)]
+    const FAKE_GPG: &str = r#"#!/bin/sh
+while [ $# -gt 2 ]; do
+    case "$1" in --output) output=$2; shift ;; esac
+    shift
+done
+if [ -n "$output" ]; then
+    cp "$1" "$output"
+elif cmp -s "$1" "$2"; then
+    echo "[GNUPG:] GOODSIG 0123456789ABCDEF Me <me@example.com>"
+fi
+"#;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_guard() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let program = dir.path().join("gpg");
+        std::fs::write(&program, FAKE_GPG).unwrap();
+        std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let config = Config {
+            data_dir: Some(dir.path().join("tasks")),
+            signing: SigningConfig {
+                key: Some("0123456789ABCDEF".to_string()),
+                program: Some(program.to_string_lossy().into_owned()),
+            },
+            ..Config::default()
+        };
+        let store = DirStore::new(dir.path().join("tasks"));
+        let (index, signature) = Signer::paths(&store);
+        let signed = || std::fs::read(&signature).ok() == std::fs::read(&index).ok();
+        // Every command is a new run
+        let run = |change: &dyn Fn()| {
+            *VERIFICATION.lock().unwrap() = None;
+            let guard = Guard::new(&config);
+            check(&config, &store);
+            change();
+            drop(guard);
+        };
+
+        // A new store gets signed
+        run(&|| store.save(&Task::new("report")).unwrap());
+        assert!(signed());
+        run(&|| store.save(&Task::new("notes")).unwrap());
+        assert!(signed());
+
+        // Changed behind its back, the index isn't signed again
+        store.save(&Task::new("planted")).unwrap();
+        run(&|| store.save(&Task::new("taxes")).unwrap());
+        assert!(!signed());
+        // Nor without a signature
+        std::fs::remove_file(&signature).unwrap();
+        run(&|| store.save(&Task::new("groceries")).unwrap());
+        assert!(!signature.exists());
+
+        // Until the files are accepted as they are
+        run(&|| {
+            store.reindex().unwrap();
+            accept();
+        });
+        assert!(signed());
+        run(&|| store.save(&Task::new("trip")).unwrap());
+        assert!(signed());
+    }
+}
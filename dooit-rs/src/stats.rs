@@ -0,0 +1,202 @@
+//! `stats`: counts of the tasks, and with `--streaks` the days in a row tasks got completed and the
+//! points earned
+//!
+//! A completed task is worth its urgency's position in the levels (1 for the lowest) for every
+//! hour of its estimate, rounded up, so unestimated tasks count as taking an hour. A streak isn't
+//! broken until a whole day goes by without completing anything.
+
+use std::{collections::BTreeSet, path::Path};
+
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
+use color_eyre::eyre::WrapErr;
+use dooit_tasks::{HumanDuration, IconSet, Task, TaskStore};
+
+use crate::{color, config::Config, i18n::tr};
+
+/// Points `task` is worth once completed, see the [module docs](self)
+fn points(task: &Task) -> u64 {
+    let weight = task.urgency.rank().map_or(1, |rank| rank as u64 + 1);
+    let hours = task.estimate.map_or(1, |HumanDuration(estimate)| {
+        (estimate.num_minutes().max(1) as u64).div_ceil(60)
+    });
+    weight * hours
+}
+
+/// Everything `stats` prints
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Stats {
+    pending: usize,
+    overdue: usize,
+    completed: usize,
+    /// Days in a row ending today (or yesterday, if nothing was completed today yet)
+    streak: usize,
+    longest_streak: usize,
+    /// Whether something was completed today
+    done_today: bool,
+    points_today: u64,
+    points_week: u64,
+    points: u64,
+}
+
+/// Days in a row in `days` ending at `last`
+fn run_ending(days: &BTreeSet<NaiveDate>, last: NaiveDate) -> usize {
+    std::iter::successors(Some(last), NaiveDate::pred_opt)
+        .take_while(|day| days.contains(day))
+        .count()
+}
+
+fn stats(tasks: &[Task], now: DateTime<Utc>) -> Stats {
+    let today = now.with_timezone(&Local).date_naive();
+    let week_ago = today - Duration::days(6);
+    let mut stats = Stats::default();
+    let mut days = BTreeSet::new();
+    for task in tasks {
+        if !task.completed {
+            stats.pending += 1;
+            stats.overdue += usize::from(task.due.is_some_and(|due| due < now));
+            continue;
+        }
+        stats.completed += 1;
+        let Some(completed_at) = task.completed_at else {
+            continue;
+        };
+        let day = completed_at.with_timezone(&Local).date_naive();
+        days.insert(day);
+        let points = points(task);
+        stats.points += points;
+        if day >= week_ago {
+            stats.points_week += points;
+        }
+        if day == today {
+            stats.points_today += points;
+        }
+    }
+
+    stats.done_today = days.contains(&today);
+    stats.streak = match stats.done_today {
+        true => run_ending(&days, today),
+        false => today
+            .pred_opt()
+            .map_or(0, |yesterday| run_ending(&days, yesterday)),
+    };
+    stats.longest_streak = days
+        .iter()
+        .filter(|day| day.succ_opt().is_none_or(|next| !days.contains(&next)))
+        .map(|last| run_ending(&days, *last))
+        .max()
+        .unwrap_or(0);
+    stats
+}
+
+/// Print the counts of the tasks, with the streaks and points if `streaks`
+///
+/// With `prompt` only the current streak and today's points are printed, in a line short enough
+/// for a shell prompt, and nothing at all if both are zero.
+pub fn run(
+    config: &Config,
+    store: &impl TaskStore,
+    streaks: bool,
+    prompt: bool,
+) -> color_eyre::Result<()> {
+    let tasks = store
+        .load_summaries_under(Path::new(""))
+        .wrap_err("load tasks")?;
+    let stats = stats(&tasks, Utc::now());
+
+    if prompt {
+        if stats.streak > 0 || stats.points_today > 0 {
+            match config.icons {
+                IconSet::Nerd => {
+                    println!("\u{f06d} {} \u{f005} {}", stats.streak, stats.points_today)
+                }
+                IconSet::Ascii => println!("{}d {}pt", stats.streak, stats.points_today),
+            }
+        }
+        return Ok(());
+    }
+
+    let counts = tr!(
+        "stats-tasks",
+        pending = stats.pending,
+        overdue = stats.overdue,
+        completed = stats.completed
+    );
+    println!("{}", color::header(&counts, config));
+    if !streaks {
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        tr!(
+            "stats-streak",
+            current = stats.streak,
+            longest = stats.longest_streak
+        )
+    );
+    println!(
+        "{}",
+        tr!(
+            "stats-points",
+            today = stats.points_today,
+            week = stats.points_week,
+            total = stats.points
+        )
+    );
+    if stats.streak > 0 && !stats.done_today {
+        println!("{}", tr!("stats-streak-at-risk"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{Duration, Local, TimeZone, Utc};
+    use dooit_tasks::{HumanDuration, Task, Urgency};
+    use pretty_assertions::assert_eq;
+
+    use super::{stats, Stats};
+
+    #[test]
+    fn test_stats() {
+        let now = Local
+            .with_ymd_and_hms(2024, 5, 10, 12, 0, 0)
+            .unwrap()
+            .with_timezone(&Utc);
+        let completed = |days: i64| Task {
+            completed_at: Some(now - Duration::days(days)),
+            ..Task::new("done").complete()
+        };
+        let tasks = [
+            // Yesterday and the two days before, then a gap and four days in a row
+            completed(1),
+            Task {
+                estimate: Some(HumanDuration(Duration::minutes(90))),
+                ..completed(1).with_urgency(Urgency::High)
+            },
+            completed(2),
+            completed(3),
+            completed(5),
+            completed(6),
+            completed(7),
+            completed(8),
+            Task::new("late").with_due_date(now - Duration::hours(1)),
+            Task::new("pending"),
+        ];
+
+        assert_eq!(
+            stats(&tasks, now),
+            Stats {
+                pending: 2,
+                overdue: 1,
+                completed: 8,
+                streak: 3,
+                longest_streak: 4,
+                done_today: false,
+                points_today: 0,
+                points_week: 11,
+                points: 13,
+            }
+        );
+    }
+}
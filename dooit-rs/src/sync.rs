@@ -0,0 +1,442 @@
+//! `sync`: keep the tasks in sync with another task directory, e.g. on a shared drive
+//!
+//! The other directory is the service of a [`Syncer`] (see [`dooit_tasks::sync`]), its tasks are
+//! kept under `--prefix` (every task by default). Tasks changed on both sides since the last sync
+//! are merged field by field, and fields both sides changed are resolved by `--ours`, `--theirs` or
+//! `--newest`. Without one of them every such conflict is shown side by side on a terminal to pick
+//! the value to keep. Without a terminal the local values are kept and the conflicts are listed.
+//!
+//! Renaming a task doesn't carry over, it keeps its old name in the other directory. Changes pulled
+//! into the task directory go through [`actions`] like any other, so they respect `read_only` and
+//! the limits, run the hooks and can be undone.
+
+use std::{
+    cell::RefCell,
+    io::IsTerminal,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+
+use color_eyre::eyre::{bail, eyre, WrapErr};
+use dooit_tasks::{
+    dirs,
+    sync::{
+        BackendError, Changes, ConflictPolicy, RemoteTask, Side, SyncBackend, SyncConflict, Syncer,
+    },
+    DirStore, Task, TaskStore,
+};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use unicode_width::UnicodeWidthStr;
+
+use crate::{
+    actions,
+    config::Config,
+    dry_run::DryRun,
+    i18n::tr,
+    prompt::{self, Prompt},
+};
+
+/// Another task directory as a [`SyncBackend`], the tasks' IDs are their names there
+///
+/// The cursor is the list of names at the last pull, which tells the tasks deleted since. Every
+/// task is pulled every time, the [`Syncer`] skips those that didn't change.
+#[derive(Debug)]
+struct DirBackend<'a> {
+    store: DryRun<'a>,
+}
+
+fn permanent(err: impl std::fmt::Display) -> BackendError {
+    BackendError::Permanent(err.to_string())
+}
+
+impl SyncBackend for DirBackend<'_> {
+    fn name(&self) -> &str {
+        "dir"
+    }
+
+    fn pull(&mut self, cursor: Option<&str>) -> Result<Changes, BackendError> {
+        let seen: Vec<String> = cursor
+            .map(serde_json::from_str)
+            .transpose()
+            .map_err(permanent)?
+            .unwrap_or_default();
+        let mut changed = Vec::new();
+        for task in self.store.load_all().map_err(permanent)? {
+            changed.push(RemoteTask {
+                id: task.name.display().to_string(),
+                modified: self.store.modified(&task.name).map_err(permanent)?,
+                task,
+            });
+        }
+
+        let names: Vec<_> = changed.iter().map(|task| task.id.clone()).collect();
+        let deleted = seen
+            .into_iter()
+            .filter(|name| !names.contains(name))
+            .collect();
+        Ok(Changes {
+            changed,
+            deleted,
+            cursor: Some(serde_json::to_string(&names).map_err(permanent)?),
+        })
+    }
+
+    fn create(&mut self, task: &Task) -> Result<RemoteTask, BackendError> {
+        self.store.save(task).map_err(permanent)?;
+        Ok(RemoteTask {
+            id: task.name.display().to_string(),
+            task: task.clone(),
+            modified: None,
+        })
+    }
+
+    fn update(&mut self, id: &str, task: &Task) -> Result<Task, BackendError> {
+        let task = Task {
+            name: PathBuf::from(id),
+            ..task.clone()
+        };
+        self.store.save(&task).map_err(permanent)?;
+        Ok(task)
+    }
+
+    fn delete(&mut self, id: &str) -> Result<(), BackendError> {
+        self.store.remove(Path::new(id)).map_err(permanent)?;
+        Ok(())
+    }
+}
+
+/// The task directory as the [`Syncer`] sees it, changing tasks through [`actions`]
+///
+/// A failed action is kept to be returned instead of the [`dooit_tasks::Error`] the syncer gets,
+/// which would lose why it failed (e.g. [`crate::failure::Failure::ReadOnly`]).
+struct Local<'a, S> {
+    config: &'a Config,
+    store: &'a S,
+    failed: RefCell<Option<color_eyre::Report>>,
+}
+
+impl<'a, S: TaskStore> Local<'a, S> {
+    fn new(config: &'a Config, store: &'a S) -> Self {
+        Self {
+            config,
+            store,
+            failed: RefCell::new(None),
+        }
+    }
+
+    fn act<T>(&self, result: color_eyre::Result<T>) -> dooit_tasks::Result<T> {
+        result.map_err(|err| {
+            let message = format!("{err:#}");
+            self.failed.replace(Some(err));
+            std::io::Error::other(message).into()
+        })
+    }
+}
+
+impl<S: TaskStore> TaskStore for Local<'_, S> {
+    fn load_all(&self) -> dooit_tasks::Result<Vec<Task>> {
+        self.store.load_all()
+    }
+
+    fn load(&self, name: &Path) -> dooit_tasks::Result<Option<Task>> {
+        self.store.load(name)
+    }
+
+    fn modified(&self, name: &Path) -> dooit_tasks::Result<Option<DateTime<Utc>>> {
+        self.store.modified(name)
+    }
+
+    fn save(&self, task: &Task) -> dooit_tasks::Result<()> {
+        self.act(actions::update(self.config, self.store, task.clone()).map(drop))
+    }
+
+    /// The pulled tasks are saved together, see [`actions::update_all`]
+    fn save_all(&self, tasks: &[Task]) -> dooit_tasks::Result<()> {
+        self.act(actions::update_all(self.config, self.store, tasks.to_vec()))
+    }
+
+    fn remove(&self, name: &Path) -> dooit_tasks::Result<bool> {
+        if self.store.load(name)?.is_none() {
+            return Ok(false);
+        }
+        self.act(actions::remove(self.config, self.store, name).map(|_| true))
+    }
+
+    fn load_under(&self, parent: &Path) -> dooit_tasks::Result<Vec<Task>> {
+        self.store.load_under(parent)
+    }
+
+    fn load_summaries_under(&self, parent: &Path) -> dooit_tasks::Result<Vec<Task>> {
+        self.store.load_summaries_under(parent)
+    }
+}
+
+/// Where the state of syncing `dir` into `prefix` is kept
+fn state_path(dir: &Path, prefix: &Path) -> color_eyre::Result<PathBuf> {
+    let key = format!("{}#{}", dir.display(), prefix.display());
+    let name = format!("dir-{}.json", utf8_percent_encode(&key, NON_ALPHANUMERIC));
+    dirs::get_state_dir()
+        .map(|state| state.join("sync").join(name))
+        .ok_or_else(|| eyre!("state dir not available"))
+}
+
+/// The lines of `local` and `remote` next to each other under their headers
+fn side_by_side(local: &str, remote: &str) -> Vec<String> {
+    let left: Vec<_> = [tr!("sync-local").as_str()]
+        .into_iter()
+        .chain(local.lines())
+        .map(str::to_string)
+        .collect();
+    let right: Vec<_> = [tr!("sync-remote").as_str()]
+        .into_iter()
+        .chain(remote.lines())
+        .map(str::to_string)
+        .collect();
+    let width = left.iter().map(|line| line.width()).max().unwrap_or(0);
+
+    (0..left.len().max(right.len()))
+        .map(|row| {
+            let left = left.get(row).map_or("", String::as_str);
+            let right = right.get(row).map_or("", String::as_str);
+            let padding = " ".repeat(width - left.width());
+            format!("{left}{padding} │ {right}").trim_end().to_string()
+        })
+        .collect()
+}
+
+/// Show `conflict` side by side and ask which value to keep, the side it comes with by default
+fn choose(editor: &mut Prompt, conflict: &SyncConflict) -> color_eyre::Result<Side> {
+    let (local, remote) = conflict.change.sides();
+    println!("\n{}: {}", conflict.name.display(), conflict.change.field());
+    for line in side_by_side(&local, &remote) {
+        println!("  {line}");
+    }
+
+    let default = match conflict.kept {
+        Side::Local => "ours",
+        Side::Remote => "theirs",
+    };
+    let choices = ["ours", "theirs"].map(String::from);
+    loop {
+        let question = tr!("sync-keep", default = default);
+        let answer = prompt::ask(editor, &format!("{question} "), choices.clone())?;
+        match answer.to_lowercase().as_str() {
+            "" => return Ok(conflict.kept),
+            "o" | "ours" => return Ok(Side::Local),
+            "t" | "theirs" => return Ok(Side::Remote),
+            _ => eprintln!("{}", tr!("unknown-answer", answer = format!("{answer:?}"))),
+        }
+    }
+}
+
+/// Sync `store` with the task directory `dir`, keeping its tasks under `prefix`
+///
+/// Conflicts are resolved by `policy`, or asked about on a terminal without one. If reading the
+/// answer fails (e.g. Ctrl-D) the remaining conflicts keep the local values.
+pub fn run(
+    config: &Config,
+    store: &DirStore,
+    dir: &Path,
+    prefix: &Path,
+    policy: Option<ConflictPolicy>,
+    dry_run: bool,
+) -> color_eyre::Result<()> {
+    let dir = dir
+        .canonicalize()
+        .wrap_err_with(|| format!("open the task directory {}", dir.display()))?;
+    if store.root().canonicalize().ok().as_deref() == Some(dir.as_path()) {
+        bail!("{} is the task directory itself", dir.display());
+    }
+    let other = DirStore::new(&dir);
+    let backend = DirBackend {
+        store: DryRun::new(&other, dry_run),
+    };
+
+    // A dry run starts from a copy of the state and throws it away after
+    let state = state_path(&dir, prefix)?;
+    let copy = match dry_run {
+        false => None,
+        true => Some(tempfile::tempdir().wrap_err("create a directory for the sync state")?),
+    };
+    let state = match &copy {
+        None => state,
+        Some(copy) => {
+            let path = copy.path().join("state.json");
+            match std::fs::copy(&state, &path) {
+                Ok(_) => {}
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                Err(err) => return Err(err).wrap_err_with(|| format!("read {}", state.display())),
+            }
+            path
+        }
+    };
+
+    let mut editor = match policy {
+        None if std::io::stdin().is_terminal() => {
+            Some(Prompt::new().wrap_err("open the terminal")?)
+        }
+        _ => None,
+    };
+    let mut failed = None;
+    let mut syncer = Syncer::new(backend, prefix, &state).with_policy(policy.unwrap_or_default());
+    let dry_store = DryRun::new(store, dry_run);
+    let local = Local::new(config, &dry_store);
+    let report = syncer.sync_with(&local, |conflict| {
+        let Some(editor) = editor.as_mut().filter(|_| failed.is_none()) else {
+            return conflict.kept;
+        };
+        choose(editor, conflict).unwrap_or_else(|err| {
+            failed = Some(err);
+            conflict.kept
+        })
+    });
+    let report = match (report, local.failed.take()) {
+        (Ok(report), _) => report,
+        (Err(_), Some(failed)) => {
+            return Err(failed.wrap_err(format!("sync with {}", dir.display())))
+        }
+        (Err(err), None) => {
+            return Err(err).wrap_err_with(|| format!("sync with {}", dir.display()))
+        }
+    };
+    if let Some(err) = failed {
+        tracing::warn!("kept the local values of the remaining conflicts: {err:#}");
+    }
+
+    if editor.is_none() {
+        for conflict in &report.conflicts {
+            let kept = match conflict.kept {
+                Side::Local => tr!("sync-local"),
+                Side::Remote => tr!("sync-remote"),
+            };
+            println!(
+                "{}",
+                tr!(
+                    "sync-conflict",
+                    name = conflict.name.display(),
+                    change = conflict.change,
+                    kept = kept
+                )
+            );
+        }
+    }
+    println!(
+        "{}",
+        tr!(
+            "sync-done",
+            pulled = report.pulled,
+            pushed = report.pushed,
+            conflicts = report.conflicts.len()
+        )
+    );
+    Ok(())
+}
+
+/// The conflict policy of `--ours`, `--theirs` or `--newest`, if one was given
+pub fn policy(ours: bool, theirs: bool, newest: bool) -> Option<ConflictPolicy> {
+    match (ours, theirs, newest) {
+        (true, _, _) => Some(ConflictPolicy::Local),
+        (_, true, _) => Some(ConflictPolicy::Remote),
+        (_, _, true) => Some(ConflictPolicy::Newest),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use dooit_tasks::{
+        sync::{Side, Syncer},
+        DirStore, Task, TaskStore, Urgency,
+    };
+    use pretty_assertions::assert_eq;
+
+    use super::{side_by_side, DirBackend, Local};
+    use crate::{
+        config::{AuditConfig, Config, JournalConfig},
+        dry_run::DryRun,
+        failure::Failure,
+    };
+
+    #[test]
+    fn test_sync_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let (local, remote) = (
+            DirStore::new(root.join("local")),
+            DirStore::new(root.join("remote")),
+        );
+        let state = root.join("state.json");
+        let config = Config {
+            audit: AuditConfig { enabled: false },
+            journal: JournalConfig { enabled: false },
+            ..Config::default()
+        };
+        let sync = |config: &Config, resolve: Side| {
+            let backend = DirBackend {
+                store: DryRun::new(&remote, false),
+            };
+            let local = Local::new(config, &local);
+            let report = Syncer::new(backend, "shared", &state).sync_with(&local, |_| resolve);
+            report.map_err(|err| local.failed.take().unwrap_or_else(|| err.into()))
+        };
+
+        remote.save(&Task::new("groceries")).unwrap();
+        local.save(&Task::new("shared/report")).unwrap();
+        sync(&config, Side::Local).unwrap();
+        assert_eq!(
+            local.load(Path::new("shared/groceries")).unwrap(),
+            Some(Task::new("shared/groceries"))
+        );
+        assert_eq!(
+            remote.load(Path::new("report")).unwrap(),
+            Some(Task::new("report"))
+        );
+
+        // Both sides change the urgency, the remote value is picked
+        local
+            .save(&Task::new("shared/report").with_urgency(Urgency::Medium))
+            .unwrap();
+        remote
+            .save(&Task::new("report").with_urgency(Urgency::High))
+            .unwrap();
+        remote.remove(Path::new("groceries")).unwrap();
+        let report = sync(&config, Side::Remote).unwrap();
+        assert_eq!(report.conflicts.len(), 1);
+        let expected = Task::new("shared/report").with_urgency(Urgency::High);
+        assert_eq!(local.load_all().unwrap(), std::slice::from_ref(&expected));
+
+        // Pulled changes are checked like any other
+        remote
+            .save(&Task::new("taxes").with_tags(["work"]))
+            .unwrap();
+        remote.remove(Path::new("report")).unwrap();
+        let read_only = Config {
+            read_only: vec!["shared".into()],
+            ..config.clone()
+        };
+        let err = sync(&read_only, Side::Remote).unwrap_err();
+        assert_eq!(Failure::of(&err), Some(Failure::ReadOnly));
+        assert_eq!(local.load_all().unwrap(), [expected]);
+        let limited = Config {
+            limits: toml::from_str("tags = { work = 0 }\nstrict = true").unwrap(),
+            ..config.clone()
+        };
+        let err = sync(&limited, Side::Remote).unwrap_err();
+        assert_eq!(Failure::of(&err), Some(Failure::OverLimit));
+
+        sync(&config, Side::Remote).unwrap();
+        let taxes = Task::new("shared/taxes").with_tags(["work"]);
+        assert_eq!(local.load_all().unwrap(), [taxes]);
+    }
+
+    #[test]
+    fn test_side_by_side() {
+        assert_eq!(
+            side_by_side("buy milk\nand eggs", "buy oat milk"),
+            ["local    │ remote", "buy milk │ buy oat milk", "and eggs │"]
+        );
+    }
+}
@@ -0,0 +1,222 @@
+//! Tasks created from template files in the config dir's `templates` directory
+//!
+//! A template looks like a task whose strings can contain `{{variable}}` placeholders, and whose
+//! due date is relative to when it's instantiated:
+//!
+//! ```toml
+//! # templates/release.toml
+//! name = "release/{{version}}"
+//! description = "Tag and publish {{version}}"
+//! due = "in 3d"
+//! urgency = "High"
+//! tags = ["release"]
+//! ```
+//!
+//! Instantiate it with `dooit-rs add --template release version=1.4`.
+//!
+//! Templates can also describe whole task trees with `subtasks`, which are named relative to their
+//! parent. Names in `depends_on` are relative to the task's parent too, so siblings refer to each
+//! other by their short name. Such blueprints can live anywhere and be written in YAML as well,
+//! create the tasks with `dooit-rs scaffold release.yaml version=1.4`.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::{bail, eyre, WrapErr};
+use dooit_tasks::{dirs, tasks::parse_date, Status, Task, Urgency};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+struct Template {
+    name: String,
+    description: Option<String>,
+    /// Anything `add --due` accepts, e.g. `in 2d` or `friday 17:00`
+    due: Option<String>,
+    #[serde(default)]
+    urgency: Urgency,
+    #[serde(default)]
+    status: Status,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    links: Vec<String>,
+    #[serde(default)]
+    depends_on: Vec<String>,
+    #[serde(default)]
+    subtasks: Vec<Template>,
+}
+
+fn template_path(name: &str) -> color_eyre::Result<PathBuf> {
+    dirs::get_config_dir()
+        .map(|dir| dir.join("templates").join(name).with_extension("toml"))
+        .ok_or_else(|| eyre!("config dir not available"))
+}
+
+/// Replace the `{{variable}}` placeholders in `text` with their value in `vars`
+fn substitute(text: &str, vars: &Vars) -> color_eyre::Result<String> {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find("{{") {
+        let end = rest[start..]
+            .find("}}")
+            .ok_or_else(|| eyre!("unclosed placeholder in {text:?}"))?;
+        let var = rest[start + 2..start + end].trim();
+        let value = vars
+            .get(var)
+            .ok_or_else(|| eyre!("no value given for {{{{{var}}}}}, pass it as {var}=..."))?;
+
+        result.push_str(&rest[..start]);
+        result.push_str(value);
+        rest = &rest[start + end + 2..];
+    }
+
+    result.push_str(rest);
+    Ok(result)
+}
+
+type Vars<'a> = BTreeMap<&'a str, &'a str>;
+
+fn parse_vars(vars: &[String]) -> color_eyre::Result<Vars<'_>> {
+    vars.iter()
+        .map(|var| {
+            var.split_once('=')
+                .ok_or_else(|| eyre!("expected variable=value, got {var:?}"))
+        })
+        .collect()
+}
+
+/// Read a template, YAML if the extension says so and TOML otherwise
+fn load(path: &Path) -> color_eyre::Result<Template> {
+    let contents = std::fs::read(path).wrap_err_with(|| format!("read {}", path.display()))?;
+
+    let parse_error = || format!("parse template {}", path.display());
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("yaml" | "yml") => serde_yaml::from_slice(&contents).wrap_err_with(parse_error),
+        _ => toml::from_slice(&contents).wrap_err_with(parse_error),
+    }
+}
+
+/// Append the tasks described by `template` to `tasks`, subtasks after their parent
+fn build(
+    template: &Template,
+    parent: Option<&Path>,
+    vars: &Vars,
+    tasks: &mut Vec<Task>,
+) -> color_eyre::Result<()> {
+    let substitute = |text: &str| substitute(text, vars);
+    let relative = |name: &str| -> color_eyre::Result<PathBuf> {
+        let name = substitute(name)?;
+        Ok(parent.map_or_else(|| PathBuf::from(&name), |parent| parent.join(&name)))
+    };
+    let substitute_all = |texts: &[String]| -> color_eyre::Result<Vec<String>> {
+        texts.iter().map(|text| substitute(text)).collect()
+    };
+
+    let name = relative(&template.name)?;
+    let mut task = Task::new(&name)
+        .with_urgency(template.urgency.clone())
+        .with_status(template.status)
+        .with_tags(substitute_all(&template.tags)?)
+        .with_links(substitute_all(&template.links)?)
+        .with_dependencies(
+            template
+                .depends_on
+                .iter()
+                .map(|name| relative(name))
+                .collect::<color_eyre::Result<Vec<_>>>()?,
+        );
+    if let Some(description) = &template.description {
+        task = task.with_description(substitute(description)?);
+    }
+    if let Some(due) = &template.due {
+        let due = substitute(due)?;
+        let due = parse_date(&due).wrap_err_with(|| format!("parse due date {due:?}"))?;
+        task = task.with_due_date(due);
+    }
+    tasks.push(task);
+
+    for subtask in &template.subtasks {
+        build(subtask, Some(&name), vars, tasks)?;
+    }
+    Ok(())
+}
+
+/// Create the tasks described by the template called `name`, `vars` are `variable=value` pairs
+pub fn instantiate(name: &str, vars: &[String]) -> color_eyre::Result<Vec<Task>> {
+    let path = template_path(name)?;
+    if !path.exists() {
+        bail!("no template named {name} (looked for {})", path.display());
+    }
+
+    scaffold(&path, vars)
+}
+
+/// Create the tasks described by the template at `path`, see [`instantiate`]
+pub fn scaffold(path: &Path, vars: &[String]) -> color_eyre::Result<Vec<Task>> {
+    let template = load(path)?;
+    let mut tasks = Vec::new();
+    build(&template, None, &parse_vars(vars)?, &mut tasks)?;
+    Ok(tasks)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::BTreeMap, path::PathBuf};
+
+    use pretty_assertions::assert_eq;
+
+    use super::{build, substitute, Template};
+
+    #[test]
+    fn test_substitute() {
+        let vars = BTreeMap::from([("version", "1.4"), ("who", "me")]);
+
+        assert_eq!(
+            substitute("release {{version}} by {{ who }}", &vars).unwrap(),
+            "release 1.4 by me"
+        );
+        assert_eq!(
+            substitute("no placeholders", &vars).unwrap(),
+            "no placeholders"
+        );
+        assert!(substitute("{{missing}}", &vars).is_err());
+        assert!(substitute("{{version", &vars).is_err());
+    }
+
+    #[test]
+    fn test_build_tree() {
+        let template: Template = serde_yaml::from_str(
+            "
+name: release/{{version}}
+subtasks:
+  - name: changelog
+  - name: publish
+    depends_on: [changelog]
+",
+        )
+        .unwrap();
+        let vars = BTreeMap::from([("version", "1.4")]);
+
+        let mut tasks = Vec::new();
+        build(&template, None, &vars, &mut tasks).unwrap();
+        let names: Vec<_> = tasks
+            .iter()
+            .map(|task| task.name.to_str().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "release/1.4",
+                "release/1.4/changelog",
+                "release/1.4/publish"
+            ]
+        );
+        assert_eq!(
+            tasks[2].depends_on,
+            [PathBuf::from("release/1.4/changelog")]
+        );
+    }
+}
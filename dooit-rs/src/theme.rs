@@ -0,0 +1,223 @@
+//! Named color themes, mapping what something is (overdue, a header, ...) to how it looks
+//!
+//! Styles are written like `bold red on blue`: any of `bold`, `dim`, `italic`, `underline`,
+//! `crossed-out` and `reversed`, a [`Color`] for the text and `on` followed by the background
+//! color. `none` is the terminal's default style.
+//!
+//! The built-in themes are `default` and `monochrome`. Themes in the config start from `default`,
+//! so they only need the roles they change.
+
+use std::{collections::BTreeMap, fmt::Display};
+
+use color_eyre::eyre::{bail, eyre};
+use dooit_tasks::Urgency;
+use serde::{Deserialize, Serialize};
+
+use crate::color::Color;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Style {
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+    pub bold: bool,
+    pub dim: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub crossed_out: bool,
+    pub reversed: bool,
+}
+
+impl Style {
+    pub fn fg(color: Color) -> Self {
+        Self {
+            fg: Some(color),
+            ..Self::default()
+        }
+    }
+
+    /// The modifiers that are set, with their names and SGR codes
+    fn modifiers(&self) -> impl Iterator<Item = (&'static str, u8)> {
+        [
+            (self.bold, "bold", 1),
+            (self.dim, "dim", 2),
+            (self.italic, "italic", 3),
+            (self.underline, "underline", 4),
+            (self.reversed, "reversed", 7),
+            (self.crossed_out, "crossed-out", 9),
+        ]
+        .into_iter()
+        .filter(|(set, ..)| *set)
+        .map(|(_, name, code)| (name, code))
+    }
+
+    /// `text` wrapped in the escape sequences for this style
+    pub fn paint(&self, text: &str) -> String {
+        let codes: Vec<_> = self
+            .modifiers()
+            .map(|(_, code)| code)
+            .chain(self.fg.map(Color::fg_code))
+            .chain(self.bg.map(|color| color.fg_code() + 10))
+            .map(|code| code.to_string())
+            .collect();
+        if codes.is_empty() {
+            return text.to_string();
+        }
+        format!("\x1b[{}m{text}\x1b[0m", codes.join(";"))
+    }
+}
+
+impl TryFrom<String> for Style {
+    type Error = color_eyre::Report;
+
+    fn try_from(style: String) -> Result<Self, Self::Error> {
+        let mut parsed = Style::default();
+        let mut words = style.split_whitespace();
+        while let Some(word) = words.next() {
+            let color = |word: &str| {
+                word.parse::<Color>()
+                    .map_err(|_| eyre!("unknown color or modifier {word:?} in style {style:?}"))
+            };
+            match word {
+                "none" => {}
+                "bold" => parsed.bold = true,
+                "dim" => parsed.dim = true,
+                "italic" => parsed.italic = true,
+                "underline" => parsed.underline = true,
+                "crossed-out" => parsed.crossed_out = true,
+                "reversed" => parsed.reversed = true,
+                "on" => match words.next() {
+                    Some(word) => parsed.bg = Some(color(word)?),
+                    None => bail!("expected a color after `on` in style {style:?}"),
+                },
+                word => parsed.fg = Some(color(word)?),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+impl Display for Style {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut words: Vec<_> = self.modifiers().map(|(name, _)| name.to_string()).collect();
+        words.extend(self.fg.map(|color| color.to_string()));
+        words.extend(self.bg.map(|color| format!("on {color}")));
+        if words.is_empty() {
+            return f.write_str("none");
+        }
+        f.write_str(&words.join(" "))
+    }
+}
+
+impl From<Style> for String {
+    fn from(style: Style) -> Self {
+        style.to_string()
+    }
+}
+
+/// How each role is shown, see the [module docs](self)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Theme {
+    /// Tasks past their due date
+    pub overdue: Style,
+    /// Tasks due within a day
+    pub due_soon: Style,
+    /// Completed tasks
+    pub completed: Style,
+    /// Titles of sections and of the task being shown
+    pub header: Style,
+    /// The task under the cursor in the TUI
+    pub selected: Style,
+    /// Tasks with these urgencies, by name (the urgency's `color` otherwise)
+    pub urgencies: BTreeMap<String, Style>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            overdue: Style::fg(Color::Red),
+            due_soon: Style::fg(Color::Yellow),
+            completed: Style {
+                dim: true,
+                crossed_out: true,
+                ..Style::default()
+            },
+            header: Style {
+                bold: true,
+                ..Style::default()
+            },
+            selected: Style {
+                reversed: true,
+                ..Style::default()
+            },
+            urgencies: BTreeMap::new(),
+        }
+    }
+}
+
+impl Theme {
+    /// Only modifiers, for terminals without colors
+    pub fn monochrome() -> Self {
+        Self {
+            overdue: Style {
+                bold: true,
+                ..Style::default()
+            },
+            due_soon: Style {
+                underline: true,
+                ..Style::default()
+            },
+            ..Self::default()
+        }
+    }
+
+    /// The built-in theme called `name`
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(Self::default()),
+            "monochrome" => Some(Self::monochrome()),
+            _ => None,
+        }
+    }
+
+    /// The style of tasks with `urgency`, if the theme has one
+    pub fn urgency(&self, urgency: &Urgency) -> Option<Style> {
+        self.urgencies
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(urgency.name()))
+            .map(|(_, style)| *style)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::Style;
+    use crate::color::Color;
+
+    #[test]
+    fn test_parse_style() {
+        let style = Style::try_from("bold bright-red on blue".to_string()).unwrap();
+        assert_eq!(
+            style,
+            Style {
+                fg: Some(Color::BrightRed),
+                bg: Some(Color::Blue),
+                bold: true,
+                ..Style::default()
+            }
+        );
+        assert_eq!(style.to_string(), "bold bright-red on blue");
+        assert_eq!(style.paint("x"), "\x1b[1;91;44mx\x1b[0m");
+
+        assert_eq!(
+            Style::try_from("none".to_string()).unwrap(),
+            Style::default()
+        );
+        assert_eq!(Style::default().paint("x"), "x");
+        assert!(Style::try_from("blinking".to_string()).is_err());
+        assert!(Style::try_from("red on".to_string()).is_err());
+    }
+}
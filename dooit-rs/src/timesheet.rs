@@ -0,0 +1,208 @@
+//! `report time`: the time logged on tasks, summed per project, tag, task or day
+//!
+//! Only the part of each time log entry inside `--since` counts. The active context applies like
+//! it does to other reports.
+
+use std::{
+    collections::BTreeMap,
+    path::{Component, Path},
+};
+
+use chrono::{DateTime, Duration, Local, Utc};
+use clap::ValueEnum;
+use color_eyre::eyre::WrapErr;
+use dooit_tasks::{duration::format_duration, Task, TaskStore, TimeEntry};
+
+use crate::{color, config::Config, context::Context, i18n::tr, report};
+
+/// What the time is summed by
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TimeGroup {
+    /// The top-level task, e.g. `website` for `website/blog/draft`
+    #[default]
+    Project,
+    /// Each tag of the task (time on tasks with several tags counts for each)
+    Tag,
+    Task,
+    /// The day the time was logged
+    Day,
+}
+
+impl TimeGroup {
+    /// Name of the first column, as in CSV output
+    fn name(self) -> &'static str {
+        match self {
+            TimeGroup::Project => "project",
+            TimeGroup::Tag => "tag",
+            TimeGroup::Task => "task",
+            TimeGroup::Day => "day",
+        }
+    }
+
+    fn title(self) -> String {
+        match self {
+            TimeGroup::Project => tr!("column-project"),
+            TimeGroup::Tag => tr!("column-tags"),
+            TimeGroup::Task => tr!("column-name"),
+            TimeGroup::Day => tr!("column-day"),
+        }
+    }
+
+    /// The groups the time logged on `task` in `entry` counts for
+    fn keys(self, task: &Task, entry: &TimeEntry) -> Vec<String> {
+        match self {
+            TimeGroup::Project => project(task).into_iter().collect(),
+            TimeGroup::Tag if task.tags.is_empty() => vec![tr!("report-no-group")],
+            TimeGroup::Tag => task.tags.clone(),
+            TimeGroup::Task => vec![task.name.display().to_string()],
+            TimeGroup::Day => vec![entry.start.with_timezone(&Local).format("%F").to_string()],
+        }
+    }
+}
+
+/// The top-level task `task` is under, or its own name if it is one
+pub fn project(task: &Task) -> Option<String> {
+    task.name
+        .components()
+        .find_map(|component| match component {
+            Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+            _ => None,
+        })
+}
+
+/// Time logged per group since `since` (all of it if `None`), and the total
+fn sum(
+    tasks: &[Task],
+    by: TimeGroup,
+    since: Option<DateTime<Utc>>,
+) -> (BTreeMap<String, Duration>, Duration) {
+    let mut groups = BTreeMap::new();
+    let mut total = Duration::zero();
+    for task in tasks {
+        for entry in &task.time_log {
+            let start = since.map_or(entry.start, |since| entry.start.max(since));
+            if entry.end <= start {
+                continue;
+            }
+            let spent = entry.end - start;
+            total = total + spent;
+            for key in by.keys(task, entry) {
+                let group = groups.entry(key).or_insert_with(Duration::zero);
+                *group = *group + spent;
+            }
+        }
+    }
+    (groups, total)
+}
+
+fn hours(duration: Duration) -> String {
+    format!("{:.2}", duration.num_seconds() as f64 / 3600.0)
+}
+
+/// `field` quoted for CSV if it has to be
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Print the time logged in the last `since` (all of it if `None`) grouped `by`, as CSV if `csv`
+pub fn run(
+    config: &Config,
+    store: &impl TaskStore,
+    by: TimeGroup,
+    since: Option<Duration>,
+    csv: bool,
+) -> color_eyre::Result<()> {
+    let context = Context::active(config)?;
+    let now = Utc::now();
+    let mut tasks = store
+        .load_under(context.as_ref().map_or(Path::new(""), Context::prefix))
+        .wrap_err("load tasks")?;
+    if let Some(context) = &context {
+        tasks.retain(|task| context.matches(task, now));
+    }
+    let (groups, total) = sum(&tasks, by, since.map(|since| now - since));
+
+    if csv {
+        println!("{},hours", by.name());
+        for (group, spent) in &groups {
+            println!("{},{}", csv_field(group), hours(*spent));
+        }
+        return Ok(());
+    }
+
+    if let Some(context) = &context {
+        println!("{}", context.header(config));
+    }
+    if groups.is_empty() {
+        println!("{}", tr!("no-time-logged"));
+        return Ok(());
+    }
+
+    let row = |group: String, spent: Duration| vec![group, hours(spent), format_duration(spent)];
+    let rows: Vec<_> = std::iter::once(vec![by.title(), tr!("column-hours"), tr!("column-spent")])
+        .chain(groups.into_iter().map(|(group, spent)| row(group, spent)))
+        .chain(std::iter::once(row(tr!("report-total"), total)))
+        .collect();
+    let lines = report::table(&rows);
+    let last = lines.len() - 1;
+    for (index, line) in lines.into_iter().enumerate() {
+        if index == 0 || index == last {
+            println!("{}", color::header(&line, config));
+        } else {
+            println!("{line}");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{Duration, TimeZone, Utc};
+    use dooit_tasks::{Task, TimeEntry};
+    use pretty_assertions::assert_eq;
+
+    use super::{sum, TimeGroup};
+
+    #[test]
+    fn test_sum() {
+        let start = Utc.with_ymd_and_hms(2024, 5, 1, 9, 0, 0).unwrap();
+        let log = |task: Task, hours: &[(i64, i64)]| Task {
+            time_log: hours
+                .iter()
+                .map(|&(from, to)| TimeEntry {
+                    start: start + Duration::hours(from),
+                    end: start + Duration::hours(to),
+                })
+                .collect(),
+            ..task
+        };
+        let tasks = [
+            log(
+                Task::new("web/blog").with_tags(["writing"]),
+                &[(0, 2), (24, 25)],
+            ),
+            log(Task::new("web").with_tags(["writing", "client"]), &[(3, 4)]),
+            log(Task::new("taxes"), &[(-2, 1)]),
+        ];
+
+        let (groups, total) = sum(&tasks, TimeGroup::Project, Some(start));
+        let groups: Vec<_> = groups.into_iter().collect();
+        assert_eq!(
+            groups,
+            [
+                ("taxes".to_string(), Duration::hours(1)),
+                ("web".to_string(), Duration::hours(4)),
+            ]
+        );
+        assert_eq!(total, Duration::hours(5));
+
+        let (groups, total) = sum(&tasks, TimeGroup::Tag, None);
+        assert_eq!(groups["writing"], Duration::hours(4));
+        assert_eq!(groups["client"], Duration::hours(1));
+        assert_eq!(total, Duration::hours(7));
+    }
+}
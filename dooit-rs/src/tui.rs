@@ -0,0 +1,635 @@
+//! Interactive terminal interface
+//!
+//! | Key         | Action                                |
+//! |-------------|---------------------------------------|
+//! | `j`/`k`     | Move the selection down/up            |
+//! | `c`/`space` | Toggle the selected task's completion |
+//! | `e`         | Edit the selected task in the editor  |
+//! | `s`         | Snooze the selected task by a day     |
+//! | `a`         | Add a task                            |
+//! | `/`         | Filter tasks by name and description  |
+//! | `o`         | Switch to the next sort mode          |
+//! | `h`         | Show/hide completed tasks             |
+//! | `r`         | Reload tasks from disk                |
+//! | `b`         | Switch between the list and the board |
+//! | `q`/`esc`   | Quit                                  |
+//!
+//! The board shows one column per [`Status`](dooit_tasks::Status) plus a column for completed
+//! tasks, `h`/`l` move between columns and `H`/`L` move the selected card to the neighbouring
+//! column.
+//!
+//! Changes made outside the interface (e.g. with an editor or by syncing) show up on their own.
+
+use std::{path::PathBuf, time::Duration as StdDuration};
+
+use chrono::Duration;
+use clap::ValueEnum;
+use color_eyre::eyre::WrapErr;
+use dooit_tasks::{tasks::sort_tasks_with, DirStore, SortMode, Task, TaskStore};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, List, ListItem, ListState, Paragraph, Wrap},
+    DefaultTerminal, Frame,
+};
+
+use crate::{
+    actions, color,
+    config::Config,
+    context::Context,
+    edit,
+    editor::Editor,
+    i18n::tr,
+    theme,
+    watch::{self, Watcher},
+};
+
+/// How long to wait for input before looking for changes on disk
+const WATCH_INTERVAL: StdDuration = StdDuration::from_millis(250);
+
+mod board;
+pub mod focus;
+
+impl From<color::Color> for Color {
+    fn from(color: color::Color) -> Self {
+        match color {
+            color::Color::Black => Color::Black,
+            color::Color::Red => Color::Red,
+            color::Color::Green => Color::Green,
+            color::Color::Yellow => Color::Yellow,
+            color::Color::Blue => Color::Blue,
+            color::Color::Magenta => Color::Magenta,
+            color::Color::Cyan => Color::Cyan,
+            color::Color::White => Color::Gray,
+            color::Color::BrightBlack => Color::DarkGray,
+            color::Color::BrightRed => Color::LightRed,
+            color::Color::BrightGreen => Color::LightGreen,
+            color::Color::BrightYellow => Color::LightYellow,
+            color::Color::BrightBlue => Color::LightBlue,
+            color::Color::BrightMagenta => Color::LightMagenta,
+            color::Color::BrightCyan => Color::LightCyan,
+            color::Color::BrightWhite => Color::White,
+        }
+    }
+}
+
+impl From<theme::Style> for Style {
+    fn from(style: theme::Style) -> Self {
+        let modifiers = [
+            (style.bold, Modifier::BOLD),
+            (style.dim, Modifier::DIM),
+            (style.italic, Modifier::ITALIC),
+            (style.underline, Modifier::UNDERLINED),
+            (style.crossed_out, Modifier::CROSSED_OUT),
+            (style.reversed, Modifier::REVERSED),
+        ]
+        .into_iter()
+        .filter(|(set, _)| *set)
+        .fold(Modifier::empty(), |modifiers, (_, modifier)| {
+            modifiers | modifier
+        });
+
+        let mut converted = Style::new().add_modifier(modifiers);
+        if let Some(fg) = style.fg {
+            converted = converted.fg(fg.into());
+        }
+        if let Some(bg) = style.bg {
+            converted = converted.bg(bg.into());
+        }
+        converted
+    }
+}
+
+#[derive(PartialEq, Eq)]
+enum View {
+    List,
+    Board,
+}
+
+enum Input {
+    Normal,
+    Filter,
+    Add(String),
+}
+
+struct App {
+    config: Config,
+    store: DirStore,
+    watcher: Watcher,
+    editor: Editor,
+    /// Every task in the store, sorted by `sort`
+    tasks: Vec<Task>,
+    /// The tasks shown in the list, after filtering
+    visible: Vec<Task>,
+    state: ListState,
+    view: View,
+    board: board::Board,
+    sort: SortMode,
+    /// The active context, tasks outside of it are never shown
+    context: Option<Context>,
+    filter: String,
+    show_completed: bool,
+    input: Input,
+    status: String,
+}
+
+pub fn run(config: Config, store: DirStore, editor: Editor) -> color_eyre::Result<()> {
+    std::fs::create_dir_all(store.root())
+        .wrap_err_with(|| format!("create {}", store.root().display()))?;
+
+    let mut app = App::new(config, store, editor)?;
+    let mut terminal = ratatui::init();
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+
+    result
+}
+
+impl App {
+    /// The list of the tasks in `store`
+    fn new(config: Config, store: DirStore, editor: Editor) -> color_eyre::Result<Self> {
+        let mut app = App {
+            context: Context::active(&config)?,
+            config,
+            watcher: Watcher::new(store.root())?,
+            store,
+            editor,
+            tasks: Vec::new(),
+            visible: Vec::new(),
+            state: ListState::default(),
+            view: View::List,
+            board: board::Board::default(),
+            sort: SortMode::default(),
+            filter: String::new(),
+            show_completed: false,
+            input: Input::Normal,
+            status: String::new(),
+        };
+        app.reload()?;
+        Ok(app)
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> color_eyre::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+
+            if !event::poll(WATCH_INTERVAL)? {
+                let changed = self.watcher.wait(Some(StdDuration::ZERO))?;
+                if !changed.is_empty() {
+                    if let Err(err) = self.apply_changes(&changed) {
+                        self.status = format!("error: {err:#}");
+                    }
+                }
+                continue;
+            }
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            let result = match self.input {
+                Input::Normal => match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                    KeyCode::Char('e') => self.edit_selected(terminal),
+                    code if self.view == View::Board => self.handle_board(code),
+                    code => self.handle_normal(code),
+                },
+                Input::Filter => {
+                    self.handle_filter(key);
+                    Ok(())
+                }
+                Input::Add(_) => self.handle_add(key),
+            };
+
+            if let Err(err) = result {
+                self.status = format!("error: {err:#}");
+            }
+        }
+    }
+
+    fn selected(&self) -> Option<&Task> {
+        match self.view {
+            View::List => self
+                .state
+                .selected()
+                .and_then(|index| self.visible.get(index)),
+            View::Board => self.board_selected(),
+        }
+    }
+
+    fn matches_filter(&self, task: &Task) -> bool {
+        let filter = self.filter.to_lowercase();
+        let now = chrono::Utc::now();
+        if !self
+            .context
+            .as_ref()
+            .is_none_or(|context| context.matches(task, now))
+        {
+            return false;
+        }
+
+        filter.is_empty()
+            || task.name.to_string_lossy().to_lowercase().contains(&filter)
+            || task
+                .description
+                .as_ref()
+                .is_some_and(|desc| desc.to_lowercase().contains(&filter))
+    }
+
+    fn reload(&mut self) -> color_eyre::Result<()> {
+        let tasks = self.store.load_all().wrap_err("load tasks")?;
+        self.tasks = sort_tasks_with(tasks, self.sort, &self.config.score);
+        self.refilter();
+        Ok(())
+    }
+
+    fn apply_changes(&mut self, changed: &[PathBuf]) -> color_eyre::Result<()> {
+        let result = watch::apply_changes(&self.store, &mut self.tasks, changed);
+        self.tasks = sort_tasks_with(
+            std::mem::take(&mut self.tasks),
+            self.sort,
+            &self.config.score,
+        );
+        self.refilter();
+        result
+    }
+
+    fn refilter(&mut self) {
+        let selected = self
+            .state
+            .selected()
+            .and_then(|index| self.visible.get(index))
+            .map(|task| task.name.clone());
+
+        self.visible = self
+            .tasks
+            .iter()
+            .filter(|task| self.show_completed || !task.completed)
+            .filter(|task| self.matches_filter(task))
+            .cloned()
+            .collect();
+
+        let index = selected
+            .and_then(|name| self.visible.iter().position(|task| task.name == name))
+            .or_else(|| (!self.visible.is_empty()).then_some(0))
+            .map(|index| index.min(self.visible.len().saturating_sub(1)));
+        self.state.select(index);
+    }
+
+    fn save(&mut self, task: Task) -> color_eyre::Result<()> {
+        actions::update(&self.config, &self.store, task)?;
+        self.reload()
+    }
+
+    fn handle_normal(&mut self, code: KeyCode) -> color_eyre::Result<()> {
+        match code {
+            KeyCode::Char('j') | KeyCode::Down => self.state.select_next(),
+            KeyCode::Char('k') | KeyCode::Up => self.state.select_previous(),
+            KeyCode::Char('c') | KeyCode::Char(' ') => {
+                if let Some(task) = self.selected().cloned() {
+                    let completed = !task.completed;
+                    let message = if completed {
+                        "task-completed"
+                    } else {
+                        "task-reopened"
+                    };
+                    self.status = tr!(message, name = task.name.display());
+                    self.save(Task { completed, ..task })?;
+                }
+            }
+            KeyCode::Char('s') => {
+                if let Some(task) = self.selected().cloned() {
+                    let task =
+                        actions::snooze(&self.config, &self.store, &task.name, Duration::days(1))?;
+                    let due = task.due.expect("snoozed tasks have a due date");
+                    let due = due.with_timezone(&chrono::Local).format("%a %F %R");
+                    self.status = tr!("task-snoozed", name = task.name.display(), due = due);
+                    self.reload()?;
+                }
+            }
+            KeyCode::Char('a') => self.input = Input::Add(String::new()),
+            KeyCode::Char('/') => self.input = Input::Filter,
+            KeyCode::Char('o') => {
+                let modes = SortMode::value_variants();
+                let current = modes
+                    .iter()
+                    .position(|mode| *mode == self.sort)
+                    .unwrap_or_default();
+                self.sort = modes[(current + 1) % modes.len()];
+                self.reload()?;
+            }
+            KeyCode::Char('h') => {
+                self.show_completed = !self.show_completed;
+                self.refilter();
+            }
+            KeyCode::Char('r') => {
+                self.reload()?;
+                self.status = tr!("tui-reloaded");
+            }
+            KeyCode::Char('b') => {
+                self.view = match self.view {
+                    View::List => View::Board,
+                    View::Board => View::List,
+                };
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn handle_filter(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Enter => self.input = Input::Normal,
+            KeyCode::Esc => {
+                self.filter.clear();
+                self.input = Input::Normal;
+            }
+            KeyCode::Backspace => {
+                self.filter.pop();
+            }
+            KeyCode::Char(c) => self.filter.push(c),
+            _ => return,
+        }
+
+        self.refilter();
+    }
+
+    fn handle_add(&mut self, key: KeyEvent) -> color_eyre::Result<()> {
+        let Input::Add(name) = &mut self.input else {
+            return Ok(());
+        };
+
+        match key.code {
+            KeyCode::Esc => self.input = Input::Normal,
+            KeyCode::Backspace => {
+                name.pop();
+            }
+            KeyCode::Char(c) => name.push(c),
+            KeyCode::Enter => {
+                let name = std::mem::take(name);
+                self.input = Input::Normal;
+                if name.trim().is_empty() {
+                    return Ok(());
+                }
+
+                let task = Task::new(name.trim());
+                self.status = tr!("task-added", name = task.name.display());
+                actions::add(&self.config, &self.store, task)?;
+                self.reload()?;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Edit the selected task like the `edit` command does (see [`edit::edit`]), suspending the
+    /// TUI while the editor runs
+    fn edit_selected(&mut self, terminal: &mut DefaultTerminal) -> color_eyre::Result<()> {
+        let Some(task) = self.selected().cloned() else {
+            return Ok(());
+        };
+        ratatui::restore();
+        let edited = edit::edit(&self.config, &self.store, &self.editor, &task.name, false);
+        *terminal = ratatui::init();
+
+        self.status = match edited {
+            Ok(Some(_)) => tr!("task-modified", name = task.name.display()),
+            Ok(None) => tr!("task-unchanged", name = task.name.display()),
+            Err(err) => format!("{err:#}"),
+        };
+        self.reload()
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, status] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+
+        match self.view {
+            View::List => self.draw_list(frame, main),
+            View::Board => self.draw_board(frame, main),
+        }
+
+        let line = match &self.input {
+            Input::Normal if self.status.is_empty() => Line::from(tr!("tui-help")),
+            Input::Normal => Line::from(self.status.as_str()),
+            Input::Filter => Line::from(format!("/{}", self.filter)),
+            Input::Add(name) => Line::from(tr!("tui-new-task", name = name)),
+        };
+        frame.render_widget(Paragraph::new(line), status);
+    }
+
+    fn draw_list(&mut self, frame: &mut Frame, main: Rect) {
+        let [list, details] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(main);
+
+        let sort = self
+            .sort
+            .to_possible_value()
+            .map(|value| value.get_name().to_string())
+            .unwrap_or_default();
+        let mut title = format!(" {} ", tr!("tui-tasks", sort = sort));
+        if let Some(context) = &self.context {
+            title.push_str(&format!("@{} ", context.name));
+        }
+        if !self.filter.is_empty() {
+            title.push_str(&format!("/{} ", self.filter));
+        }
+
+        let theme = self.config.theme();
+        let now = chrono::Utc::now();
+        let items: Vec<_> = self
+            .visible
+            .iter()
+            .map(|task| {
+                let style = color::style(task, &self.config, now);
+                ListItem::new(Line::styled(
+                    format!(
+                        "[{}] {} {}",
+                        if task.completed { 'x' } else { ' ' },
+                        task.urgency.icon(self.config.icons),
+                        task.name.display()
+                    ),
+                    Style::from(style),
+                ))
+            })
+            .collect();
+        let tasks = List::new(items)
+            .block(Block::bordered().title(title))
+            .highlight_style(Style::from(theme.selected));
+        frame.render_stateful_widget(tasks, list, &mut self.state);
+
+        let text = match self.selected() {
+            Some(task) => {
+                let mut lines = vec![
+                    Line::from(Span::styled(
+                        task.name.display().to_string(),
+                        Style::from(theme.header),
+                    )),
+                    Line::from(tr!("tui-urgency", urgency = task.urgency)),
+                    Line::from(tr!("tui-status", status = task.status)),
+                    Line::from(tr!(
+                        "tui-due",
+                        due = task
+                            .due
+                            .map(|due| due.to_string())
+                            .unwrap_or_else(|| "-".to_string())
+                    )),
+                    Line::from(tr!(
+                        "tui-completed",
+                        completed = tr!(if task.completed { "tui-yes" } else { "tui-no" })
+                    )),
+                    Line::default(),
+                ];
+                lines.extend(
+                    task.description
+                        .iter()
+                        .flat_map(|desc| desc.lines())
+                        .map(|line| Line::from(line.to_string())),
+                );
+                lines
+            }
+            None => vec![Line::from(tr!("no-tasks"))],
+        };
+        frame.render_widget(
+            Paragraph::new(text)
+                .block(Block::bordered().title(format!(" {} ", tr!("tui-details"))))
+                .wrap(Wrap { trim: false }),
+            details,
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{Duration, Utc};
+    use dooit_tasks::{DirStore, SortMode, Task, TaskStore, Urgency};
+    use pretty_assertions::assert_eq;
+    use ratatui::{
+        backend::TestBackend,
+        crossterm::event::{KeyCode, KeyEvent, KeyModifiers},
+        Terminal,
+    };
+
+    use super::{App, Input};
+    use crate::{
+        config::{AuditConfig, Config, JournalConfig},
+        editor::Editor,
+    };
+
+    fn visible(app: &App) -> Vec<String> {
+        let names = app
+            .visible
+            .iter()
+            .map(|task| task.name.display().to_string());
+        names.collect()
+    }
+
+    fn screen(app: &mut App) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(80, 10)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let cells: Vec<_> = buffer.content().iter().map(|cell| cell.symbol()).collect();
+        let rows = cells.chunks(buffer.area.width.into());
+        rows.map(|row| row.concat().trim_end().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_app() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = DirStore::new(dir.path());
+        for task in [
+            Task::new("groceries"),
+            Task::new("report").with_urgency(Urgency::Medium),
+            Task::new("taxes")
+                .with_urgency(Urgency::High)
+                .with_description("due in april".to_string()),
+            Task::new("old").complete(),
+        ] {
+            store.save(&task).unwrap();
+        }
+        let config = Config {
+            audit: AuditConfig { enabled: false },
+            journal: JournalConfig { enabled: false },
+            ..Config::default()
+        };
+        let mut app = App::new(config, store, Editor::parse("true").unwrap()).unwrap();
+        app.context = None;
+        app.refilter();
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        let load = |app: &App, name: &str| app.store.load(name.as_ref()).unwrap().unwrap();
+
+        // Most urgent first, the details of the selected task beside the list
+        assert_eq!(visible(&app), ["taxes", "report", "groceries"]);
+        let lines = screen(&mut app);
+        assert!(
+            lines[1].contains("taxes") && lines[1].contains("│taxes"),
+            "{lines:#?}"
+        );
+        assert!(
+            lines.iter().any(|line| line.contains("due in april")),
+            "{lines:#?}"
+        );
+
+        // `c` completes the selected task, `h` toggles showing the completed ones
+        app.handle_normal(KeyCode::Char('j')).unwrap();
+        app.handle_normal(KeyCode::Char('c')).unwrap();
+        assert!(load(&app, "report").completed);
+        assert_eq!(visible(&app), ["taxes", "groceries"]);
+        assert_eq!(app.status, "Completed report");
+        app.handle_normal(KeyCode::Char('h')).unwrap();
+        assert_eq!(visible(&app), ["taxes", "report", "groceries", "old"]);
+        app.handle_normal(KeyCode::Char('h')).unwrap();
+
+        // `s` pushes the selected task a day forward
+        let selected = app.selected().unwrap().name.clone();
+        app.handle_normal(KeyCode::Char('s')).unwrap();
+        let due = load(&app, &selected.display().to_string()).due.unwrap();
+        assert!((due - Utc::now() - Duration::days(1)).num_minutes().abs() < 1);
+
+        // `o` switches to the next sort mode
+        while app.sort != SortMode::NameAscending {
+            let sort = app.sort;
+            app.handle_normal(KeyCode::Char('o')).unwrap();
+            assert_ne!(app.sort, sort);
+        }
+        assert_eq!(visible(&app), ["groceries", "taxes"]);
+
+        // The filter applies while typing and matches names and descriptions, ignoring case
+        app.handle_normal(KeyCode::Char('/')).unwrap();
+        for c in "APR".chars() {
+            app.handle_filter(key(KeyCode::Char(c)));
+        }
+        assert_eq!(visible(&app), ["taxes"]);
+        app.handle_filter(key(KeyCode::Backspace));
+        app.handle_filter(key(KeyCode::Backspace));
+        assert_eq!(visible(&app), ["taxes"]);
+        app.handle_filter(key(KeyCode::Backspace));
+        assert_eq!(visible(&app), ["groceries", "taxes"]);
+        app.handle_filter(key(KeyCode::Char('g')));
+        app.handle_filter(key(KeyCode::Enter));
+        assert_eq!(visible(&app), ["groceries"]);
+        assert!(matches!(app.input, Input::Normal));
+        app.handle_normal(KeyCode::Char('/')).unwrap();
+        app.handle_filter(key(KeyCode::Esc));
+        assert_eq!(visible(&app), ["groceries", "taxes"]);
+
+        // `a` asks for the name of a new task, added on enter
+        app.handle_normal(KeyCode::Char('a')).unwrap();
+        for c in "notes".chars() {
+            app.handle_add(key(KeyCode::Char(c))).unwrap();
+        }
+        assert!(screen(&mut app).last().unwrap().contains("notes"));
+        app.handle_add(key(KeyCode::Enter)).unwrap();
+        assert_eq!(visible(&app), ["groceries", "notes", "taxes"]);
+        assert_eq!(load(&app, "notes"), Task::new("notes"));
+        assert_eq!(screen(&mut app).last().unwrap(), "Added notes");
+    }
+}
@@ -0,0 +1,166 @@
+//! Kanban board view
+
+use dooit_tasks::{Status, Task};
+use ratatui::{
+    crossterm::event::KeyCode,
+    layout::{Constraint, Layout, Rect},
+    style::Style,
+    widgets::{Block, List, ListItem, ListState},
+    Frame,
+};
+
+use super::App;
+use crate::i18n::tr;
+
+/// Messages with the titles of the columns
+const COLUMNS: [&str; 4] = [
+    "board-pending",
+    "board-started",
+    "board-waiting",
+    "board-done",
+];
+const DONE: usize = COLUMNS.len() - 1;
+
+/// Position of the selected card
+#[derive(Debug, Default)]
+pub struct Board {
+    column: usize,
+    row: usize,
+}
+
+fn column_of(task: &Task) -> usize {
+    if task.completed {
+        return DONE;
+    }
+
+    match task.status {
+        Status::Pending => 0,
+        Status::Started => 1,
+        Status::Waiting => 2,
+    }
+}
+
+/// Move `task` to `column`, reopening it if it leaves the done column
+fn move_to(task: Task, column: usize) -> Task {
+    let status = match column {
+        0 => Status::Pending,
+        1 => Status::Started,
+        2 => Status::Waiting,
+        _ => {
+            return Task {
+                completed: true,
+                ..task
+            }
+        }
+    };
+
+    Task {
+        completed: false,
+        status,
+        ..task
+    }
+}
+
+impl App {
+    fn board_columns(&self) -> [Vec<&Task>; COLUMNS.len()] {
+        let mut columns: [Vec<&Task>; COLUMNS.len()] = Default::default();
+
+        for task in self.tasks.iter().filter(|task| self.matches_filter(task)) {
+            columns[column_of(task)].push(task);
+        }
+
+        columns
+    }
+
+    pub(super) fn board_selected(&self) -> Option<&Task> {
+        let columns = self.board_columns();
+        columns[self.board.column].get(self.board.row).copied()
+    }
+
+    fn clamp_board_row(&mut self) {
+        let len = self.board_columns()[self.board.column].len();
+        self.board.row = self.board.row.min(len.saturating_sub(1));
+    }
+
+    pub(super) fn handle_board(&mut self, code: KeyCode) -> color_eyre::Result<()> {
+        match code {
+            KeyCode::Char('h') | KeyCode::Left => {
+                self.board.column = self.board.column.saturating_sub(1);
+            }
+            KeyCode::Char('l') | KeyCode::Right => {
+                self.board.column = (self.board.column + 1).min(DONE);
+            }
+            KeyCode::Char('j') | KeyCode::Down => self.board.row += 1,
+            KeyCode::Char('k') | KeyCode::Up => self.board.row = self.board.row.saturating_sub(1),
+            KeyCode::Char('H') => self.move_card(self.board.column.checked_sub(1))?,
+            KeyCode::Char('L') => {
+                self.move_card(Some(self.board.column + 1).filter(|&column| column <= DONE))?
+            }
+            code => self.handle_normal(code)?,
+        }
+
+        self.clamp_board_row();
+        Ok(())
+    }
+
+    fn move_card(&mut self, column: Option<usize>) -> color_eyre::Result<()> {
+        let (Some(column), Some(task)) = (column, self.board_selected().cloned()) else {
+            return Ok(());
+        };
+
+        let name = task.name.clone();
+        self.status = tr!(
+            "board-moved",
+            name = name.display(),
+            column = tr!(COLUMNS[column])
+        );
+        self.save(move_to(task, column))?;
+
+        self.board.column = column;
+        self.board.row = self.board_columns()[column]
+            .iter()
+            .position(|task| task.name == name)
+            .unwrap_or_default();
+
+        Ok(())
+    }
+
+    pub(super) fn draw_board(&mut self, frame: &mut Frame, area: Rect) {
+        self.clamp_board_row();
+
+        let areas = Layout::horizontal([Constraint::Ratio(1, COLUMNS.len() as u32); COLUMNS.len()])
+            .split(area);
+
+        let icons = self.config.icons;
+        let theme = self.config.theme();
+        for (index, (tasks, title)) in self.board_columns().into_iter().zip(COLUMNS).enumerate() {
+            let focused = index == self.board.column;
+            let items: Vec<_> = tasks
+                .iter()
+                .map(|task| {
+                    ListItem::new(format!(
+                        "{} {}",
+                        task.urgency.icon(icons),
+                        task.name.display()
+                    ))
+                })
+                .collect();
+
+            let title = tr!(title);
+            let mut block = Block::bordered().title(format!(" {title} ({}) ", items.len()));
+            if focused {
+                block = block.border_style(Style::from(theme.header));
+            }
+
+            let mut state = ListState::default();
+            state.select(focused.then_some(self.board.row));
+            frame.render_stateful_widget(
+                List::new(items)
+                    .block(block)
+                    .highlight_style(Style::from(theme.selected)),
+                areas[index],
+                &mut state,
+            );
+        }
+    }
+}
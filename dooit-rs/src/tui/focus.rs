@@ -0,0 +1,267 @@
+//! Full screen view of a single task
+//!
+//! The task's checklist are the lines of its description looking like `- [ ] item` (or `- [x]
+//! item` once checked). Leaving focus mode logs the time spent into the task if it was at least a
+//! minute.
+
+use std::{path::Path, time::Duration as StdDuration};
+
+use chrono::{DateTime, Duration, Utc};
+use color_eyre::eyre::eyre;
+use dooit_tasks::{duration::format_duration, DirStore, Task, TaskStore, TimeEntry};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Alignment, Constraint, Flex, Layout},
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, List, ListItem, ListState, Paragraph, Wrap},
+    DefaultTerminal, Frame,
+};
+
+use crate::{actions, config::Config, i18n::tr};
+
+const UNCHECKED: &str = "- [ ] ";
+const CHECKED: &str = "- [x] ";
+
+/// Checklist items of `description` as (line, checked, text)
+fn checklist(description: &str) -> Vec<(usize, bool, &str)> {
+    description
+        .lines()
+        .enumerate()
+        .filter_map(|(line, text)| {
+            let text = text.trim_start();
+            if let Some(item) = text.strip_prefix(UNCHECKED) {
+                Some((line, false, item))
+            } else {
+                text.strip_prefix(CHECKED).map(|item| (line, true, item))
+            }
+        })
+        .collect()
+}
+
+/// Check or uncheck the item on `line` of `description`
+fn toggle(description: &str, line: usize) -> String {
+    description
+        .lines()
+        .enumerate()
+        .map(|(index, text)| match index == line {
+            true if text.contains(UNCHECKED) => text.replacen(UNCHECKED, CHECKED, 1),
+            true => text.replacen(CHECKED, UNCHECKED, 1),
+            false => text.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn clock(duration: Duration) -> String {
+    let seconds = duration.num_seconds();
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+struct Focus {
+    config: Config,
+    store: DirStore,
+    task: Task,
+    started: DateTime<Utc>,
+    state: ListState,
+    status: String,
+}
+
+pub fn run(config: Config, store: DirStore, name: &Path) -> color_eyre::Result<()> {
+    let task = store
+        .load(name)?
+        .ok_or_else(|| eyre!("no task named {}", name.display()))?;
+
+    let mut focus = Focus {
+        config,
+        store,
+        task,
+        started: Utc::now(),
+        state: ListState::default().with_selected(Some(0)),
+        status: String::new(),
+    };
+
+    let mut terminal = ratatui::init();
+    let result = focus.run(&mut terminal);
+    ratatui::restore();
+    result?;
+
+    focus.log_session()
+}
+
+impl Focus {
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> color_eyre::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+
+            // Redraw every second to keep the timers running
+            if !event::poll(StdDuration::from_secs(1))? {
+                continue;
+            }
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            let result = match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Char('j') | KeyCode::Down => {
+                    self.state.select_next();
+                    Ok(())
+                }
+                KeyCode::Char('k') | KeyCode::Up => {
+                    self.state.select_previous();
+                    Ok(())
+                }
+                KeyCode::Char(' ') | KeyCode::Enter => self.toggle_selected(),
+                KeyCode::Char('c') => {
+                    self.task = actions::complete(&self.config, &self.store, &self.task.name)?;
+                    return Ok(());
+                }
+                _ => Ok(()),
+            };
+
+            if let Err(err) = result {
+                self.status = format!("error: {err:#}");
+            }
+        }
+    }
+
+    fn toggle_selected(&mut self) -> color_eyre::Result<()> {
+        let Some(description) = &self.task.description else {
+            return Ok(());
+        };
+        let items = checklist(description);
+        let Some((line, ..)) = self.state.selected().and_then(|index| items.get(index)) else {
+            return Ok(());
+        };
+
+        let task = Task {
+            description: Some(toggle(description, *line)),
+            ..self.task.clone()
+        };
+        actions::update(&self.config, &self.store, task.clone())?;
+        self.task = task;
+        Ok(())
+    }
+
+    fn log_session(mut self) -> color_eyre::Result<()> {
+        let end = Utc::now();
+        if end - self.started < Duration::minutes(1) {
+            return Ok(());
+        }
+
+        // Changes made while focusing (e.g. from another terminal) shouldn't be overwritten
+        if let Some(task) = self.store.load(&self.task.name)? {
+            self.task = task;
+        }
+        self.task.time_log.push(TimeEntry {
+            start: self.started,
+            end,
+        });
+        actions::update(&self.config, &self.store, self.task)?;
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let now = Utc::now();
+        let task = &self.task;
+
+        let block = Block::bordered().title(format!(" {} ", tr!("focus-title")));
+        let [area] = Layout::horizontal([Constraint::Percentage(80)])
+            .flex(Flex::Center)
+            .areas(frame.area());
+        let [area] = Layout::vertical([Constraint::Percentage(80)])
+            .flex(Flex::Center)
+            .areas(area);
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let description = task.description.as_deref().unwrap_or_default();
+        let items = checklist(description);
+        let notes: Vec<_> = description
+            .lines()
+            .enumerate()
+            .filter(|(line, _)| !items.iter().any(|(item, ..)| item == line))
+            .map(|(_, text)| Line::from(text))
+            .collect();
+
+        let [header, notes_area, list_area, footer] = Layout::vertical([
+            Constraint::Length(6),
+            Constraint::Length(notes.len() as u16),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ])
+        .spacing(1)
+        .areas(inner);
+
+        let due = match task.due {
+            _ if task.completed => tr!("focus-completed"),
+            Some(due) if due > now => tr!("focus-due-in", time = clock(due - now)),
+            Some(due) => tr!("focus-overdue-by", time = clock(now - due)),
+            None => tr!("focus-no-due"),
+        };
+        let theme = self.config.theme();
+        let bold = Style::from(theme.header);
+        let header_lines = vec![
+            Line::default(),
+            Line::styled(task.name.to_string_lossy().to_uppercase(), bold),
+            Line::default(),
+            Line::from(due),
+            Line::from(tr!("focus-focused-for", time = clock(now - self.started))),
+            Line::from(tr!(
+                "focus-logged-before",
+                time = format_duration(task.time_spent())
+            )),
+        ];
+        frame.render_widget(
+            Paragraph::new(header_lines).alignment(Alignment::Center),
+            header,
+        );
+        frame.render_widget(Paragraph::new(notes).wrap(Wrap { trim: false }), notes_area);
+
+        let done = items.iter().filter(|(_, checked, _)| *checked).count();
+        let list = List::new(items.iter().map(|(_, checked, text)| {
+            ListItem::new(Line::from(vec![
+                Span::raw(if *checked { "[x] " } else { "[ ] " }),
+                Span::raw(*text),
+            ]))
+        }))
+        .block(Block::new().title(tr!("focus-checklist", done = done, total = items.len())))
+        .highlight_style(Style::from(theme.selected));
+        if !items.is_empty() {
+            frame.render_stateful_widget(list, list_area, &mut self.state);
+        }
+
+        let footer_line = match self.status.as_str() {
+            "" => Line::from(tr!("focus-help")),
+            status => Line::from(status),
+        };
+        frame.render_widget(footer_line, footer);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::{checklist, toggle};
+
+    #[test]
+    fn test_checklist() {
+        let description = "notes\n- [ ] first\n  - [x] second";
+        assert_eq!(
+            checklist(description),
+            vec![(1, false, "first"), (2, true, "second")]
+        );
+        assert_eq!(toggle(description, 1), "notes\n- [x] first\n  - [x] second");
+        assert_eq!(toggle(description, 2), "notes\n- [ ] first\n  - [ ] second");
+    }
+}
@@ -0,0 +1,51 @@
+//! `verify`: the task files that changed since dooit-rs last wrote them, see [`dooit_tasks::index`]
+
+use color_eyre::eyre::{bail, WrapErr};
+use dooit_tasks::{
+    index::{Difference, INDEX_FILE},
+    DirStore,
+};
+
+use crate::{color, config::Config, i18n::tr, signing};
+
+/// Print the task files that don't match the index, and fail if there are any
+///
+/// With `accept` the index is rewritten from the files as they are instead, unless `dry_run`.
+pub fn run(
+    config: &Config,
+    store: &DirStore,
+    accept: bool,
+    dry_run: bool,
+) -> color_eyre::Result<()> {
+    let differences = store.verify().wrap_err("verify the task files")?;
+    for (path, difference) in &differences {
+        let path = path.display();
+        let line = match difference {
+            Difference::Modified => tr!("verify-modified", path = path),
+            Difference::Missing => tr!("verify-missing", path = path),
+            Difference::Unindexed => tr!("verify-unindexed", path = path),
+        };
+        println!("{line}");
+    }
+
+    if accept && dry_run {
+        let index = store.root().join(INDEX_FILE);
+        println!("{}", tr!("would-modify", path = index.display()));
+        return Ok(());
+    }
+    if accept {
+        let count = store.reindex().wrap_err("index the task files")?;
+        signing::accept();
+        println!("{}", tr!("verify-accepted", count = count));
+        return Ok(());
+    }
+    if !differences.is_empty() {
+        bail!(
+            "{} task file(s) differ from the index, run `dooit-rs verify --accept` once they look \
+             right",
+            differences.len()
+        );
+    }
+    println!("{}", color::header(&tr!("verify-ok"), config));
+    Ok(())
+}
@@ -0,0 +1,101 @@
+//! Reading the tasks from the daemon's memory while it's running, for read commands like `list`
+//!
+//! Listing the tasks from disk reads and parses every task file, which takes a while with a lot of
+//! tasks. The daemon keeps them in memory sorted by name, so it answers with the tasks under a
+//! parent right away. Commands fall back to reading the files when the daemon isn't running, is
+//! watching another data dir, or hasn't caught up with the latest changes: the store's index (see
+//! [`dooit_tasks::index`]) changed since it last read the tasks.
+
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use dooit_tasks::{DirStore, Task, TaskStore};
+
+/// A [`DirStore`] loading the summaries of tasks from the daemon when it can
+///
+/// Everything else goes to the files, so it can be used like the [`DirStore`] it wraps.
+#[derive(Debug, Clone, Copy)]
+pub struct ViaDaemon<'a> {
+    store: &'a DirStore,
+}
+
+impl<'a> ViaDaemon<'a> {
+    pub fn new(store: &'a DirStore) -> Self {
+        Self { store }
+    }
+
+    /// The summaries of the tasks under `parent` according to the daemon, if it's up to date
+    #[cfg(unix)]
+    fn query(&self, parent: &Path) -> Option<Vec<Task>> {
+        use dooit_tasks::index::INDEX_FILE;
+
+        use crate::ipc::{self, Request, Response};
+
+        let request = Request::Summaries {
+            root: self.store.root().to_path_buf(),
+            under: parent.to_path_buf(),
+        };
+        let response = match ipc::query(&request) {
+            Ok(response) => response,
+            Err(err) => {
+                tracing::debug!("reading the tasks from disk: {err:#}");
+                return None;
+            }
+        };
+        let Response::Summaries { loaded_at, tasks } = response else {
+            tracing::debug!("reading the tasks from disk, the daemon answered {response:?}");
+            return None;
+        };
+
+        let changed = std::fs::metadata(self.store.root().join(INDEX_FILE))
+            .and_then(|metadata| metadata.modified())
+            .map(DateTime::<Utc>::from);
+        if changed.is_ok_and(|changed| changed > loaded_at) {
+            tracing::debug!("reading the tasks from disk, the daemon hasn't caught up yet");
+            return None;
+        }
+        Some(tasks)
+    }
+}
+
+impl TaskStore for ViaDaemon<'_> {
+    fn load_all(&self) -> dooit_tasks::Result<Vec<Task>> {
+        self.store.load_all()
+    }
+
+    fn load(&self, name: &Path) -> dooit_tasks::Result<Option<Task>> {
+        self.store.load(name)
+    }
+
+    fn modified(&self, name: &Path) -> dooit_tasks::Result<Option<DateTime<Utc>>> {
+        self.store.modified(name)
+    }
+
+    fn load_under(&self, parent: &Path) -> dooit_tasks::Result<Vec<Task>> {
+        self.store.load_under(parent)
+    }
+
+    fn load_summaries_under(&self, parent: &Path) -> dooit_tasks::Result<Vec<Task>> {
+        #[cfg(unix)]
+        if let Some(tasks) = self.query(parent) {
+            return Ok(tasks);
+        }
+        self.store.load_summaries_under(parent)
+    }
+
+    fn save(&self, task: &Task) -> dooit_tasks::Result<()> {
+        self.store.save(task)
+    }
+
+    fn save_all(&self, tasks: &[Task]) -> dooit_tasks::Result<()> {
+        self.store.save_all(tasks)
+    }
+
+    fn remove(&self, name: &Path) -> dooit_tasks::Result<bool> {
+        self.store.remove(name)
+    }
+
+    fn rename(&self, from: &Path, task: &Task) -> dooit_tasks::Result<()> {
+        self.store.rename(from, task)
+    }
+}
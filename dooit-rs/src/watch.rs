@@ -0,0 +1,211 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::mpsc::{channel, Receiver, RecvTimeoutError},
+    time::Duration,
+};
+
+use chrono::Local;
+use color_eyre::eyre::WrapErr;
+use dooit_tasks::{DirStore, EventKind, Task, TaskEvent, TaskStore};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher as _};
+
+use crate::{color, config::Config, i18n::tr};
+
+/// Editors and sync tools touch several files at once, wait this long for things to settle
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// Notifies about changes to a directory tree
+pub struct Watcher {
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<notify::Event>>,
+}
+
+impl Watcher {
+    pub fn new(dir: &Path) -> color_eyre::Result<Self> {
+        let (tx, events) = channel();
+        let mut watcher = notify::recommended_watcher(tx).wrap_err("create file watcher")?;
+        watcher
+            .watch(dir, RecursiveMode::Recursive)
+            .wrap_err_with(|| format!("watch {}", dir.display()))?;
+
+        Ok(Self {
+            _watcher: watcher,
+            events,
+        })
+    }
+
+    /// Block until something changes or `timeout` elapses
+    ///
+    /// Returns the paths that changed, which is empty on timeout.
+    pub fn wait(&self, timeout: Option<Duration>) -> color_eyre::Result<Vec<PathBuf>> {
+        let first = match timeout {
+            Some(timeout) => match self.events.recv_timeout(timeout) {
+                Ok(event) => event,
+                Err(RecvTimeoutError::Timeout) => return Ok(Vec::new()),
+                Err(RecvTimeoutError::Disconnected) => panic!("file watcher stopped"),
+            },
+            None => self.events.recv().expect("file watcher stopped"),
+        };
+
+        let mut paths = first.wrap_err("watch for changes")?.paths;
+        while let Ok(event) = self.events.recv_timeout(DEBOUNCE) {
+            paths.extend(event.wrap_err("watch for changes")?.paths);
+        }
+
+        paths.sort();
+        paths.dedup();
+        Ok(paths)
+    }
+}
+
+/// Bring `tasks` up to date with the `changed` paths of `store`
+///
+/// Only the changed task files are re-read, unless a whole directory changed (e.g. a project was
+/// moved or deleted) in which case everything is reloaded.
+pub fn apply_changes(
+    store: &DirStore,
+    tasks: &mut Vec<Task>,
+    changed: &[PathBuf],
+) -> color_eyre::Result<()> {
+    for path in changed {
+        let Some(name) = store.task_name(path) else {
+            let in_store = path
+                .strip_prefix(store.root())
+                .is_ok_and(|relative| !relative.to_string_lossy().starts_with('.'));
+            // Other files (e.g. editor backups) are ignored
+            if in_store && path.extension().is_none() && (path.is_dir() || !path.exists()) {
+                *tasks = store.load_all().wrap_err("reload tasks")?;
+                return Ok(());
+            }
+            continue;
+        };
+
+        tasks.retain(|task| task.name != name);
+        if let Some(task) = store
+            .load(&name)
+            .wrap_err_with(|| format!("reload task {}", name.display()))?
+        {
+            tasks.push(task);
+        }
+    }
+
+    Ok(())
+}
+
+/// What happened to the tasks going from `old` to `new`, by task name
+///
+/// Deleted tasks are reported as they were before.
+fn events(old: &[Task], new: &[Task]) -> Vec<TaskEvent> {
+    let before: HashMap<&Path, &Task> =
+        old.iter().map(|task| (task.name.as_path(), task)).collect();
+    let after: HashMap<&Path, &Task> = new.iter().map(|task| (task.name.as_path(), task)).collect();
+
+    let mut events: Vec<_> = new
+        .iter()
+        .filter_map(|task| {
+            let kind = match before.get(task.name.as_path()) {
+                None => EventKind::Added,
+                Some(old) if *old == task => return None,
+                Some(old) if !old.completed && task.completed => EventKind::Completed,
+                Some(_) => EventKind::Modified,
+            };
+            Some(TaskEvent::new(kind, task.clone()))
+        })
+        .chain(
+            old.iter()
+                .filter(|task| !after.contains_key(task.name.as_path()))
+                .map(|task| TaskEvent::new(EventKind::Deleted, task.clone())),
+        )
+        .collect();
+    events.sort_by(|a, b| a.task.name.cmp(&b.task.name));
+    events
+}
+
+/// Print a line for every change to the tasks until interrupted, a JSON [`TaskEvent`] if `json`
+pub fn run(config: &Config, store: &DirStore, json: bool) -> color_eyre::Result<()> {
+    std::fs::create_dir_all(store.root())
+        .wrap_err_with(|| format!("create {}", store.root().display()))?;
+    let watcher = Watcher::new(store.root())?;
+    let mut tasks = store.load_all().wrap_err("load tasks")?;
+
+    loop {
+        let changed = watcher.wait(None)?;
+        let old = tasks.clone();
+        if let Err(err) = apply_changes(store, &mut tasks, &changed) {
+            tracing::warn!("{err:#}");
+            continue;
+        }
+
+        for event in events(&old, &tasks) {
+            if json {
+                let line = serde_json::to_string(&event).wrap_err("serialize the event")?;
+                println!("{line}");
+                continue;
+            }
+            let kind = match event.event {
+                EventKind::Added => tr!("watch-added"),
+                EventKind::Modified => tr!("watch-modified"),
+                EventKind::Completed => tr!("watch-completed"),
+                EventKind::Deleted => tr!("watch-deleted"),
+                EventKind::Overdue => tr!("watch-overdue"),
+            };
+            let at = event
+                .timestamp
+                .with_timezone(&Local)
+                .format("%T")
+                .to_string();
+            let line = tr!(
+                "watch-event",
+                at = at,
+                event = kind,
+                name = event.task.name.display()
+            );
+            match config.color.enabled() {
+                true => println!(
+                    "{}",
+                    color::style(&event.task, config, event.timestamp).paint(&line)
+                ),
+                false => println!("{line}"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use dooit_tasks::{EventKind, Task};
+    use pretty_assertions::assert_eq;
+
+    use super::events;
+
+    #[test]
+    fn test_events() {
+        let old = [
+            Task::new("kept"),
+            Task::new("edited"),
+            Task::new("finished"),
+            Task::new("gone"),
+        ];
+        let new = [
+            Task::new("kept"),
+            Task::new("edited").with_tags(["work"]),
+            Task::new("finished").complete(),
+            Task::new("new"),
+        ];
+
+        let events: Vec<_> = events(&old, &new)
+            .into_iter()
+            .map(|event| (event.task.name.to_string_lossy().into_owned(), event.event))
+            .collect();
+        assert_eq!(
+            events,
+            [
+                ("edited".to_string(), EventKind::Modified),
+                ("finished".to_string(), EventKind::Completed),
+                ("gone".to_string(), EventKind::Deleted),
+                ("new".to_string(), EventKind::Added),
+            ]
+        );
+    }
+}
@@ -0,0 +1,20 @@
+use std::time::Duration;
+
+use dooit_tasks::TaskEvent;
+
+use crate::config::Webhook;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Send `event` to every interested webhook
+///
+/// Failures are reported on stderr but never abort the command that triggered the event.
+pub fn fire(webhooks: &[Webhook], event: &TaskEvent) {
+    let agent = ureq::AgentBuilder::new().timeout(TIMEOUT).build();
+
+    for hook in webhooks.iter().filter(|hook| hook.wants(event.event)) {
+        if let Err(err) = agent.post(&hook.url).send_json(event) {
+            tracing::warn!("failed to deliver webhook to {}: {err}", hook.url);
+        }
+    }
+}
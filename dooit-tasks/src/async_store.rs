@@ -0,0 +1,171 @@
+use std::{
+    ffi::OsStr,
+    future::Future,
+    path::{Path, PathBuf},
+};
+
+use tokio::{fs, task::JoinSet};
+
+use crate::{DirStore, Result, Task};
+
+/// Like [`TaskStore`](crate::TaskStore), but without blocking the executor
+pub trait AsyncTaskStore {
+    /// Load every task in the store
+    fn load_all(&self) -> impl Future<Output = Result<Vec<Task>>> + Send;
+
+    /// Load the task called `name`, if it exists
+    fn load(&self, name: &Path) -> impl Future<Output = Result<Option<Task>>> + Send;
+
+    /// Create or overwrite a task
+    fn save(&self, task: &Task) -> impl Future<Output = Result<()>> + Send;
+
+    /// Remove the task called `name`, returns `Ok(false)` if it didn't exist
+    fn remove(&self, name: &Path) -> impl Future<Output = Result<bool>> + Send;
+}
+
+impl DirStore {
+    /// [`record`](Self::record) on tokio's blocking threads
+    async fn record_blocking(&self, task_path: PathBuf, contents: Option<Vec<u8>>) {
+        let store = self.clone();
+        let indexed =
+            tokio::task::spawn_blocking(move || store.record(&task_path, contents.as_deref()));
+        if let Err(err) = indexed.await {
+            tracing::warn!("failed to update the index: {err}");
+        }
+    }
+}
+
+/// Files are read concurrently, each on its own tokio task
+impl AsyncTaskStore for DirStore {
+    async fn load_all(&self) -> Result<Vec<Task>> {
+        let mut dirs = vec![self.root().to_path_buf()];
+        let mut reads = JoinSet::new();
+
+        while let Some(dir) = dirs.pop() {
+            let mut entries = match fs::read_dir(&dir).await {
+                Ok(entries) => entries,
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound && dir == self.root() => {
+                    return Ok(Vec::new());
+                }
+                Err(err) => return Err(err.into()),
+            };
+
+            while let Some(entry) = entries.next_entry().await? {
+                let path = entry.path();
+                if !crate::store::valid_entry(&path) {
+                    continue;
+                }
+
+                if path.extension() == Some(OsStr::new(self.format().extension())) {
+                    let store = self.clone();
+                    reads.spawn(async move {
+                        let contents = fs::read(&path).await?;
+                        store.parse(path, &contents)
+                    });
+                    continue;
+                }
+
+                let hidden = entry.file_name().to_string_lossy().starts_with('.');
+                if !hidden && entry.file_type().await?.is_dir() {
+                    dirs.push(path);
+                }
+            }
+        }
+
+        let mut tasks = Vec::with_capacity(reads.len());
+        while let Some(task) = reads.join_next().await {
+            tasks.push(task.map_err(std::io::Error::other)??);
+        }
+        Ok(tasks)
+    }
+
+    async fn load(&self, name: &Path) -> Result<Option<Task>> {
+        let path = self.task_path(name)?;
+        match fs::read(&path).await {
+            Ok(contents) => self.parse(path, &contents).map(Some),
+            Err(err) => match err.kind() {
+                std::io::ErrorKind::NotFound => Ok(None),
+                _ => Err(err.into()),
+            },
+        }
+    }
+
+    async fn save(&self, task: &Task) -> Result<()> {
+        let task_path = self.task_path(&task.name)?;
+
+        if let Some(parent) = task_path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        let contents = self.serialize(task)?;
+        let tmp = self.tmp_path(&task_path);
+        fs::write(&tmp, &contents).await?;
+        fs::rename(tmp, &task_path).await?;
+        self.record_blocking(task_path, Some(contents)).await;
+        Ok(())
+    }
+
+    async fn remove(&self, name: &Path) -> Result<bool> {
+        let task_path = self.task_path(name)?;
+        match fs::remove_file(&task_path).await {
+            Ok(()) => {
+                self.record_blocking(task_path, None).await;
+                match fs::remove_dir_all(self.attachments_dir(name)?).await {
+                    Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                        tracing::warn!(
+                            "failed to delete the attachments of {}: {err}",
+                            name.display()
+                        )
+                    }
+                    _ => {}
+                }
+                // Drop the directories of its subtasks and of its siblings if they're empty now
+                for dir in name
+                    .ancestors()
+                    .take(2)
+                    .filter(|dir| !dir.as_os_str().is_empty())
+                {
+                    let _ = fs::remove_dir(self.root().join(dir)).await;
+                }
+                Ok(true)
+            }
+            Err(err) => match err.kind() {
+                std::io::ErrorKind::NotFound => Ok(false),
+                _ => Err(err.into()),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::AsyncTaskStore;
+    use crate::{DirStore, Task, TaskStore};
+
+    #[tokio::test]
+    async fn test_async_store_matches_sync() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let store = DirStore::new(root);
+        let tasks = [Task::new("a"), Task::new("a/b"), Task::new("c")];
+
+        for task in &tasks {
+            AsyncTaskStore::save(&store, task).await.unwrap();
+        }
+        let mut loaded = AsyncTaskStore::load_all(&store).await.unwrap();
+        loaded.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(loaded, tasks);
+        assert_eq!(TaskStore::load_all(&store).unwrap().len(), tasks.len());
+
+        assert!(AsyncTaskStore::remove(&store, "a/b".as_ref())
+            .await
+            .unwrap());
+        assert_eq!(
+            AsyncTaskStore::load(&store, "a/b".as_ref()).await.unwrap(),
+            None
+        );
+        assert!(!root.join("a").exists());
+    }
+}
@@ -0,0 +1,116 @@
+//! Task files that don't lead back to their task, e.g. copies made by sync tools
+//!
+//! A task is saved to the file its name leads to (see [`DirStore::task_path`]), but every file in
+//! the store's format is loaded. When a sync tool can't merge two versions of a file it keeps both,
+//! e.g. `foo.toml` and `foo (conflicted copy).toml`, and the task is loaded twice.
+//! [`DirStore::conflicts`] finds those files, loading a store only warns about them.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use crate::{DirStore, Result, Task};
+
+/// Task files that don't lead back to their task, paths are relative to the store's root
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Conflict {
+    /// Several files hold the task called `name`, the one its name leads to first (if it's one of
+    /// them)
+    Duplicate { name: PathBuf, paths: Vec<PathBuf> },
+    /// The only file holding the task called `name` isn't the one its name leads to
+    Misplaced { name: PathBuf, path: PathBuf },
+}
+
+/// Warn about the tasks loaded more than once
+pub(crate) fn warn_duplicates(tasks: &[Task]) {
+    let mut names = HashSet::new();
+    for task in tasks {
+        if !names.insert(&task.name) {
+            tracing::warn!(
+                "several files hold the task {}, e.g. copies made by a sync tool",
+                task.name.display()
+            );
+        }
+    }
+}
+
+impl DirStore {
+    /// The conflicting task files, sorted by the name of their task
+    pub fn conflicts(&self) -> Result<Vec<Conflict>> {
+        let mut files = Vec::new();
+        self.task_files(self.root(), &mut files)?;
+        files.sort();
+
+        let mut by_name: BTreeMap<PathBuf, Vec<PathBuf>> = BTreeMap::new();
+        for path in files {
+            let full_path = self.root().join(&path);
+            let contents = std::fs::read(&full_path)?;
+            let task = self.parse(full_path, &contents)?;
+            by_name.entry(task.name).or_default().push(path);
+        }
+
+        let conflicts = by_name.into_iter().filter_map(|(name, mut paths)| {
+            let expected = self.task_path(&name).ok();
+            let expected = expected
+                .as_deref()
+                .and_then(|path| path.strip_prefix(self.root()).ok())
+                .map(Path::to_path_buf);
+            paths.sort_by_key(|path| Some(path) != expected.as_ref());
+            match &paths[..] {
+                [path] if Some(path) == expected.as_ref() => None,
+                [path] => Some(Conflict::Misplaced {
+                    name,
+                    path: path.clone(),
+                }),
+                _ => Some(Conflict::Duplicate { name, paths }),
+            }
+        });
+        Ok(conflicts.collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use pretty_assertions::assert_eq;
+
+    use super::Conflict;
+    use crate::{DirStore, Task, TaskStore};
+
+    #[test]
+    fn test_conflicts() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let store = DirStore::new(root);
+        for name in ["fine", "report", "release/notes"] {
+            store.save(&Task::new(name)).unwrap();
+        }
+        assert_eq!(store.conflicts().unwrap(), []);
+
+        let write = |path: &str, name: &str| {
+            let contents = store.serialize(&Task::new(name)).unwrap();
+            std::fs::write(root.join(path), contents).unwrap()
+        };
+        write("report (conflicted copy).toml", "report");
+        write("release/moved.toml", "release/notes-old");
+        assert_eq!(store.load_all().unwrap().len(), 5);
+        assert_eq!(
+            store.conflicts().unwrap(),
+            [
+                Conflict::Misplaced {
+                    name: "release/notes-old".into(),
+                    path: "release/moved.toml".into(),
+                },
+                Conflict::Duplicate {
+                    name: "report".into(),
+                    paths: vec![
+                        PathBuf::from("report.toml"),
+                        PathBuf::from("report (conflicted copy).toml"),
+                    ],
+                },
+            ]
+        );
+    }
+}
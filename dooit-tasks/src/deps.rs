@@ -0,0 +1,156 @@
+//! Dependencies between tasks, see [`Task::depends_on`]
+//!
+//! Only dependencies between the tasks passed in count, others (e.g. on tasks outside a project
+//! or on completed ones) are treated as done.
+
+use std::{collections::HashMap, path::Path};
+
+use chrono::Duration;
+
+use crate::{Error, Result, Task};
+
+/// The longest chain of pending tasks that have to be done one after another
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CriticalPath<'a> {
+    /// The tasks in the order they have to be done
+    pub tasks: Vec<&'a Task>,
+    /// Sum of the remaining estimates of the tasks
+    pub duration: Duration,
+}
+
+impl<'a> CriticalPath<'a> {
+    /// Tasks on the path without an estimate, which count as taking no time
+    pub fn unestimated(&self) -> impl Iterator<Item = &'a Task> + '_ {
+        self.tasks
+            .iter()
+            .copied()
+            .filter(|task| task.remaining().is_none())
+    }
+}
+
+/// When a task can be done at the earliest, and the dependency that decides it
+#[derive(Debug, Clone, Copy)]
+struct Finish<'a> {
+    after: Duration,
+    gated_by: Option<&'a Path>,
+}
+
+/// Compute the [`Finish`] of `task` and its dependencies into `finish`
+fn earliest_finish<'a>(
+    task: &'a Task,
+    pending: &HashMap<&'a Path, &'a Task>,
+    finish: &mut HashMap<&'a Path, Finish<'a>>,
+    visiting: &mut Vec<&'a Path>,
+) -> Result<Duration> {
+    if let Some(done) = finish.get(task.name.as_path()) {
+        return Ok(done.after);
+    }
+    if visiting.contains(&task.name.as_path()) {
+        return Err(Error::DependencyCycle(task.name.clone()));
+    }
+    visiting.push(&task.name);
+
+    let mut start = Finish {
+        after: Duration::zero(),
+        gated_by: None,
+    };
+    for dependency in &task.depends_on {
+        let Some(dependency) = pending.get(dependency.as_path()) else {
+            continue;
+        };
+        let after = earliest_finish(dependency, pending, finish, visiting)?;
+        if start.gated_by.is_none() || after > start.after {
+            start = Finish {
+                after,
+                gated_by: Some(&dependency.name),
+            };
+        }
+    }
+
+    visiting.pop();
+    let after = start.after + task.remaining().unwrap_or_else(Duration::zero);
+    finish.insert(&task.name, Finish { after, ..start });
+    Ok(after)
+}
+
+/// The chain of pending `tasks` that takes the longest to finish, given their dependencies
+///
+/// Each task takes its [remaining](Task::remaining) estimate, tasks without one take no time. Ties
+/// go to the chain ending with the task that comes first in `tasks`. Returns `None` if every task
+/// is completed, and an error if the dependencies form a cycle.
+pub fn critical_path(tasks: &[Task]) -> Result<Option<CriticalPath<'_>>> {
+    let pending: HashMap<&Path, &Task> = tasks
+        .iter()
+        .filter(|task| !task.completed)
+        .map(|task| (task.name.as_path(), task))
+        .collect();
+
+    let mut finish = HashMap::new();
+    let mut last: Option<(&Task, Duration)> = None;
+    for task in tasks.iter().filter(|task| !task.completed) {
+        let after = earliest_finish(task, &pending, &mut finish, &mut Vec::new())?;
+        if last.is_none_or(|(_, longest)| after > longest) {
+            last = Some((task, after));
+        }
+    }
+
+    let Some((last, duration)) = last else {
+        return Ok(None);
+    };
+    let mut path = vec![last];
+    while let Some(gate) = finish[path[path.len() - 1].name.as_path()].gated_by {
+        path.push(pending[gate]);
+    }
+    path.reverse();
+
+    Ok(Some(CriticalPath {
+        tasks: path,
+        duration,
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{Duration, TimeZone, Utc};
+    use pretty_assertions::assert_eq;
+
+    use super::critical_path;
+    use crate::{Error, HumanDuration, Task, TimeEntry};
+
+    fn task(name: &str, hours: i64, depends_on: &[&str]) -> Task {
+        Task {
+            estimate: Some(HumanDuration(Duration::hours(hours))),
+            ..Task::new(name).with_dependencies(depends_on.iter().copied())
+        }
+    }
+
+    #[test]
+    fn test_critical_path() {
+        let start = Utc.with_ymd_and_hms(2024, 5, 1, 9, 0, 0).unwrap();
+        let mut started = task("started", 4, &[]);
+        started.time_log.push(TimeEntry {
+            start,
+            end: start + Duration::hours(1),
+        });
+        let tasks = [
+            task("design", 2, &[]),
+            task("build", 1, &["design"]),
+            started,
+            task("ship", 1, &["design", "started", "done", "elsewhere"]),
+            task("done", 8, &[]).complete(),
+        ];
+
+        let path = critical_path(&tasks).unwrap().unwrap();
+        let names: Vec<_> = path.tasks.iter().map(|task| task.name.as_path()).collect();
+        assert_eq!(names, ["started", "ship"].map(std::path::Path::new));
+        assert_eq!(path.duration, Duration::hours(4));
+        assert_eq!(path.unestimated().count(), 0);
+
+        let cycle = [task("a", 1, &["b"]), task("b", 1, &["a"])];
+        assert!(matches!(
+            critical_path(&cycle),
+            Err(Error::DependencyCycle(_))
+        ));
+        assert_eq!(critical_path(&tasks[4..]).unwrap(), None);
+    }
+}
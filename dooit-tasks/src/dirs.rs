@@ -1,9 +1,12 @@
-use std::{ffi::OsStr, path::Path};
+use std::path::Path;
 
 use directories::ProjectDirs;
 use lazy_static::lazy_static;
 
-use crate::Task;
+use crate::{
+    store::{DirStore, TaskStore},
+    Result, Task,
+};
 
 lazy_static! {
     static ref PROJECT_DIRS: Option<ProjectDirs> = ProjectDirs::from("rs", "salameme", "dooit-rs");
@@ -21,38 +24,13 @@ pub fn get_data_dir() -> Option<&'static Path> {
     get_project_dirs().map(ProjectDirs::data_dir)
 }
 
-pub fn get_tasks() -> std::io::Result<Vec<Task>> {
-    let data_dir = get_data_dir().ok_or_else(|| {
-        std::io::Error::new(std::io::ErrorKind::NotFound, "data dir not available")
-    })?;
-
-    match data_dir.read_dir() {
-        Ok(_) => get_tasks_in_dir_recursive(data_dir),
-        Err(err) => match err.kind() {
-            std::io::ErrorKind::NotFound => Ok(Vec::new()),
-            _ => Err(err),
-        },
-    }
+/// Directory for state that should persist between runs but isn't user data
+///
+/// Only Linux has a dedicated state directory, other platforms use the cache directory.
+pub fn get_state_dir() -> Option<&'static Path> {
+    get_project_dirs().map(|dirs| dirs.state_dir().unwrap_or_else(|| dirs.cache_dir()))
 }
 
-fn get_tasks_in_dir_recursive(dir: &std::path::Path) -> std::io::Result<Vec<Task>> {
-    let mut tasks = vec![];
-
-    for file in dir.read_dir()? {
-        let file = file?;
-        let path = file.path();
-
-        if path.extension() == Some(OsStr::new("toml")) {
-            tasks.push(toml::from_slice(&std::fs::read(path)?)?);
-            continue;
-        }
-
-        if !path.is_dir() {
-            continue;
-        }
-
-        tasks.extend(get_tasks_in_dir_recursive(&path)?);
-    }
-
-    Ok(tasks)
+pub fn get_tasks() -> Result<Vec<Task>> {
+    DirStore::open_default()?.load_all()
 }
@@ -0,0 +1,131 @@
+use std::{fmt::Display, str::FromStr};
+
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Result};
+
+const UNITS: [(char, i64); 5] = [
+    ('w', 7 * 24 * 60 * 60),
+    ('d', 24 * 60 * 60),
+    ('h', 60 * 60),
+    ('m', 60),
+    ('s', 1),
+];
+
+/// Parse a duration like `30m`, `2d` or `1h30m`
+///
+/// Supported units are weeks (`w`), days (`d`), hours (`h`), minutes (`m`) and seconds (`s`).
+pub fn parse_duration(duration: &str) -> Result<Duration> {
+    let invalid = || Error::InvalidDuration(duration.to_string());
+
+    let (negative, mut rest) = match duration.trim().strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, duration.trim()),
+    };
+
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+
+    let mut seconds = 0i64;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .ok_or_else(invalid)?;
+        let amount: i64 = rest[..digits].parse().map_err(|_| invalid())?;
+        let unit = rest[digits..].chars().next().ok_or_else(invalid)?;
+        let (_, scale) = UNITS
+            .iter()
+            .find(|(name, _)| *name == unit)
+            .ok_or_else(invalid)?;
+
+        seconds = amount
+            .checked_mul(*scale)
+            .and_then(|amount| seconds.checked_add(amount))
+            .ok_or_else(invalid)?;
+        rest = &rest[digits + unit.len_utf8()..];
+    }
+
+    Ok(Duration::seconds(if negative { -seconds } else { seconds }))
+}
+
+/// Format a duration in the same format [`parse_duration`] accepts
+pub fn format_duration(duration: Duration) -> String {
+    let mut seconds = duration.num_seconds();
+    if seconds == 0 {
+        return "0s".to_string();
+    }
+
+    let mut formatted = String::new();
+    if seconds < 0 {
+        formatted.push('-');
+        seconds = -seconds;
+    }
+
+    for (unit, scale) in UNITS {
+        if seconds >= scale {
+            formatted.push_str(&format!("{}{unit}", seconds / scale));
+            seconds %= scale;
+        }
+    }
+
+    formatted
+}
+
+/// A [`Duration`] (de)serialized in a human friendly format (e.g. `1h30m`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HumanDuration(pub Duration);
+
+impl FromStr for HumanDuration {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_duration(s).map(Self)
+    }
+}
+
+impl Display for HumanDuration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", format_duration(self.0))
+    }
+}
+
+impl Serialize for HumanDuration {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for HumanDuration {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let duration = String::deserialize(deserializer)?;
+        duration.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::Duration;
+    use pretty_assertions::assert_eq;
+
+    use super::{format_duration, parse_duration};
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("30m").unwrap(), Duration::minutes(30));
+        assert_eq!(parse_duration("1h30m").unwrap(), Duration::minutes(90));
+        assert_eq!(parse_duration("2w").unwrap(), Duration::days(14));
+        assert_eq!(parse_duration("-1d").unwrap(), Duration::days(-1));
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("10").is_err());
+        assert!(parse_duration("1y").is_err());
+    }
+
+    #[test]
+    fn test_format_duration_roundtrip() {
+        for duration in ["0s", "45s", "1h30m", "1w2d", "-3h"] {
+            assert_eq!(format_duration(parse_duration(duration).unwrap()), duration);
+        }
+    }
+}
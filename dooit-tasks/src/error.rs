@@ -0,0 +1,64 @@
+use std::path::PathBuf;
+
+use crate::format::{FormatError, TaskFormat};
+
+/// Everything that can go wrong when working with tasks
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// A task file isn't valid in its format or doesn't describe a task
+    #[error("couldn't parse task file {path} as {format}")]
+    Parse {
+        path: PathBuf,
+        format: TaskFormat,
+        #[source]
+        source: FormatError,
+    },
+    #[error("couldn't serialize task {name} as {format}")]
+    Serialize {
+        name: PathBuf,
+        format: TaskFormat,
+        #[source]
+        source: FormatError,
+    },
+    /// The platform doesn't tell where to keep data (usually because `$HOME` isn't set)
+    #[error("data dir not available")]
+    MissingDataDir,
+    /// The name would escape the store (absolute paths, `..`, etc.) or isn't valid UTF-8
+    #[error("invalid task name {0:?}")]
+    InvalidName(PathBuf),
+    /// The task doesn't make sense, see [`Task::validate`](crate::Task::validate)
+    #[error("invalid task {name}: {reason}")]
+    InvalidTask { name: PathBuf, reason: String },
+    #[error("unknown urgency {name:?}, expected one of {}", expected.join(", "))]
+    UnknownUrgency { name: String, expected: Vec<String> },
+    /// An urgency level passed to [`set_levels`](crate::urgency::set_levels) is empty or repeated
+    #[error("invalid urgency level {0:?}")]
+    InvalidUrgencyLevel(String),
+    /// The task depends on itself through other tasks
+    #[error("task {0} depends on itself through its dependencies")]
+    DependencyCycle(PathBuf),
+    #[error("couldn't parse {0:?} as a duration (e.g. 30m, 2d, 1h30m)")]
+    InvalidDuration(String),
+    #[error("couldn't parse {0:?} as a date (e.g. 2023-01-31, 17:00, tomorrow, friday, in 2h)")]
+    InvalidDate(String),
+    /// A request to a sync backend failed, after retrying if it was worth it
+    #[cfg(feature = "sync")]
+    #[error("syncing with {backend} failed")]
+    Sync {
+        backend: String,
+        #[source]
+        source: crate::sync::BackendError,
+    },
+    #[cfg(feature = "sync")]
+    #[error("couldn't read or write sync state {path}")]
+    SyncState {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
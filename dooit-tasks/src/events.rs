@@ -0,0 +1,37 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::Task;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventKind {
+    /// A new task was created
+    Added,
+    /// A task was changed, other than by completing it
+    Modified,
+    /// A task was marked as completed
+    Completed,
+    /// A task was deleted, the event has the task as it was
+    Deleted,
+    /// A pending task went past its due date
+    Overdue,
+}
+
+/// Something that happened to a task, suitable for sending to external consumers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskEvent {
+    pub event: EventKind,
+    pub timestamp: DateTime<Utc>,
+    pub task: Task,
+}
+
+impl TaskEvent {
+    pub fn new(event: EventKind, task: Task) -> Self {
+        Self {
+            event,
+            timestamp: Utc::now(),
+            task,
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::Task;
+
+#[cfg(not(any(feature = "toml", feature = "json", feature = "yaml")))]
+compile_error!("enable at least one of the toml, json or yaml features");
+
+/// Error of the library behind a [`TaskFormat`]
+pub type FormatError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Text format tasks are stored in, each one is behind the cargo feature of the same name
+///
+/// The default is the first enabled one of TOML, JSON and YAML.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum TaskFormat {
+    #[cfg(feature = "toml")]
+    Toml,
+    #[cfg(feature = "json")]
+    Json,
+    #[cfg(feature = "yaml")]
+    Yaml,
+}
+
+impl Default for TaskFormat {
+    fn default() -> Self {
+        Self::ALL[0]
+    }
+}
+
+impl TaskFormat {
+    /// Every enabled format
+    pub const ALL: &'static [TaskFormat] = &[
+        #[cfg(feature = "toml")]
+        TaskFormat::Toml,
+        #[cfg(feature = "json")]
+        TaskFormat::Json,
+        #[cfg(feature = "yaml")]
+        TaskFormat::Yaml,
+    ];
+
+    /// Extension of files in this format, without the leading dot
+    pub fn extension(self) -> &'static str {
+        match self {
+            #[cfg(feature = "toml")]
+            TaskFormat::Toml => "toml",
+            #[cfg(feature = "json")]
+            TaskFormat::Json => "json",
+            #[cfg(feature = "yaml")]
+            TaskFormat::Yaml => "yaml",
+        }
+    }
+
+    /// The enabled format using `extension`, if any
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            "yml" => Self::from_extension("yaml"),
+            extension => Self::ALL
+                .iter()
+                .copied()
+                .find(|format| format.extension() == extension),
+        }
+    }
+
+    pub fn serialize(self, task: &Task) -> Result<Vec<u8>, FormatError> {
+        match self {
+            #[cfg(feature = "toml")]
+            TaskFormat::Toml => Ok(toml::to_vec(task)?),
+            #[cfg(feature = "json")]
+            TaskFormat::Json => Ok(serde_json::to_vec_pretty(task)?),
+            #[cfg(feature = "yaml")]
+            TaskFormat::Yaml => Ok(serde_yaml::to_string(task)?.into_bytes()),
+        }
+    }
+
+    pub fn deserialize(self, contents: &[u8]) -> Result<Task, FormatError> {
+        match self {
+            #[cfg(feature = "toml")]
+            TaskFormat::Toml => Ok(toml::from_slice(contents)?),
+            #[cfg(feature = "json")]
+            TaskFormat::Json => Ok(serde_json::from_slice(contents)?),
+            #[cfg(feature = "yaml")]
+            TaskFormat::Yaml => Ok(serde_yaml::from_slice(contents)?),
+        }
+    }
+
+    /// Deserialize a task without its time log and habit occurrences, which are left empty
+    ///
+    /// Both grow with every pomodoro or day and listings don't need them. TOML is only parsed up
+    /// to them (they're the only tables, so they come last), other formats are parsed whole.
+    pub fn deserialize_summary(self, contents: &[u8]) -> Result<Task, FormatError> {
+        match self {
+            #[cfg(feature = "toml")]
+            TaskFormat::Toml => {
+                let contents = std::str::from_utf8(contents)?;
+                Ok(toml::from_str(&contents[..toml_tables_start(contents)])?)
+            }
+            #[allow(unreachable_patterns)]
+            _ => {
+                let mut task = self.deserialize(contents)?;
+                task.occurrences.clear();
+                task.time_log.clear();
+                Ok(task)
+            }
+        }
+    }
+}
+
+/// Offset of the first table header in a TOML document, skipping multi-line strings
+#[cfg(feature = "toml")]
+fn toml_tables_start(contents: &str) -> usize {
+    let mut offset = 0;
+    let mut string: Option<&str> = None;
+    for line in contents.split_inclusive('\n') {
+        if string.is_none() && line.trim_start().starts_with('[') {
+            return offset;
+        }
+        for delimiter in ["\"\"\"", "'''"] {
+            let toggles = line.matches(delimiter).count() % 2 == 1;
+            if toggles && string.is_none_or(|open| open == delimiter) {
+                string = match string {
+                    Some(_) => None,
+                    None => Some(delimiter),
+                };
+            }
+        }
+        offset += line.len();
+    }
+    offset
+}
+
+impl Display for TaskFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.extension())
+    }
+}
+
+impl FromStr for TaskFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_extension(&s.to_lowercase()).ok_or_else(|| {
+            let enabled: Vec<_> = Self::ALL.iter().map(|format| format.extension()).collect();
+            format!(
+                "unknown format {s:?}, expected one of {}",
+                enabled.join(", ")
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{TimeZone, Utc};
+    use pretty_assertions::assert_eq;
+
+    use super::TaskFormat;
+    use crate::{Occurrence, Task, TimeEntry};
+
+    #[test]
+    fn test_round_trip() {
+        let start = Utc.with_ymd_and_hms(2024, 5, 1, 9, 0, 0).unwrap();
+        let mut task = Task::new("project/task")
+            .with_description("notes\n[[time_log]]\n".to_string())
+            .with_due_date(start)
+            .with_tags(["work"])
+            .with_habit(chrono::Duration::days(1));
+        task.occurrences.push(Occurrence {
+            due: start - chrono::Duration::days(1),
+            completed_at: None,
+        });
+        task.time_log.push(TimeEntry {
+            start,
+            end: start + chrono::Duration::hours(1),
+        });
+
+        for &format in TaskFormat::ALL {
+            let serialized = format.serialize(&task).unwrap();
+            assert_eq!(format.deserialize(&serialized).unwrap(), task, "{format}");
+            assert_eq!(
+                format.deserialize_summary(&serialized).unwrap(),
+                Task {
+                    occurrences: Vec::new(),
+                    time_log: Vec::new(),
+                    ..task.clone()
+                },
+                "{format}"
+            );
+            assert_eq!(format.extension().parse::<TaskFormat>(), Ok(format));
+        }
+    }
+}
@@ -0,0 +1,255 @@
+//! Hashes of the task files as they were last written, to notice files changed behind its back
+//!
+//! A [`DirStore`] keeps its index in `.index` at its root, with a `<sha256>  <path>` line for every
+//! task file and paths relative to the root, so `sha256sum -c .index` checks it too. Saving or
+//! removing a task through the store updates the index, [`DirStore::verify`] compares the files to
+//! it. Archived tasks aren't indexed.
+//!
+//! Updates lock `.index.lock` and re-read the index before replacing it, so stores writing to the
+//! same directory at once (e.g. the daemon and a command) don't drop each other's entries. Every
+//! update rewrites the whole index, so [`TaskStore::save_all`](crate::TaskStore::save_all) does it
+//! once for all the tasks it saves.
+
+use std::{
+    collections::BTreeMap,
+    ffi::OsStr,
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{DirStore, Result};
+
+/// Name of the index inside a store's root
+pub const INDEX_FILE: &str = ".index";
+
+/// Name of the file locked while updating the index, next to it
+const LOCK_FILE: &str = ".index.lock";
+
+/// SHA-256 of `contents`, in lowercase hex
+pub fn hash(contents: &[u8]) -> String {
+    Sha256::digest(contents)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Hashes of task files by their path relative to the store's root
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Index(BTreeMap<PathBuf, String>);
+
+impl Index {
+    /// Read an index in the format of `sha256sum`, skipping lines that aren't
+    pub fn parse(text: &str) -> Self {
+        let hashes = text
+            .lines()
+            .filter_map(|line| line.split_once("  "))
+            .map(|(hash, path)| (PathBuf::from(path), hash.to_string()))
+            .collect();
+        Self(hashes)
+    }
+
+    /// Hash of the file at `path`, if it's indexed
+    pub fn get(&self, path: &Path) -> Option<&str> {
+        self.0.get(path).map(String::as_str)
+    }
+
+    /// The indexed files, relative to the store's root
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.0.keys().map(PathBuf::as_path)
+    }
+
+    /// Index the file at `path` as having `contents`, or forget it if `None`
+    pub fn set(&mut self, path: PathBuf, contents: Option<&[u8]>) {
+        match contents {
+            Some(contents) => self.0.insert(path, hash(contents)),
+            None => self.0.remove(&path),
+        };
+    }
+
+    /// Take the hashes of the files in `other`, which were written after the ones in `self`
+    pub fn extend(&mut self, other: Index) {
+        self.0.extend(other.0);
+    }
+}
+
+impl Display for Index {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (path, hash) in &self.0 {
+            writeln!(f, "{hash}  {}", path.display())?;
+        }
+        Ok(())
+    }
+}
+
+/// How a task file differs from the index
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Difference {
+    /// The file's contents don't match their hash
+    Modified,
+    /// The file is indexed but doesn't exist
+    Missing,
+    /// The file isn't indexed, e.g. it was created by hand or before the index existed
+    Unindexed,
+}
+
+impl DirStore {
+    fn index_path(&self) -> PathBuf {
+        self.root().join(INDEX_FILE)
+    }
+
+    /// The index of the store, empty if it doesn't have one yet
+    pub fn index(&self) -> Result<Index> {
+        match std::fs::read_to_string(self.index_path()) {
+            Ok(text) => Ok(Index::parse(&text)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Index::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Make `change` to the index as it is on disk now, holding the lock until it's replaced
+    pub(crate) fn update_index(&self, change: impl FnOnce(&mut Index)) -> Result<()> {
+        std::fs::create_dir_all(self.root())?;
+        let lock = std::fs::File::create(self.root().join(LOCK_FILE))?;
+        lock.lock()?;
+
+        let mut index = self.index()?;
+        change(&mut index);
+        let path = self.index_path();
+        let tmp = path.with_extension(std::process::id().to_string());
+        std::fs::write(&tmp, index.to_string())?;
+        Ok(std::fs::rename(tmp, path)?)
+    }
+
+    /// Index the task file at `task_path` as having `contents`, or forget it if `None`
+    ///
+    /// The task was already written, so failing to index it is only logged and shows up in
+    /// [`verify`](Self::verify) later.
+    pub(crate) fn record(&self, task_path: &Path, contents: Option<&[u8]>) {
+        let Ok(relative) = task_path.strip_prefix(self.root()) else {
+            return;
+        };
+        let indexed = self.update_index(|index| index.set(relative.to_path_buf(), contents));
+        if let Err(err) = indexed {
+            tracing::warn!("failed to index {}: {err}", task_path.display());
+        }
+    }
+
+    /// Paths of the task files under `dir`, relative to the root
+    pub(crate) fn task_files(&self, dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+        let entries = match dir.read_dir() {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err.into()),
+        };
+        for entry in entries {
+            let path = entry?.path();
+            if !crate::store::valid_entry(&path) {
+                continue;
+            }
+            let hidden = path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+            if path.extension() == Some(OsStr::new(self.format().extension())) {
+                files.extend(path.strip_prefix(self.root()).ok().map(Path::to_path_buf));
+            } else if !hidden && path.is_dir() {
+                self.task_files(&path, files)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The task files that don't match the index, sorted by path
+    pub fn verify(&self) -> Result<Vec<(PathBuf, Difference)>> {
+        let mut index = self.index()?;
+        let mut files = Vec::new();
+        self.task_files(self.root(), &mut files)?;
+
+        let mut differences = Vec::new();
+        for path in files {
+            let contents = std::fs::read(self.root().join(&path))?;
+            match index.0.remove(&path) {
+                None => differences.push((path, Difference::Unindexed)),
+                Some(indexed) if indexed != hash(&contents) => {
+                    differences.push((path, Difference::Modified))
+                }
+                Some(_) => {}
+            }
+        }
+        differences.extend(index.0.into_keys().map(|path| (path, Difference::Missing)));
+        differences.sort();
+        Ok(differences)
+    }
+
+    /// Index the task files as they are now, returns how many there are
+    pub fn reindex(&self) -> Result<usize> {
+        let mut files = Vec::new();
+        self.task_files(self.root(), &mut files)?;
+
+        let mut index = Index::default();
+        for path in files {
+            let contents = std::fs::read(self.root().join(&path))?;
+            index.set(path, Some(&contents));
+        }
+        let indexed = index.0.len();
+        self.update_index(|old| *old = index)?;
+        Ok(indexed)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use pretty_assertions::assert_eq;
+
+    use super::Difference;
+    use crate::{DirStore, Task, TaskStore};
+
+    #[test]
+    fn test_verify() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let store = DirStore::new(root);
+        for name in ["kept", "edited", "removed", "release/notes"] {
+            store.save(&Task::new(name)).unwrap();
+        }
+        store.remove("removed".as_ref()).unwrap();
+        assert_eq!(store.verify().unwrap(), []);
+
+        let path = |name: &str| store.task_path(name.as_ref()).unwrap();
+        std::fs::write(path("edited"), "name = \"edited\"\n").unwrap();
+        std::fs::remove_file(path("release/notes")).unwrap();
+        std::fs::write(path("by-hand"), "name = \"by-hand\"\n").unwrap();
+        let relative = |name: &str| PathBuf::from(format!("{name}.toml"));
+        assert_eq!(
+            store.verify().unwrap(),
+            [
+                (relative("by-hand"), Difference::Unindexed),
+                (relative("edited"), Difference::Modified),
+                (relative("release/notes"), Difference::Missing),
+            ]
+        );
+
+        assert_eq!(store.reindex().unwrap(), 3);
+        assert_eq!(store.verify().unwrap(), []);
+    }
+
+    #[test]
+    fn test_concurrent_saves() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let stores = [DirStore::new(root), DirStore::new(root)];
+        std::thread::scope(|scope| {
+            for (thread, store) in stores.iter().enumerate() {
+                scope.spawn(move || {
+                    for task in 0..20 {
+                        store.save(&Task::new(format!("{thread}-{task}"))).unwrap();
+                    }
+                });
+            }
+        });
+        assert_eq!(stores[0].verify().unwrap(), []);
+    }
+}
@@ -0,0 +1,140 @@
+//! Iterator adapters filtering and grouping tasks, see [`TaskIterExt`]
+//!
+//! The adapters that depend on the time compare against the current one, use the predicates on
+//! [`Task`] (e.g. [`Task::is_overdue`]) to pick another.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use chrono::{Duration, Utc};
+
+use crate::{Task, Urgency};
+
+/// Adapters for iterators over tasks, e.g. `tasks.iter().pending().due_within(week)`
+pub trait TaskIterExt<'a>: Iterator<Item = &'a Task> + Sized {
+    /// Tasks that haven't been completed
+    fn pending(self) -> impl Iterator<Item = &'a Task> {
+        self.filter(|task| !task.completed)
+    }
+
+    /// Tasks that have been completed
+    fn completed(self) -> impl Iterator<Item = &'a Task> {
+        self.filter(|task| task.completed)
+    }
+
+    /// Pending tasks past their due date
+    fn overdue(self) -> impl Iterator<Item = &'a Task> {
+        let now = Utc::now();
+        self.filter(move |task| task.is_overdue(now))
+    }
+
+    /// Pending tasks due within `within` from now, overdue ones included
+    fn due_within(self, within: Duration) -> impl Iterator<Item = &'a Task> {
+        let now = Utc::now();
+        self.filter(move |task| task.is_due_within(within, now))
+    }
+
+    /// Tasks that aren't hidden by [`Task::wait`]
+    fn visible(self) -> impl Iterator<Item = &'a Task> {
+        let now = Utc::now();
+        self.filter(move |task| !task.is_waiting(now))
+    }
+
+    /// Tasks with `tag`
+    fn tagged(self, tag: &str) -> impl Iterator<Item = &'a Task> {
+        self.filter(move |task| task.tags.iter().any(|other| other == tag))
+    }
+
+    /// `parent` and its subtasks
+    fn under(self, parent: &Path) -> impl Iterator<Item = &'a Task> {
+        self.filter(move |task| task.name.starts_with(parent))
+    }
+
+    /// Tasks by the task they are under, top level tasks under an empty path
+    fn grouped_by_parent(self) -> BTreeMap<PathBuf, Vec<&'a Task>> {
+        let mut groups: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for task in self {
+            let parent = task.name.parent().unwrap_or(Path::new(""));
+            groups.entry(parent.to_path_buf()).or_default().push(task);
+        }
+        groups
+    }
+
+    /// Tasks by tag, tasks with several tags are in several groups and untagged ones in none
+    fn grouped_by_tag(self) -> BTreeMap<String, Vec<&'a Task>> {
+        let mut groups: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for task in self {
+            for tag in &task.tags {
+                groups.entry(tag.clone()).or_default().push(task);
+            }
+        }
+        groups
+    }
+
+    /// Tasks by urgency, least urgent first
+    fn grouped_by_urgency(self) -> BTreeMap<Urgency, Vec<&'a Task>> {
+        let mut groups: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for task in self {
+            groups.entry(task.urgency.clone()).or_default().push(task);
+        }
+        groups
+    }
+}
+
+impl<'a, I: Iterator<Item = &'a Task>> TaskIterExt<'a> for I {}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use chrono::{Duration, Utc};
+    use pretty_assertions::assert_eq;
+
+    use super::TaskIterExt;
+    use crate::Task;
+
+    fn names(tasks: Vec<&Task>) -> Vec<&str> {
+        tasks
+            .into_iter()
+            .map(|task| task.name.to_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_adapters() {
+        let now = Utc::now();
+        let tasks = [
+            Task::new("release").with_due_date(now - Duration::hours(1)),
+            Task::new("release/notes").with_due_date(now + Duration::days(3)),
+            Task::new("release/tag")
+                .with_due_date(now + Duration::days(30))
+                .with_tags(["git"]),
+            Task::new("groceries").complete(),
+        ];
+        assert_eq!(
+            names(
+                tasks
+                    .iter()
+                    .pending()
+                    .due_within(Duration::weeks(1))
+                    .collect()
+            ),
+            ["release", "release/notes"]
+        );
+        assert_eq!(names(tasks.iter().overdue().collect()), ["release"]);
+        assert_eq!(names(tasks.iter().completed().collect()), ["groceries"]);
+        assert_eq!(names(tasks.iter().tagged("git").collect()), ["release/tag"]);
+
+        let groups = tasks.iter().grouped_by_parent();
+        assert_eq!(
+            groups.keys().collect::<Vec<_>>(),
+            [&PathBuf::from(""), &PathBuf::from("release")]
+        );
+        assert_eq!(
+            names(groups[&PathBuf::from("release")].clone()),
+            ["release/notes", "release/tag"]
+        );
+    }
+}
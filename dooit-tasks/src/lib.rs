@@ -1,3 +1,35 @@
+#[cfg(feature = "tokio")]
+pub mod async_store;
+#[cfg(feature = "fs")]
+pub mod conflicts;
+pub mod deps;
+#[cfg(feature = "fs")]
 pub mod dirs;
+pub mod duration;
+pub mod error;
+pub mod events;
+pub mod format;
+#[cfg(feature = "fs")]
+pub mod index;
+pub mod iter;
+pub mod merge;
+pub mod score;
+pub mod store;
+#[cfg(feature = "sync")]
+pub mod sync;
 pub mod tasks;
-pub use tasks::{Task, SortMode, Urgency};
+pub mod urgency;
+#[cfg(feature = "tokio")]
+pub use async_store::AsyncTaskStore;
+pub use duration::HumanDuration;
+pub use error::{Error, Result};
+pub use events::{EventKind, TaskEvent};
+pub use format::TaskFormat;
+pub use iter::TaskIterExt;
+pub use merge::{FieldChange, MergeOptions, Merged};
+pub use score::{urgency_score, OrderedByScore, ScoreConfig};
+#[cfg(feature = "fs")]
+pub use store::DirStore;
+pub use store::{ListFilter, Page, TaskStore};
+pub use tasks::{IconSet, Occurrence, SortMode, Status, Task, TimeEntry};
+pub use urgency::{Urgency, UrgencyLevel};
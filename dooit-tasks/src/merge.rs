@@ -0,0 +1,651 @@
+//! Field level comparison and three-way merging of tasks
+//!
+//! Lists (tags, links, dependencies, attachments, occurrences and the time log) are merged as sets:
+//! everything either side added is kept and everything either side removed is dropped. Other
+//! fields take the value of the side that changed it, if both changed it to different values the
+//! result keeps ours and reports theirs as a conflict.
+
+use std::{fmt::Display, path::PathBuf};
+
+use chrono::{DateTime, Utc};
+
+use crate::{HumanDuration, Occurrence, Status, Task, TimeEntry, Urgency};
+
+/// A field that differs between two versions of a task
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FieldChange {
+    Name {
+        old: PathBuf,
+        new: PathBuf,
+    },
+    Description {
+        old: Option<String>,
+        new: Option<String>,
+    },
+    Due {
+        old: Option<DateTime<Utc>>,
+        new: Option<DateTime<Utc>>,
+    },
+    Urgency {
+        old: Urgency,
+        new: Urgency,
+    },
+    Completed {
+        old: bool,
+        new: bool,
+    },
+    CompletedAt {
+        old: Option<DateTime<Utc>>,
+        new: Option<DateTime<Utc>>,
+    },
+    Status {
+        old: Status,
+        new: Status,
+    },
+    Tags {
+        added: Vec<String>,
+        removed: Vec<String>,
+    },
+    Links {
+        added: Vec<String>,
+        removed: Vec<String>,
+    },
+    DependsOn {
+        added: Vec<PathBuf>,
+        removed: Vec<PathBuf>,
+    },
+    Wait {
+        old: Option<DateTime<Utc>>,
+        new: Option<DateTime<Utc>>,
+    },
+    Scheduled {
+        old: Option<DateTime<Utc>>,
+        new: Option<DateTime<Utc>>,
+    },
+    Review {
+        old: Option<DateTime<Utc>>,
+        new: Option<DateTime<Utc>>,
+    },
+    Estimate {
+        old: Option<HumanDuration>,
+        new: Option<HumanDuration>,
+    },
+    Assignee {
+        old: Option<String>,
+        new: Option<String>,
+    },
+    Recur {
+        old: Option<HumanDuration>,
+        new: Option<HumanDuration>,
+    },
+    Habit {
+        old: bool,
+        new: bool,
+    },
+    Attachments {
+        added: Vec<String>,
+        removed: Vec<String>,
+    },
+    Occurrences {
+        added: Vec<Occurrence>,
+        removed: Vec<Occurrence>,
+    },
+    TimeLog {
+        added: Vec<TimeEntry>,
+        removed: Vec<TimeEntry>,
+    },
+}
+
+impl FieldChange {
+    /// Name of the changed field, as it appears in task files
+    pub fn field(&self) -> &'static str {
+        match self {
+            FieldChange::Name { .. } => "name",
+            FieldChange::Description { .. } => "description",
+            FieldChange::Due { .. } => "due",
+            FieldChange::Urgency { .. } => "urgency",
+            FieldChange::Completed { .. } => "completed",
+            FieldChange::CompletedAt { .. } => "completed_at",
+            FieldChange::Status { .. } => "status",
+            FieldChange::Tags { .. } => "tags",
+            FieldChange::Links { .. } => "links",
+            FieldChange::DependsOn { .. } => "depends_on",
+            FieldChange::Wait { .. } => "wait",
+            FieldChange::Scheduled { .. } => "scheduled",
+            FieldChange::Review { .. } => "review",
+            FieldChange::Estimate { .. } => "estimate",
+            FieldChange::Assignee { .. } => "assignee",
+            FieldChange::Recur { .. } => "recur",
+            FieldChange::Habit { .. } => "habit",
+            FieldChange::Attachments { .. } => "attachments",
+            FieldChange::Occurrences { .. } => "occurrences",
+            FieldChange::TimeLog { .. } => "time_log",
+        }
+    }
+
+    /// Make the change to `task`: set the field to the new value, or add and remove the elements
+    /// of a list
+    pub fn apply(&self, task: &mut Task) {
+        fn update<T: PartialEq + Clone>(values: &mut Vec<T>, added: &[T], removed: &[T]) {
+            values.retain(|value| !removed.contains(value));
+            for value in added {
+                if !values.contains(value) {
+                    values.push(value.clone());
+                }
+            }
+        }
+
+        match self.clone() {
+            FieldChange::Name { new, .. } => task.name = new,
+            FieldChange::Description { new, .. } => task.description = new,
+            FieldChange::Due { new, .. } => task.due = new,
+            FieldChange::Urgency { new, .. } => task.urgency = new,
+            FieldChange::Completed { new, .. } => task.completed = new,
+            FieldChange::CompletedAt { new, .. } => task.completed_at = new,
+            FieldChange::Status { new, .. } => task.status = new,
+            FieldChange::Tags { added, removed } => update(&mut task.tags, &added, &removed),
+            FieldChange::Links { added, removed } => update(&mut task.links, &added, &removed),
+            FieldChange::DependsOn { added, removed } => {
+                update(&mut task.depends_on, &added, &removed)
+            }
+            FieldChange::Wait { new, .. } => task.wait = new,
+            FieldChange::Scheduled { new, .. } => task.scheduled = new,
+            FieldChange::Review { new, .. } => task.review = new,
+            FieldChange::Estimate { new, .. } => task.estimate = new,
+            FieldChange::Assignee { new, .. } => task.assignee = new,
+            FieldChange::Recur { new, .. } => task.recur = new,
+            FieldChange::Habit { new, .. } => task.habit = new,
+            FieldChange::Attachments { added, removed } => {
+                update(&mut task.attachments, &added, &removed)
+            }
+            FieldChange::Occurrences { added, removed } => {
+                update(&mut task.occurrences, &added, &removed)
+            }
+            FieldChange::TimeLog { added, removed } => update(&mut task.time_log, &added, &removed),
+        }
+    }
+}
+
+fn optional(value: &Option<impl Display>) -> String {
+    value
+        .as_ref()
+        .map_or_else(|| "none".to_string(), ToString::to_string)
+}
+
+fn list(values: impl IntoIterator<Item = impl Display>) -> String {
+    values
+        .into_iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl FieldChange {
+    /// The old and the new value as text, for lists the elements only the old or the new one has
+    pub fn sides(&self) -> (String, String) {
+        match self {
+            FieldChange::Name { old, new } => {
+                (old.display().to_string(), new.display().to_string())
+            }
+            FieldChange::Description { old, new } => (optional(old), optional(new)),
+            FieldChange::Due { old, new }
+            | FieldChange::CompletedAt { old, new }
+            | FieldChange::Wait { old, new }
+            | FieldChange::Scheduled { old, new }
+            | FieldChange::Review { old, new } => (optional(old), optional(new)),
+            FieldChange::Urgency { old, new } => (format!("{old:?}"), format!("{new:?}")),
+            FieldChange::Completed { old, new } | FieldChange::Habit { old, new } => {
+                (old.to_string(), new.to_string())
+            }
+            FieldChange::Status { old, new } => (old.to_string(), new.to_string()),
+            FieldChange::Tags { added, removed }
+            | FieldChange::Links { added, removed }
+            | FieldChange::Attachments { added, removed } => (list(removed), list(added)),
+            FieldChange::DependsOn { added, removed } => {
+                let paths = |paths: &[PathBuf]| list(paths.iter().map(|path| path.display()));
+                (paths(removed), paths(added))
+            }
+            FieldChange::Estimate { old, new } => (optional(old), optional(new)),
+            FieldChange::Assignee { old, new } => (optional(old), optional(new)),
+            FieldChange::Recur { old, new } => (optional(old), optional(new)),
+            FieldChange::Occurrences { added, removed } => {
+                let occurrences = |occurrences: &[Occurrence]| {
+                    list(
+                        occurrences
+                            .iter()
+                            .map(|occurrence| match occurrence.completed_at {
+                                Some(completed_at) => {
+                                    format!("{} (done {completed_at})", occurrence.due)
+                                }
+                                None => format!("{} (missed)", occurrence.due),
+                            }),
+                    )
+                };
+                (occurrences(removed), occurrences(added))
+            }
+            FieldChange::TimeLog { added, removed } => {
+                let entries = |entries: &[TimeEntry]| {
+                    list(
+                        entries
+                            .iter()
+                            .map(|entry| format!("{}..{}", entry.start, entry.end)),
+                    )
+                };
+                (entries(removed), entries(added))
+            }
+        }
+    }
+}
+
+impl Display for FieldChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let field = self.field();
+        let (old, new) = self.sides();
+        match self {
+            FieldChange::Tags { .. }
+            | FieldChange::Links { .. }
+            | FieldChange::DependsOn { .. }
+            | FieldChange::Attachments { .. }
+            | FieldChange::Occurrences { .. }
+            | FieldChange::TimeLog { .. } => write!(f, "{field}: +[{new}] -[{old}]"),
+            _ => write!(f, "{field}: {old} -> {new}"),
+        }
+    }
+}
+
+/// How to resolve fields both sides of a merge changed
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MergeOptions {
+    /// Take the later due date instead of reporting a conflict
+    pub latest_due_wins: bool,
+}
+
+/// Result of [`Task::merge`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Merged {
+    pub task: Task,
+    /// Changes from ours to theirs that couldn't be applied, ours was kept for these fields
+    pub conflicts: Vec<FieldChange>,
+}
+
+/// Push the change from `old` to `new` if they differ
+fn scalar<T: PartialEq + Clone>(
+    old: &T,
+    new: &T,
+    change: impl FnOnce(T, T) -> FieldChange,
+    changes: &mut Vec<FieldChange>,
+) {
+    if old != new {
+        changes.push(change(old.clone(), new.clone()));
+    }
+}
+
+/// Push the elements added to and removed from `old` if there are any
+fn set<T: PartialEq + Clone>(
+    old: &[T],
+    new: &[T],
+    change: impl FnOnce(Vec<T>, Vec<T>) -> FieldChange,
+    changes: &mut Vec<FieldChange>,
+) {
+    let missing_from = |values: &[T], other: &[T]| -> Vec<T> {
+        values
+            .iter()
+            .filter(|value| !other.contains(value))
+            .cloned()
+            .collect()
+    };
+
+    let added = missing_from(new, old);
+    let removed = missing_from(old, new);
+    if !added.is_empty() || !removed.is_empty() {
+        changes.push(change(added, removed));
+    }
+}
+
+/// Three-way merge of a single value, keeps ours and records a conflict if both sides changed it
+fn merge_scalar<T: PartialEq + Clone>(
+    base: &T,
+    ours: &T,
+    theirs: &T,
+    change: impl FnOnce(T, T) -> FieldChange,
+    conflicts: &mut Vec<FieldChange>,
+) -> T {
+    if ours == base {
+        theirs.clone()
+    } else {
+        if theirs != base {
+            scalar(ours, theirs, change, conflicts);
+        }
+        ours.clone()
+    }
+}
+
+/// Three-way merge of a list, keeping additions and removals from both sides
+fn merge_set<T: PartialEq + Clone>(base: &[T], ours: &[T], theirs: &[T]) -> Vec<T> {
+    let mut merged: Vec<T> = ours
+        .iter()
+        .filter(|value| !base.contains(value) || theirs.contains(value))
+        .cloned()
+        .collect();
+    for value in theirs {
+        if !base.contains(value) && !merged.contains(value) {
+            merged.push(value.clone());
+        }
+    }
+    merged
+}
+
+impl Task {
+    /// Fields that changed from `self` to `other`
+    pub fn diff(&self, other: &Task) -> Vec<FieldChange> {
+        let mut changes = Vec::new();
+
+        scalar(
+            &self.name,
+            &other.name,
+            |old, new| FieldChange::Name { old, new },
+            &mut changes,
+        );
+        scalar(
+            &self.description,
+            &other.description,
+            |old, new| FieldChange::Description { old, new },
+            &mut changes,
+        );
+        scalar(
+            &self.due,
+            &other.due,
+            |old, new| FieldChange::Due { old, new },
+            &mut changes,
+        );
+        scalar(
+            &self.urgency,
+            &other.urgency,
+            |old, new| FieldChange::Urgency { old, new },
+            &mut changes,
+        );
+        scalar(
+            &self.completed,
+            &other.completed,
+            |old, new| FieldChange::Completed { old, new },
+            &mut changes,
+        );
+        scalar(
+            &self.completed_at,
+            &other.completed_at,
+            |old, new| FieldChange::CompletedAt { old, new },
+            &mut changes,
+        );
+        scalar(
+            &self.status,
+            &other.status,
+            |old, new| FieldChange::Status { old, new },
+            &mut changes,
+        );
+        set(
+            &self.tags,
+            &other.tags,
+            |added, removed| FieldChange::Tags { added, removed },
+            &mut changes,
+        );
+        set(
+            &self.links,
+            &other.links,
+            |added, removed| FieldChange::Links { added, removed },
+            &mut changes,
+        );
+        set(
+            &self.depends_on,
+            &other.depends_on,
+            |added, removed| FieldChange::DependsOn { added, removed },
+            &mut changes,
+        );
+        scalar(
+            &self.wait,
+            &other.wait,
+            |old, new| FieldChange::Wait { old, new },
+            &mut changes,
+        );
+        scalar(
+            &self.scheduled,
+            &other.scheduled,
+            |old, new| FieldChange::Scheduled { old, new },
+            &mut changes,
+        );
+        scalar(
+            &self.review,
+            &other.review,
+            |old, new| FieldChange::Review { old, new },
+            &mut changes,
+        );
+        scalar(
+            &self.estimate,
+            &other.estimate,
+            |old, new| FieldChange::Estimate { old, new },
+            &mut changes,
+        );
+        scalar(
+            &self.assignee,
+            &other.assignee,
+            |old, new| FieldChange::Assignee { old, new },
+            &mut changes,
+        );
+        scalar(
+            &self.recur,
+            &other.recur,
+            |old, new| FieldChange::Recur { old, new },
+            &mut changes,
+        );
+        scalar(
+            &self.habit,
+            &other.habit,
+            |old, new| FieldChange::Habit { old, new },
+            &mut changes,
+        );
+        set(
+            &self.attachments,
+            &other.attachments,
+            |added, removed| FieldChange::Attachments { added, removed },
+            &mut changes,
+        );
+        set(
+            &self.occurrences,
+            &other.occurrences,
+            |added, removed| FieldChange::Occurrences { added, removed },
+            &mut changes,
+        );
+        set(
+            &self.time_log,
+            &other.time_log,
+            |added, removed| FieldChange::TimeLog { added, removed },
+            &mut changes,
+        );
+
+        changes
+    }
+
+    /// Merge the changes `ours` and `theirs` made to `base` with the default [`MergeOptions`]
+    pub fn merge(base: &Task, ours: &Task, theirs: &Task) -> Merged {
+        Self::merge_with(base, ours, theirs, MergeOptions::default())
+    }
+
+    /// Merge the changes `ours` and `theirs` made to `base`
+    ///
+    /// The name is always taken from `ours`.
+    pub fn merge_with(base: &Task, ours: &Task, theirs: &Task, options: MergeOptions) -> Merged {
+        let mut conflicts = Vec::new();
+
+        let due = match (ours.due, theirs.due) {
+            (Some(ours), Some(theirs)) if options.latest_due_wins => Some(ours.max(theirs)),
+            _ => merge_scalar(
+                &base.due,
+                &ours.due,
+                &theirs.due,
+                |old, new| FieldChange::Due { old, new },
+                &mut conflicts,
+            ),
+        };
+
+        let task = Task {
+            name: ours.name.clone(),
+            description: merge_scalar(
+                &base.description,
+                &ours.description,
+                &theirs.description,
+                |old, new| FieldChange::Description { old, new },
+                &mut conflicts,
+            ),
+            due,
+            urgency: merge_scalar(
+                &base.urgency,
+                &ours.urgency,
+                &theirs.urgency,
+                |old, new| FieldChange::Urgency { old, new },
+                &mut conflicts,
+            ),
+            completed: merge_scalar(
+                &base.completed,
+                &ours.completed,
+                &theirs.completed,
+                |old, new| FieldChange::Completed { old, new },
+                &mut conflicts,
+            ),
+            completed_at: merge_scalar(
+                &base.completed_at,
+                &ours.completed_at,
+                &theirs.completed_at,
+                |old, new| FieldChange::CompletedAt { old, new },
+                &mut conflicts,
+            ),
+            status: merge_scalar(
+                &base.status,
+                &ours.status,
+                &theirs.status,
+                |old, new| FieldChange::Status { old, new },
+                &mut conflicts,
+            ),
+            tags: merge_set(&base.tags, &ours.tags, &theirs.tags),
+            links: merge_set(&base.links, &ours.links, &theirs.links),
+            depends_on: merge_set(&base.depends_on, &ours.depends_on, &theirs.depends_on),
+            wait: merge_scalar(
+                &base.wait,
+                &ours.wait,
+                &theirs.wait,
+                |old, new| FieldChange::Wait { old, new },
+                &mut conflicts,
+            ),
+            scheduled: merge_scalar(
+                &base.scheduled,
+                &ours.scheduled,
+                &theirs.scheduled,
+                |old, new| FieldChange::Scheduled { old, new },
+                &mut conflicts,
+            ),
+            review: merge_scalar(
+                &base.review,
+                &ours.review,
+                &theirs.review,
+                |old, new| FieldChange::Review { old, new },
+                &mut conflicts,
+            ),
+            estimate: merge_scalar(
+                &base.estimate,
+                &ours.estimate,
+                &theirs.estimate,
+                |old, new| FieldChange::Estimate { old, new },
+                &mut conflicts,
+            ),
+            assignee: merge_scalar(
+                &base.assignee,
+                &ours.assignee,
+                &theirs.assignee,
+                |old, new| FieldChange::Assignee { old, new },
+                &mut conflicts,
+            ),
+            recur: merge_scalar(
+                &base.recur,
+                &ours.recur,
+                &theirs.recur,
+                |old, new| FieldChange::Recur { old, new },
+                &mut conflicts,
+            ),
+            habit: merge_scalar(
+                &base.habit,
+                &ours.habit,
+                &theirs.habit,
+                |old, new| FieldChange::Habit { old, new },
+                &mut conflicts,
+            ),
+            attachments: merge_set(&base.attachments, &ours.attachments, &theirs.attachments),
+            occurrences: merge_set(&base.occurrences, &ours.occurrences, &theirs.occurrences),
+            time_log: merge_set(&base.time_log, &ours.time_log, &theirs.time_log),
+        };
+
+        Merged { task, conflicts }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{Duration, TimeZone, Utc};
+    use pretty_assertions::assert_eq;
+
+    use super::{FieldChange, MergeOptions};
+    use crate::{Task, Urgency};
+
+    #[test]
+    fn test_merge() {
+        let due = Utc.with_ymd_and_hms(2024, 5, 1, 9, 0, 0).unwrap();
+        let base = Task::new("a").with_tags(["x", "y"]).with_due_date(due);
+        let ours = base
+            .clone()
+            .with_tags(["x", "ours"])
+            .with_urgency(Urgency::High)
+            .with_due_date(due + Duration::days(1));
+        let theirs = base
+            .clone()
+            .with_tags(["x", "y", "theirs"])
+            .with_description("notes".to_string())
+            .with_due_date(due + Duration::days(2));
+
+        assert_eq!(
+            base.diff(&theirs),
+            [
+                FieldChange::Description {
+                    old: None,
+                    new: Some("notes".to_string())
+                },
+                FieldChange::Due {
+                    old: Some(due),
+                    new: Some(due + Duration::days(2))
+                },
+                FieldChange::Tags {
+                    added: vec!["theirs".to_string()],
+                    removed: vec![]
+                },
+            ]
+        );
+
+        let merged = Task::merge(&base, &ours, &theirs);
+        let expected = ours
+            .clone()
+            .with_tags(["x", "ours", "theirs"])
+            .with_description("notes".to_string());
+        assert_eq!(merged.task, expected);
+        assert_eq!(
+            merged.conflicts,
+            [FieldChange::Due {
+                old: ours.due,
+                new: theirs.due
+            }]
+        );
+
+        let options = MergeOptions {
+            latest_due_wins: true,
+        };
+        let merged = Task::merge_with(&base, &ours, &theirs, options);
+        assert_eq!(merged.task.due, theirs.due);
+        assert_eq!(merged.conflicts, []);
+    }
+}
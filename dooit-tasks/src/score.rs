@@ -0,0 +1,184 @@
+//! A single number ranking how much a task needs attention, built from weighted coefficients
+//!
+//! The score adds up:
+//!
+//! - the coefficient of the task's urgency (custom urgencies without one use the coefficient of
+//!   the closest built-in urgency below them, see [`Urgency::builtin`])
+//! - `due` times how close the task is to its due date, from 0.2 two weeks or more before it's due
+//!   to 1 a week or more after it's overdue
+//! - the coefficient of its status
+//! - the coefficient of every tag that has one
+//!
+//! Completed tasks always score 0.
+
+use std::{cmp::Ordering, collections::BTreeMap};
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{Status, Task, Urgency};
+
+/// Coefficients of [`urgency_score`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScoreConfig {
+    pub low: f64,
+    pub medium: f64,
+    pub high: f64,
+    /// Coefficients of custom urgencies, by name
+    pub urgencies: BTreeMap<String, f64>,
+    /// Weight of the due date, multiplied by how close the task is to being due
+    pub due: f64,
+    pub started: f64,
+    pub waiting: f64,
+    /// Extra score for tasks with these tags (use negative values to push them down)
+    pub tags: BTreeMap<String, f64>,
+}
+
+impl Default for ScoreConfig {
+    fn default() -> Self {
+        Self {
+            low: 0.0,
+            medium: 3.9,
+            high: 6.0,
+            urgencies: BTreeMap::new(),
+            due: 12.0,
+            started: 4.0,
+            waiting: -3.0,
+            tags: BTreeMap::new(),
+        }
+    }
+}
+
+impl ScoreConfig {
+    fn for_urgency(&self, urgency: &Urgency) -> f64 {
+        match urgency {
+            Urgency::Low => self.low,
+            Urgency::Medium => self.medium,
+            Urgency::High => self.high,
+            Urgency::Custom(name) => match self.urgencies.get(name) {
+                Some(score) => *score,
+                None => self.for_urgency(&urgency.builtin()),
+            },
+        }
+    }
+
+    fn for_status(&self, status: Status) -> f64 {
+        match status {
+            Status::Pending => 0.0,
+            Status::Started => self.started,
+            Status::Waiting => self.waiting,
+        }
+    }
+}
+
+/// How close `due` is, from 0.2 (due in two weeks or more) to 1.0 (overdue by a week or more)
+fn due_proximity(due: DateTime<Utc>, now: DateTime<Utc>) -> f64 {
+    let days_overdue = (now - due).num_seconds() as f64 / Duration::days(1).num_seconds() as f64;
+    let days_overdue = days_overdue.clamp(-14.0, 7.0);
+    // Linear between the two extremes
+    0.2 + (days_overdue + 14.0) * 0.8 / 21.0
+}
+
+/// Score of `task` at `now`, see the [module docs](self)
+pub fn urgency_score_at(task: &Task, config: &ScoreConfig, now: DateTime<Utc>) -> f64 {
+    if task.completed {
+        return 0.0;
+    }
+
+    let due = task
+        .due
+        .map_or(0.0, |due| config.due * due_proximity(due, now));
+    let tags: f64 = task
+        .tags
+        .iter()
+        .filter_map(|tag| config.tags.get(tag))
+        .sum();
+
+    config.for_urgency(&task.urgency) + due + config.for_status(task.status) + tags
+}
+
+/// Score of `task` right now, see the [module docs](self)
+pub fn urgency_score(task: &Task, config: &ScoreConfig) -> f64 {
+    urgency_score_at(task, config, Utc::now())
+}
+
+/// A task ordered by its [`urgency_score`], ties are broken by name
+#[derive(Debug, Clone)]
+pub struct OrderedByScore {
+    pub score: f64,
+    pub task: Task,
+}
+
+impl OrderedByScore {
+    pub fn new(task: Task, config: &ScoreConfig) -> Self {
+        Self {
+            score: urgency_score(&task, config),
+            task,
+        }
+    }
+}
+
+impl PartialEq for OrderedByScore {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for OrderedByScore {}
+
+impl PartialOrd for OrderedByScore {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OrderedByScore {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score
+            .total_cmp(&other.score)
+            .then_with(|| self.task.name.cmp(&other.task.name))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{Duration, TimeZone, Utc};
+    use pretty_assertions::assert_eq;
+
+    use super::{urgency_score_at, ScoreConfig};
+    use crate::{Status, Task, Urgency};
+
+    #[test]
+    fn test_urgency_score() {
+        let now = Utc.with_ymd_and_hms(2024, 5, 1, 9, 0, 0).unwrap();
+        let config = ScoreConfig {
+            tags: [("later".to_string(), -1.0)].into(),
+            ..ScoreConfig::default()
+        };
+        let score = |task: &Task| urgency_score_at(task, &config, now);
+
+        assert_eq!(score(&Task::new("a")), 0.0);
+        assert_eq!(score(&Task::new("a").with_urgency(Urgency::High)), 6.0);
+        assert_eq!(
+            score(&Task::new("a").with_due_date(now + Duration::weeks(3))),
+            12.0 * 0.2
+        );
+        assert_eq!(
+            score(&Task::new("a").with_due_date(now - Duration::weeks(1))),
+            12.0
+        );
+        assert_eq!(
+            score(
+                &Task::new("a")
+                    .with_status(Status::Started)
+                    .with_tags(["later"])
+            ),
+            3.0
+        );
+        assert_eq!(
+            score(&Task::new("a").with_urgency(Urgency::High).complete()),
+            0.0
+        );
+    }
+}
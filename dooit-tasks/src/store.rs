@@ -0,0 +1,682 @@
+use std::path::{Path, PathBuf};
+#[cfg(feature = "fs")]
+use std::{collections::HashSet, ffi::OsStr, path::Component};
+
+use chrono::{DateTime, Utc};
+
+#[cfg(feature = "fs")]
+use crate::{dirs, index::Index, Error, TaskFormat};
+use crate::{tasks::sort_tasks_with, Result, ScoreConfig, SortMode, Task};
+
+/// Which tasks [`TaskStore::list_page`] lists, by default the pending ones that aren't waiting or
+/// overdue, like `dooit-rs list`
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ListFilter {
+    /// Only this task and its subtasks, every task if empty
+    pub under: PathBuf,
+    /// Include completed tasks
+    pub completed: bool,
+    /// Include tasks past their due date
+    pub overdue: bool,
+    /// How the score sort modes rank the tasks
+    pub score: ScoreConfig,
+}
+
+impl ListFilter {
+    pub fn matches(&self, task: &Task, now: DateTime<Utc>) -> bool {
+        task.name.starts_with(&self.under)
+            && (!task.completed || self.completed)
+            && !task.is_waiting(now)
+            && (task.due.is_none_or(|due| due >= now) || self.overdue)
+    }
+}
+
+/// A page of the tasks listed by [`TaskStore::list_page`]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Page {
+    pub tasks: Vec<Task>,
+    /// Number of tasks matching the filter, on every page
+    pub total: usize,
+}
+
+/// Persistent storage for tasks, addressed by their name
+pub trait TaskStore {
+    /// Load every task in the store
+    fn load_all(&self) -> Result<Vec<Task>>;
+
+    /// Load the task called `name`, if it exists
+    fn load(&self, name: &Path) -> Result<Option<Task>>;
+
+    /// When the task called `name` was last changed, if it exists and the store keeps track
+    ///
+    /// Used to find the newest side of a conflict when syncing, `None` by default.
+    fn modified(&self, _name: &Path) -> Result<Option<DateTime<Utc>>> {
+        Ok(None)
+    }
+
+    /// Create or overwrite a task
+    fn save(&self, task: &Task) -> Result<()>;
+
+    /// Create or overwrite every task in `tasks`
+    ///
+    /// Saves them one by one by default, stores that can write many tasks at once for less than
+    /// that (like in a single transaction) should override this.
+    fn save_all(&self, tasks: &[Task]) -> Result<()> {
+        tasks.iter().try_for_each(|task| self.save(task))
+    }
+
+    /// Remove the task called `name`, returns `Ok(false)` if it didn't exist
+    fn remove(&self, name: &Path) -> Result<bool>;
+
+    /// Save `task`, which was called `from` before, and remove the task called `from`
+    ///
+    /// Stores keeping more than the task itself (like the attachments of a [`DirStore`]) should
+    /// override this to move it along.
+    fn rename(&self, from: &Path, task: &Task) -> Result<()> {
+        self.save(task)?;
+        if from != task.name {
+            self.remove(from)?;
+        }
+        Ok(())
+    }
+
+    /// Load `parent` and its subtasks, every task if `parent` is empty
+    ///
+    /// Loads every task and keeps the ones under `parent` by default, stores that can find them
+    /// directly should override this.
+    fn load_under(&self, parent: &Path) -> Result<Vec<Task>> {
+        let mut tasks = self.load_all()?;
+        tasks.retain(|task| task.name.starts_with(parent));
+        Ok(tasks)
+    }
+
+    /// [`load_under`](Self::load_under) for listing tasks, skipping their time logs and habit
+    /// occurrences
+    ///
+    /// The `time_log` and `occurrences` of the tasks are always empty, so they must not be saved
+    /// back. Stores that can't skip reading them drop them after loading.
+    fn load_summaries_under(&self, parent: &Path) -> Result<Vec<Task>> {
+        let mut tasks = self.load_under(parent)?;
+        for task in &mut tasks {
+            task.occurrences.clear();
+            task.time_log.clear();
+        }
+        Ok(tasks)
+    }
+
+    /// The tasks matching `filter` sorted by `sort`, skipping the first `offset` and keeping at
+    /// most `limit`
+    ///
+    /// The tasks are summaries, see [`load_summaries_under`](Self::load_summaries_under). Loads,
+    /// filters and sorts every task under [`ListFilter::under`] by default, stores that can filter
+    /// and sort in their queries (like SQL databases) should override this.
+    fn list_page(
+        &self,
+        filter: &ListFilter,
+        sort: SortMode,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Page> {
+        let now = Utc::now();
+        let mut tasks = self.load_summaries_under(&filter.under)?;
+        tasks.retain(|task| filter.matches(task, now));
+        let total = tasks.len();
+        let tasks = sort_tasks_with(tasks, sort, &filter.score)
+            .into_iter()
+            .skip(offset)
+            .take(limit)
+            .collect();
+        Ok(Page { tasks, total })
+    }
+}
+
+/// Whether the file or directory at `path` can back tasks, warns about the ones that can't
+///
+/// Task names are kept as strings in the task files, so a file whose name isn't valid UTF-8
+/// wasn't written by a store, and loading it would give its task a name that doesn't lead back to
+/// it.
+#[cfg(feature = "fs")]
+pub(crate) fn valid_entry(path: &Path) -> bool {
+    if path.file_name().and_then(OsStr::to_str).is_some() {
+        return true;
+    }
+    tracing::warn!("skipping {}: its name isn't valid UTF-8", path.display());
+    false
+}
+
+/// Directory inside a store's root holding its archived tasks
+#[cfg(feature = "fs")]
+const ARCHIVE_DIR: &str = ".archive";
+
+/// Directory inside a task's directory holding its attachments
+#[cfg(feature = "fs")]
+const ATTACHMENTS_DIR: &str = ".attachments";
+
+/// Stores each task as a file (TOML by default), subtasks live in subdirectories of their parent
+///
+/// Hidden directories (e.g. `.git` or the archive) and files in other formats are skipped when
+/// loading tasks.
+#[cfg(feature = "fs")]
+#[derive(Debug, Clone)]
+pub struct DirStore {
+    root: PathBuf,
+    format: TaskFormat,
+}
+
+#[cfg(feature = "fs")]
+impl DirStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            format: TaskFormat::default(),
+        }
+    }
+
+    /// Store tasks in `format` instead of the default one
+    pub fn with_format(mut self, format: TaskFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Open the store in the user's data directory
+    pub fn open_default() -> Result<Self> {
+        dirs::get_data_dir()
+            .map(Self::new)
+            .ok_or(Error::MissingDataDir)
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn format(&self) -> TaskFormat {
+        self.format
+    }
+
+    /// Store holding the tasks archived from this one
+    pub fn archive(&self) -> DirStore {
+        DirStore::new(self.root.join(ARCHIVE_DIR)).with_format(self.format)
+    }
+
+    /// Move the task called `name` into the [archive](Self::archive), returns `Ok(false)` if it
+    /// didn't exist
+    ///
+    /// An archived task with the same name is overwritten. Only the task is moved, its subtasks stay
+    /// where they are, so archive them first.
+    pub fn archive_task(&self, name: &Path) -> Result<bool> {
+        let moved = self.move_task(&self.archive(), name)?;
+        if moved {
+            self.record(&self.task_path(name)?, None);
+        }
+        Ok(moved)
+    }
+
+    /// Move the task called `name` back out of the [archive](Self::archive), returns `Ok(false)`
+    /// if it isn't archived
+    ///
+    /// Undoes [`archive_task`](Self::archive_task), a task with the same name is overwritten.
+    pub fn unarchive_task(&self, name: &Path) -> Result<bool> {
+        let moved = self.archive().move_task(self, name)?;
+        if moved {
+            let task_path = self.task_path(name)?;
+            let contents = std::fs::read(&task_path)?;
+            self.record(&task_path, Some(&contents));
+        }
+        Ok(moved)
+    }
+
+    /// Move the task called `name` and its attachments to `to`, returns `Ok(false)` if it doesn't
+    /// exist
+    fn move_task(&self, to: &DirStore, name: &Path) -> Result<bool> {
+        let moved = to.task_path(name)?;
+        if let Some(parent) = moved.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        match std::fs::rename(self.task_path(name)?, moved) {
+            Ok(()) => {
+                let attachments = self.attachments_dir(name)?;
+                if attachments.exists() {
+                    let moved = to.attachments_dir(name)?;
+                    std::fs::create_dir_all(moved.parent().expect("inside the task's directory"))?;
+                    std::fs::rename(attachments, moved)?;
+                }
+                Ok(true)
+            }
+            Err(err) => match err.kind() {
+                std::io::ErrorKind::NotFound => Ok(false),
+                _ => Err(err.into()),
+            },
+        }
+    }
+
+    /// Path of the file backing the task called `name`
+    ///
+    /// Fails if `name` would escape the store (absolute paths, `..`, etc.) or isn't valid UTF-8.
+    pub fn task_path(&self, name: &Path) -> Result<PathBuf> {
+        let is_relative = name
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+
+        if !is_relative || name.as_os_str().is_empty() || name.to_str().is_none() {
+            return Err(Error::InvalidName(name.to_path_buf()));
+        }
+
+        // Appended rather than set, names like `release/1.4` would lose their last part otherwise
+        let mut task_path = self.root.join(name).into_os_string();
+        task_path.push(".");
+        task_path.push(self.format.extension());
+        Ok(task_path.into())
+    }
+
+    /// Directory holding the [attachments](Task::attachments) of the task called `name`
+    ///
+    /// It's a hidden directory among the task's subtasks, so it's skipped when loading them, and
+    /// it's deleted along with the task.
+    pub fn attachments_dir(&self, name: &Path) -> Result<PathBuf> {
+        self.task_path(name)?;
+        Ok(self.root.join(name).join(ATTACHMENTS_DIR))
+    }
+
+    /// Name of the task backed by the file at `path`, the inverse of [`task_path`](Self::task_path)
+    ///
+    /// Returns `None` for paths that don't back a task, like ones in hidden directories or that
+    /// aren't valid UTF-8.
+    pub fn task_name(&self, path: &Path) -> Option<PathBuf> {
+        let relative = path.strip_prefix(&self.root).ok()?;
+        relative.to_str()?;
+        let hidden = relative
+            .components()
+            .any(|component| component.as_os_str().to_string_lossy().starts_with('.'));
+
+        if hidden || relative.extension() != Some(OsStr::new(self.format.extension())) {
+            return None;
+        }
+        Some(relative.with_extension(""))
+    }
+}
+
+#[cfg(feature = "fs")]
+impl TaskStore for DirStore {
+    fn load_all(&self) -> Result<Vec<Task>> {
+        self.load_tree(Path::new(""), false)
+    }
+
+    fn load(&self, name: &Path) -> Result<Option<Task>> {
+        let path = self.task_path(name)?;
+        match std::fs::read(&path) {
+            Ok(contents) => self.parse(path, &contents).map(Some),
+            Err(err) => match err.kind() {
+                std::io::ErrorKind::NotFound => Ok(None),
+                _ => Err(err.into()),
+            },
+        }
+    }
+
+    fn modified(&self, name: &Path) -> Result<Option<DateTime<Utc>>> {
+        let path = self.task_path(name)?;
+        match std::fs::metadata(path) {
+            Ok(metadata) => Ok(Some(metadata.modified()?.into())),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn save(&self, task: &Task) -> Result<()> {
+        let task_path = self.task_path(&task.name)?;
+
+        if let Some(parent) = task_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        // Replace the file in one step, so nobody ever reads half a task
+        let contents = self.serialize(task)?;
+        let tmp = self.tmp_path(&task_path);
+        std::fs::write(&tmp, &contents)?;
+        std::fs::rename(tmp, &task_path)?;
+        self.record(&task_path, Some(&contents));
+        Ok(())
+    }
+
+    fn remove(&self, name: &Path) -> Result<bool> {
+        let task_path = self.task_path(name)?;
+        match std::fs::remove_file(&task_path) {
+            Ok(()) => {
+                self.record(&task_path, None);
+                match std::fs::remove_dir_all(self.attachments_dir(name)?) {
+                    Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                        tracing::warn!(
+                            "failed to delete the attachments of {}: {err}",
+                            name.display()
+                        )
+                    }
+                    _ => {}
+                }
+                // Drop the directories of its subtasks and of its siblings if they're empty now
+                for dir in name
+                    .ancestors()
+                    .take(2)
+                    .filter(|dir| !dir.as_os_str().is_empty())
+                {
+                    let _ = std::fs::remove_dir(self.root.join(dir));
+                }
+                Ok(true)
+            }
+            Err(err) => match err.kind() {
+                std::io::ErrorKind::NotFound => Ok(false),
+                _ => Err(err.into()),
+            },
+        }
+    }
+
+    /// Creates every directory once and updates the index once, instead of once per task
+    ///
+    /// Tasks written before one fails stay written (and indexed).
+    fn save_all(&self, tasks: &[Task]) -> Result<()> {
+        let mut index = Index::default();
+        let mut dirs = HashSet::new();
+        let written = tasks.iter().try_for_each(|task| {
+            let task_path = self.task_path(&task.name)?;
+            if let Some(parent) = task_path.parent() {
+                if dirs.insert(parent.to_path_buf()) {
+                    std::fs::create_dir_all(parent)?;
+                }
+            }
+
+            let contents = self.serialize(task)?;
+            let tmp = self.tmp_path(&task_path);
+            std::fs::write(&tmp, &contents)?;
+            std::fs::rename(tmp, &task_path)?;
+            if let Ok(relative) = task_path.strip_prefix(&self.root) {
+                index.set(relative.to_path_buf(), Some(&contents));
+            }
+            Ok(())
+        });
+
+        if let Err(err) = self.update_index(|old| old.extend(index)) {
+            tracing::warn!("failed to index the saved tasks: {err}");
+        }
+        written
+    }
+
+    /// Moves the attachments along, subtasks stay where they are
+    fn rename(&self, from: &Path, task: &Task) -> Result<()> {
+        self.save(task)?;
+        if from == task.name {
+            return Ok(());
+        }
+        let attachments = self.attachments_dir(from)?;
+        if attachments.exists() {
+            let moved = self.attachments_dir(&task.name)?;
+            std::fs::create_dir_all(moved.parent().expect("inside the task's directory"))?;
+            std::fs::rename(attachments, moved)?;
+        }
+        self.remove(from)?;
+        Ok(())
+    }
+
+    /// Only reads the file of `parent` and the directory of its subtasks
+    fn load_under(&self, parent: &Path) -> Result<Vec<Task>> {
+        self.load_tree(parent, false)
+    }
+
+    /// Stops reading TOML files at the time log and occurrences, see
+    /// [`TaskFormat::deserialize_summary`]
+    fn load_summaries_under(&self, parent: &Path) -> Result<Vec<Task>> {
+        self.load_tree(parent, true)
+    }
+}
+
+#[cfg(feature = "fs")]
+impl DirStore {
+    /// Where a task is written before replacing `task_path`, ignored when loading tasks
+    pub(crate) fn tmp_path(&self, task_path: &Path) -> PathBuf {
+        task_path.with_extension(format!(
+            "{}.{}",
+            self.format.extension(),
+            std::process::id()
+        ))
+    }
+
+    pub(crate) fn serialize(&self, task: &Task) -> Result<Vec<u8>> {
+        self.format
+            .serialize(task)
+            .map_err(|source| Error::Serialize {
+                name: task.name.clone(),
+                format: self.format,
+                source,
+            })
+    }
+
+    pub(crate) fn parse(&self, path: PathBuf, contents: &[u8]) -> Result<Task> {
+        self.format
+            .deserialize(contents)
+            .map_err(|source| Error::Parse {
+                path,
+                format: self.format,
+                source,
+            })
+    }
+
+    /// Like [`parse`](Self::parse) but without the time log and occurrences if `summary`
+    fn parse_file(&self, path: PathBuf, contents: &[u8], summary: bool) -> Result<Task> {
+        if !summary {
+            return self.parse(path, contents);
+        }
+        self.format
+            .deserialize_summary(contents)
+            .map_err(|source| Error::Parse {
+                path,
+                format: self.format,
+                source,
+            })
+    }
+
+    /// Load `parent` and its subtasks (every task if it's empty), see [`Self::parse_file`]
+    fn load_tree(&self, parent: &Path, summaries: bool) -> Result<Vec<Task>> {
+        let start = std::time::Instant::now();
+        let mut tasks = Vec::new();
+
+        if !parent.as_os_str().is_empty() {
+            let path = self.task_path(parent)?;
+            match std::fs::read(&path) {
+                Ok(contents) => tasks.push(self.parse_file(path, &contents, summaries)?),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        let dir = self.root.join(parent);
+        match dir.read_dir() {
+            Ok(_) => tasks.extend(self.load_dir(&dir, summaries)?),
+            Err(err) => match err.kind() {
+                std::io::ErrorKind::NotFound | std::io::ErrorKind::NotADirectory => {}
+                _ => return Err(err.into()),
+            },
+        }
+
+        crate::conflicts::warn_duplicates(&tasks);
+        tracing::debug!(
+            "loaded {} tasks from {} in {:?}",
+            tasks.len(),
+            dir.display(),
+            start.elapsed()
+        );
+        Ok(tasks)
+    }
+
+    fn load_dir(&self, dir: &Path, summaries: bool) -> Result<Vec<Task>> {
+        let mut tasks = vec![];
+
+        for file in dir.read_dir()? {
+            let file = file?;
+            let path = file.path();
+            if !valid_entry(&path) {
+                continue;
+            }
+
+            if path.extension() == Some(OsStr::new(self.format.extension())) {
+                let contents = std::fs::read(&path)?;
+                tasks.push(self.parse_file(path, &contents, summaries)?);
+                continue;
+            }
+
+            let hidden = file.file_name().to_string_lossy().starts_with('.');
+            if hidden || !path.is_dir() {
+                continue;
+            }
+
+            tasks.extend(self.load_dir(&path, summaries)?);
+        }
+
+        Ok(tasks)
+    }
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod test {
+    use std::path::Path;
+
+    use pretty_assertions::assert_eq;
+
+    use super::{DirStore, ListFilter, Page, TaskStore};
+    use crate::{SortMode, Task};
+
+    #[test]
+    fn test_load_under() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let store = DirStore::new(root);
+        for name in [
+            "release",
+            "release/notes",
+            "release/notes/draft",
+            "releases",
+            "groceries",
+        ] {
+            store.save(&Task::new(name)).unwrap();
+        }
+
+        let mut names: Vec<_> = store
+            .load_under(Path::new("release"))
+            .unwrap()
+            .into_iter()
+            .map(|task| task.name)
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            ["release", "release/notes", "release/notes/draft"].map(Path::new)
+        );
+        assert_eq!(store.load_under(Path::new("missing")).unwrap(), []);
+        assert_eq!(store.load_under(Path::new("")).unwrap().len(), 5);
+
+        assert!(store.remove(Path::new("release/notes/draft")).unwrap());
+        assert!(store.remove(Path::new("release/notes")).unwrap());
+        assert!(!root.join("release").exists());
+    }
+
+    #[test]
+    fn test_save_all() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let store = DirStore::new(root);
+        let tasks: Vec<_> = (0..100)
+            .map(|number| Task::new(format!("import/{}/task-{number}", number % 3)))
+            .collect();
+
+        store.save_all(&tasks).unwrap();
+        let mut loaded = store.load_all().unwrap();
+        loaded.sort_by(|a, b| a.name.cmp(&b.name));
+        let mut expected = tasks.clone();
+        expected.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(loaded, expected);
+        assert_eq!(store.verify().unwrap(), []);
+    }
+
+    #[test]
+    fn test_rename() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let store = DirStore::new(root);
+        store.save(&Task::new("draft")).unwrap();
+        let attachments = store.attachments_dir(Path::new("draft")).unwrap();
+        std::fs::create_dir_all(&attachments).unwrap();
+        std::fs::write(attachments.join("notes.txt"), "notes").unwrap();
+
+        let moved = Task::new("release/notes");
+        store.rename(Path::new("draft"), &moved).unwrap();
+        assert_eq!(store.load_all().unwrap(), [moved]);
+        let attachments = store.attachments_dir(Path::new("release/notes")).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(attachments.join("notes.txt")).unwrap(),
+            "notes"
+        );
+        assert!(!root.join("draft").exists());
+    }
+
+    #[test]
+    fn test_list_page() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let store = DirStore::new(root);
+        for task in [
+            Task::new("a"),
+            Task::new("b"),
+            Task::new("c"),
+            Task::new("done").complete(),
+            Task::new("c/sub"),
+        ] {
+            store.save(&task).unwrap();
+        }
+
+        let names =
+            |page: Page| -> Vec<_> { page.tasks.into_iter().map(|task| task.name).collect() };
+        let filter = ListFilter::default();
+        let page = store
+            .list_page(&filter, SortMode::NameAscending, 1, 2)
+            .unwrap();
+        assert_eq!(page.total, 4);
+        assert_eq!(names(page), ["b", "c"].map(Path::new));
+
+        let filter = ListFilter {
+            under: "c".into(),
+            completed: true,
+            ..ListFilter::default()
+        };
+        let page = store
+            .list_page(&filter, SortMode::NameDescending, 0, 10)
+            .unwrap();
+        assert_eq!(page.total, 2);
+        assert_eq!(names(page), ["c/sub", "c"].map(Path::new));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_names() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let store = DirStore::new(root);
+        store.save(&Task::new("cafe")).unwrap();
+        let latin1 = OsStr::from_bytes(b"caf\xe9");
+        std::fs::copy(
+            root.join("cafe.toml"),
+            root.join(latin1).with_extension("toml"),
+        )
+        .unwrap();
+        std::fs::create_dir(root.join(latin1)).unwrap();
+        std::fs::copy(root.join("cafe.toml"), root.join(latin1).join("menu.toml")).unwrap();
+
+        let names: Vec<_> = store
+            .load_all()
+            .unwrap()
+            .into_iter()
+            .map(|task| task.name)
+            .collect();
+        assert_eq!(names, [Path::new("cafe")]);
+        assert!(store.task_path(Path::new(latin1)).is_err());
+        assert_eq!(store.task_name(&root.join(latin1).join("menu.toml")), None);
+    }
+}
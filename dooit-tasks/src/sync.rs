@@ -0,0 +1,740 @@
+//! Syncing a store with a remote service (CalDAV, Todoist, GitHub issues, …)
+//!
+//! An integration only implements [`SyncBackend`]: listing the remote tasks changed since the last
+//! sync, and creating, updating and deleting remote tasks. [`Syncer`] does the rest:
+//!
+//! - Remote tasks live under a prefix of the store, e.g. `todoist/`, local tasks created there are
+//!   pushed to the service
+//! - The remote ID of every synced task and both sides' versions of it at the last sync are kept in
+//!   a [`SyncState`] file between runs
+//! - Tasks changed on both sides are merged field by field (see [`Task::merge_with`]), fields both
+//!   changed are resolved by the [`ConflictPolicy`], or by the caller one by one with
+//!   [`Syncer::sync_with`] (e.g. by asking the user)
+//! - Requests are spaced by the backend's [`min_interval`](SyncBackend::min_interval) and retried
+//!   with exponential backoff when they fail with a [`BackendError::Transient`] error
+//!
+//! Remote changes are found by comparing to what the service returned the last time, so fields it
+//! doesn't have (like the time log) are kept locally instead of being cleared on every sync.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, FieldChange, MergeOptions, Result, Task, TaskStore};
+
+/// A task as the service has it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteTask {
+    /// ID of the task on the service
+    pub id: String,
+    /// The task, named relative to the [`Syncer`]'s prefix
+    pub task: Task,
+    /// When the task was last changed on the service, if it says, see [`ConflictPolicy::Newest`]
+    pub modified: Option<DateTime<Utc>>,
+}
+
+/// What changed on the service since the last sync
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Changes {
+    /// Tasks created or updated
+    pub changed: Vec<RemoteTask>,
+    /// IDs of the tasks deleted
+    pub deleted: Vec<String>,
+    /// Passed to the next [`pull`](SyncBackend::pull), e.g. a sync token or a timestamp
+    pub cursor: Option<String>,
+}
+
+/// Why a request to the service failed
+#[derive(Debug, thiserror::Error)]
+pub enum BackendError {
+    /// Worth trying again, e.g. a timeout or the service asking to slow down
+    #[error("{message}")]
+    Transient {
+        message: String,
+        /// How long the service asked to wait
+        retry_after: Option<Duration>,
+    },
+    /// Trying again won't help, e.g. the credentials were rejected
+    #[error("{0}")]
+    Permanent(String),
+}
+
+/// A remote service tasks are synced with, see the [module docs](self)
+pub trait SyncBackend {
+    /// Name of the service, e.g. `todoist`, also naming its [`SyncState`] file
+    fn name(&self) -> &str;
+
+    /// Shortest time between two requests to the service
+    fn min_interval(&self) -> Duration {
+        Duration::ZERO
+    }
+
+    /// Tasks changed since `cursor`, every task if it's `None`
+    fn pull(&mut self, cursor: Option<&str>) -> Result<Changes, BackendError>;
+
+    /// Create `task` on the service, returns it as the service stored it
+    fn create(&mut self, task: &Task) -> Result<RemoteTask, BackendError>;
+
+    /// Replace the task with ID `id`, returns it as the service stored it
+    fn update(&mut self, id: &str, task: &Task) -> Result<Task, BackendError>;
+
+    /// Delete the task with ID `id`, succeeds if it was already gone
+    fn delete(&mut self, id: &str) -> Result<(), BackendError>;
+}
+
+/// Which side wins for fields both sides changed since the last sync
+///
+/// Either way, changes to different fields are all kept, and a task deleted on one side but changed
+/// on the other is kept.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictPolicy {
+    /// Keep the local value
+    #[default]
+    #[serde(alias = "ours")]
+    Local,
+    /// Take the service's value
+    #[serde(alias = "theirs")]
+    Remote,
+    /// Take the value of the side that changed the task last, the local one if the service or the
+    /// store doesn't say when (see [`RemoteTask::modified`] and [`TaskStore::modified`])
+    Newest,
+}
+
+/// One of the sides of a sync
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    /// The store
+    Local,
+    /// The service
+    Remote,
+}
+
+/// A field both sides changed since the last sync
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncConflict {
+    /// Name of the local task
+    pub name: PathBuf,
+    /// The change from the local value to the service's
+    pub change: FieldChange,
+    /// The side whose value was kept
+    pub kept: Side,
+}
+
+/// A task as it was after the last sync
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Synced {
+    /// The task in the store
+    pub local: Task,
+    /// The task as the service returned it, named like `local`
+    pub remote: Task,
+}
+
+/// What a [`Syncer`] remembers between runs
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncState {
+    /// Returned by the last [`pull`](SyncBackend::pull)
+    pub cursor: Option<String>,
+    /// Synced tasks by their remote ID
+    pub tasks: BTreeMap<String, Synced>,
+}
+
+impl SyncState {
+    /// Read the state from `path`, the empty state if it doesn't exist
+    pub fn load(path: &Path) -> Result<Self> {
+        match std::fs::read(path) {
+            Ok(contents) => serde_json::from_slice(&contents).map_err(|source| Error::SyncState {
+                path: path.to_path_buf(),
+                source,
+            }),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let contents = serde_json::to_vec_pretty(self).map_err(|source| Error::SyncState {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let tmp = path.with_extension(format!("json.{}", std::process::id()));
+        std::fs::write(&tmp, contents)?;
+        Ok(std::fs::rename(tmp, path)?)
+    }
+}
+
+/// How often and how patiently failed requests are retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Retry {
+    /// Tries of every request, including the first
+    pub attempts: u32,
+    /// Wait before the first retry, doubled for every further one
+    pub backoff: Duration,
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Self {
+            attempts: 4,
+            backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+/// What a [`Syncer::sync`] did
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SyncReport {
+    /// Local tasks created, changed or removed
+    pub pulled: usize,
+    /// Remote tasks created, changed or deleted
+    pub pushed: usize,
+    /// Fields both sides changed
+    pub conflicts: Vec<SyncConflict>,
+}
+
+/// Syncs the tasks under a prefix of a store with a [`SyncBackend`], see the [module docs](self)
+#[derive(Debug)]
+pub struct Syncer<B> {
+    backend: B,
+    prefix: PathBuf,
+    state_path: PathBuf,
+    policy: ConflictPolicy,
+    retry: Retry,
+    last_request: Option<Instant>,
+}
+
+impl<B: SyncBackend> Syncer<B> {
+    /// Sync `backend` with the tasks under `prefix`, keeping the state in `state_path`
+    pub fn new(backend: B, prefix: impl Into<PathBuf>, state_path: impl Into<PathBuf>) -> Self {
+        Self {
+            backend,
+            prefix: prefix.into(),
+            state_path: state_path.into(),
+            policy: ConflictPolicy::default(),
+            retry: Retry::default(),
+            last_request: None,
+        }
+    }
+
+    /// Where the state of `backend` is kept by default, `<state dir>/sync/<name>.json`
+    pub fn default_state_path(backend: &B) -> Option<PathBuf> {
+        crate::dirs::get_state_dir()
+            .map(|dir| dir.join("sync").join(format!("{}.json", backend.name())))
+    }
+
+    pub fn with_policy(mut self, policy: ConflictPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn with_retry(mut self, retry: Retry) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    /// Call the backend, waiting for the rate limit and retrying transient errors
+    fn request<T>(
+        &mut self,
+        mut request: impl FnMut(&mut B) -> Result<T, BackendError>,
+    ) -> Result<T> {
+        let mut attempt = 0;
+        loop {
+            let interval = self.backend.min_interval();
+            if let Some(last) = self.last_request {
+                std::thread::sleep(interval.saturating_sub(last.elapsed()));
+            }
+            self.last_request = Some(Instant::now());
+
+            attempt += 1;
+            match request(&mut self.backend) {
+                Ok(value) => return Ok(value),
+                Err(BackendError::Transient {
+                    message,
+                    retry_after,
+                }) if attempt < self.retry.attempts => {
+                    let wait = retry_after.unwrap_or(self.retry.backoff * 2u32.pow(attempt - 1));
+                    tracing::debug!(
+                        "{} failed ({message}), retrying in {wait:?}",
+                        self.backend.name()
+                    );
+                    std::thread::sleep(wait);
+                }
+                Err(source) => {
+                    return Err(Error::Sync {
+                        backend: self.backend.name().to_string(),
+                        source,
+                    })
+                }
+            }
+        }
+    }
+
+    /// `task` named like the service names it
+    fn remote_view(&self, task: &Task) -> Task {
+        let name = task.name.strip_prefix(&self.prefix).unwrap_or(&task.name);
+        Task {
+            name: name.to_path_buf(),
+            ..task.clone()
+        }
+    }
+
+    /// The side the policy keeps the values of for conflicts in the task called `name`
+    fn winner(
+        &self,
+        store: &impl TaskStore,
+        name: &Path,
+        remote_modified: Option<DateTime<Utc>>,
+    ) -> Result<Side> {
+        Ok(match self.policy {
+            ConflictPolicy::Local => Side::Local,
+            ConflictPolicy::Remote => Side::Remote,
+            ConflictPolicy::Newest => match (store.modified(name)?, remote_modified) {
+                (Some(local), Some(remote)) if remote > local => Side::Remote,
+                _ => Side::Local,
+            },
+        })
+    }
+
+    /// Merge the changes both sides made since `synced`, named like the local task
+    ///
+    /// Every field both changed is passed to `resolve` with the side `winner` keeps, which returns
+    /// the side to keep. Also returns whether the merged task has local changes the service doesn't
+    /// have yet.
+    fn merge(
+        &self,
+        synced: &Synced,
+        local: &Task,
+        remote: &Task,
+        winner: Side,
+        resolve: &mut impl FnMut(&SyncConflict) -> Side,
+        conflicts: &mut Vec<SyncConflict>,
+    ) -> (Task, bool) {
+        let remote = Task {
+            name: local.name.clone(),
+            ..remote.clone()
+        };
+        // The remote changes applied to the last synced local task, which keeps the fields the
+        // service doesn't have
+        let theirs = Task::merge_with(
+            &synced.remote,
+            &remote,
+            &synced.local,
+            MergeOptions::default(),
+        );
+        // Ours keeps the local values of the conflicts, the ones the service's values win are
+        // changed after
+        let merged = Task::merge_with(&synced.local, local, &theirs.task, MergeOptions::default());
+        let mut task = merged.task;
+        for change in merged.conflicts {
+            let mut conflict = SyncConflict {
+                name: local.name.clone(),
+                change,
+                kept: winner,
+            };
+            conflict.kept = resolve(&conflict);
+            if conflict.kept == Side::Remote {
+                conflict.change.apply(&mut task);
+            }
+            conflicts.push(conflict);
+        }
+        let merged = Task {
+            name: local.name.clone(),
+            ..task
+        };
+        let changed = merged != theirs.task;
+        (merged, changed)
+    }
+
+    /// Pull the service's changes into `store` and push the local ones
+    ///
+    /// The state is saved once everything went through, if something fails halfway the next sync
+    /// starts over from the previous state, which merges the changes already made again.
+    pub fn sync(&mut self, store: &impl TaskStore) -> Result<SyncReport> {
+        self.sync_with(store, |conflict| conflict.kept)
+    }
+
+    /// [`sync`](Self::sync), letting `resolve` pick the side to keep for every field both sides
+    /// changed
+    ///
+    /// `resolve` gets the conflict with the side the policy keeps, e.g. to fall back to it.
+    pub fn sync_with(
+        &mut self,
+        store: &impl TaskStore,
+        mut resolve: impl FnMut(&SyncConflict) -> Side,
+    ) -> Result<SyncReport> {
+        let mut state = SyncState::load(&self.state_path)?;
+        let mut report = SyncReport::default();
+        let cursor = state.cursor.clone();
+        let changes = self.request(|backend| backend.pull(cursor.as_deref()))?;
+
+        let mut local: HashMap<PathBuf, Task> = store
+            .load_under(&self.prefix)?
+            .into_iter()
+            .map(|task| (task.name.clone(), task))
+            .collect();
+        // Remote IDs already handled while pulling
+        let mut done = BTreeSet::new();
+        // Saved together once every change was pulled, see `TaskStore::save_all`
+        let mut pulled = Vec::new();
+
+        for RemoteTask {
+            id,
+            task: remote,
+            modified,
+        } in changes.changed
+        {
+            done.insert(id.clone());
+            let Some(synced) = state.tasks.get(&id) else {
+                // New on the service
+                let task = Task {
+                    name: self.prefix.join(&remote.name),
+                    ..remote.clone()
+                };
+                let task = match local.get(&task.name) {
+                    Some(existing) => existing.clone(),
+                    None => {
+                        pulled.push(task.clone());
+                        report.pulled += 1;
+                        task
+                    }
+                };
+                let remote = Task {
+                    name: task.name.clone(),
+                    ..remote
+                };
+                local.insert(task.name.clone(), task.clone());
+                state.tasks.insert(
+                    id,
+                    Synced {
+                        local: task,
+                        remote,
+                    },
+                );
+                continue;
+            };
+
+            let current = local
+                .get(&synced.local.name)
+                .cloned()
+                .unwrap_or_else(|| synced.local.clone());
+            let winner = self.winner(store, &current.name, modified)?;
+            let (merged, changed) = self.merge(
+                synced,
+                &current,
+                &remote,
+                winner,
+                &mut resolve,
+                &mut report.conflicts,
+            );
+            if local.get(&merged.name) != Some(&merged) {
+                pulled.push(merged.clone());
+                report.pulled += 1;
+            }
+            let remote = if changed {
+                let pushed = self.remote_view(&merged);
+                let stored = self.request(|backend| backend.update(&id, &pushed))?;
+                report.pushed += 1;
+                stored
+            } else {
+                remote
+            };
+            let remote = Task {
+                name: merged.name.clone(),
+                ..remote
+            };
+            local.insert(merged.name.clone(), merged.clone());
+            state.tasks.insert(
+                id,
+                Synced {
+                    local: merged,
+                    remote,
+                },
+            );
+        }
+
+        store.save_all(&pulled)?;
+
+        for id in changes.deleted {
+            done.insert(id.clone());
+            let Some(synced) = state.tasks.remove(&id) else {
+                continue;
+            };
+            match local.get(&synced.local.name) {
+                // Changed locally since, so it's created again
+                Some(task) if *task != synced.local => {
+                    let pushed = self.remote_view(task);
+                    let created = self.request(|backend| backend.create(&pushed))?;
+                    report.pushed += 1;
+                    let remote = Task {
+                        name: task.name.clone(),
+                        ..created.task
+                    };
+                    let local = task.clone();
+                    state.tasks.insert(created.id, Synced { local, remote });
+                }
+                Some(task) => {
+                    store.remove(&task.name)?;
+                    report.pulled += 1;
+                    local.remove(&synced.local.name);
+                }
+                None => {}
+            }
+        }
+
+        // Local changes to tasks the service didn't change
+        let ids: Vec<_> = state
+            .tasks
+            .keys()
+            .filter(|id| !done.contains(*id))
+            .cloned()
+            .collect();
+        for id in ids {
+            let synced = &state.tasks[&id];
+            match local.get(&synced.local.name) {
+                None => {
+                    self.request(|backend| backend.delete(&id))?;
+                    report.pushed += 1;
+                    state.tasks.remove(&id);
+                }
+                Some(task) if *task != synced.local => {
+                    let task = task.clone();
+                    let pushed = self.remote_view(&task);
+                    let stored = self.request(|backend| backend.update(&id, &pushed))?;
+                    report.pushed += 1;
+                    let remote = Task {
+                        name: task.name.clone(),
+                        ..stored
+                    };
+                    state.tasks.insert(
+                        id,
+                        Synced {
+                            local: task,
+                            remote,
+                        },
+                    );
+                }
+                Some(_) => {}
+            }
+        }
+
+        // Tasks created locally
+        let synced: BTreeSet<_> = state
+            .tasks
+            .values()
+            .map(|synced| synced.local.name.clone())
+            .collect();
+        let mut created: Vec<_> = local
+            .into_values()
+            .filter(|task| task.name != self.prefix && !synced.contains(&task.name))
+            .collect();
+        created.sort_by(|a, b| a.name.cmp(&b.name));
+        for task in created {
+            let pushed = self.remote_view(&task);
+            let stored = self.request(|backend| backend.create(&pushed))?;
+            report.pushed += 1;
+            let remote = Task {
+                name: task.name.clone(),
+                ..stored.task
+            };
+            state.tasks.insert(
+                stored.id,
+                Synced {
+                    local: task,
+                    remote,
+                },
+            );
+        }
+
+        state.cursor = changes.cursor;
+        state.save(&self.state_path)?;
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::BTreeMap, path::Path, time::Duration};
+
+    use pretty_assertions::assert_eq;
+
+    use super::{
+        BackendError, Changes, ConflictPolicy, RemoteTask, Retry, Side, SyncBackend, Syncer,
+    };
+    use crate::{DirStore, FieldChange, Task, TaskStore, Urgency};
+
+    /// A service keeping its tasks in memory, without a time log, failing the first request
+    ///
+    /// Pulled tasks count as changed when they're pulled.
+    #[derive(Debug, Default)]
+    struct Memory {
+        tasks: BTreeMap<String, Task>,
+        changed: Vec<String>,
+        deleted: Vec<String>,
+        next_id: usize,
+        flaky: bool,
+    }
+
+    impl Memory {
+        fn store(&mut self, id: &str, task: &Task) -> Task {
+            let task = Task {
+                time_log: Vec::new(),
+                ..task.clone()
+            };
+            self.tasks.insert(id.to_string(), task.clone());
+            task
+        }
+    }
+
+    impl SyncBackend for Memory {
+        fn name(&self) -> &str {
+            "memory"
+        }
+
+        fn pull(&mut self, _: Option<&str>) -> Result<Changes, BackendError> {
+            if std::mem::take(&mut self.flaky) {
+                return Err(BackendError::Transient {
+                    message: "try again".to_string(),
+                    retry_after: Some(Duration::ZERO),
+                });
+            }
+            let changed = self
+                .changed
+                .drain(..)
+                .map(|id| RemoteTask {
+                    task: self.tasks[&id].clone(),
+                    id,
+                    modified: Some(chrono::Utc::now()),
+                })
+                .collect();
+            Ok(Changes {
+                changed,
+                deleted: self.deleted.drain(..).collect(),
+                cursor: Some("now".to_string()),
+            })
+        }
+
+        fn create(&mut self, task: &Task) -> Result<RemoteTask, BackendError> {
+            self.next_id += 1;
+            let id = self.next_id.to_string();
+            let task = self.store(&id, task);
+            Ok(RemoteTask {
+                id,
+                task,
+                modified: None,
+            })
+        }
+
+        fn update(&mut self, id: &str, task: &Task) -> Result<Task, BackendError> {
+            Ok(self.store(id, task))
+        }
+
+        fn delete(&mut self, id: &str) -> Result<(), BackendError> {
+            self.tasks.remove(id);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_sync() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let store = DirStore::new(root.join("tasks"));
+        let mut backend = Memory {
+            flaky: true,
+            ..Memory::default()
+        };
+        backend.store("a", &Task::new("groceries"));
+        backend.changed.push("a".to_string());
+        let mut syncer =
+            Syncer::new(backend, "remote", root.join("state.json")).with_retry(Retry {
+                attempts: 2,
+                backoff: Duration::ZERO,
+            });
+
+        // The remote task is pulled, the local one pushed
+        store.save(&Task::new("remote/taxes")).unwrap();
+        store.save(&Task::new("elsewhere")).unwrap();
+        let report = syncer.sync(&store).unwrap();
+        assert_eq!((report.pulled, report.pushed), (1, 1));
+        assert!(store.load(Path::new("remote/groceries")).unwrap().is_some());
+        assert_eq!(syncer.backend().tasks["1"].name, Path::new("taxes"));
+
+        // Both change the same task, the local urgency and the remote description are kept
+        let local = Task {
+            time_log: vec![crate::TimeEntry {
+                start: chrono::Utc::now(),
+                end: chrono::Utc::now(),
+            }],
+            ..Task::new("remote/groceries").with_urgency(Urgency::High)
+        };
+        store.save(&local).unwrap();
+        let backend = &mut syncer.backend;
+        let remote = Task::new("groceries")
+            .with_urgency(Urgency::Medium)
+            .with_description("milk".to_string());
+        backend.store("a", &remote);
+        backend.changed.push("a".to_string());
+        let report = syncer.sync(&store).unwrap();
+        assert_eq!(report.conflicts.len(), 1);
+        let merged = store.load(Path::new("remote/groceries")).unwrap().unwrap();
+        assert_eq!(merged.urgency, Urgency::High);
+        assert_eq!(merged.description.as_deref(), Some("milk"));
+        assert_eq!(merged.time_log.len(), 1);
+        assert_eq!(syncer.backend().tasks["a"].urgency, Urgency::High);
+
+        // Both change the urgency again, the service changed it last
+        syncer = syncer.with_policy(ConflictPolicy::Newest);
+        let change = |syncer: &mut Syncer<Memory>, local: &Task, urgency| {
+            store
+                .save(&local.clone().with_urgency(Urgency::Low))
+                .unwrap();
+            let remote = syncer.backend.tasks["a"].clone().with_urgency(urgency);
+            syncer.backend.store("a", &remote);
+            syncer.backend.changed.push("a".to_string());
+        };
+        change(&mut syncer, &merged, Urgency::Medium);
+        let report = syncer.sync(&store).unwrap();
+        assert_eq!(report.conflicts[0].kept, Side::Remote);
+        let merged = store.load(Path::new("remote/groceries")).unwrap().unwrap();
+        assert_eq!(merged.urgency, Urgency::Medium);
+
+        // And once more, picking the local urgency
+        change(&mut syncer, &merged, Urgency::High);
+        let report = syncer
+            .sync_with(&store, |conflict| {
+                assert_eq!(conflict.kept, Side::Remote);
+                Side::Local
+            })
+            .unwrap();
+        let change = FieldChange::Urgency {
+            old: Urgency::Low,
+            new: Urgency::High,
+        };
+        assert_eq!(report.conflicts[0].change, change);
+        let merged = store.load(Path::new("remote/groceries")).unwrap().unwrap();
+        assert_eq!(merged.urgency, Urgency::Low);
+        assert_eq!(syncer.backend().tasks["a"].urgency, Urgency::Low);
+
+        // Deleted remotely, and locally
+        syncer.backend.tasks.remove("a");
+        syncer.backend.deleted.push("a".to_string());
+        store.remove(Path::new("remote/taxes")).unwrap();
+        let report = syncer.sync(&store).unwrap();
+        assert_eq!((report.pulled, report.pushed), (1, 1));
+        assert_eq!(store.load(Path::new("remote/groceries")).unwrap(), None);
+        assert!(syncer.backend().tasks.is_empty());
+        assert_eq!(store.load_all().unwrap().len(), 1);
+    }
+}
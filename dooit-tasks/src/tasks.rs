@@ -1,10 +1,25 @@
-use std::{fmt::Display, path::PathBuf};
+use std::{
+    cmp::Ordering,
+    fmt::Display,
+    path::{Component, PathBuf},
+};
 
-use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use chrono::{
+    DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, Utc, Weekday,
+};
+#[cfg(feature = "cli")]
 use clap::{Args, ValueEnum};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Default, ValueEnum, Clone, Copy)]
+use crate::{
+    duration::parse_duration,
+    score::{OrderedByScore, ScoreConfig},
+    Error, HumanDuration, Result, Urgency,
+};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+#[serde(rename_all = "kebab-case")]
 pub enum SortMode {
     /// Sort by urgency (least urgent first)
     UrgencyAscending,
@@ -19,47 +34,159 @@ pub enum SortMode {
     NameAscending,
     /// Sort by the item's name (Descending)
     NameDescending,
+    /// Sort by urgency score (lowest first), see [`crate::score`]
+    ScoreAscending,
+    /// Sort by urgency score (highest first), see [`crate::score`]
+    ScoreDescending,
+}
+
+/// Glyphs used to show urgencies
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IconSet {
+    /// Icons from a [Nerd Font](https://www.nerdfonts.com)
+    #[default]
+    Nerd,
+    /// Plain ASCII for terminals without a patched font
+    Ascii,
 }
 
-#[derive(
-    Debug, Default, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, ValueEnum, Serialize, Deserialize,
-)]
-pub enum Urgency {
+/// Progress of a task that hasn't been completed yet
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+pub enum Status {
+    /// Nobody has started working on it
     #[default]
-    Low,
-    Medium,
-    High,
+    Pending,
+    /// Work on the task is in progress
+    Started,
+    /// Blocked on something or someone else
+    Waiting,
 }
 
-impl Display for Urgency {
+impl Display for Status {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
             "{}",
             match self {
-                Urgency::Low => " ",
-                Urgency::Medium => "",
-                Urgency::High => "",
+                Status::Pending => "Pending",
+                Status::Started => "Started",
+                Status::Waiting => "Waiting",
             }
         )
     }
 }
 
-#[derive(Debug, Args, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(Args))]
 pub struct Task {
     /// Name of the task (subtasks can be created by namig them task/subtask)
     pub name: PathBuf,
     /// Description of the task
     pub description: Option<String>,
     /// Due date of the task
-    #[arg(short, long, value_parser = parse_date)]
+    #[cfg_attr(feature = "cli", arg(short, long, value_parser = parse_date))]
     pub due: Option<DateTime<Utc>>,
     /// Urgency of the task
-    #[arg(short, long, value_enum, default_value_t)]
+    #[cfg_attr(
+        feature = "cli",
+        arg(short, long, default_value_t, value_parser = str::parse::<Urgency>)
+    )]
     pub urgency: Urgency,
     /// Whether the task has been completed or not
-    #[arg(short, long)]
+    #[cfg_attr(feature = "cli", arg(short, long))]
     pub completed: bool,
+    /// When the task was completed
+    #[cfg_attr(feature = "cli", arg(skip))]
+    #[serde(default)]
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Progress of the task (ignored once it's completed)
+    #[cfg_attr(feature = "cli", arg(short, long, value_enum, default_value_t))]
+    #[serde(default)]
+    pub status: Status,
+    /// Tags to group related tasks (can be repeated)
+    #[cfg_attr(feature = "cli", arg(short, long = "tag"))]
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// URLs related to the task (can be repeated)
+    #[cfg_attr(feature = "cli", arg(short, long = "link"))]
+    #[serde(default)]
+    pub links: Vec<String>,
+    /// Tasks that have to be completed before this one (can be repeated)
+    #[cfg_attr(feature = "cli", arg(long))]
+    #[serde(default)]
+    pub depends_on: Vec<PathBuf>,
+    /// Hide the task until this date
+    #[cfg_attr(feature = "cli", arg(long, value_parser = parse_date))]
+    #[serde(default)]
+    pub wait: Option<DateTime<Utc>>,
+    /// Day the task is planned to be worked on
+    #[cfg_attr(feature = "cli", arg(long, value_parser = parse_date))]
+    #[serde(default)]
+    pub scheduled: Option<DateTime<Utc>>,
+    /// Bring the task up for review again on this date, e.g. for someday/maybe tasks
+    #[cfg_attr(feature = "cli", arg(long, value_parser = parse_date))]
+    #[serde(default)]
+    pub review: Option<DateTime<Utc>>,
+    /// How long the task is expected to take (e.g. 30m, 2h, 1d)
+    #[cfg_attr(feature = "cli", arg(long))]
+    #[serde(default)]
+    pub estimate: Option<HumanDuration>,
+    /// Who is working on the task, for task directories shared by a team
+    #[cfg_attr(feature = "cli", arg(long))]
+    #[serde(default)]
+    pub assignee: Option<String>,
+    /// Repeat the task this often (e.g. 1d, 1w): completing it moves it to its next occurrence,
+    /// see [`Task::next_occurrence`]
+    #[cfg_attr(feature = "cli", arg(long))]
+    #[serde(default)]
+    pub recur: Option<HumanDuration>,
+    /// Track which occurrences of the recurring task were completed and which were missed
+    #[cfg_attr(feature = "cli", arg(long, requires = "recur"))]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub habit: bool,
+    /// File names of the files attached to the task, kept in its
+    /// [attachments directory](crate::DirStore::attachments_dir)
+    #[cfg_attr(feature = "cli", arg(skip))]
+    #[serde(default)]
+    pub attachments: Vec<String>,
+    /// Time spent working on the task
+    ///
+    /// Keep this last (but for the occurrences), TOML needs tables (like the entries) after plain
+    /// values.
+    #[cfg_attr(feature = "cli", arg(skip))]
+    #[serde(default)]
+    pub time_log: Vec<TimeEntry>,
+    /// The past occurrences of a habit, oldest first
+    ///
+    /// After the time log, which is written as a plain value when it's empty, and left out when
+    /// it's empty itself.
+    #[cfg_attr(feature = "cli", arg(skip))]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub occurrences: Vec<Occurrence>,
+}
+
+/// A period of time spent working on a task
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct TimeEntry {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl TimeEntry {
+    pub fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+}
+
+/// A past occurrence of a habit, see [`Task::habit`]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct Occurrence {
+    /// When the occurrence was due
+    pub due: DateTime<Utc>,
+    /// When it was completed, none if it was missed
+    pub completed_at: Option<DateTime<Utc>>,
 }
 
 impl Task {
@@ -72,6 +199,21 @@ impl Task {
             due: Default::default(),
             urgency: Default::default(),
             completed: Default::default(),
+            completed_at: Default::default(),
+            status: Default::default(),
+            tags: Default::default(),
+            links: Default::default(),
+            depends_on: Default::default(),
+            wait: Default::default(),
+            scheduled: Default::default(),
+            review: Default::default(),
+            estimate: Default::default(),
+            assignee: Default::default(),
+            recur: Default::default(),
+            habit: Default::default(),
+            attachments: Default::default(),
+            time_log: Default::default(),
+            occurrences: Default::default(),
         }
     }
 
@@ -85,33 +227,247 @@ impl Task {
         self
     }
 
-    pub fn with_ugency(mut self, urgency: Urgency) -> Self {
+    pub fn with_urgency(mut self, urgency: Urgency) -> Self {
         self.urgency = urgency;
         self
     }
 
+    #[deprecated = "use `with_urgency` instead"]
+    pub fn with_ugency(self, urgency: Urgency) -> Self {
+        self.with_urgency(urgency)
+    }
+
+    pub fn with_status(mut self, status: Status) -> Self {
+        self.status = status;
+        self
+    }
+
+    pub fn with_tags(mut self, tags: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.tags = tags.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn with_links(mut self, links: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.links = links.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn with_dependencies(
+        mut self,
+        depends_on: impl IntoIterator<Item = impl Into<PathBuf>>,
+    ) -> Self {
+        self.depends_on = depends_on.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn with_wait(mut self, wait: DateTime<Utc>) -> Self {
+        self.wait = Some(wait);
+        self
+    }
+
+    pub fn with_scheduled(mut self, scheduled: DateTime<Utc>) -> Self {
+        self.scheduled = Some(scheduled);
+        self
+    }
+
+    pub fn with_review(mut self, review: DateTime<Utc>) -> Self {
+        self.review = Some(review);
+        self
+    }
+
+    pub fn with_estimate(mut self, estimate: Duration) -> Self {
+        self.estimate = Some(HumanDuration(estimate));
+        self
+    }
+
+    pub fn with_recurrence(mut self, every: Duration) -> Self {
+        self.recur = Some(HumanDuration(every));
+        self
+    }
+
+    /// Make the task a habit repeating `every` so often
+    pub fn with_habit(self, every: Duration) -> Self {
+        Self {
+            habit: true,
+            ..self.with_recurrence(every)
+        }
+    }
+
+    /// Check the task makes sense before it gets saved
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |reason: &str| {
+            Err(Error::InvalidTask {
+                name: self.name.clone(),
+                reason: reason.to_string(),
+            })
+        };
+
+        let is_relative = self
+            .name
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+        if !is_relative || self.name.as_os_str().is_empty() || self.name.to_str().is_none() {
+            return Err(Error::InvalidName(self.name.clone()));
+        }
+        if self.depends_on.contains(&self.name) {
+            return invalid("it depends on itself");
+        }
+        if self.completed_at.is_some() && !self.completed {
+            return invalid("it has a completion date but isn't completed");
+        }
+        if let (Some(wait), Some(due)) = (self.wait, self.due) {
+            if wait > due {
+                return invalid("it's hidden until after its due date");
+            }
+        }
+        if self
+            .estimate
+            .is_some_and(|HumanDuration(estimate)| estimate <= Duration::zero())
+        {
+            return invalid("the estimate isn't positive");
+        }
+        if self
+            .recur
+            .is_some_and(|HumanDuration(every)| every <= Duration::zero())
+        {
+            return invalid("it doesn't recur after a positive duration");
+        }
+        if self.habit && self.recur.is_none() {
+            return invalid("it's a habit but doesn't recur");
+        }
+        if self.time_log.iter().any(|entry| entry.end < entry.start) {
+            return invalid("a time log entry ends before it starts");
+        }
+
+        Ok(())
+    }
+
+    /// Show the task with `icons` instead of the default ones
+    pub fn display(&self, icons: IconSet) -> TaskDisplay<'_> {
+        TaskDisplay { task: self, icons }
+    }
+
+    /// Total time logged on the task
+    pub fn time_spent(&self) -> Duration {
+        self.time_log
+            .iter()
+            .map(TimeEntry::duration)
+            .fold(Duration::zero(), |total, spent| total + spent)
+    }
+
+    /// What's left of the estimate after the time spent, zero once completed
+    pub fn remaining(&self) -> Option<Duration> {
+        if self.completed {
+            return Some(Duration::zero());
+        }
+        let HumanDuration(estimate) = self.estimate?;
+        Some((estimate - self.time_spent()).max(Duration::zero()))
+    }
+
     pub fn complete(mut self) -> Self {
         self.completed = true;
         self
     }
+
+    /// Move the recurring task to its next occurrence after it was completed at `now`, instead
+    /// of leaving it completed for good
+    ///
+    /// The completed occurrence is the one due last before `now` (or the upcoming one if none
+    /// is), the next one is [`Task::recur`] after it. Habits record the completed occurrence in
+    /// [`Task::occurrences`], along with the earlier ones that passed without being completed.
+    /// Tasks that don't recur are returned as they are.
+    pub fn next_occurrence(mut self, now: DateTime<Utc>) -> Self {
+        let Some(HumanDuration(every)) = self.recur.filter(|every| every.0 > Duration::zero())
+        else {
+            return self;
+        };
+
+        let mut due = self.due.unwrap_or(now);
+        while due + every <= now {
+            if self.habit {
+                self.occurrences.push(Occurrence {
+                    due,
+                    completed_at: None,
+                });
+            }
+            due += every;
+        }
+        if self.habit {
+            self.occurrences.push(Occurrence {
+                due,
+                completed_at: Some(now),
+            });
+        }
+
+        Self {
+            due: Some(due + every),
+            completed: false,
+            completed_at: None,
+            status: Status::Pending,
+            // It was planned for the occurrence that was completed
+            scheduled: None,
+            ..self
+        }
+    }
+
+    /// Whether the task is pending and was due before `now`
+    pub fn is_overdue(&self, now: DateTime<Utc>) -> bool {
+        !self.completed && self.due.is_some_and(|due| due < now)
+    }
+
+    /// Whether the task is pending and due within `within` from `now` (or overdue)
+    pub fn is_due_within(&self, within: Duration, now: DateTime<Utc>) -> bool {
+        !self.completed && self.due.is_some_and(|due| due <= now + within)
+    }
+
+    /// Whether the task is still hidden by [`Task::wait`] at `now`
+    pub fn is_waiting(&self, now: DateTime<Utc>) -> bool {
+        self.wait.is_some_and(|wait| wait > now)
+    }
+
+    /// Whether the [`Task::review`] date of the pending task has come at `now`
+    pub fn is_up_for_review(&self, now: DateTime<Utc>) -> bool {
+        !self.completed && self.review.is_some_and(|review| review <= now)
+    }
 }
 
 impl Display for Task {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.display(IconSet::default()).fmt(f)
+    }
+}
+
+/// Helper to show a [`Task`] with a specific [`IconSet`], see [`Task::display`]
+pub struct TaskDisplay<'a> {
+    task: &'a Task,
+    icons: IconSet,
+}
+
+impl Display for TaskDisplay<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let task = self.task;
         write!(
             f,
             "- [{}] {}",
-            if self.completed { 'x' } else { ' ' },
-            self.urgency
+            if task.completed { 'x' } else { ' ' },
+            task.urgency.icon(self.icons)
         )?;
 
-        if let Some(date) = self.due {
+        if let Some(date) = task.due {
             write!(f, " {date}")?;
         }
 
-        write!(f, " {}", self.name.as_path().as_os_str().to_string_lossy())?;
+        write!(f, " {}", task.name.as_path().as_os_str().to_string_lossy())?;
 
-        if let Some(desc) = &self.description {
+        for tag in &task.tags {
+            write!(f, " +{tag}")?;
+        }
+
+        if let Some(assignee) = &task.assignee {
+            write!(f, " @{assignee}")?;
+        }
+
+        if let Some(desc) = &task.description {
             write!(f, "\n    {desc}")?;
         }
 
@@ -132,10 +488,16 @@ fn sort_tasks_name(tasks: &mut [Task]) {
 }
 
 fn sort_tasks_urgency(tasks: &mut [Task]) {
-    tasks.sort_by_key(|task| task.urgency);
+    tasks.sort_by(|a, b| a.urgency.cmp(&b.urgency));
 }
 
+/// Sort `tasks` using the default [`ScoreConfig`] for the score modes
 pub fn sort_tasks(tasks: Vec<Task>, mode: SortMode) -> Vec<Task> {
+    sort_tasks_with(tasks, mode, &ScoreConfig::default())
+}
+
+/// Sort `tasks`, ranking them with `score` in the score modes
+pub fn sort_tasks_with(tasks: Vec<Task>, mode: SortMode, score: &ScoreConfig) -> Vec<Task> {
     match mode {
         SortMode::UrgencyAscending => {
             let mut sorted = tasks;
@@ -186,13 +548,164 @@ pub fn sort_tasks(tasks: Vec<Task>, mode: SortMode) -> Vec<Task> {
             sorted.reverse();
             sorted
         }
+        SortMode::ScoreAscending => {
+            let mut scored: Vec<_> = tasks
+                .into_iter()
+                .map(|task| OrderedByScore::new(task, score))
+                .collect();
+            scored.sort();
+            scored.into_iter().map(|scored| scored.task).collect()
+        }
+        SortMode::ScoreDescending => {
+            let mut sorted = sort_tasks_with(tasks, SortMode::ScoreAscending, score);
+            sorted.reverse();
+            sorted
+        }
     }
 }
 
-fn parse_date(date: &str) -> std::io::Result<DateTime<Utc>> {
+/// Sort `tasks` with `compare`, tasks that compare equal keep their order
+///
+/// Comparisons are usually built from the extractors in [`key`], e.g. the most urgent tasks
+/// first and then the ones due soonest:
+/// `sort_tasks_by(tasks, then(reverse(by_key(key::urgency)), by_key(key::due)))`.
+pub fn sort_tasks_by(
+    mut tasks: Vec<Task>,
+    compare: impl FnMut(&Task, &Task) -> Ordering,
+) -> Vec<Task> {
+    tasks.sort_by(compare);
+    tasks
+}
+
+/// Compare tasks by the value `key` extracts from them, see [`key`]
+pub fn by_key<K: Ord>(mut key: impl FnMut(&Task) -> K) -> impl FnMut(&Task, &Task) -> Ordering {
+    move |a, b| key(a).cmp(&key(b))
+}
+
+/// Compare tasks with `first`, and with `second` if they are equal
+pub fn then(
+    mut first: impl FnMut(&Task, &Task) -> Ordering,
+    mut second: impl FnMut(&Task, &Task) -> Ordering,
+) -> impl FnMut(&Task, &Task) -> Ordering {
+    move |a, b| first(a, b).then_with(|| second(a, b))
+}
+
+/// Reverse the order of `compare`
+pub fn reverse(
+    mut compare: impl FnMut(&Task, &Task) -> Ordering,
+) -> impl FnMut(&Task, &Task) -> Ordering {
+    move |a, b| compare(b, a)
+}
+
+/// Values to sort tasks by with [`by_key`]
+pub mod key {
+    use std::path::PathBuf;
+
+    use chrono::{DateTime, Duration, Utc};
+
+    use crate::{HumanDuration, Status, Task, Urgency};
+
+    /// The task's name, tasks sort before their subtasks
+    pub fn name(task: &Task) -> PathBuf {
+        task.name.clone()
+    }
+
+    /// The task's urgency, see [`Urgency`]'s ordering
+    pub fn urgency(task: &Task) -> Urgency {
+        task.urgency.clone()
+    }
+
+    /// The due date, tasks without one sort after every dated task
+    pub fn due(task: &Task) -> (bool, Option<DateTime<Utc>>) {
+        (task.due.is_none(), task.due)
+    }
+
+    /// Whether the task was completed, pending tasks first
+    pub fn completed(task: &Task) -> bool {
+        task.completed
+    }
+
+    /// The task's progress, see [`Status`]
+    pub fn status(task: &Task) -> Status {
+        task.status
+    }
+
+    /// The estimate, tasks without one first
+    pub fn estimate(task: &Task) -> Option<HumanDuration> {
+        task.estimate
+    }
+
+    /// Time logged working on the task
+    pub fn time_spent(task: &Task) -> Duration {
+        task.time_spent()
+    }
+
+    /// Whether the task has `tag`, tasks without it first
+    pub fn has_tag(tag: &str) -> impl Fn(&Task) -> bool + '_ {
+        move |task| task.tags.iter().any(|other| other == tag)
+    }
+}
+
+fn midnight() -> NaiveTime {
+    NaiveTime::from_num_seconds_from_midnight_opt(0, 0).expect("midnight is valid")
+}
+
+/// Parse a time with or without seconds
+fn parse_time(time: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(time, "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(time, "%H:%M"))
+        .ok()
+}
+
+/// Parse dates relative to `now` like `tomorrow`, `next friday 17:00` or `in 2h`
+fn parse_relative_date(date: &str, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    let date = date.trim().to_lowercase();
+
+    if let Some(duration) = date.strip_prefix("in ") {
+        return parse_duration(duration).ok().map(|duration| now + duration);
+    }
+
+    let mut words = date.split_whitespace().peekable();
+    let next = words.next_if_eq(&"next").is_some();
+    let day = match words.next()? {
+        "now" if !next => return words.next().is_none().then_some(now),
+        "today" if !next => now.date_naive(),
+        "tomorrow" if !next => now.date_naive().succ_opt()?,
+        "yesterday" if !next => now.date_naive().pred_opt()?,
+        weekday => {
+            let weekday: Weekday = weekday.parse().ok()?;
+            let days_ahead =
+                (7 + weekday.num_days_from_monday() - now.weekday().num_days_from_monday()) % 7;
+            // "friday" on a friday means today, "next friday" always means a later day
+            let days_ahead = if next && days_ahead == 0 {
+                7
+            } else {
+                days_ahead
+            };
+            now.date_naive() + Duration::days(days_ahead.into())
+        }
+    };
+
+    let time = match words.next() {
+        Some(time) => parse_time(time)?,
+        None => midnight(),
+    };
+    if words.next().is_some() {
+        return None;
+    }
+
+    day.and_time(time).and_local_timezone(Local).earliest()
+}
+
+/// Parse a date, time, datetime or a date relative to today (see [`parse_relative_date`])
+pub fn parse_date(date: &str) -> Result<DateTime<Utc>> {
     let today = Local::now();
 
-    if let Ok(time) = date.parse::<NaiveTime>() {
+    if let Some(date) = parse_relative_date(date, today) {
+        return Ok(date.into());
+    }
+
+    if let Some(time) = parse_time(date) {
         return Ok(today
             .date_naive()
             .and_time(time)
@@ -204,9 +717,7 @@ fn parse_date(date: &str) -> std::io::Result<DateTime<Utc>> {
 
     if let Ok(date) = date.parse::<NaiveDate>() {
         return Ok(date
-            .and_time(
-                NaiveTime::from_num_seconds_from_midnight_opt(0, 0).expect("midnight is valid"),
-            )
+            .and_time(midnight())
             .and_local_timezone(Local)
             .earliest()
             .expect("valid date")
@@ -221,30 +732,57 @@ fn parse_date(date: &str) -> std::io::Result<DateTime<Utc>> {
             .into());
     }
 
-    todo!("parse {date} as datetime")
+    Err(Error::InvalidDate(date.to_string()))
 }
 
 #[cfg(test)]
 mod test {
+    use chrono::{Local, NaiveDate, TimeZone};
     use pretty_assertions::assert_eq;
 
-    use crate::{tasks::sort_tasks, Task, Urgency};
+    use crate::{
+        tasks::{by_key, key, parse_relative_date, reverse, sort_tasks, sort_tasks_by, then},
+        Occurrence, Task, Urgency,
+    };
+
+    #[test]
+    fn test_sort_tasks_by() {
+        let due = Local.with_ymd_and_hms(2023, 1, 2, 0, 0, 0).unwrap().into();
+        let tasks = vec![
+            Task::new("a"),
+            Task::new("b").with_urgency(Urgency::High),
+            Task::new("c").with_due_date(due),
+            Task::new("d").with_tags(["work"]),
+        ];
+        let sorted = sort_tasks_by(
+            tasks,
+            then(
+                reverse(by_key(key::has_tag("work"))),
+                then(reverse(by_key(key::urgency)), by_key(key::due)),
+            ),
+        );
+        let names: Vec<_> = sorted
+            .iter()
+            .map(|task| task.name.to_str().unwrap())
+            .collect();
+        assert_eq!(names, ["d", "b", "c", "a"]);
+    }
 
     #[test]
     fn test_task_name_sorting_asc() {
         let tasks = vec![
-            Task::new("c").with_ugency(Urgency::Medium),
-            Task::new("b").with_ugency(Urgency::High),
-            Task::new("b").with_ugency(Urgency::Low),
-            Task::new("a").with_ugency(Urgency::Medium),
+            Task::new("c").with_urgency(Urgency::Medium),
+            Task::new("b").with_urgency(Urgency::High),
+            Task::new("b").with_urgency(Urgency::Low),
+            Task::new("a").with_urgency(Urgency::Medium),
             Task::new("a"),
         ];
         let expect = vec![
-            Task::new("a").with_ugency(Urgency::Medium),
+            Task::new("a").with_urgency(Urgency::Medium),
             Task::new("a"),
-            Task::new("b").with_ugency(Urgency::High),
-            Task::new("b").with_ugency(Urgency::Low),
-            Task::new("c").with_ugency(Urgency::Medium),
+            Task::new("b").with_urgency(Urgency::High),
+            Task::new("b").with_urgency(Urgency::Low),
+            Task::new("c").with_urgency(Urgency::Medium),
         ];
         let tasks = sort_tasks(tasks, crate::SortMode::NameAscending);
         assert_eq!(tasks, expect);
@@ -253,20 +791,124 @@ mod test {
     #[test]
     fn test_task_name_sorting_des() {
         let tasks = vec![
-            Task::new("a").with_ugency(Urgency::Medium),
+            Task::new("a").with_urgency(Urgency::Medium),
             Task::new("a"),
-            Task::new("b").with_ugency(Urgency::High),
-            Task::new("b").with_ugency(Urgency::Low),
-            Task::new("c").with_ugency(Urgency::Medium),
+            Task::new("b").with_urgency(Urgency::High),
+            Task::new("b").with_urgency(Urgency::Low),
+            Task::new("c").with_urgency(Urgency::Medium),
         ];
         let expect = vec![
-            Task::new("c").with_ugency(Urgency::Medium),
-            Task::new("b").with_ugency(Urgency::High),
-            Task::new("b").with_ugency(Urgency::Low),
-            Task::new("a").with_ugency(Urgency::Medium),
+            Task::new("c").with_urgency(Urgency::Medium),
+            Task::new("b").with_urgency(Urgency::High),
+            Task::new("b").with_urgency(Urgency::Low),
+            Task::new("a").with_urgency(Urgency::Medium),
             Task::new("a"),
         ];
         let tasks = sort_tasks(tasks, crate::SortMode::NameDescending);
         assert_eq!(tasks, expect);
     }
+
+    #[test]
+    fn test_validate() {
+        let due = Local
+            .with_ymd_and_hms(2023, 2, 1, 10, 30, 0)
+            .unwrap()
+            .into();
+        let task = Task::new("a").with_due_date(due);
+
+        assert!(task.validate().is_ok());
+        assert!(Task::new("").validate().is_err());
+        assert!(Task::new("../a").validate().is_err());
+        assert!(task.clone().with_dependencies(["a"]).validate().is_err());
+        assert!(task
+            .clone()
+            .with_wait(due + chrono::Duration::days(1))
+            .validate()
+            .is_err());
+        assert!(task
+            .clone()
+            .with_estimate(chrono::Duration::zero())
+            .validate()
+            .is_err());
+        assert!(task
+            .clone()
+            .with_recurrence(-chrono::Duration::days(1))
+            .validate()
+            .is_err());
+        assert!(Task {
+            habit: true,
+            ..task
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn test_next_occurrence() {
+        let day = chrono::Duration::days(1);
+        let due = Local.with_ymd_and_hms(2023, 2, 1, 9, 0, 0).unwrap().into();
+        let task = Task::new("a").with_due_date(due).with_habit(day).complete();
+
+        // Completed two days late: the first two occurrences were missed
+        let now = due + day * 2 + chrono::Duration::hours(1);
+        let next = task.clone().next_occurrence(now);
+        assert!(!next.completed);
+        assert_eq!(next.due, Some(due + day * 3));
+        assert_eq!(
+            next.occurrences,
+            [
+                Occurrence {
+                    due,
+                    completed_at: None
+                },
+                Occurrence {
+                    due: due + day,
+                    completed_at: None
+                },
+                Occurrence {
+                    due: due + day * 2,
+                    completed_at: Some(now)
+                },
+            ]
+        );
+
+        // Completed ahead of time, and not a habit
+        let now = due - chrono::Duration::hours(1);
+        let next = Task {
+            habit: false,
+            ..task.clone()
+        }
+        .next_occurrence(now);
+        assert_eq!(next.due, Some(due + day));
+        assert!(next.occurrences.is_empty());
+
+        // Tasks that don't recur are left alone
+        let task = Task::new("b").complete();
+        assert_eq!(task.clone().next_occurrence(now), task);
+    }
+
+    #[test]
+    fn test_parse_relative_date() {
+        // A wednesday
+        let now = Local.with_ymd_and_hms(2023, 2, 1, 10, 30, 0).unwrap();
+        let day = |day, hour| {
+            let date = NaiveDate::from_ymd_opt(2023, 2, day).unwrap();
+            Local
+                .from_local_datetime(&date.and_hms_opt(hour, 0, 0).unwrap())
+                .unwrap()
+        };
+
+        assert_eq!(parse_relative_date("now", now), Some(now));
+        assert_eq!(parse_relative_date("Tomorrow", now), Some(day(2, 0)));
+        assert_eq!(parse_relative_date("today 17:00", now), Some(day(1, 17)));
+        assert_eq!(parse_relative_date("friday", now), Some(day(3, 0)));
+        assert_eq!(parse_relative_date("wed", now), Some(day(1, 0)));
+        assert_eq!(parse_relative_date("next wed 9:00", now), Some(day(8, 9)));
+        assert_eq!(
+            parse_relative_date("in 1d", now),
+            Some(now + chrono::Duration::days(1))
+        );
+        assert_eq!(parse_relative_date("next tomorrow", now), None);
+        assert_eq!(parse_relative_date("friday soon", now), None);
+    }
 }
@@ -0,0 +1,211 @@
+//! How urgent a task is, on a scale programs can extend
+//!
+//! The built-in levels are `low`, `medium` and `high`. A program can replace them with its own
+//! ordered list by calling [`set_levels`] once at startup, e.g. to add `someday` below `low` and
+//! `critical` above `high`. Urgencies are stored by name, so tasks keep theirs even if it's later
+//! removed from the list (they are sorted below every defined level then).
+
+use std::{cmp::Ordering, fmt::Display, str::FromStr, sync::OnceLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, IconSet, Result};
+
+/// A named urgency and how it's shown
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UrgencyLevel {
+    /// Name used on the command line and in task files
+    pub name: String,
+    /// Glyph shown with [`IconSet::Nerd`]
+    pub icon: String,
+    /// Glyph shown with [`IconSet::Ascii`], `icon` if unset
+    pub ascii: Option<String>,
+}
+
+impl UrgencyLevel {
+    fn new(name: &str, icon: &str, ascii: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            icon: icon.to_string(),
+            ascii: Some(ascii.to_string()),
+        }
+    }
+}
+
+static LEVELS: OnceLock<Vec<UrgencyLevel>> = OnceLock::new();
+
+fn builtin_levels() -> Vec<UrgencyLevel> {
+    vec![
+        UrgencyLevel::new("low", " ", " "),
+        UrgencyLevel::new("medium", "", "!"),
+        UrgencyLevel::new("high", "", "!!"),
+    ]
+}
+
+/// Replace the built-in levels with `levels`, lowest urgency first
+///
+/// Fails if a name is empty or repeated, or if the levels were already set (or used).
+pub fn set_levels(levels: Vec<UrgencyLevel>) -> Result<()> {
+    for (index, level) in levels.iter().enumerate() {
+        let repeated = levels[..index]
+            .iter()
+            .any(|other| other.name.eq_ignore_ascii_case(&level.name));
+        if level.name.trim().is_empty() || repeated {
+            return Err(Error::InvalidUrgencyLevel(level.name.clone()));
+        }
+    }
+
+    LEVELS
+        .set(levels)
+        .map_err(|levels| Error::InvalidUrgencyLevel(levels[0].name.clone()))
+}
+
+/// The urgency levels, lowest first
+pub fn levels() -> &'static [UrgencyLevel] {
+    LEVELS.get_or_init(builtin_levels)
+}
+
+/// How urgent a task is, see the [module docs](self)
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum Urgency {
+    #[default]
+    Low,
+    Medium,
+    High,
+    /// A level added with [`set_levels`]
+    Custom(String),
+}
+
+impl Urgency {
+    pub fn name(&self) -> &str {
+        match self {
+            Urgency::Low => "Low",
+            Urgency::Medium => "Medium",
+            Urgency::High => "High",
+            Urgency::Custom(name) => name,
+        }
+    }
+
+    /// Position of the urgency in [`levels`], `None` if it isn't one of them
+    pub fn rank(&self) -> Option<usize> {
+        levels()
+            .iter()
+            .position(|level| level.name.eq_ignore_ascii_case(self.name()))
+    }
+
+    /// The closest built-in urgency at or below this one in [`levels`], or [`Urgency::Low`]
+    ///
+    /// Lets settings that only exist for the built-in levels (e.g. score coefficients) apply to
+    /// custom ones.
+    pub fn builtin(&self) -> Urgency {
+        let Some(rank) = self.rank() else {
+            return Urgency::Low;
+        };
+        levels()[..=rank]
+            .iter()
+            .rev()
+            .map(|level| Urgency::from(level.name.clone()))
+            .find(|urgency| !matches!(urgency, Urgency::Custom(_)))
+            .unwrap_or(Urgency::Low)
+    }
+
+    /// Glyph of the urgency, `?` if it isn't one of the [`levels`]
+    pub fn icon(&self, icons: IconSet) -> &'static str {
+        let Some(level) = self.rank().map(|rank| &levels()[rank]) else {
+            return "?";
+        };
+        match icons {
+            IconSet::Nerd => &level.icon,
+            IconSet::Ascii => level.ascii.as_deref().unwrap_or(&level.icon),
+        }
+    }
+}
+
+impl From<String> for Urgency {
+    /// Never fails so tasks with urgencies that aren't [`levels`] still load
+    fn from(name: String) -> Self {
+        match name.to_lowercase().as_str() {
+            "low" => Urgency::Low,
+            "medium" => Urgency::Medium,
+            "high" => Urgency::High,
+            _ => Urgency::Custom(name),
+        }
+    }
+}
+
+impl From<Urgency> for String {
+    fn from(urgency: Urgency) -> Self {
+        urgency.name().to_string()
+    }
+}
+
+impl FromStr for Urgency {
+    type Err = Error;
+
+    /// Parse one of the [`levels`] or built-in urgencies by name, ignoring case
+    fn from_str(name: &str) -> Result<Self> {
+        match levels()
+            .iter()
+            .find(|level| level.name.eq_ignore_ascii_case(name))
+        {
+            Some(level) => Ok(Urgency::from(level.name.clone())),
+            None => match Urgency::from(name.to_string()) {
+                Urgency::Custom(_) => Err(Error::UnknownUrgency {
+                    name: name.to_string(),
+                    expected: levels().iter().map(|level| level.name.clone()).collect(),
+                }),
+                builtin => Ok(builtin),
+            },
+        }
+    }
+}
+
+impl Display for Urgency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl PartialOrd for Urgency {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Urgency {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.rank()
+            .cmp(&other.rank())
+            .then_with(|| self.name().cmp(other.name()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use serde::{
+        de::value::{Error, StrDeserializer},
+        Deserialize,
+    };
+
+    use super::Urgency;
+
+    #[test]
+    fn test_urgency_names() {
+        assert_eq!("HIGH".parse::<Urgency>().unwrap(), Urgency::High);
+        assert!("critical".parse::<Urgency>().is_err());
+
+        let custom = Urgency::deserialize(StrDeserializer::<Error>::new("Critical")).unwrap();
+        assert_eq!(custom, Urgency::Custom("Critical".to_string()));
+        assert_eq!(String::from(custom.clone()), "Critical");
+        assert_eq!(custom.builtin(), Urgency::Low);
+
+        let mut urgencies = [custom, Urgency::High, Urgency::Low, Urgency::Medium];
+        urgencies.sort();
+        assert_eq!(
+            urgencies.map(|urgency| urgency.to_string()),
+            ["Critical", "Low", "Medium", "High"]
+        );
+    }
+}